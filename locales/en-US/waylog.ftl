@@ -0,0 +1,247 @@
+# Messages of the waylog command line, in English. Every message here must
+# also be in the other catalogs under locales/; `cargo test` checks it.
+
+## Shared
+
+# Joins the parts of a one-line summary
+list-separator = {", "}
+unknown-provider = '{ $name }' is not a recognized provider.
+available-providers = Available providers:
+wrote = Wrote { $path }
+aborted = Aborted.
+encryption-passphrase = Passphrase for the waylog history
+lock-waiting = Waiting for { $holder } to finish...
+lock-another-process = another waylog process
+webhook-failed = Could not post to the webhook: { $error }
+
+## pull
+
+pull-start = Pulling chat history for project: { $path }
+pull-provider-header = [{ $provider }] Found { $count } sessions
+pull-synced = Synced: { $file } ({ $count } new messages)
+pull-malformed-lines = { $file }: skipped { $count } malformed { $count ->
+        [one] line
+       *[other] lines
+    }; the rest of the session was synced
+pull-would-create = Would create { $target } ({ $count } messages from { $file })
+pull-would-append = Would append to { $target } ({ $count } messages from { $file })
+pull-dry-run-summary = Dry run: would create { $create } files and append to { $append }, writing { $messages } messages; { $uptodate } sessions up to date. Nothing was written.
+pull-up-to-date = Up to date: { $file }
+pull-failed = Failed to sync { $file }: { $error }
+pull-skipped = Skipped: { $file } ({ $reason })
+pull-complete = Pull complete!
+pull-no-sessions = No sessions found for this project.
+pull-column-provider = Provider
+pull-column-found = Found
+pull-column-synced = Synced
+pull-column-would-sync = Would sync
+pull-column-up-to-date = Up to date
+pull-column-skipped = Skipped
+pull-column-failed = Failed
+pull-column-new-messages = New msgs
+pull-total = Total
+pull-progress-scanning = { $provider }: scanning
+pull-progress-parsing = { $provider }: parsing
+pull-progress-exporting = { $provider }: exporting
+pull-timings = Timings for { $provider }: scan { $scan }, parse { $parse }, export { $export }, state save { $save }
+pull-timings-session = { $file } (parse { $parse }, export { $export })
+pull-project-not-initialized = { $count } sessions, not initialized; run `waylog init` there
+all-projects-pulled = { $count } projects pulled
+all-projects-failed = { $count } failed
+all-projects-uninitialized = { $count } without .waylog skipped (-v lists them)
+all-projects-unmapped = { $count } sessions of unknown projects skipped
+all-projects-summary = All projects: { $summary }.
+
+## batch
+
+batch-done = done
+batch-exited = exited with { $code }
+batch-killed = killed by a signal
+batch-result = { $label }: { $status }, { $count } { $count ->
+        [one] session
+       *[other] sessions
+    } synced
+batch-summary = Batch: { $succeeded } succeeded, { $failed } failed
+
+## completions
+
+completions-install = Install { $shell } completions at { $path }?
+completions-replace = Replace { $shell } completions at { $path }?
+completions-installed = Installed completions to { $path }
+completions-add-to = Add this to { $file } if it is not there yet:
+completions-then-new-shell = then open a new shell.
+completions-new-shell = Open a new shell to use them.
+
+## doctor
+
+doctor-installed = { $provider }: installed, { $count } sessions for this project
+doctor-not-installed = { $provider }: not installed
+doctor-unrecognized = { $provider }: { $count } unrecognized events since last upgrade — consider updating waylog
+doctor-event-types = event types: { $types }
+
+## export
+
+export-summary = Exported { $messages } messages from { $sessions } sessions
+export-summary-to = Exported { $messages } messages from { $sessions } sessions to { $path }
+export-background = Writing { $path } in the background; `waylog export --status` shows how far it has got
+export-status-none = No SQLite export has been recorded in this project
+export-status-running = Writing { $path }: { $written } of { $total } messages ({ $percent }%)
+export-status-eta = , about { $left } left
+export-status-stalled = ; no progress for { $idle }, the export may have stopped
+export-status-done = Wrote { $written } messages to { $path } in { $took }
+export-status-failed = Writing { $path } failed after { $written } of { $total } messages: { $error }
+
+## files
+
+files-none = No file reads or edits recorded in { $name }
+files-header = { $count } files touched in { $name }
+files-access = { $reads } read  { $edits } edit  { $path }
+
+## gc
+
+gc-would-remove = Would remove: { $path }
+gc-removed = Removed: { $path }
+gc-summary = { $removed } attachment blobs removed ({ $bytes } bytes), { $kept } still referenced
+gc-summary-dry-run = { $removed } attachment blobs would be removed ({ $bytes } bytes), { $kept } still referenced
+
+## git
+
+git-committed = Committed the history as { $hash }: { $subject }
+git-ignored = Not committing the history: .waylog/history is ignored by git ([git] auto_commit)
+git-dirty = Not committing the history while the work tree has other changes ([git] on_dirty = "skip")
+git-commit-failed = Could not commit the history: { $error }
+
+## import
+
+import-start = Importing { $count } conversations from { $file } ({ $format })
+import-imported = Imported: { $file } ({ $count } messages)
+import-skipped = Skipped: { $id } (already imported, use --force to overwrite)
+import-complete = Import complete! { $imported } sessions imported, { $skipped } skipped.
+
+## init
+
+init-found-tracking = Found existing tracking at: { $path }
+init-not-initialized = Not initialized.
+init-prompt = Start tracking AI chat history in this directory?
+init-prompt-path = Path: { $path }
+init-home-directory = { $path } is your home directory. Tracking it makes every project below it share one history, and syncing scans all of your provider sessions.
+init-all-providers = all providers
+init-config-written = Wrote { $path } (syncing { $providers })
+init-config-kept = Keeping existing config at { $path }
+init-welcome = Welcome to waylog! Setting up this project; every choice can be changed later in .waylog/config.toml.
+init-gitignore-updated = Updated { $path } to ignore { $entries }
+init-pull-prompt = Pull the { $count } existing sessions now?
+
+## merge, note, open
+
+merged = Merged { $sessions } sessions ({ $messages } messages) into { $path }
+note-added = Noted in { $path }
+opening = Opening { $path }
+revealing = Revealing { $path }
+
+## redact
+
+redact-contains-secrets = Contains secrets: { $file }
+redact-redacted = Redacted: { $file }
+redact-summary = { $redacted } of { $total } history files redacted
+redact-summary-dry-run = { $redacted } of { $total } history files contain secrets
+
+## scan-secrets
+
+scan-found = { $location }: { $rule } ({ $preview })
+scan-summary = { $found } secrets in { $affected } of { $total } files; `waylog scan-secrets --fix` redacts them
+scan-summary-fixed = Redacted { $found } secrets in { $affected } of { $total } files
+scan-clean = No secrets found in { $total } files
+
+## reexport
+
+reexport-summary = Regenerated { $regenerated } pages in { $path } ({ $unchanged } unchanged)
+
+## related, relink
+
+related-none = No other session edited the files of { $name }
+related-header = { $count } sessions edited the same files as { $name }
+relinked = Linked { $path } ({ $count } { $count ->
+        [one] session
+       *[other] sessions
+    } found)
+
+## repair
+
+repair-conflict-markers = resolved conflict markers
+repair-merged = merged { $files }
+repair-added = { $file }: { $what }, added { $count } { $count ->
+        [one] message
+       *[other] messages
+    }
+repair-would-add = { $file }: { $what }, would add { $count } { $count ->
+        [one] message
+       *[other] messages
+    }
+repair-summary = { $repaired } repairs among { $total } history files (repaired)
+repair-summary-dry-run = { $repaired } repairs among { $total } history files (need repair)
+
+## run
+
+run-missing-agent = Missing required argument <AGENT>
+run-usage = Usage: waylog run <AGENT> [ARGS]...
+run-available-agents = Available agents:
+run-example = Example:
+run-unknown-agent = '{ $name }' is not a recognized agent.
+run-did-you-mean-pull = Did you mean to run 'waylog pull'?
+run-timed-out = --timeout reached; stopping { $agent } and syncing its transcript
+run-live-sync-degraded = Live sync stopped ({ $error }); restarting in { $seconds }s. The session is still synced when the agent exits
+run-recording-to = Recording the terminal to { $path }
+run-session-named = Session saved as { $path }
+run-name-prompt = Name this session (Enter to keep the generated name)
+run-tags-prompt = Tags, comma-separated (Enter for none)
+run-naming-failed = Could not name the session: { $error }
+run-commits-failed = Could not list the session's commits: { $error }
+run-recording-needs-pty = Not recording: the agent only runs in a pseudo-terminal when waylog runs in a terminal with `[run] pty` on
+run-final-sync-detached = Finishing the sync in the background ({ $command })
+run-agent-not-installed = { $command } is not installed or not in PATH
+run-install-first = Please install it first before using waylog.
+
+## serve, site
+
+serving = Serving the history on { $url } (/sessions, /sessions/{"{"}id{"}"}, /search?q=); Ctrl+C stops
+site-built = Built { $pages } pages for { $sessions } sessions in { $path }
+
+## snapshot
+
+snapshot-created = Created snapshot '{ $name }' ({ $sessions } sessions, { $messages } messages)
+snapshot-entry = { $name } ({ $sessions } sessions, { $messages } messages)
+snapshot-none = No snapshots yet. Record one with `waylog snapshot create <name>`.
+diff-new = new, { $messages } messages
+diff-appended = +{ $added } messages ({ $before } -> { $after })
+diff-rewritten = rewritten, { $messages } messages
+diff-removed = removed
+diff-range = From '{ $from }' to '{ $to }'
+diff-since = Since '{ $from }' ({ $created })
+diff-summary = { $range }: { $new } new sessions, { $appended } sessions with new messages, { $added } messages added, { $rewritten } rewritten, { $removed } removed
+
+## tail, trash
+
+tail-following = Following { $provider } session { $path }
+tail-waiting = No sessions for { $path } yet; waiting for one to start
+trash-entry = { $id } (deleted { $deleted })
+trash-summary = { $count } files in the trash (purged after { $days } days)
+trash-summary-kept = { $count } files in the trash (kept until restored)
+trash-restored = Restored { $path }
+
+## usage
+
+usage-no-sessions = { $provider }: no sessions
+usage-sessions = { $sessions } sessions, { $size }
+usage-range = { $sessions }, { $first } to { $last }
+usage-total = Total for this project: { $summary }
+usage-total-global = Total for all projects on this machine: { $summary }
+
+## validate
+
+validate-event-type = event type
+validate-field = field
+validate-content-type = content type
+validate-checked = { $files } files, { $events } events checked
+validate-unknown = Unknown { $kind } `{ $value }` ({ $count }x)
+validate-clean = No format changes detected
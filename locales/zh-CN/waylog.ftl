@@ -0,0 +1,232 @@
+# waylog 命令行的简体中文消息。每条消息都要与 locales/en-US/waylog.ftl
+# 中的一一对应；`cargo test` 会检查。
+
+## Shared
+
+# Joins the parts of a one-line summary
+list-separator = {"，"}
+unknown-provider = 无法识别的提供方 '{ $name }'。
+available-providers = 可用的提供方：
+wrote = 已写入 { $path }
+aborted = 已取消。
+encryption-passphrase = waylog 记录的密码
+lock-waiting = 正在等待 { $holder } 结束...
+lock-another-process = 另一个 waylog 进程
+webhook-failed = 无法发送到 webhook：{ $error }
+
+## pull
+
+pull-start = 正在拉取项目的聊天记录：{ $path }
+pull-provider-header = [{ $provider }] 找到 { $count } 个会话
+pull-synced = 已同步：{ $file }（{ $count } 条新消息）
+pull-malformed-lines = { $file }：跳过了 { $count } 行格式错误的内容；会话的其余部分已同步
+pull-would-create = 将创建 { $target }（来自 { $file } 的 { $count } 条消息）
+pull-would-append = 将追加到 { $target }（来自 { $file } 的 { $count } 条消息）
+pull-dry-run-summary = 演练：将创建 { $create } 个文件并追加到 { $append } 个文件，共写入 { $messages } 条消息；{ $uptodate } 个会话已是最新。未写入任何内容。
+pull-up-to-date = 已是最新：{ $file }
+pull-failed = 同步 { $file } 失败：{ $error }
+pull-skipped = 已跳过：{ $file }（{ $reason }）
+pull-complete = 拉取完成！
+pull-no-sessions = 此项目没有找到会话。
+pull-column-provider = 提供方
+pull-column-found = 找到
+pull-column-synced = 已同步
+pull-column-would-sync = 将同步
+pull-column-up-to-date = 已是最新
+pull-column-skipped = 已跳过
+pull-column-failed = 失败
+pull-column-new-messages = 新消息
+pull-total = 合计
+pull-progress-scanning = { $provider }：扫描中
+pull-progress-parsing = { $provider }：解析中
+pull-progress-exporting = { $provider }：导出中
+pull-timings = { $provider } 的耗时：扫描 { $scan }，解析 { $parse }，导出 { $export }，保存状态 { $save }
+pull-timings-session = { $file }（解析 { $parse }，导出 { $export }）
+pull-project-not-initialized = { $count } 个会话，未初始化；请在该目录运行 `waylog init`
+all-projects-pulled = 已拉取 { $count } 个项目
+all-projects-failed = { $count } 个失败
+all-projects-uninitialized = 跳过了 { $count } 个没有 .waylog 的项目（-v 可列出）
+all-projects-unmapped = 跳过了 { $count } 个未知项目的会话
+all-projects-summary = 所有项目：{ $summary }。
+
+## batch
+
+batch-done = 完成
+batch-exited = 退出码 { $code }
+batch-killed = 被信号终止
+batch-result = { $label }：{ $status }，同步了 { $count } 个会话
+batch-summary = 批处理：{ $succeeded } 个成功，{ $failed } 个失败
+
+## completions
+
+completions-install = 将 { $shell } 补全安装到 { $path }？
+completions-replace = 替换 { $path } 处的 { $shell } 补全？
+completions-installed = 已将补全安装到 { $path }
+completions-add-to = 如果 { $file } 中还没有，请添加以下内容：
+completions-then-new-shell = 然后打开一个新的 shell。
+completions-new-shell = 打开一个新的 shell 即可使用。
+
+## doctor
+
+doctor-installed = { $provider }：已安装，此项目有 { $count } 个会话
+doctor-not-installed = { $provider }：未安装
+doctor-unrecognized = { $provider }：自上次升级以来有 { $count } 个无法识别的事件——建议更新 waylog
+doctor-event-types = 事件类型：{ $types }
+
+## export
+
+export-summary = 已从 { $sessions } 个会话导出 { $messages } 条消息
+export-summary-to = 已从 { $sessions } 个会话导出 { $messages } 条消息到 { $path }
+export-background = 正在后台写入 { $path }；`waylog export --status` 可查看进度
+export-status-none = 本项目没有 SQLite 导出记录
+export-status-running = 正在写入 { $path }：{ $total } 条消息中已写入 { $written } 条（{ $percent }%）
+export-status-eta = ，约剩 { $left }
+export-status-stalled = ；已 { $idle } 没有进展，导出可能已停止
+export-status-done = 已将 { $written } 条消息写入 { $path }，用时 { $took }
+export-status-failed = 写入 { $path } 失败，{ $total } 条消息中已写入 { $written } 条：{ $error }
+
+## files
+
+files-none = { $name } 中没有记录文件读取或编辑
+files-header = { $name } 涉及 { $count } 个文件
+files-access = { $reads } 读  { $edits } 改  { $path }
+
+## gc
+
+gc-would-remove = 将删除：{ $path }
+gc-removed = 已删除：{ $path }
+gc-summary = 已删除 { $removed } 个附件数据块（{ $bytes } 字节），{ $kept } 个仍被引用
+gc-summary-dry-run = 将删除 { $removed } 个附件数据块（{ $bytes } 字节），{ $kept } 个仍被引用
+
+## git
+
+git-committed = 已将记录提交为 { $hash }：{ $subject }
+git-ignored = 不提交记录：.waylog/history 被 git 忽略（[git] auto_commit）
+git-dirty = 工作区有其他改动，不提交记录（[git] on_dirty = "skip"）
+git-commit-failed = 无法提交记录：{ $error }
+
+## import
+
+import-start = 正在从 { $file } 导入 { $count } 个对话（{ $format }）
+import-imported = 已导入：{ $file }（{ $count } 条消息）
+import-skipped = 已跳过：{ $id }（已导入过，使用 --force 覆盖）
+import-complete = 导入完成！导入了 { $imported } 个会话，跳过 { $skipped } 个。
+
+## init
+
+init-found-tracking = 已在此处跟踪：{ $path }
+init-not-initialized = 尚未初始化。
+init-prompt = 在此目录中开始跟踪 AI 聊天记录？
+init-prompt-path = 路径：{ $path }
+init-home-directory = { $path } 是你的主目录。跟踪它会让其下所有项目共用一份记录，并且同步时会扫描你所有提供方的会话。
+init-all-providers = 所有提供方
+init-config-written = 已写入 { $path }（同步 { $providers }）
+init-config-kept = 保留 { $path } 处已有的配置
+init-welcome = 欢迎使用 waylog！正在设置此项目；所有选项之后都可以在 .waylog/config.toml 中修改。
+init-gitignore-updated = 已更新 { $path }，忽略 { $entries }
+init-pull-prompt = 现在拉取已有的 { $count } 个会话？
+
+## merge, note, open
+
+merged = 已将 { $sessions } 个会话（{ $messages } 条消息）合并到 { $path }
+note-added = 已记录到 { $path }
+opening = 正在打开 { $path }
+revealing = 正在显示 { $path }
+
+## redact
+
+redact-contains-secrets = 含有机密：{ $file }
+redact-redacted = 已脱敏：{ $file }
+redact-summary = { $total } 个记录文件中有 { $redacted } 个已脱敏
+redact-summary-dry-run = { $total } 个记录文件中有 { $redacted } 个含有机密
+
+## scan-secrets
+
+scan-found = { $location }：{ $rule }（{ $preview }）
+scan-summary = { $total } 个文件中有 { $affected } 个含有 { $found } 处机密；`waylog scan-secrets --fix` 可将其脱敏
+scan-summary-fixed = 已对 { $total } 个文件中 { $affected } 个文件的 { $found } 处机密脱敏
+scan-clean = { $total } 个文件中未发现机密
+
+## reexport
+
+reexport-summary = 已在 { $path } 重新生成 { $regenerated } 个页面（{ $unchanged } 个未变）
+
+## related, relink
+
+related-none = 没有其他会话编辑过 { $name } 的文件
+related-header = { $count } 个会话编辑过与 { $name } 相同的文件
+relinked = 已关联 { $path }（找到 { $count } 个会话）
+
+## repair
+
+repair-conflict-markers = 解决了冲突标记
+repair-merged = 合并了 { $files }
+repair-added = { $file }：{ $what }，添加了 { $count } 条消息
+repair-would-add = { $file }：{ $what }，将添加 { $count } 条消息
+repair-summary = { $total } 个记录文件中有 { $repaired } 处修复（已修复）
+repair-summary-dry-run = { $total } 个记录文件中有 { $repaired } 处修复（需要修复）
+
+## run
+
+run-missing-agent = 缺少必需参数 <AGENT>
+run-usage = 用法：waylog run <AGENT> [ARGS]...
+run-available-agents = 可用的智能体：
+run-example = 示例：
+run-unknown-agent = 无法识别的智能体 '{ $name }'。
+run-did-you-mean-pull = 你是想运行 'waylog pull' 吗？
+run-timed-out = 已达到 --timeout；正在停止 { $agent } 并同步其记录
+run-live-sync-degraded = 实时同步已停止（{ $error }）；{ $seconds } 秒后重试。智能体退出时会话仍会同步
+run-recording-to = 正在将终端录制到 { $path }
+run-session-named = 会话已保存为 { $path }
+run-name-prompt = 为此会话命名（按 Enter 保留生成的名称）
+run-tags-prompt = 标签，以逗号分隔（按 Enter 表示无）
+run-naming-failed = 无法为会话命名：{ $error }
+run-commits-failed = 无法列出会话的提交：{ $error }
+run-recording-needs-pty = 不录制：只有当 waylog 在终端中运行且开启 `[run] pty` 时，智能体才会运行在伪终端中
+run-final-sync-detached = 正在后台完成同步（{ $command }）
+run-agent-not-installed = { $command } 未安装或不在 PATH 中
+run-install-first = 请先安装它，再使用 waylog。
+
+## serve, site
+
+serving = 正在 { $url } 提供记录（/sessions、/sessions/{"{"}id{"}"}、/search?q=）；按 Ctrl+C 停止
+site-built = 已在 { $path } 为 { $sessions } 个会话生成 { $pages } 个页面
+
+## snapshot
+
+snapshot-created = 已创建快照 '{ $name }'（{ $sessions } 个会话，{ $messages } 条消息）
+snapshot-entry = { $name }（{ $sessions } 个会话，{ $messages } 条消息）
+snapshot-none = 还没有快照。使用 `waylog snapshot create <name>` 创建一个。
+diff-new = 新增，{ $messages } 条消息
+diff-appended = +{ $added } 条消息（{ $before } -> { $after }）
+diff-rewritten = 已重写，{ $messages } 条消息
+diff-removed = 已删除
+diff-range = 从 '{ $from }' 到 '{ $to }'
+diff-since = 自 '{ $from }'（{ $created }）以来
+diff-summary = { $range }：{ $new } 个新会话，{ $appended } 个会话有新消息，新增 { $added } 条消息，{ $rewritten } 个重写，{ $removed } 个删除
+
+## tail, trash
+
+tail-following = 正在跟随 { $provider } 会话 { $path }
+tail-waiting = { $path } 还没有会话；等待会话开始
+trash-entry = { $id }（删除于 { $deleted }）
+trash-summary = 回收站中有 { $count } 个文件（{ $days } 天后清除）
+trash-summary-kept = 回收站中有 { $count } 个文件（保留至恢复）
+trash-restored = 已恢复 { $path }
+
+## usage
+
+usage-no-sessions = { $provider }：没有会话
+usage-sessions = { $sessions } 个会话，{ $size }
+usage-range = { $sessions }，{ $first } 至 { $last }
+usage-total = 此项目合计：{ $summary }
+usage-total-global = 本机所有项目合计：{ $summary }
+
+## validate
+
+validate-event-type = 事件类型
+validate-field = 字段
+validate-content-type = 内容类型
+validate-checked = 检查了 { $files } 个文件、{ $events } 个事件
+validate-unknown = 未知{ $kind } `{ $value }`（{ $count } 次）
+validate-clean = 未发现格式变化
@@ -1,4 +1,6 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "waylog")]
@@ -8,9 +10,10 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Enable verbose logging
-    #[arg(short, long, global = true)]
-    pub verbose: bool,
+    /// More detail: -v lists synced sessions and logs at info level, -vv
+    /// lists every session and logs debug, -vvv shows source paths and traces
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
 
     /// Suppress all output (except errors)
     #[arg(short, long, global = true)]
@@ -19,6 +22,19 @@ pub struct Cli {
     /// Output format
     #[arg(long, default_value = "text", global = true)]
     pub output: OutputFormat,
+
+    /// Print JSON (the same as `--output json`)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// When to color the output; `auto` colors a terminal unless NO_COLOR is set
+    #[arg(long, value_enum, default_value = "auto", global = true)]
+    pub color: ColorWhen,
+
+    /// If another waylog is writing to the project, wait for it to finish
+    /// instead of failing
+    #[arg(long, global = true)]
+    pub wait: bool,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -27,10 +43,262 @@ pub enum OutputFormat {
     Json,
 }
 
+/// `--color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+/// External chat export formats understood by `waylog import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// ChatGPT data export (conversations.json)
+    #[value(name = "chatgpt")]
+    ChatGpt,
+    /// Claude.ai data export (conversations.json)
+    #[value(name = "claude-ai")]
+    ClaudeAi,
+    /// OpenAI chat format ({"messages": [...]})
+    #[value(name = "openai")]
+    OpenAi,
+    /// JSONL written by `waylog export --format jsonl`
+    #[value(name = "waylog-jsonl")]
+    WaylogJsonl,
+}
+
+/// Dataset formats written by `waylog export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per message (re-importable with `waylog import`)
+    Jsonl,
+    /// One row of metadata per message (tokens, tool calls, length), without content
+    Csv,
+    /// A static site: one page per session plus an index (requires --out DIR)
+    Html,
+    /// A SQLite database (.waylog/history.db by default), kept up to date by later syncs
+    Sqlite,
+}
+
+/// How history files are named (the `filename_template` written by setup)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HistoryLayout {
+    /// By start time: `2025-01-15_09-30-00Z-claude-fix-the-build.md`
+    Dated,
+    /// Grouped by agent: `claude-2025-01-15-fix-the-build.md`
+    Provider,
+    /// By title only: `fix-the-build.md`
+    Title,
+}
+
+/// Whether the project's `.gitignore` keeps `.waylog` out of git
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GitignorePolicy {
+    /// Commit the history with the code (nothing is added)
+    Commit,
+    /// Keep all of `.waylog/` out of git
+    Ignore,
+    /// Commit the history, but keep this machine's logs and sync state out of git
+    Local,
+}
+
+/// Formats of `waylog run --record`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+    /// An asciinema v2 cast, with timing, for `asciinema play`
+    Cast,
+    /// The raw output, escape sequences included (`less -R`)
+    Log,
+}
+
+/// Derived formats `waylog reexport` regenerates from the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReexportFormat {
+    /// One HTML page per session plus an index, as written by `export --format html`
+    Html,
+}
+
+/// Formats of the single document written by `waylog export --merge`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeFormat {
+    Markdown,
+    Html,
+}
+
+/// What `waylog export --sample` draws from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SampleUnit {
+    /// Keep or drop whole sessions
+    Session,
+    /// Keep or drop individual messages
+    Message,
+}
+
+/// Groups that are sampled separately, so each keeps its share of the sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Stratify {
+    Provider,
+}
+
+/// Parse a fraction between 0 and 1 for `--sample`
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Ok(f),
+        _ => Err(format!("expected a number between 0 and 1, got '{}'", s)),
+    }
+}
+
+/// Parse a date (`YYYY-MM-DD`, taken as midnight UTC) or an RFC 3339 timestamp
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("expected YYYY-MM-DD or an RFC 3339 timestamp, got '{}'", s))
+}
+
+/// Parse a span of time such as `90s`, `30m`, `12h`, `7d` or `2w`
+fn parse_age(s: &str) -> Option<chrono::Duration> {
+    s.char_indices()
+        .last()
+        .and_then(|(i, unit)| Some((s[..i].parse::<i64>().ok()?, unit)))
+        .and_then(|(n, unit)| match unit {
+            's' => chrono::Duration::try_seconds(n),
+            'm' => chrono::Duration::try_minutes(n),
+            'h' => chrono::Duration::try_hours(n),
+            'd' => chrono::Duration::try_days(n),
+            'w' => chrono::Duration::try_weeks(n),
+            _ => None,
+        })
+}
+
+/// Parse a `--since`/`--until` bound: an age such as `30m`, `12h`, `7d` or
+/// `2w` before now, or a date as for `parse_date`
+fn parse_time_bound(s: &str) -> Result<DateTime<Utc>, String> {
+    match parse_age(s) {
+        Some(age) if age >= chrono::Duration::zero() => Ok(Utc::now() - age),
+        _ => parse_date(s).map_err(|_| {
+            format!(
+                "expected an age like 7d, 12h or 2w, YYYY-MM-DD or an RFC 3339 timestamp, got '{}'",
+                s
+            )
+        }),
+    }
+}
+
+/// Parse a `--timeout` such as `90s`, `45m` or `2h`
+fn parse_timeout(s: &str) -> Result<std::time::Duration, String> {
+    parse_age(s)
+        .and_then(|age| age.to_std().ok())
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| format!("expected a duration like 90s, 45m or 2h, got '{}'", s))
+}
+
+/// Parse a watcher interval such as `250ms`, `2s` or `1m` (zero is allowed)
+fn parse_interval(s: &str) -> Result<std::time::Duration, String> {
+    let parsed = match s.strip_suffix("ms") {
+        Some(millis) => millis.parse().ok().map(std::time::Duration::from_millis),
+        None => parse_age(s).and_then(|age| age.to_std().ok()),
+    };
+    parsed.ok_or_else(|| format!("expected a duration like 250ms, 2s or 1m, got '{}'", s))
+}
+
+/// Parse a snapshot name: it becomes a file name inside `.waylog/snapshots`
+fn parse_snapshot_name(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.starts_with('.') || s.contains(['/', '\\']) {
+        return Err(format!(
+            "'{}' is not a valid snapshot name (no slashes or leading dot)",
+            s
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// Parse a `KEY=VALUE` pair for `--env`
+fn parse_env_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Run an AI CLI tool and automatically sync its chat history
     Run {
+        /// Set an environment variable for the agent (KEY=VALUE, repeatable)
+        #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+        env: Vec<(String, String)>,
+
+        /// Remove an environment variable before launching the agent (repeatable)
+        #[arg(long = "unset-env", value_name = "NAME")]
+        unset_env: Vec<String>,
+
+        /// Start the agent with a minimal environment (PATH, HOME, TERM, ...)
+        #[arg(long)]
+        clean_env: bool,
+
+        /// Print each message to stderr as soon as it is recorded
+        #[arg(long)]
+        echo_sync: bool,
+
+        /// Stop the agent after this long (e.g. 90s, 45m, 2h), sync its
+        /// transcript and exit with code 124
+        #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+        timeout: Option<std::time::Duration>,
+
+        /// Also sync the sessions of every other installed agent while this
+        /// one runs (e.g. one in another terminal)
+        #[arg(long)]
+        all_providers: bool,
+
+        /// Wait this long without file events before syncing (e.g. 50ms, 1s;
+        /// default `[watch] debounce_ms`)
+        #[arg(long, value_name = "DURATION", value_parser = parse_interval)]
+        debounce: Option<std::time::Duration>,
+
+        /// Sync the same session at most once per this long (e.g. 500ms, 5s;
+        /// default `[watch] min_sync_interval_ms`)
+        #[arg(long, value_name = "DURATION", value_parser = parse_interval)]
+        min_sync_interval: Option<std::time::Duration>,
+
+        /// Resync every watched session this often in case an event was
+        /// missed, or poll this often (e.g. 10s, 2m; default `[watch] rescan_interval`)
+        #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+        rescan_interval: Option<std::time::Duration>,
+
+        /// Let the agent use waylog's terminal directly instead of a
+        /// pseudo-terminal of its own
+        #[arg(long, conflicts_with = "record")]
+        no_pty: bool,
+
+        /// Record the agent's terminal to .waylog/recordings/ (`--record`
+        /// for a cast, `--record=log` for the raw output)
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "cast"
+        )]
+        record: Option<RecordFormat>,
+
+        /// Ask for a title and tags for the session when the agent exits,
+        /// and name its history file after the title
+        #[arg(long)]
+        ask_name: bool,
+
+        /// Name the session: the history file is named after it instead of
+        /// the first message, and it is the session's title
+        #[arg(long, value_name = "NAME")]
+        session_name: Option<String>,
+
+        /// Track the current directory even if it is the home directory
+        #[arg(long)]
+        here: bool,
+
         /// The AI tool to run (codex, claude, gemini)
         agent: Option<String>,
 
@@ -39,6 +307,50 @@ pub enum Commands {
         args: Vec<String>,
     },
 
+    /// Run an agent non-interactively once per prompt and sync each
+    /// resulting session with a label
+    Batch {
+        /// The AI tool to run (codex, claude, gemini or an `[agents]` alias)
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// File with one prompt per line (blank lines and `#` comments are skipped)
+        #[arg(long, value_name = "FILE")]
+        prompts: PathBuf,
+
+        /// Label sessions `<LABEL>-1`, `<LABEL>-2`, ... (default: the prompts file name)
+        #[arg(long)]
+        label: Option<String>,
+
+        /// Additional arguments to pass to the agent on every run
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Start tracking this directory: find installed agents, write a starter config and pull
+    Init {
+        /// Don't ask anything: run the first pull and keep the defaults of
+        /// the choices not given below
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Track the current directory even if it is the home directory
+        #[arg(long)]
+        here: bool,
+
+        /// Agent `waylog run` starts when none is given
+        #[arg(long, value_name = "AGENT")]
+        agent: Option<String>,
+
+        /// How history files are named
+        #[arg(long, value_enum)]
+        layout: Option<HistoryLayout>,
+
+        /// What the project's .gitignore keeps out of git (default: `[git] gitignore`)
+        #[arg(long, value_enum)]
+        gitignore: Option<GitignorePolicy>,
+    },
+
     /// Pull chat history from providers
     Pull {
         /// Specific provider to pull (if not specified, pulls all)
@@ -48,5 +360,453 @@ pub enum Commands {
         /// Force re-pull even if up to date
         #[arg(short, long)]
         force: bool,
+
+        /// Replace paths, usernames, hostnames and emails with placeholders
+        #[arg(long)]
+        anonymize: bool,
+
+        /// Only sync this session: its provider session file, or the id (a
+        /// unique prefix, or part of the history file name) of a synced one
+        #[arg(long, value_name = "ID|FILE")]
+        session: Option<String>,
+
+        /// Rewrite one session's history file from its source, like
+        /// `--session` with `--force`
+        #[arg(long, value_name = "ID|FILE", conflicts_with = "session")]
+        rebuild: Option<String>,
+
+        /// Show which history files would be created or appended to, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only sessions modified since then (an age like 7d, 12h or 2w, or a date)
+        #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+        since: Option<DateTime<Utc>>,
+
+        /// Only sessions last modified before then (an age or a date)
+        #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+        until: Option<DateTime<Utc>>,
+
+        /// Write the outcome of every session (status, new messages, errors,
+        /// durations) to `.waylog/last-sync.json`
+        #[arg(long)]
+        report: bool,
+
+        /// Report how long scanning, parsing, exporting and saving took, per
+        /// provider, and the slowest sessions
+        #[arg(long)]
+        timings: bool,
+
+        /// Pull into every initialized project the providers have sessions for
+        #[arg(long, conflicts_with_all = ["session", "rebuild"])]
+        all_projects: bool,
+
+        /// Print the JSON Schema of what `--output json` prints, and exit
+        #[arg(long)]
+        schema: bool,
+    },
+
+    /// Import chat exports from ChatGPT, Claude.ai or OpenAI-format JSON
+    Import {
+        /// Path to the export file (e.g. conversations.json)
+        file: PathBuf,
+
+        /// Export format (detected from the file contents if not specified)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+
+        /// Overwrite sessions that have already been imported
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Export sessions as a dataset
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: ExportFormat,
+
+        /// Write to this file (a directory for html) instead of stdout or the default database
+        #[arg(long, value_name = "PATH", required_if_eq("format", "html"))]
+        out: Option<PathBuf>,
+
+        /// Only export sessions from this provider
+        #[arg(short, long)]
+        provider: Option<String>,
+
+        /// Keep this fraction (0-1) of sessions or messages
+        #[arg(long, value_name = "FRACTION", value_parser = parse_fraction)]
+        sample: Option<f64>,
+
+        /// Seed for --sample; the same seed always selects the same items
+        #[arg(long, default_value_t = 0, requires = "sample")]
+        seed: u64,
+
+        /// Sample whole sessions or individual messages
+        #[arg(long, value_enum, default_value = "session", requires = "sample")]
+        sample_by: SampleUnit,
+
+        /// Sample each group separately so every group keeps its share
+        #[arg(long, value_enum, requires = "sample")]
+        stratify: Option<Stratify>,
+
+        /// Replace paths, usernames, hostnames and emails with placeholders
+        #[arg(long)]
+        anonymize: bool,
+
+        /// Only sessions modified since then (an age like 7d, 12h or 2w, or a date)
+        #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+        since: Option<DateTime<Utc>>,
+
+        /// Only sessions last modified before then (an age or a date)
+        #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+        until: Option<DateTime<Utc>>,
+
+        /// Only sessions with a message containing this text (case-insensitive)
+        #[arg(long, value_name = "TEXT")]
+        query: Option<String>,
+
+        /// Write the sessions as one document, in time order with a table of contents
+        #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["format", "sample"])]
+        merge: Option<MergeFormat>,
+
+        /// Title of the --merge document
+        #[arg(long, requires = "merge")]
+        title: Option<String>,
+
+        /// With --format sqlite, write the database in a background process
+        /// and return at once; `--status` shows how far it has got
+        #[arg(long)]
+        background: bool,
+
+        /// Show the progress of the last --format sqlite export and exit
+        #[arg(long, conflicts_with_all = ["background", "merge"])]
+        status: bool,
+    },
+
+    /// Regenerate derived formats for history files updated since a date
+    Reexport {
+        /// Format to regenerate
+        #[arg(long, value_enum, default_value = "html")]
+        format: ReexportFormat,
+
+        /// Directory the derived files are written to
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+
+        /// Only files whose `updated_at` is at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        changed_since: Option<DateTime<Utc>>,
+    },
+
+    /// Build a static site from the history: a page per session and indexes
+    /// by month, provider and tag, e.g. to publish on GitHub Pages
+    Site {
+        /// Directory the site is written to [default: site, in the project]
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+
+        /// Title of the index page [default: the project directory's name]
+        #[arg(long)]
+        title: Option<String>,
+    },
+
+    /// Merge split sessions into a single markdown file
+    Merge {
+        /// Sessions to merge (ID, ID prefix or filename fragment); the first one is kept
+        #[arg(required = true, num_args = 2..)]
+        sessions: Vec<String>,
+    },
+
+    /// List the project files a session read or edited
+    Files {
+        /// Session ID (or prefix) or part of the markdown filename
+        session: String,
+    },
+
+    /// Show sessions that edited the same files as a session
+    Related {
+        /// Session ID (or prefix) or part of the markdown filename
+        #[arg(required_unless_present = "index")]
+        session: Option<String>,
+
+        /// Write `.waylog/index.md` listing the sessions that edited each file
+        #[arg(long, conflicts_with = "session")]
+        index: bool,
+    },
+
+    /// Scrub secrets from already-synced history files using the redaction rules
+    Redact {
+        /// List the files that contain secrets without rewriting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Audit history files (or any markdown, such as an archive made before
+    /// redaction) for secrets and print the file and line of each
+    ScanSecrets {
+        /// Markdown files or directories to scan [default: .waylog/history]
+        paths: Vec<PathBuf>,
+
+        /// Rewrite the files with their secrets redacted
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Serve the history to agents over the Model Context Protocol (stdio)
+    Mcp,
+
+    /// Serve the history as JSON over HTTP (/sessions, /sessions/{id}, /search?q=)
+    Serve {
+        /// Address to listen on; anything but loopback shares the history with the network
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        /// Port to listen on (0 picks a free one)
+        #[arg(long, default_value_t = 8787)]
+        port: u16,
+    },
+
+    /// Merge history files that came apart while synced between machines:
+    /// conflict copies, git conflict markers, and several files for one session
+    Repair {
+        /// List what would be repaired without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check provider session files for event types and fields waylog does not know
+    Validate {
+        /// Provider to check (if not specified, checks all enabled providers)
+        #[arg(short, long)]
+        provider: Option<String>,
+    },
+
+    /// Check provider installs and report upstream format changes seen while syncing
+    Doctor,
+
+    /// Report session counts, disk usage and date ranges per provider
+    Usage {
+        /// Scan every project on this machine instead of the current one
+        #[arg(long)]
+        global: bool,
+    },
+
+    /// Record named checkpoints of the history
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+
+    /// Show sessions that are new or grew since a snapshot
+    Diff {
+        /// Snapshot to compare against (see `waylog snapshot create`)
+        #[arg(long, value_name = "NAME", value_parser = parse_snapshot_name)]
+        since_tag: String,
+    },
+
+    /// List or restore files waylog deleted
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+
+    /// Remove attachment blobs no longer referenced by any session
+    Gc {
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Register paths this project had before it was moved or renamed, so the
+    /// sessions agents recorded under them are synced again
+    Relink {
+        /// A previous path of the project (repeatable)
+        #[arg(long = "old-path", value_name = "PATH", required = true)]
+        old_paths: Vec<PathBuf>,
+    },
+
+    /// Print a shell completion script, or install one with `completions install`
+    #[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
+    Completions {
+        /// Shell to generate completions for
+        #[arg(required = true)]
+        shell: Option<clap_complete::Shell>,
+
+        #[command(subcommand)]
+        action: Option<CompletionsAction>,
+    },
+
+    /// Machine-readable contracts for tools built on waylog's output
+    Api {
+        #[command(subcommand)]
+        action: ApiAction,
+    },
+
+    /// Open a synced session in your editor
+    #[command(alias = "show")]
+    Open {
+        /// Session ID (or prefix) or part of the markdown filename
+        #[arg(conflicts_with = "latest", required_unless_present = "latest")]
+        id: Option<String>,
+
+        /// Open the most recently updated session
+        #[arg(long)]
+        latest: bool,
+
+        /// Open with the OS default application instead of $VISUAL/$EDITOR
+        #[arg(long)]
+        gui: bool,
+
+        /// Reveal the file in the system file manager instead of opening it
+        #[arg(long, conflicts_with = "gui")]
+        reveal: bool,
+    },
+
+    /// Upload a synced session as a GitHub Gist (or to a paste service) and
+    /// print its URL, e.g. to link it from a code review
+    Gist {
+        /// Session ID (or prefix) or part of the markdown filename
+        #[arg(conflicts_with = "latest", required_unless_present = "latest")]
+        id: Option<String>,
+
+        /// Share the most recently updated session
+        #[arg(long)]
+        latest: bool,
+
+        /// Replace paths, user and host names and emails with placeholders
+        #[arg(long)]
+        anonymize: bool,
+
+        /// Run the redaction rules again, with the built-in detectors on even
+        /// if `[redaction]` turns them off
+        #[arg(long)]
+        redact: bool,
+
+        /// Create a public gist instead of a secret one
+        #[arg(long, conflicts_with = "paste")]
+        public: bool,
+
+        /// Upload to `[share] paste_url` instead of GitHub
+        #[arg(long)]
+        paste: bool,
+    },
+
+    /// Follow the latest session and print its messages as they arrive
+    /// (like `tail -f`), e.g. to watch an agent from a second terminal
+    Tail {
+        /// Only follow this provider's sessions (claude, gemini, codex);
+        /// by default the most recent session of any enabled provider
+        provider: Option<String>,
+
+        /// Messages of the current session to print before following
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+    },
+
+    /// Add a note to the session of the `waylog run` active in this
+    /// project, e.g. from a second terminal
+    Note {
+        /// Text of the note (several words are joined with spaces)
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+}
+
+/// `waylog snapshot` subcommands
+#[derive(Subcommand, Debug)]
+pub enum SnapshotAction {
+    /// Record the sessions, message counts and checksums of the history under a name
+    Create {
+        #[arg(value_parser = parse_snapshot_name)]
+        name: String,
     },
+
+    /// Show recorded snapshots, oldest first
+    List,
+
+    /// Show sessions that were added, grew, were rewritten or removed between two snapshots
+    Diff {
+        /// Earlier snapshot
+        #[arg(value_parser = parse_snapshot_name)]
+        from: String,
+
+        /// Later snapshot (the current history if not given)
+        #[arg(value_parser = parse_snapshot_name)]
+        to: Option<String>,
+    },
+}
+
+/// `waylog completions` subcommands
+#[derive(Subcommand, Debug)]
+pub enum CompletionsAction {
+    /// Write the completion script to where your shell loads completions from
+    Install {
+        /// Shell to install completions for (detected from $SHELL if not given)
+        #[arg(long)]
+        shell: Option<clap_complete::Shell>,
+
+        /// Install without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+/// `waylog api` subcommands
+#[derive(Subcommand, Debug)]
+pub enum ApiAction {
+    /// Print the JSON Schemas of the JSONL export, the exporter session JSON
+    /// and the `--output json` events
+    DumpSchema {
+        /// Only this schema (all of them if not given)
+        schema: Option<SchemaName>,
+
+        /// Write one `<id>.schema.json` file per schema into this directory
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+}
+
+/// Schemas `waylog api dump-schema` can print
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaName {
+    /// One line of `export --format jsonl`
+    Message,
+    /// The session JSON given to `[export] command`
+    Session,
+    /// One line printed with `--output json`
+    Event,
+    /// The document printed by `pull --output json`
+    Pull,
+}
+
+/// `waylog trash` subcommands
+#[derive(Subcommand, Debug)]
+pub enum TrashAction {
+    /// Show deleted files, most recent first
+    List,
+
+    /// Move a deleted file back (by id or id fragment from `waylog trash list`)
+    Restore { entry: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_bound() {
+        assert_eq!(
+            parse_time_bound("2025-01-15").unwrap(),
+            parse_date("2025-01-15T00:00:00Z").unwrap()
+        );
+        let week_ago = parse_time_bound("7d").unwrap();
+        assert!(
+            (Utc::now() - week_ago - chrono::Duration::days(7))
+                .num_seconds()
+                .abs()
+                < 60
+        );
+        assert!(parse_time_bound("7x").is_err());
+    }
 }
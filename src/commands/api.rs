@@ -0,0 +1,49 @@
+use crate::cli::SchemaName;
+use crate::error::Result;
+use crate::exporter::schema;
+use crate::output::Output;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::Path;
+
+/// `waylog api dump-schema`: print one schema or all of them (as an object
+/// keyed by id), or write them as files into `out`
+pub fn handle_dump_schema(
+    name: Option<SchemaName>,
+    out: Option<&Path>,
+    output: &mut Output,
+) -> Result<()> {
+    let schemas: Vec<(&str, Value)> = match name {
+        Some(SchemaName::Message) => vec![(crate::exporter::jsonl::SCHEMA, schema::message())],
+        Some(SchemaName::Session) => vec![(schema::SESSION_SCHEMA, schema::session())],
+        Some(SchemaName::Event) => vec![(schema::EVENT_SCHEMA, schema::event())],
+        Some(SchemaName::Pull) => vec![(schema::PULL_SCHEMA, schema::pull())],
+        None => schema::all(),
+    };
+
+    if let Some(dir) = out {
+        std::fs::create_dir_all(dir)?;
+        for (id, schema) in &schemas {
+            let path = dir.join(format!("{}.schema.json", id));
+            std::fs::write(&path, serde_json::to_string_pretty(schema)? + "\n")?;
+            output.schema_written(&path)?;
+        }
+        return Ok(());
+    }
+
+    let document = match (name, schemas) {
+        (Some(_), mut schemas) => schemas.remove(0).1,
+        (None, schemas) => Value::Object(
+            schemas
+                .into_iter()
+                .map(|(id, schema)| (id.to_string(), schema))
+                .collect::<Map<_, _>>(),
+        ),
+    };
+    writeln!(
+        std::io::stdout(),
+        "{}",
+        serde_json::to_string_pretty(&document)?
+    )?;
+    Ok(())
+}
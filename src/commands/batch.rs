@@ -0,0 +1,162 @@
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::providers::base::Provider;
+use crate::synchronizer::{SyncStatus, Synchronizer};
+use crate::{session, utils};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::process::Command;
+
+/// Options for `waylog batch`
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub agent: Option<String>,
+    /// File with one prompt per line
+    pub prompts: PathBuf,
+    /// Sessions are labelled `<label>-<n>`; the prompts file name by default
+    pub label: Option<String>,
+    /// Extra arguments passed to the agent on every run
+    pub args: Vec<String>,
+}
+
+/// Run the agent non-interactively once per prompt, then sync the sessions
+/// each run recorded with a `label` in their frontmatter
+pub async fn handle_batch(
+    options: BatchOptions,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let (provider, command) = super::run::resolve_agent(options.agent, output)?;
+    let prompts = read_prompts(&options.prompts)?;
+    let label = options.label.unwrap_or_else(|| {
+        options
+            .prompts
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "batch".to_string())
+    });
+
+    utils::path::ensure_dir_exists(&utils::path::get_waylog_dir(&project_path))?;
+    let tracker =
+        Arc::new(session::SessionTracker::new(project_path.clone(), provider.clone()).await?);
+
+    let mut failed = 0;
+    for (i, prompt) in prompts.iter().enumerate() {
+        let run_label = format!("{}-{}", label, i + 1);
+        output.batch_prompt(&run_label, prompt)?;
+
+        let mut args = options.args.clone();
+        args.extend(provider.print_args(prompt));
+        let before = session_files(provider.as_ref(), &project_path).await?;
+        let status = Command::new(&command[0])
+            .args(&command[1..])
+            .args(&args)
+            .env_clear()
+            .envs(super::run::child_env(
+                std::env::vars_os(),
+                &crate::config::get().run,
+                &Default::default(),
+            ))
+            .stdin(Stdio::null())
+            .status()
+            .await?;
+
+        let invocation =
+            utils::string::shell_join(command.iter().chain(args.iter()).map(String::as_str));
+        // Quoted so that `:` or `#` in prompts can't break the YAML frontmatter
+        let synchronizer =
+            Synchronizer::new(provider.clone(), project_path.clone(), tracker.clone())
+                .with_frontmatter("label", serde_json::to_string(&run_label)?)
+                .with_frontmatter("invocation", serde_json::to_string(&invocation)?);
+
+        let mut synced = 0;
+        for file in changed_since(provider.as_ref(), &project_path, &before).await? {
+            match synchronizer.sync_session(&file, false).await? {
+                SyncStatus::Synced { .. } => synced += 1,
+                SyncStatus::Failed(e) => {
+                    let name = file.file_name().unwrap_or_default().to_string_lossy();
+                    output.failed(&name, &e)?;
+                }
+                _ => {}
+            }
+        }
+        tracker.save_state().await?;
+
+        if !status.success() {
+            failed += 1;
+        }
+        output.batch_result(&run_label, status.code(), synced)?;
+    }
+
+    output.batch_summary(prompts.len() - failed, failed)?;
+    if failed > 0 {
+        return Err(WaylogError::BatchFailed(failed, prompts.len()));
+    }
+    Ok(())
+}
+
+/// The prompts of a prompts file: one per line, skipping blank lines and
+/// `#` comments
+fn read_prompts(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_prompts(&content))
+}
+
+fn parse_prompts(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Size and modification time of each of the project's session files
+async fn session_files(
+    provider: &dyn Provider,
+    project_path: &Path,
+) -> Result<HashMap<PathBuf, (u64, Option<SystemTime>)>> {
+    Ok(provider
+        .get_all_sessions(project_path)
+        .await?
+        .into_iter()
+        .map(|file| {
+            let stamp = stamp(&file);
+            (file, stamp)
+        })
+        .collect())
+}
+
+/// The project's session files created or written to since `before` was taken
+async fn changed_since(
+    provider: &dyn Provider,
+    project_path: &Path,
+    before: &HashMap<PathBuf, (u64, Option<SystemTime>)>,
+) -> Result<Vec<PathBuf>> {
+    let mut sessions = provider.get_all_sessions(project_path).await?;
+    sessions.retain(|file| before.get(file) != Some(&stamp(file)));
+    // Oldest first, so the history is written in the order of the runs
+    sessions.reverse();
+    Ok(sessions)
+}
+
+fn stamp(file: &Path) -> (u64, Option<SystemTime>) {
+    match std::fs::metadata(file) {
+        Ok(metadata) => (metadata.len(), metadata.modified().ok()),
+        Err(_) => (0, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prompts_skips_blanks_and_comments() {
+        let prompts = parse_prompts("# setup\nfirst prompt\n\n  second prompt  \n#done\n");
+        assert_eq!(prompts, ["first prompt", "second prompt"]);
+    }
+}
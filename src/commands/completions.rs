@@ -0,0 +1,169 @@
+use crate::cli::Cli;
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::providers;
+use clap::builder::PossibleValuesParser;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn handle_completions(shell: Shell, out: &mut impl Write) -> Result<()> {
+    let mut command = completion_command();
+    clap_complete::generate(shell, &mut command, "waylog", out);
+    Ok(())
+}
+
+/// Where a shell loads a completion script from, and what has to go into its
+/// startup file for that to happen
+#[derive(Debug, PartialEq)]
+pub struct InstallTarget {
+    pub path: PathBuf,
+    /// Startup file and the lines to add to it, if the shell needs any
+    pub setup: Option<(&'static str, String)>,
+}
+
+/// `waylog completions install`: write the script for `shell` (or the one in
+/// $SHELL) to its conventional location after asking
+pub fn handle_install(shell: Option<Shell>, yes: bool, output: &mut Output) -> Result<()> {
+    let shell = shell
+        .or_else(Shell::from_env)
+        .ok_or(WaylogError::UnknownShell)?;
+    let home = home::home_dir()
+        .ok_or_else(|| WaylogError::PathError("could not find your home directory".into()))?;
+    let env_dir = |name| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    let target = install_target(
+        shell,
+        &home,
+        env_dir("XDG_DATA_HOME"),
+        env_dir("XDG_CONFIG_HOME"),
+    )
+    .ok_or(WaylogError::UnknownShell)?;
+
+    if !yes {
+        output.completions_prompt(shell, &target.path)?;
+        if !dialoguer::Confirm::new()
+            .default(true)
+            .show_default(true)
+            .interact()
+            .unwrap_or(false)
+        {
+            output.aborted()?;
+            return Ok(());
+        }
+    }
+
+    let mut script = Vec::new();
+    handle_completions(shell, &mut script)?;
+    if let Some(dir) = target.path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&target.path, script)?;
+    output.completions_installed(&target.path, target.setup.as_ref())?;
+    Ok(())
+}
+
+/// The conventional completion location of each shell
+pub fn install_target(
+    shell: Shell,
+    home: &Path,
+    data_home: Option<PathBuf>,
+    config_home: Option<PathBuf>,
+) -> Option<InstallTarget> {
+    let data_home = data_home.unwrap_or_else(|| home.join(".local/share"));
+    let config_home = config_home.unwrap_or_else(|| home.join(".config"));
+
+    let target = match shell {
+        // Loaded on demand by the bash-completion package
+        Shell::Bash => InstallTarget {
+            path: data_home.join("bash-completion/completions/waylog"),
+            setup: None,
+        },
+        Shell::Zsh => InstallTarget {
+            path: home.join(".zfunc/_waylog"),
+            setup: Some((
+                "~/.zshrc",
+                "fpath=(~/.zfunc $fpath)\nautoload -Uz compinit && compinit".to_string(),
+            )),
+        },
+        Shell::Fish => InstallTarget {
+            path: config_home.join("fish/completions/waylog.fish"),
+            setup: None,
+        },
+        Shell::Elvish => InstallTarget {
+            path: config_home.join("elvish/lib/waylog.elv"),
+            setup: Some(("~/.config/elvish/rc.elv", "use waylog".to_string())),
+        },
+        Shell::PowerShell => {
+            let path = config_home.join("powershell/waylog.ps1");
+            let setup = format!(". \"{}\"", path.display());
+            InstallTarget {
+                path,
+                setup: Some(("$PROFILE", setup)),
+            }
+        }
+        _ => return None,
+    };
+    Some(target)
+}
+
+/// Build the CLI definition used for completion scripts.
+///
+/// Provider arguments are free-form at runtime so that unknown names get a friendly
+/// error, but for completions we advertise the known provider names.
+fn completion_command() -> clap::Command {
+    let providers = PossibleValuesParser::new(providers::list_providers());
+
+    // `mut_args` edits in place; `mut_arg` would re-append the positional `agent`
+    // after the trailing `args`, which clap rejects
+    let with_providers = |cmd: clap::Command, id: &'static str| {
+        cmd.mut_args(|arg| {
+            if arg.get_id() == id {
+                arg.value_parser(providers.clone())
+            } else {
+                arg
+            }
+        })
+    };
+
+    Cli::command()
+        .mut_subcommand("run", |cmd| with_providers(cmd, "agent"))
+        .mut_subcommand("pull", |cmd| with_providers(cmd, "provider"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completions_include_provider_names() {
+        let mut buffer = Vec::new();
+        handle_completions(Shell::Bash, &mut buffer).unwrap();
+        let script = String::from_utf8(buffer).unwrap();
+
+        assert!(script.contains("pull"));
+        assert!(script.contains("claude gemini codex"));
+    }
+
+    #[test]
+    fn test_install_targets() {
+        let home = Path::new("/home/me");
+        let bash = install_target(Shell::Bash, home, None, None).unwrap();
+        assert_eq!(
+            bash.path,
+            home.join(".local/share/bash-completion/completions/waylog")
+        );
+        assert!(bash.setup.is_none());
+
+        let fish = install_target(Shell::Fish, home, None, Some("/xdg".into())).unwrap();
+        assert_eq!(fish.path, Path::new("/xdg/fish/completions/waylog.fish"));
+
+        let zsh = install_target(Shell::Zsh, home, None, None).unwrap();
+        assert_eq!(zsh.path, home.join(".zfunc/_waylog"));
+        assert!(zsh.setup.unwrap().1.contains("fpath=(~/.zfunc $fpath)"));
+    }
+}
@@ -0,0 +1,13 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::session::snapshot::Snapshot;
+use std::path::PathBuf;
+
+pub async fn handle_diff(
+    since_tag: String,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let tag = Snapshot::load(&project_path, &since_tag)?;
+    super::snapshot::report_changes(&tag, None, &project_path, output).await
+}
@@ -0,0 +1,33 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::providers;
+use crate::providers::telemetry::FormatTelemetry;
+use std::path::PathBuf;
+
+pub async fn handle_doctor(project_path: PathBuf, output: &mut Output) -> Result<()> {
+    let telemetry = FormatTelemetry::load(&project_path);
+
+    for name in providers::list_providers() {
+        let provider = providers::get_provider(name)?;
+        if !provider.is_installed() {
+            output.doctor_not_installed(name)?;
+            continue;
+        }
+
+        let sessions = match provider.get_project_sessions(&project_path).await {
+            Ok(sessions) => sessions.len(),
+            Err(e) => {
+                tracing::debug!("Could not list {} sessions: {}", name, e);
+                0
+            }
+        };
+        output.doctor_provider(name, sessions)?;
+
+        let unknown = telemetry.totals(name);
+        if !unknown.is_empty() {
+            output.doctor_unrecognized_events(name, &unknown)?;
+        }
+    }
+
+    Ok(())
+}
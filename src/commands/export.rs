@@ -0,0 +1,360 @@
+use crate::error::{Result, WaylogError};
+use crate::exporter::anonymize::Anonymizer;
+use crate::exporter::csv;
+use crate::exporter::html;
+use crate::exporter::jsonl;
+use crate::exporter::markdown;
+use crate::exporter::markdown::labels::Labels;
+use crate::exporter::redact::Redactor;
+use crate::exporter::sample::{self, SampleUnit, Stratify};
+use crate::exporter::sqlite;
+use crate::output::Output;
+use crate::providers;
+use crate::providers::base::{ChatSession, TimeRange};
+use crate::session::access::{self, Target};
+use crate::session::exclude::SessionFilter;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub use crate::cli::{ExportFormat, MergeFormat};
+
+/// Options for `waylog export`
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Write here instead of stdout
+    pub out: Option<PathBuf>,
+    pub provider: Option<String>,
+    pub sampling: Option<Sampling>,
+    /// Strip paths, names and emails (`--anonymize`)
+    pub anonymize: bool,
+    /// Only sessions modified within this range
+    pub range: TimeRange,
+    /// Only sessions with a message containing this text (`--query`)
+    pub query: Option<String>,
+    /// Write one combined document instead (`--merge`)
+    pub merge: Option<MergeFormat>,
+    /// Title of the combined document
+    pub title: Option<String>,
+    /// Write the SQLite database in a detached process (`--background`)
+    pub background: bool,
+}
+
+/// `--sample` settings
+#[derive(Debug, Clone, Copy)]
+pub struct Sampling {
+    pub fraction: f64,
+    pub seed: u64,
+    pub unit: SampleUnit,
+    pub stratify: Option<Stratify>,
+}
+
+pub async fn handle_export(
+    options: ExportOptions,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    if options.background {
+        if options.format != ExportFormat::Sqlite {
+            return Err(WaylogError::PathError(
+                "--background needs --format sqlite".to_string(),
+            ));
+        }
+        let database = sqlite_target(&options, &project_path)?;
+        // The same export again, minus --background, from the same directory
+        // so a relative --out means the same file
+        let args: Vec<String> = std::env::args()
+            .skip(1)
+            .filter(|arg| arg != "--background")
+            .collect();
+        crate::utils::process::spawn_detached(&args, &std::env::current_dir()?)?;
+        output.export_backgrounded(&database)?;
+        return Ok(());
+    }
+
+    let provider_names = match &options.provider {
+        Some(name) => vec![name.clone()],
+        None => crate::config::get()
+            .providers
+            .default_providers()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    };
+
+    let mut sessions = Vec::new();
+    for name in &provider_names {
+        let provider = match providers::get_provider(name) {
+            Ok(p) => p,
+            Err(WaylogError::ProviderNotFound(invalid)) => {
+                output.unknown_provider(&invalid)?;
+                return Err(WaylogError::ProviderNotFound(invalid));
+            }
+            Err(e) => return Err(e),
+        };
+        if !provider.is_installed() {
+            continue;
+        }
+        sessions.extend(load_sessions(&provider, &project_path, &options.range).await?);
+    }
+    sessions.sort_by_key(|s| s.started_at);
+    if let Some(query) = &options.query {
+        sessions.retain(|session| matches_query(session, query));
+    }
+    if options.anonymize {
+        let anonymizer = Anonymizer::new(&project_path);
+        sessions = sessions
+            .iter()
+            .map(|s| anonymizer.anonymize_session(s))
+            .collect();
+    }
+
+    if let Some(format) = options.merge {
+        return write_combined(&sessions, format, &options, &project_path, output);
+    }
+
+    let sampling = options.sampling;
+    let stratum = |provider: &str| match sampling.and_then(|s| s.stratify) {
+        Some(Stratify::Provider) => provider.to_string(),
+        None => String::new(),
+    };
+
+    if let Some(s) = sampling.filter(|s| s.unit == SampleUnit::Session) {
+        sessions = sample::sample(
+            sessions,
+            s.fraction,
+            s.seed,
+            |session| session.session_id.clone(),
+            |session| stratum(&session.provider),
+        );
+    }
+
+    let mut records: Vec<_> = sessions.iter().flat_map(jsonl::records).collect();
+    if let Some(s) = sampling.filter(|s| s.unit == SampleUnit::Message) {
+        records = sample::sample(
+            records,
+            s.fraction,
+            s.seed,
+            |record| record.key(),
+            |record| stratum(&record.provider),
+        );
+    }
+
+    let mut exported_sessions: Vec<_> = records.iter().map(|r| &r.session_id).collect();
+    exported_sessions.dedup();
+    access::record(
+        &project_path,
+        "export",
+        &exported_sessions,
+        options.out.as_deref().map(Target::File),
+    )?;
+
+    match options.format {
+        ExportFormat::Jsonl => match &options.out {
+            Some(path) => {
+                let file = std::fs::File::create(path)?;
+                jsonl::write_records(std::io::BufWriter::new(file), &records)?;
+            }
+            None => {
+                let stdout = std::io::stdout();
+                jsonl::write_records(stdout.lock(), &records)?;
+                stdout.lock().flush()?;
+            }
+        },
+        ExportFormat::Csv => match &options.out {
+            Some(path) => {
+                let file = std::fs::File::create(path)?;
+                csv::write_rows(std::io::BufWriter::new(file), &records)?;
+            }
+            None => csv::write_rows(std::io::stdout().lock(), &records)?,
+        },
+        ExportFormat::Html => {
+            let dir = options.out.as_deref().ok_or_else(|| {
+                WaylogError::PathError("--format html needs --out <DIR>".to_string())
+            })?;
+            write_site(dir, jsonl::into_sessions(records.iter().cloned()))?;
+        }
+        ExportFormat::Sqlite => {
+            let path = sqlite_target(&options, &project_path)?;
+            let mut archive = sqlite::Archive::open(&path)?;
+            let sessions = jsonl::into_sessions(records.iter().cloned());
+            let pending = sessions
+                .iter()
+                .map(|session| archive.pending(session))
+                .sum::<Result<usize>>()?;
+            let mut status = sqlite::Backfill::start(&project_path, &path, pending);
+            status.save().await?;
+            let progress = output.create_progress(pending as u64, "history.db");
+            let result = archive
+                .backfill(
+                    &sessions,
+                    &crate::config::get().export.sqlite,
+                    &mut status,
+                    |written| {
+                        if let Some(pb) = &progress {
+                            pb.inc(written as u64);
+                        }
+                    },
+                )
+                .await;
+            if let Some(pb) = progress {
+                pb.finish_and_clear();
+            }
+            status.finish(&result).await?;
+            result?;
+            output.export_summary(exported_sessions.len(), records.len(), Some(&path))?;
+            return Ok(());
+        }
+    }
+
+    output.export_summary(
+        exported_sessions.len(),
+        records.len(),
+        options.out.as_deref(),
+    )?;
+
+    Ok(())
+}
+
+/// `waylog export --status`: how far the last `--format sqlite` export got
+pub fn handle_status(project_path: &Path, output: &mut Output) -> Result<()> {
+    output.backfill_status(sqlite::Backfill::load(project_path).as_ref())?;
+    Ok(())
+}
+
+/// The database `--format sqlite` writes to
+fn sqlite_target(options: &ExportOptions, project_path: &Path) -> Result<PathBuf> {
+    // The archive inside the project would hold the sessions in plaintext
+    if options.out.is_none() && crate::exporter::encryption::enabled() {
+        return Err(WaylogError::Config(
+            "the SQLite archive is not encrypted; with [encryption], pass --out with a path outside the project"
+                .to_string(),
+        ));
+    }
+    Ok(options
+        .out
+        .clone()
+        .unwrap_or_else(|| sqlite::database_path(project_path)))
+}
+
+/// Whether any message of a session contains `query`, ignoring case
+fn matches_query(session: &ChatSession, query: &str) -> bool {
+    let query = query.to_lowercase();
+    session
+        .messages
+        .iter()
+        .any(|message| message.content.to_lowercase().contains(&query))
+}
+
+/// Write `sessions` as one document for `--merge`
+fn write_combined(
+    sessions: &[ChatSession],
+    format: MergeFormat,
+    options: &ExportOptions,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<()> {
+    let ids: Vec<_> = sessions.iter().map(|s| &s.session_id).collect();
+    access::record(
+        project_path,
+        "export",
+        &ids,
+        options.out.as_deref().map(Target::File),
+    )?;
+
+    let title = match (&options.title, &options.query) {
+        (Some(title), _) => title.clone(),
+        (None, Some(query)) => format!("Sessions mentioning \"{}\"", query),
+        (None, None) => "Sessions".to_string(),
+    };
+    let document = match format {
+        MergeFormat::Markdown => markdown::render_combined(sessions, &title)?,
+        MergeFormat::Html => html::render_combined(sessions, &title, &Labels::from_config()),
+    };
+    match &options.out {
+        Some(path) => std::fs::write(path, document)?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(document.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+
+    let messages = sessions.iter().map(|s| s.messages.len()).sum();
+    output.export_summary(sessions.len(), messages, options.out.as_deref())?;
+    Ok(())
+}
+
+/// Write one page per session and an index page into `dir`
+fn write_site(dir: &Path, sessions: Vec<ChatSession>) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let labels = Labels::from_config();
+
+    let mut entries = Vec::new();
+    for session in &sessions {
+        let filename = html::page_filename(session);
+        std::fs::write(dir.join(&filename), html::render_session(session, &labels))?;
+        entries.push(html::IndexEntry::for_session(filename, session, &labels));
+    }
+    std::fs::write(dir.join(html::INDEX_FILE), html::render_index(&entries))?;
+    Ok(())
+}
+
+/// Parse every session of a provider for this project modified within
+/// `range`, leaving out excluded sessions and redacting secrets as the
+/// markdown export does
+pub(crate) async fn load_sessions(
+    provider: &std::sync::Arc<dyn providers::base::Provider>,
+    project_path: &Path,
+    range: &TimeRange,
+) -> Result<Vec<ChatSession>> {
+    let filter = SessionFilter::from_config();
+    let redactor = Redactor::from_config();
+
+    let mut sessions = Vec::new();
+    for path in provider.get_sessions_in_range(project_path, range).await? {
+        let mut session = match provider.parse_session(&path).await {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::debug!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if session.messages.is_empty()
+            || !range.contains(&session)
+            || filter.reason(&session).is_some()
+        {
+            continue;
+        }
+        session.messages = redactor.redact_messages(&session.messages).into_owned();
+        sessions.push(session);
+    }
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{ChatMessage, MessageMetadata, MessageRole};
+    use chrono::Utc;
+
+    #[test]
+    fn test_matches_query_ignores_case() {
+        let session = ChatSession {
+            session_id: "s".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            messages: vec![ChatMessage {
+                id: "m".to_string(),
+                timestamp: Utc::now(),
+                role: MessageRole::User,
+                content: "Why does cargo build fail on main?".to_string(),
+                metadata: MessageMetadata::default(),
+            }],
+        };
+        assert!(matches_query(&session, "CARGO BUILD"));
+        assert!(!matches_query(&session, "README"));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::providers;
+use crate::providers::base::{ChatMessage, FileAccessKind};
+use crate::session::SessionTracker;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How often a file was read and edited during a session
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileCounts {
+    pub reads: usize,
+    pub edits: usize,
+}
+
+pub async fn handle_files(query: String, project_path: PathBuf, output: &mut Output) -> Result<()> {
+    let state = SessionTracker::load_project_state(&project_path).await?;
+    let target = state
+        .find_session(&query)
+        .ok_or_else(|| WaylogError::SessionNotFound(query.clone()))?;
+
+    // File accesses are not written to markdown, so re-parse the source session
+    let provider = providers::get_provider(&target.provider)?;
+    let session = providers::find_session(&provider, &project_path, &target.session_id)
+        .await?
+        .ok_or_else(|| WaylogError::SourceNotFound(target.session_id.clone()))?;
+
+    let files = collect_file_accesses(&session.messages, &project_path);
+
+    output.files_header(&target.markdown_path, files.len())?;
+    for (path, counts) in &files {
+        output.file_access(path, counts.reads, counts.edits)?;
+    }
+
+    Ok(())
+}
+
+/// Count reads and edits per file, with paths shown relative to the project.
+/// Most-touched files come first.
+pub fn collect_file_accesses(
+    messages: &[ChatMessage],
+    project_path: &Path,
+) -> Vec<(String, FileCounts)> {
+    let mut counts: BTreeMap<String, FileCounts> = BTreeMap::new();
+    for access in messages.iter().flat_map(|m| &m.metadata.file_accesses) {
+        let path = Path::new(&access.path);
+        let display = path
+            .strip_prefix(project_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+        let entry = counts.entry(display).or_default();
+        match access.kind {
+            FileAccessKind::Read => entry.reads += 1,
+            FileAccessKind::Edit => entry.edits += 1,
+        }
+    }
+
+    let mut files: Vec<_> = counts.into_iter().collect();
+    files.sort_by_key(|(_, c)| std::cmp::Reverse(c.reads + c.edits));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{FileAccess, MessageMetadata, MessageRole};
+    use chrono::Utc;
+
+    fn message(accesses: &[(&str, FileAccessKind)]) -> ChatMessage {
+        ChatMessage {
+            id: "1".to_string(),
+            timestamp: Utc::now(),
+            role: MessageRole::Assistant,
+            content: String::new(),
+            metadata: MessageMetadata {
+                file_accesses: accesses
+                    .iter()
+                    .map(|(path, kind)| FileAccess {
+                        path: path.to_string(),
+                        kind: *kind,
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_collect_file_accesses() {
+        let messages = vec![
+            message(&[
+                ("/p/src/main.rs", FileAccessKind::Read),
+                ("/p/README.md", FileAccessKind::Read),
+            ]),
+            message(&[
+                ("/p/src/main.rs", FileAccessKind::Edit),
+                ("/p/src/main.rs", FileAccessKind::Edit),
+                ("/etc/hosts", FileAccessKind::Read),
+            ]),
+        ];
+
+        let files = collect_file_accesses(&messages, Path::new("/p"));
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].0, "src/main.rs");
+        assert_eq!(files[0].1, FileCounts { reads: 1, edits: 2 });
+        assert!(files.iter().any(|(p, _)| p == "/etc/hosts"));
+        assert!(files.iter().any(|(p, _)| p == "README.md"));
+    }
+}
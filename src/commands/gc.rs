@@ -0,0 +1,28 @@
+use crate::error::Result;
+use crate::exporter::attachments;
+use crate::init::{subdirs, WAYLOG_DIR};
+use crate::output::Output;
+use crate::session::trash;
+use crate::utils::path;
+use std::path::PathBuf;
+
+pub async fn handle_gc(dry_run: bool, project_path: PathBuf, output: &mut Output) -> Result<()> {
+    let store_dir = project_path.join(WAYLOG_DIR).join(subdirs::ATTACHMENTS);
+    let history_dir = path::get_waylog_dir(&project_path);
+
+    // Files in the trash keep their attachments so they can be restored
+    let markdown_dirs = [history_dir, trash::trash_dir(&project_path)];
+    let report = attachments::collect_garbage(&store_dir, &markdown_dirs, dry_run)?;
+
+    for blob in &report.removed {
+        output.gc_removed(blob, dry_run)?;
+    }
+    output.gc_summary(
+        report.removed.len(),
+        report.freed_bytes,
+        report.kept,
+        dry_run,
+    )?;
+
+    Ok(())
+}
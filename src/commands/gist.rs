@@ -0,0 +1,170 @@
+use crate::error::{Result, WaylogError};
+use crate::exporter::{anonymize::Anonymizer, redact::Redactor};
+use crate::output::Output;
+use crate::session::access::{self, Target};
+use crate::session::catalog;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// What `waylog gist` does to the session before it leaves the machine, and where it goes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GistOptions {
+    pub anonymize: bool,
+    pub redact: bool,
+    pub public: bool,
+    /// Upload to `[share] paste_url` instead of GitHub
+    pub paste: bool,
+}
+
+pub async fn handle_gist(
+    id: Option<String>,
+    latest: bool,
+    options: GistOptions,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let query = if latest {
+        catalog::list(&project_path)
+            .await?
+            .into_iter()
+            .next()
+            .map(|s| s.session_id)
+            .ok_or_else(|| WaylogError::SessionNotFound("--latest".to_string()))?
+    } else {
+        id.unwrap_or_default()
+    };
+    let (session, content) = catalog::read(&project_path, &query).await?;
+    let content = prepare(&project_path, &content, options);
+
+    let config = &crate::config::get().share;
+    let url = if options.paste {
+        paste(&config.paste_url, &content).await?
+    } else {
+        let name = session
+            .markdown_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("{}.md", session.session_id));
+        let gist = json!({
+            "description": session.title,
+            "public": options.public || config.public,
+            "files": { name: { "content": content } },
+        });
+        create_gist(&config.github_api, &gist).await?
+    };
+
+    access::record(
+        &project_path,
+        "gist",
+        &[&session.session_id],
+        Some(Target::Url(&url)),
+    )?;
+    output.shared(&url)?;
+    Ok(())
+}
+
+/// The session as uploaded: redacted again and anonymized if asked to
+fn prepare(project: &Path, content: &str, options: GistOptions) -> String {
+    let mut content = content.to_string();
+    if options.redact {
+        let mut config = crate::config::get().redaction.clone();
+        config.enabled = true;
+        config.builtin = true;
+        content = Redactor::new(&config).redact(&content).into_owned();
+    }
+    if options.anonymize {
+        content = Anonymizer::new(project).anonymize(&content);
+    }
+    content
+}
+
+/// Create a gist through the GitHub API at `api` and return its page
+async fn create_gist(api: &str, gist: &Value) -> Result<String> {
+    let token = github_token().await.ok_or_else(|| {
+        WaylogError::ShareFailed(
+            "no GitHub token; set GITHUB_TOKEN or log in with `gh auth login`".to_string(),
+        )
+    })?;
+    let response = reqwest::Client::new()
+        .post(format!("{}/gists", api.trim_end_matches('/')))
+        .bearer_auth(token)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .header(reqwest::header::USER_AGENT, "waylog")
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(gist)?)
+        .send()
+        .await
+        .map_err(|e| WaylogError::ShareFailed(e.to_string()))?;
+
+    let status = response.status();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| WaylogError::ShareFailed(e.to_string()))?;
+    if !status.is_success() {
+        // GitHub explains errors in a JSON `message`, but any body will do
+        let message = serde_json::from_slice::<Value>(&body)
+            .ok()
+            .and_then(|body| body.get("message")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+        return Err(WaylogError::ShareFailed(format!(
+            "GitHub answered {} {}",
+            status,
+            message.chars().take(200).collect::<String>()
+        )));
+    }
+    let body: Value = serde_json::from_slice(&body)
+        .map_err(|e| WaylogError::ShareFailed(format!("GitHub answered with bad JSON: {}", e)))?;
+    body.get("html_url")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| WaylogError::ShareFailed("GitHub did not say where the gist is".to_string()))
+}
+
+/// `GITHUB_TOKEN`, `GH_TOKEN`, or the token the GitHub CLI is logged in with
+async fn github_token() -> Option<String> {
+    let from_env = ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|token| !token.trim().is_empty());
+    if from_env.is_some() {
+        return from_env;
+    }
+    let output = tokio::process::Command::new("gh")
+        .args(["auth", "token"])
+        .output()
+        .await
+        .ok()?;
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !token.is_empty()).then_some(token)
+}
+
+/// POST `content` to the paste service at `url` and return the URL it answers with
+async fn paste(url: &str, content: &str) -> Result<String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            "text/markdown; charset=utf-8",
+        )
+        .body(content.to_string())
+        .send()
+        .await
+        .map_err(|e| WaylogError::ShareFailed(e.to_string()))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| WaylogError::ShareFailed(e.to_string()))?;
+    let pasted = body.trim();
+    if !status.is_success() || !pasted.starts_with("http") {
+        return Err(WaylogError::ShareFailed(format!(
+            "{} answered {} {}",
+            url,
+            status,
+            pasted.chars().take(200).collect::<String>()
+        )));
+    }
+    Ok(pasted.to_string())
+}
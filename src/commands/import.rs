@@ -0,0 +1,51 @@
+use crate::error::Result;
+use crate::importer::{self, ImportFormat};
+use crate::output::Output;
+use crate::session::SessionTracker;
+use crate::{exporter, utils};
+use std::path::PathBuf;
+
+pub async fn handle_import(
+    file: PathBuf,
+    format: Option<ImportFormat>,
+    force: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let (format, sessions) = importer::import_file(&file, format, &project_path).await?;
+    output.import_start(&file, format.provider_name(), sessions.len())?;
+
+    let history_dir = utils::path::get_waylog_dir(&project_path);
+    utils::path::ensure_dir_exists(&history_dir)?;
+
+    // Sessions imported earlier are recognised by their session_id frontmatter
+    let state = SessionTracker::load_project_state(&project_path).await?;
+
+    let mut imported = 0;
+    let mut skipped = 0;
+
+    for session in sessions {
+        let markdown_path = match state.get_session(&session.session_id) {
+            Some(_) if !force => {
+                output.import_skipped(&session.session_id)?;
+                skipped += 1;
+                continue;
+            }
+            Some(existing) => existing.markdown_path.clone(),
+            None => exporter::session_path(&history_dir, &session),
+        };
+
+        exporter::create_markdown_file(&markdown_path, &session).await?;
+
+        let filename = markdown_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        output.imported(&filename, session.messages.len())?;
+        imported += 1;
+    }
+
+    output.import_summary(imported, skipped)?;
+
+    Ok(())
+}
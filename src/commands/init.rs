@@ -0,0 +1,457 @@
+use crate::cli::HistoryLayout;
+use crate::config::{Config, GitignorePolicy};
+use crate::error::Result;
+use crate::init::{subdirs, WAYLOG_DIR};
+use crate::output::Output;
+use crate::providers;
+use crate::utils::path;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// What `waylog init` found for one provider
+#[derive(Debug, Clone)]
+pub struct ProviderProbe {
+    pub name: &'static str,
+    pub installed: bool,
+    /// Existing sessions for this project
+    pub sessions: usize,
+}
+
+/// Answers to the setup questions given as flags; the others are asked for,
+/// or left at their defaults when nobody can answer
+#[derive(Debug, Clone, Default)]
+pub struct SetupOptions {
+    pub agent: Option<String>,
+    pub layout: Option<HistoryLayout>,
+    pub gitignore: Option<GitignorePolicy>,
+}
+
+/// What the starter config is written with
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SetupChoices {
+    agent: Option<String>,
+    layout: HistoryLayout,
+    gitignore: GitignorePolicy,
+}
+
+/// Set up `.waylog` with a starter config enabling the providers that are
+/// relevant here. Returns whether the first pull should run.
+pub async fn handle_init(
+    yes: bool,
+    setup: SetupOptions,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<bool> {
+    let interactive = !yes && std::io::stdin().is_terminal();
+    let probes = setup_project(setup, interactive, project_path, output).await?;
+
+    let sessions: usize = probes.iter().map(|p| p.sessions).sum();
+    if sessions == 0 {
+        return Ok(false);
+    }
+    if yes {
+        return Ok(true);
+    }
+    if output.quiet() || output.json() {
+        return Ok(false);
+    }
+    output.init_pull_prompt(sessions)?;
+    Ok(dialoguer::Confirm::new()
+        .default(true)
+        .show_default(true)
+        .interact()
+        .unwrap_or(false))
+}
+
+/// The guided setup of `init`, run when `waylog run` or `pull` starts tracking
+/// a directory that has no config yet. `agent` is the one being run, if any.
+pub async fn handle_first_run(
+    agent: Option<&str>,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<()> {
+    if Config::path(project_path).exists() {
+        return Ok(());
+    }
+    output.welcome()?;
+    let setup = SetupOptions {
+        agent: agent.map(str::to_string),
+        ..SetupOptions::default()
+    };
+    let interactive = std::io::stdin().is_terminal();
+    setup_project(setup, interactive, project_path, output).await?;
+    Ok(())
+}
+
+/// Probe the providers and write the starter config, unless there is one
+async fn setup_project(
+    setup: SetupOptions,
+    interactive: bool,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<Vec<ProviderProbe>> {
+    path::ensure_dir_exists(&path::get_waylog_dir(project_path))?;
+
+    let mut probes = Vec::new();
+    for name in providers::list_providers() {
+        let provider = providers::get_provider(name)?;
+        let installed = provider.is_installed();
+        let sessions = if installed {
+            provider
+                .get_all_sessions(project_path)
+                .await
+                .map(|s| s.len())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let probe = ProviderProbe {
+            name,
+            installed,
+            sessions,
+        };
+        output.init_probe(&probe)?;
+        probes.push(probe);
+    }
+
+    let config_path = Config::path(project_path);
+    if config_path.exists() {
+        output.init_config_kept(&config_path)?;
+        // Running `init` again applies a changed `[git] gitignore`
+        let gitignore = match setup.gitignore {
+            Some(gitignore) => gitignore,
+            None => Config::load(project_path)?.git.gitignore,
+        };
+        apply_gitignore(gitignore, project_path, output)?;
+        return Ok(probes);
+    }
+
+    let enabled = relevant_providers(&probes);
+    let interactive = interactive && !output.quiet() && !output.json();
+    let choices = choose(setup, &enabled, interactive, output)?;
+    std::fs::write(&config_path, starter_config(&enabled, &choices))?;
+    output.init_config_written(&config_path, &enabled)?;
+
+    apply_gitignore(choices.gitignore, project_path, output)?;
+    Ok(probes)
+}
+
+/// Bring the project's .gitignore in line with `policy`, and say so if it changed
+fn apply_gitignore(
+    policy: GitignorePolicy,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<()> {
+    if update_gitignore(project_path, policy)? {
+        output.gitignore_updated(&project_path.join(".gitignore"), &ignored_entries(policy))?;
+    }
+    Ok(())
+}
+
+/// Fill in the choices not given as flags, asking for them if `interactive`
+fn choose(
+    setup: SetupOptions,
+    enabled: &[&str],
+    interactive: bool,
+    output: &mut Output,
+) -> Result<SetupChoices> {
+    let agent = match setup.agent {
+        Some(agent) => Some(agent),
+        None if interactive && !enabled.is_empty() => {
+            let mut items: Vec<_> = enabled.iter().map(|name| name.to_string()).collect();
+            items.push("none (always name one)".to_string());
+            let picked = select(
+                output,
+                "Agent to start with a plain `waylog run`:",
+                &items,
+                0,
+            )?;
+            enabled.get(picked).map(|name| name.to_string())
+        }
+        None => None,
+    };
+
+    const LAYOUTS: [HistoryLayout; 3] = [
+        HistoryLayout::Dated,
+        HistoryLayout::Provider,
+        HistoryLayout::Title,
+    ];
+    let layout = match setup.layout {
+        Some(layout) => layout,
+        None if interactive => {
+            let items = [
+                "by date   2025-01-15_09-30-00Z-claude-fix-the-build.md",
+                "by agent  claude-2025-01-15-fix-the-build.md",
+                "by title  fix-the-build.md",
+            ];
+            LAYOUTS[select(output, "How should history files be named?", &items, 0)?]
+        }
+        None => HistoryLayout::Dated,
+    };
+
+    let gitignore = match setup.gitignore {
+        Some(gitignore) => gitignore,
+        None if interactive => {
+            const POLICIES: [GitignorePolicy; 3] = [
+                GitignorePolicy::Commit,
+                GitignorePolicy::Local,
+                GitignorePolicy::Ignore,
+            ];
+            let items = [
+                "commit all of .waylog with the code",
+                "commit the history, ignore logs and sync state",
+                "keep all of .waylog out of git (.gitignore)",
+            ];
+            POLICIES[select(output, "Should the history be committed?", &items, 0)?]
+        }
+        None => GitignorePolicy::Commit,
+    };
+
+    Ok(SetupChoices {
+        agent,
+        layout,
+        gitignore,
+    })
+}
+
+/// Ask one setup question; a prompt that cannot be shown picks `default`
+fn select<T: ToString>(
+    output: &mut Output,
+    question: &str,
+    items: &[T],
+    default: usize,
+) -> Result<usize> {
+    output.setup_question(question)?;
+    Ok(dialoguer::Select::new()
+        .items(items)
+        .default(default)
+        .interact()
+        .unwrap_or(default))
+}
+
+/// The .gitignore entries `policy` needs
+fn ignored_entries(policy: GitignorePolicy) -> Vec<String> {
+    match policy {
+        GitignorePolicy::Commit => Vec::new(),
+        GitignorePolicy::Ignore => vec![format!("{}/", WAYLOG_DIR)],
+        // state.json and the other sync bookkeeping of this machine
+        GitignorePolicy::Local => vec![
+            format!("{}/{}/", WAYLOG_DIR, subdirs::LOGS),
+            format!("{}/*.json", WAYLOG_DIR),
+            format!("{}/{}", WAYLOG_DIR, crate::session::lock::LOCK_FILE),
+        ],
+    }
+}
+
+/// Add the entries `policy` needs to the project's .gitignore. With
+/// `Local`, an entry ignoring all of `.waylog` is taken out, since the
+/// history could not be committed otherwise. Entries are never removed for
+/// `Commit`. Returns whether the file changed.
+fn update_gitignore(project_path: &Path, policy: GitignorePolicy) -> Result<bool> {
+    let gitignore = project_path.join(".gitignore");
+    let original = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    let ignores_all = |line: &str| {
+        matches!(
+            line.trim(),
+            ".waylog" | ".waylog/" | "/.waylog" | "/.waylog/"
+        )
+    };
+
+    let mut lines: Vec<&str> = original.lines().collect();
+    let before = lines.len();
+    if policy == GitignorePolicy::Local {
+        lines.retain(|line| !ignores_all(line));
+    }
+    let entries = ignored_entries(policy);
+    let covered = |entry: &String| {
+        lines.iter().any(|line| {
+            let line = line.trim();
+            line == entry.as_str()
+                || line.strip_prefix('/') == Some(entry.as_str())
+                || ignores_all(line)
+        })
+    };
+    let missing: Vec<&String> = entries.iter().filter(|entry| !covered(entry)).collect();
+    if missing.is_empty() && lines.len() == before {
+        return Ok(false);
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    std::fs::write(&gitignore, content)?;
+    Ok(true)
+}
+
+/// Providers that already have sessions here, or, if none do, the installed ones
+fn relevant_providers(probes: &[ProviderProbe]) -> Vec<&'static str> {
+    let with_sessions: Vec<_> = probes
+        .iter()
+        .filter(|p| p.sessions > 0)
+        .map(|p| p.name)
+        .collect();
+    if !with_sessions.is_empty() {
+        return with_sessions;
+    }
+    probes
+        .iter()
+        .filter(|p| p.installed)
+        .map(|p| p.name)
+        .collect()
+}
+
+fn starter_config(enabled: &[&str], choices: &SetupChoices) -> String {
+    let mut config = String::from(
+        "# waylog project config, written by `waylog init`.\n\
+         # Every setting is optional; see the README for the full list.\n\n\
+         [providers]\n",
+    );
+    if enabled.is_empty() {
+        config.push_str("# No agents were found; all are synced once installed.\n");
+        config.push_str(&format!(
+            "# enabled = [{}]\n",
+            quoted(&providers::list_providers())
+        ));
+    } else {
+        config.push_str("# Agents found for this project when it was initialized\n");
+        config.push_str(&format!("enabled = [{}]\n", quoted(enabled)));
+    }
+
+    config.push_str("\n[run]\n# Agent started by `waylog run` without one\n");
+    match &choices.agent {
+        Some(agent) => config.push_str(&format!("default_agent = \"{}\"\n", agent)),
+        None => config.push_str("# default_agent = \"claude\"\n"),
+    }
+
+    config.push_str("\n[export]\n# How history files are named\n");
+    config.push_str(&format!(
+        "filename_template = \"{}\"\n",
+        filename_template(choices.layout)
+    ));
+
+    config.push_str(
+        "\n[git]\n# What .gitignore keeps out of git: \"commit\" (nothing), \"local\" (logs\n\
+         # and sync state) or \"ignore\" (all of .waylog); run `waylog init` to apply\n",
+    );
+    config.push_str(&format!(
+        "gitignore = \"{}\"\n",
+        match choices.gitignore {
+            GitignorePolicy::Commit => "commit",
+            GitignorePolicy::Ignore => "ignore",
+            GitignorePolicy::Local => "local",
+        }
+    ));
+    config
+}
+
+/// The `filename_template` of a layout
+fn filename_template(layout: HistoryLayout) -> &'static str {
+    match layout {
+        HistoryLayout::Dated => crate::config::DEFAULT_FILENAME_TEMPLATE,
+        HistoryLayout::Provider => "{provider}-{date}-{slug}",
+        HistoryLayout::Title => "{slug}",
+    }
+}
+
+fn quoted(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|n| format!("\"{}\"", n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(name: &'static str, installed: bool, sessions: usize) -> ProviderProbe {
+        ProviderProbe {
+            name,
+            installed,
+            sessions,
+        }
+    }
+
+    #[test]
+    fn test_relevant_providers() {
+        let probes = [
+            probe("claude", true, 3),
+            probe("gemini", true, 0),
+            probe("codex", false, 0),
+        ];
+        assert_eq!(relevant_providers(&probes), ["claude"]);
+
+        let probes = [probe("claude", true, 0), probe("codex", false, 0)];
+        assert_eq!(relevant_providers(&probes), ["claude"]);
+    }
+
+    #[test]
+    fn test_starter_config_parses() {
+        let defaults = SetupChoices {
+            agent: None,
+            layout: HistoryLayout::Dated,
+            gitignore: GitignorePolicy::Commit,
+        };
+        let config: Config =
+            toml::from_str(&starter_config(&["claude", "codex"], &defaults)).unwrap();
+        assert_eq!(config.providers.default_providers(), ["claude", "codex"]);
+        assert_eq!(config.run.default_agent, None);
+        assert_eq!(
+            config.export.filename_template,
+            crate::config::DEFAULT_FILENAME_TEMPLATE
+        );
+
+        // Without agents, the defaults stay in place
+        let config: Config = toml::from_str(&starter_config(&[], &defaults)).unwrap();
+        assert_eq!(
+            config.providers.default_providers(),
+            providers::list_providers()
+        );
+
+        let choices = SetupChoices {
+            agent: Some("codex".to_string()),
+            layout: HistoryLayout::Provider,
+            ..defaults
+        };
+        let config: Config = toml::from_str(&starter_config(&["codex"], &choices)).unwrap();
+        assert_eq!(config.run.default_agent.as_deref(), Some("codex"));
+        assert_eq!(config.git.gitignore, GitignorePolicy::Commit);
+
+        let choices = SetupChoices {
+            gitignore: GitignorePolicy::Local,
+            ..choices
+        };
+        let config: Config = toml::from_str(&starter_config(&["codex"], &choices)).unwrap();
+        assert_eq!(config.git.gitignore, GitignorePolicy::Local);
+        assert_eq!(config.export.filename_template, "{provider}-{date}-{slug}");
+    }
+
+    #[test]
+    fn test_update_gitignore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let read = || std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target").unwrap();
+        assert!(!update_gitignore(dir.path(), GitignorePolicy::Commit).unwrap());
+        assert!(update_gitignore(dir.path(), GitignorePolicy::Ignore).unwrap());
+        assert!(!update_gitignore(dir.path(), GitignorePolicy::Ignore).unwrap());
+        assert_eq!(read(), "target\n.waylog/\n");
+
+        // Ignoring everything already covers the local files
+        assert!(!update_gitignore(dir.path(), GitignorePolicy::Commit).unwrap());
+        assert_eq!(read(), "target\n.waylog/\n");
+
+        // Local keeps the history committable
+        assert!(update_gitignore(dir.path(), GitignorePolicy::Local).unwrap());
+        assert!(!update_gitignore(dir.path(), GitignorePolicy::Local).unwrap());
+        assert_eq!(
+            read(),
+            "target\n.waylog/logs/\n.waylog/*.json\n.waylog/waylog.lock\n"
+        );
+    }
+}
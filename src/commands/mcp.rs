@@ -0,0 +1,185 @@
+//! `waylog mcp`: a Model Context Protocol server on stdin/stdout, so that
+//! agents can search and read the project's history themselves. Requests
+//! and replies are JSON-RPC 2.0 messages, one per line.
+use crate::error::Result;
+use crate::exporter::markdown::parts;
+use crate::session::{access, catalog};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Protocol version answered when the client asks for one we don't know
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+pub async fn handle_mcp(project_path: PathBuf) -> Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => answer(&project_path, &request).await,
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(reply) = reply {
+            let mut reply = serde_json::to_vec(&reply)?;
+            reply.push(b'\n');
+            stdout.write_all(&reply).await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// The reply to one request, or `None` for a notification
+async fn answer(project: &Path, request: &Value) -> Option<Value> {
+    let id = request.get("id").cloned()?;
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => {
+            let version = params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(PROTOCOL_VERSION);
+            json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "waylog", "version": env!("CARGO_PKG_VERSION") },
+            })
+        }
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let Some(name) = params.get("name").and_then(Value::as_str) else {
+                return Some(error(id, INVALID_PARAMS, "missing tool name"));
+            };
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            match call_tool(project, name, &arguments).await {
+                Ok(Some(text)) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Ok(None) => {
+                    return Some(error(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("unknown tool '{}'", name),
+                    ))
+                }
+                // Tool failures go to the agent, which may try something else
+                Err(e) => json!({
+                    "content": [{ "type": "text", "text": e.to_string() }],
+                    "isError": true,
+                }),
+            }
+        }
+        _ => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("unknown method '{}'", method),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// The tools offered, with the JSON Schemas of their arguments
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search_sessions",
+            "description": "Search the project's recorded agent sessions for words (all must occur, case is ignored). Returns the matching sessions, most recent first, with a snippet around the first word.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Words to look for" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Most sessions to return (default 10)" },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_session",
+            "description": "Fetch the full markdown transcript of one recorded session.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Session id, id prefix, or part of the history file name" },
+                },
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "recent_context",
+            "description": "The last messages of the most recently updated sessions, to catch up on recent work.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "sessions": { "type": "integer", "minimum": 1, "description": "How many sessions (default 3)" },
+                    "messages": { "type": "integer", "minimum": 1, "description": "Messages per session (default 10)" },
+                },
+            },
+        },
+    ])
+}
+
+/// Run tool `name` and return its text result, or `None` if there is no such tool
+async fn call_tool(project: &Path, name: &str, arguments: &Value) -> Result<Option<String>> {
+    let number = |key: &str, default: usize| {
+        arguments
+            .get(key)
+            .and_then(Value::as_u64)
+            .map_or(default, |n| n.max(1) as usize)
+    };
+
+    match name {
+        "search_sessions" => {
+            let query = arguments.get("query").and_then(Value::as_str).unwrap_or("");
+            let mut hits = catalog::search(project, query).await?;
+            hits.truncate(number("limit", 10));
+            Ok(Some(serde_json::to_string_pretty(&hits)?))
+        }
+        "get_session" => {
+            let query = arguments.get("id").and_then(Value::as_str).unwrap_or("");
+            let (session, content) = catalog::read(project, query).await?;
+            access::record(project, "mcp", &[&session.session_id], None)?;
+            Ok(Some(content))
+        }
+        "recent_context" => {
+            let sessions: Vec<_> = catalog::list(project)
+                .await?
+                .into_iter()
+                .take(number("sessions", 3))
+                .collect();
+            let messages = number("messages", 10);
+
+            let mut context = String::new();
+            for session in &sessions {
+                let last = parts::existing_parts(&project.join(&session.markdown_path))
+                    .pop()
+                    .unwrap_or_else(|| project.join(&session.markdown_path));
+                let content = crate::exporter::encryption::read(&last).await?;
+                context.push_str(&format!(
+                    "# {} ({}, {})\n\n",
+                    session.title, session.provider, session.session_id
+                ));
+                context.push_str(&catalog::last_messages(&content, messages));
+            }
+            let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+            access::record(project, "mcp", &ids, None)?;
+            Ok(Some(context))
+        }
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::session::edits::ExportedFiles;
+use crate::session::state::SessionState;
+use crate::session::{merge, trash, SessionTracker};
+use crate::{exporter, providers};
+use std::path::PathBuf;
+
+pub async fn handle_merge(
+    ids: Vec<String>,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let state = SessionTracker::load_project_state(&project_path).await?;
+
+    // 1. Resolve the selected sessions (the first one keeps its id and file)
+    let mut targets: Vec<SessionState> = Vec::new();
+    for id in &ids {
+        let target = state
+            .find_session(id)
+            .ok_or_else(|| WaylogError::SessionNotFound(id.clone()))?;
+        if !targets.iter().any(|t| t.session_id == target.session_id) {
+            targets.push(target.clone());
+        }
+    }
+
+    if targets.len() < 2 {
+        return Err(WaylogError::Internal(
+            "merge needs at least two different sessions".to_string(),
+        ));
+    }
+
+    // 2. Re-parse the source files so messages keep their ids and metadata
+    let mut sessions = Vec::new();
+    for target in &targets {
+        let provider = providers::get_provider(&target.provider)?;
+        let session = providers::find_session(&provider, &project_path, &target.session_id)
+            .await?
+            .ok_or_else(|| WaylogError::SourceNotFound(target.session_id.clone()))?;
+        sessions.push(session);
+    }
+
+    let merged = merge::merge_sessions(&sessions)
+        .ok_or_else(|| WaylogError::Internal("nothing to merge".to_string()))?;
+
+    // 3. Rewrite the primary file, recording per-source counts so later syncs
+    //    append to the merged file instead of recreating the originals
+    let source_counts: Vec<(String, usize)> = sessions
+        .iter()
+        .map(|s| (s.session_id.clone(), s.messages.len()))
+        .collect();
+    let markdown_path = targets[0].markdown_path.clone();
+    exporter::create_markdown_file_with(
+        &markdown_path,
+        &merged,
+        &[(
+            "merged_sessions",
+            exporter::format_merged_sessions(&source_counts),
+        )],
+    )
+    .await?;
+    ExportedFiles::refresh(&project_path, &markdown_path)?;
+
+    for target in &targets[1..] {
+        if target.markdown_path != markdown_path
+            && exporter::encryption::exists(&target.markdown_path)
+        {
+            trash::move_to_trash(&project_path, &target.markdown_path)?;
+            exporter::public::remove(&target.markdown_path).await?;
+        }
+    }
+
+    output.merged(&markdown_path, targets.len(), merged.messages.len())?;
+
+    Ok(())
+}
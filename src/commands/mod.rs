@@ -1,5 +1,61 @@
+pub mod api;
+pub mod batch;
+pub mod completions;
+pub mod diff;
+pub mod doctor;
+pub mod export;
+pub mod files;
+pub mod gc;
+pub mod gist;
+pub mod import;
+pub mod init;
+pub mod mcp;
+pub mod merge;
+pub mod note;
+pub mod open;
 pub mod pull;
+pub mod redact;
+pub mod reexport;
+pub mod related;
+pub mod relink;
+pub mod repair;
 pub mod run;
+pub mod scan_secrets;
+pub mod serve;
+pub mod site;
+pub mod snapshot;
+pub mod tail;
+pub mod trash;
+pub mod usage;
+pub mod validate;
 
+pub use api::handle_dump_schema;
+pub use batch::handle_batch;
+pub use completions::handle_completions;
+pub use diff::handle_diff;
+pub use doctor::handle_doctor;
+pub use export::handle_export;
+pub use files::handle_files;
+pub use gc::handle_gc;
+pub use gist::handle_gist;
+pub use import::handle_import;
+pub use init::{handle_first_run, handle_init};
+pub use mcp::handle_mcp;
+pub use merge::handle_merge;
+pub use note::handle_note;
+pub use open::handle_open;
 pub use pull::handle_pull;
+pub use redact::handle_redact;
+pub use reexport::handle_reexport;
+pub use related::handle_related;
+pub use relink::handle_relink;
+pub use repair::handle_repair;
 pub use run::handle_run;
+pub use scan_secrets::handle_scan_secrets;
+pub use serve::handle_serve;
+pub use site::handle_site;
+pub use snapshot::handle_snapshot;
+pub use tail::handle_tail;
+pub use trash::handle_trash;
+pub use usage::handle_usage;
+pub use validate::handle_validate;
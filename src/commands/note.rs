@@ -0,0 +1,16 @@
+use crate::error::{Result, WaylogError};
+use crate::ipc::{self, Reply, Request};
+use crate::output::Output;
+use std::path::PathBuf;
+
+/// Hand `text` to the `waylog run` active in the project, which adds it to
+/// the history file of the agent's session
+pub async fn handle_note(text: String, project_path: PathBuf, output: &mut Output) -> Result<()> {
+    match ipc::send(&project_path, &Request::Note { text }).await? {
+        Reply::Noted { markdown_path } => {
+            output.note_added(&markdown_path)?;
+            Ok(())
+        }
+        Reply::Failed { error } => Err(WaylogError::NoteFailed(error)),
+    }
+}
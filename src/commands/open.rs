@@ -0,0 +1,203 @@
+use crate::error::{Result, WaylogError};
+use crate::exporter::encryption;
+use crate::output::Output;
+use crate::session::access::{self, Target};
+use crate::session::SessionTracker;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub async fn handle_open(
+    id: Option<String>,
+    latest: bool,
+    gui: bool,
+    reveal: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let state = SessionTracker::load_project_state(&project_path).await?;
+
+    let session = if latest {
+        state
+            .sessions
+            .values()
+            .max_by_key(|s| {
+                std::fs::metadata(encryption::stored_path(&s.markdown_path))
+                    .and_then(|m| m.modified())
+                    .ok()
+            })
+            .ok_or_else(|| WaylogError::SessionNotFound("--latest".to_string()))?
+    } else {
+        let query = id.unwrap_or_default();
+        match state.find_session(&query) {
+            Some(s) => s,
+            None => return Err(WaylogError::SessionNotFound(query)),
+        }
+    };
+    let markdown_path = session.markdown_path.clone();
+    access::record(
+        &project_path,
+        "open",
+        &[&session.session_id],
+        Some(Target::File(&markdown_path)),
+    )?;
+
+    let stored = encryption::stored_path(&markdown_path);
+    if reveal {
+        output.revealing(&stored)?;
+        return reveal_in_file_manager(&stored);
+    }
+
+    if stored != markdown_path {
+        return open_encrypted(&markdown_path, gui, output).await;
+    }
+
+    output.opening(&markdown_path)?;
+
+    if gui {
+        open_with_default_app(&markdown_path)
+    } else {
+        open_in_editor(&markdown_path)
+    }
+}
+
+/// Open an encrypted history file: a terminal editor gets a decrypted copy
+/// only it can read, and changes are encrypted back. Other applications
+/// would keep the plaintext around, so without an editor (or with --gui)
+/// the session is printed instead.
+async fn open_encrypted(markdown_path: &Path, gui: bool, output: &mut Output) -> Result<()> {
+    let content = encryption::read(markdown_path).await?;
+    let editor = editor().filter(|_| !gui);
+    let Some(editor) = editor else {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        return Ok(stdout.flush()?);
+    };
+
+    output.opening(markdown_path)?;
+    let mut copy = tempfile::Builder::new()
+        .prefix("waylog-")
+        .suffix(".md")
+        .tempfile()?;
+    copy.write_all(content.as_bytes())?;
+    copy.flush()?;
+    run_editor(&editor, copy.path())?;
+
+    let edited = std::fs::read_to_string(copy.path())?;
+    if edited != content {
+        encryption::write(markdown_path, edited).await?;
+    }
+    Ok(())
+}
+
+/// The user's terminal editor ($VISUAL, then $EDITOR)
+fn editor() -> Option<String> {
+    std::env::var("VISUAL")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|v| !v.trim().is_empty())
+}
+
+/// Open a file in the user's terminal editor ($VISUAL, then $EDITOR)
+fn open_in_editor(path: &Path) -> Result<()> {
+    let Some(editor) = editor() else {
+        tracing::debug!("Neither $VISUAL nor $EDITOR is set, using the OS default opener");
+        return open_with_default_app(path);
+    };
+    run_editor(&editor, path)
+}
+
+/// Run `editor` on `path` and wait for it to exit
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    // Editors are often configured with arguments, e.g. `code --wait`, or
+    // with a quoted path containing spaces
+    let words = crate::utils::string::split_command(editor);
+    let Some((program, args)) = words.split_first() else {
+        return open_with_default_app(path);
+    };
+    let status = Command::new(program).args(args).arg(path).status()?;
+
+    if !status.success() {
+        return Err(WaylogError::ChildProcessFailed(status.code().unwrap_or(1)));
+    }
+    Ok(())
+}
+
+/// Open a file with the platform's default application
+fn open_with_default_app(path: &Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut c = Command::new("open");
+        c.arg(path);
+        c
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]).arg(path);
+        c
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        let mut c = Command::new("xdg-open");
+        c.arg(path);
+        c
+    };
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(WaylogError::ChildProcessFailed(status.code().unwrap_or(1)));
+    }
+    Ok(())
+}
+
+/// Show a file selected in the platform's file manager
+fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let status = Command::new("open").arg("-R").arg(path).status()?;
+        if !status.success() {
+            return Err(WaylogError::ChildProcessFailed(status.code().unwrap_or(1)));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // explorer.exe reports a non-zero exit code even when it succeeds
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path);
+        Command::new("explorer").arg(select).status()?;
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        // Most Linux file managers implement the freedesktop FileManager1 interface,
+        // which can highlight a file; otherwise just open the containing directory
+        let uri = format!("file://{}", path.display());
+        let revealed = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", uri))
+            .arg("string:")
+            .status()
+            .is_ok_and(|s| s.success());
+
+        if revealed {
+            return Ok(());
+        }
+
+        tracing::debug!("FileManager1 unavailable, opening the containing directory");
+        let parent = path.parent().unwrap_or(path);
+        open_with_default_app(parent)
+    }
+}
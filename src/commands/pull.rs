@@ -1,18 +1,252 @@
 use crate::error::{Result, WaylogError};
-use crate::output::Output;
-use crate::synchronizer::SyncStatus;
-use crate::{providers, session, synchronizer};
-use std::path::PathBuf;
+use crate::exporter::anonymize::Anonymizer;
+use crate::init::WAYLOG_DIR;
+use crate::notify::{self, Trigger};
+use crate::output::{ColorMode, Output};
+use crate::providers::base::TimeRange;
+use crate::session::report::SyncReport;
+use crate::synchronizer::{SessionTimings, SyncStatus};
+use crate::{git, providers, session, synchronizer};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
+/// Options for `waylog pull`
+#[derive(Debug, Clone, Default)]
+pub struct PullOptions {
+    /// Only sync this provider
+    pub provider: Option<String>,
+    /// Re-sync sessions that are up to date
+    pub force: bool,
+    /// Strip paths, names and emails from what is written
+    pub anonymize: bool,
+    /// Only sync this session: a provider session file, or a synced session's id
+    pub session: Option<String>,
+    /// Rewrite that session's history file even if it was edited by hand
+    pub rebuild: bool,
+    /// Report what would be written without writing anything
+    pub dry_run: bool,
+    /// Only sessions modified within this range
+    pub range: TimeRange,
+    /// Wait for a locked project instead of failing it
+    pub wait: bool,
+    /// Write the outcome of every session to `.waylog/last-sync.json`
+    pub report: bool,
+    /// Report how long each phase took, per provider and per session
+    pub timings: bool,
+}
+
+/// What `pull` did with one provider's sessions, a row of its summary table
+#[derive(Debug, Clone, Default)]
+pub struct ProviderCounts {
+    pub provider: String,
+    pub found: usize,
+    /// Synced, or with `--dry-run` would be
+    pub synced: usize,
+    pub up_to_date: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub new_messages: usize,
+}
+
+impl ProviderCounts {
+    /// The sums of `rows`, as a row named `Total`
+    pub fn total(rows: &[ProviderCounts]) -> Self {
+        rows.iter().fold(
+            Self {
+                provider: "Total".to_string(),
+                ..Self::default()
+            },
+            |total, row| Self {
+                found: total.found + row.found,
+                synced: total.synced + row.synced,
+                up_to_date: total.up_to_date + row.up_to_date,
+                skipped: total.skipped + row.skipped,
+                failed: total.failed + row.failed,
+                new_messages: total.new_messages + row.new_messages,
+                ..total
+            },
+        )
+    }
+}
+
+/// Where the time of a provider's pull went (`--timings`)
+#[derive(Debug, Clone, Default)]
+pub struct ProviderTimings {
+    pub provider: String,
+    /// Listing the provider's session files
+    pub scan: Duration,
+    /// The parse and export phases of all its sessions
+    pub parse: Duration,
+    pub export: Duration,
+    /// Saving what was synced and where parsing stopped
+    pub save: Duration,
+    pub sessions: Vec<(PathBuf, SessionTimings)>,
+}
+
+impl ProviderTimings {
+    fn add(&mut self, path: &Path, timings: SessionTimings) {
+        self.parse += timings.parse;
+        self.export += timings.export;
+        self.sessions.push((path.to_path_buf(), timings));
+    }
+}
+
+/// `waylog pull --all-projects`: pull into every initialized project the
+/// providers have sessions for. Each project is pulled by a `waylog pull`
+/// running in it, so its own `.waylog/config.toml` (redaction, exclusions,
+/// layout) applies.
+pub async fn handle_pull_all_projects(
+    options: PullOptions,
+    verbose: u8,
+    output: &mut Output,
+) -> Result<()> {
+    let providers = match &options.provider {
+        Some(name) => vec![providers::get_provider(name)?],
+        None => providers::enabled_providers(),
+    };
+    let providers: Vec<_> = providers.into_iter().filter(|p| p.is_installed()).collect();
+    let (projects, unmapped) = providers::discover_projects(&providers).await?;
+
+    let args = project_pull_args(&options, verbose, output);
+    let (mut pulled, mut failed, mut uninitialized) = (0, 0, 0);
+    for (project, sessions) in &projects {
+        if !project.join(WAYLOG_DIR).is_dir() {
+            if project.is_dir() {
+                output.project_not_initialized(project, *sessions, verbose > 0)?;
+                uninitialized += 1;
+            }
+            continue;
+        }
+
+        output.project_header(project, *sessions)?;
+        let status = tokio::process::Command::new(std::env::current_exe()?)
+            .args(&args)
+            .current_dir(project)
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => pulled += 1,
+            Ok(status) => {
+                output.failed(
+                    &project.display().to_string(),
+                    &format!("pull exited with {}", status),
+                )?;
+                failed += 1;
+            }
+            Err(e) => {
+                output.failed(&project.display().to_string(), &e.to_string())?;
+                failed += 1;
+            }
+        }
+    }
+
+    output.all_projects_summary(pulled, failed, uninitialized, unmapped)?;
+    if failed > 0 {
+        return Err(WaylogError::ProjectsFailed(failed, pulled + failed));
+    }
+    Ok(())
+}
+
+/// Arguments of the `waylog pull` run in each project by `--all-projects`
+fn project_pull_args(options: &PullOptions, verbose: u8, output: &Output) -> Vec<String> {
+    let mut args = vec!["pull".to_string()];
+    if let Some(provider) = &options.provider {
+        args.extend(["--provider".to_string(), provider.clone()]);
+    }
+    for (flag, set) in [
+        ("--force", options.force),
+        ("--anonymize", options.anonymize),
+        ("--dry-run", options.dry_run),
+        ("--quiet", output.quiet()),
+        ("--wait", options.wait),
+        ("--report", options.report),
+        ("--timings", options.timings),
+    ] {
+        if set {
+            args.push(flag.to_string());
+        }
+    }
+    for (flag, bound) in [
+        ("--since", options.range.since),
+        ("--until", options.range.until),
+    ] {
+        if let Some(bound) = bound {
+            args.extend([flag.to_string(), bound.to_rfc3339()]);
+        }
+    }
+    if verbose > 0 {
+        args.push(format!("-{}", "v".repeat(verbose.into())));
+    }
+    if output.json() {
+        args.extend(["--output".to_string(), "json".to_string()]);
+    }
+    match output.color() {
+        ColorMode::Auto => {}
+        ColorMode::Always => args.extend(["--color".to_string(), "always".to_string()]),
+        ColorMode::Never => args.extend(["--color".to_string(), "never".to_string()]),
+    }
+    args
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || a.canonicalize()
+            .ok()
+            .is_some_and(|a| b.canonicalize().ok() == Some(a))
+}
+
+/// The provider and source file of the session `--session` names: a session
+/// file, or a synced session found by its id or history file name
+async fn resolve_session(query: &str, project_path: &Path) -> Result<(String, PathBuf)> {
+    let file = Path::new(query);
+    if file.is_file() {
+        let provider = providers::provider_for_file(file)?;
+        return Ok((provider.name().to_string(), file.to_path_buf()));
+    }
+
+    let state = session::SessionTracker::load_project_state(project_path).await?;
+    let synced = state
+        .find_session(query)
+        .ok_or_else(|| WaylogError::SessionNotFound(query.to_string()))?;
+    if synced.file_path.is_file() {
+        return Ok((synced.provider.clone(), synced.file_path.clone()));
+    }
+
+    // Synced before its source was recorded: look among the project's sessions
+    let provider = providers::get_provider(&synced.provider)?;
+    for file in provider.get_project_sessions(project_path).await? {
+        match provider.parse_session(&file).await {
+            Ok(parsed) if parsed.session_id == synced.session_id => {
+                return Ok((synced.provider.clone(), file));
+            }
+            Ok(_) => {}
+            Err(e) => debug!("Skipping {}: {}", file.display(), e),
+        }
+    }
+    Err(WaylogError::SourceNotFound(synced.session_id.clone()))
+}
+
 pub async fn handle_pull(
-    provider_name: Option<String>,
-    force: bool,
-    verbose: bool,
+    options: PullOptions,
+    verbose: u8,
     project_path: PathBuf,
     output: &mut Output,
 ) -> Result<()> {
+    let PullOptions {
+        provider: provider_name,
+        force,
+        anonymize,
+        session,
+        rebuild,
+        dry_run,
+        range,
+        report: write_report,
+        timings,
+        ..
+    } = options;
+
     // 1. Validate provider first (before any other operations)
     // This ensures we catch invalid providers even if project is not initialized
     if let Some(ref name) = provider_name {
@@ -26,22 +260,37 @@ pub async fn handle_pull(
         }
     }
 
+    // `--output json` prints one document with the whole outcome instead of
+    // events (`waylog init` pulls through here too)
+    output.hold_json_events();
     output.pull_start(&project_path)?;
 
+    let session = match session {
+        Some(query) => Some(resolve_session(&query, &project_path).await?),
+        None => None,
+    };
+
     // Filter providers
+    // Providers that were not asked for are left out when they have nothing here
+    let explicit = provider_name.is_some() || session.is_some();
     let providers_to_sync = if let Some(name) = provider_name {
         vec![providers::get_provider(&name)?]
+    } else if let Some((provider, _)) = &session {
+        vec![providers::get_provider(provider)?]
     } else {
-        // Sync all known providers
-        vec![
-            providers::get_provider("claude")?,
-            providers::get_provider("gemini")?,
-            providers::get_provider("codex")?,
-        ]
+        // Sync the providers enabled in config (all known providers by default)
+        providers::enabled_providers()
     };
 
-    let mut total_synced = 0;
-    let mut total_uptodate = 0;
+    let mut report = (write_report || output.json()).then(|| SyncReport::new(dry_run));
+    // One row per provider of the summary table
+    let mut table = Vec::new();
+    // Where the time went, per provider (`--timings`)
+    let mut all_timings = Vec::new();
+    // History files written, for `[git] auto_commit` and `[notify]`
+    let mut written = Vec::new();
+    // Files a dry run would create and append to, and the messages they would get
+    let (mut would_create, mut would_append) = (0, 0);
 
     for provider in providers_to_sync {
         if !provider.is_installed() {
@@ -52,64 +301,243 @@ pub async fn handle_pull(
         // Create session tracker and synchronizer
         let tracker =
             Arc::new(session::SessionTracker::new(project_path.clone(), provider.clone()).await?);
-        let synchronizer = synchronizer::Synchronizer::new(
+        let mut synchronizer = synchronizer::Synchronizer::new(
             provider.clone(),
             project_path.clone(),
             tracker.clone(),
         );
+        if anonymize {
+            synchronizer = synchronizer.with_anonymizer(Anonymizer::new(&project_path));
+        }
+        if dry_run {
+            synchronizer = synchronizer.with_dry_run();
+        }
+        if !range.is_unbounded() {
+            synchronizer = synchronizer.with_time_range(range);
+        }
+
+        // One bar per provider: a spinner while it scans for sessions, then a
+        // bar over them labelled with the phase of the session being synced
+        let progress = session
+            .is_none()
+            .then(|| output.create_spinner(&Output::pull_progress_label(provider.name(), None)))
+            .flatten();
+        if let Some(bar) = &progress {
+            let bar = bar.clone();
+            let name = provider.name().to_string();
+            synchronizer = synchronizer.with_phase_listener(Arc::new(move |phase| {
+                bar.set_prefix(Output::pull_progress_label(&name, Some(phase)));
+            }));
+        }
+
+        let scanning = Instant::now();
+        let files = match &session {
+            Some((_, file)) => {
+                if rebuild {
+                    // Taken as written by waylog, so it is replaced rather
+                    // than kept as a hand edit
+                    let state = tracker.get_state().await;
+                    if let Some(synced) = state
+                        .sessions
+                        .values()
+                        .find(|s| same_file(&s.file_path, file))
+                    {
+                        tracker.record_file(&synced.markdown_path).await;
+                    }
+                }
+                Ok(vec![file.clone()])
+            }
+            None => synchronizer.sessions().await,
+        };
+        let mut provider_timings = ProviderTimings {
+            provider: provider.name().to_string(),
+            scan: scanning.elapsed(),
+            ..ProviderTimings::default()
+        };
+
+        let mut listed = false;
+        let results = match files {
+            Ok(files) => {
+                if let Some(bar) = &progress {
+                    Output::spinner_to_bar(bar, files.len() as u64);
+                }
+                let results = synchronizer
+                    .sync_files(files, force, |file, index, _| {
+                        if let Some(bar) = &progress {
+                            bar.set_position(index as u64);
+                            bar.set_message(
+                                file.file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                                    .to_string(),
+                            );
+                        }
+                    })
+                    .await;
+                if let Some(bar) = progress {
+                    bar.finish_and_clear();
+                }
+                Ok(results)
+            }
+            Err(e) => {
+                if let Some(bar) = progress {
+                    bar.finish_and_clear();
+                }
+                Err(e)
+            }
+        };
 
-        match synchronizer.sync_all(force).await {
+        match results {
+            Ok(results) if results.is_empty() && !explicit => {
+                debug!(
+                    "Skipping {} (no sessions for this project)",
+                    provider.name()
+                );
+            }
             Ok(results) => {
-                // Print section header
-                output.provider_header(provider.name(), results.len())?;
+                // Per-file lines are for -v; the table at the end sums them up
+                if verbose > 0 {
+                    output.provider_header(provider.name(), results.len())?;
+                }
 
-                let mut provider_uptodate = 0;
-                let mut provider_synced = 0;
-                let mut provider_skipped = 0;
-                let mut _provider_failed = 0;
+                let mut counts = ProviderCounts {
+                    provider: provider.name().to_string(),
+                    found: results.len(),
+                    ..ProviderCounts::default()
+                };
+                let mut synced_files = Vec::new();
 
-                for (path, status) in results {
-                    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+                for (path, status, took) in results {
+                    if let Some(report) = &mut report {
+                        report.add(provider.name(), &path, &status, took.total);
+                    }
+                    provider_timings.add(&path, took);
+                    let filename = if verbose > 2 {
+                        path.to_string_lossy()
+                    } else {
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    };
                     match status {
-                        SyncStatus::Synced { new_messages } => {
-                            output.synced(&filename, new_messages, verbose)?;
-                            provider_synced += 1;
+                        SyncStatus::Synced {
+                            new_messages,
+                            warnings,
+                        } => {
+                            output.synced(&filename, new_messages, verbose > 0)?;
+                            if warnings > 0 {
+                                output.malformed_lines(&filename, warnings)?;
+                            }
+                            counts.synced += 1;
+                            counts.new_messages += new_messages;
+                            synced_files.push(path.clone());
+                        }
+                        SyncStatus::WouldSync {
+                            markdown_path,
+                            new_messages,
+                            create,
+                        } => {
+                            output.would_sync(&filename, &markdown_path, new_messages, create)?;
+                            if create {
+                                would_create += 1;
+                            } else {
+                                would_append += 1;
+                            }
+                            counts.synced += 1;
+                            counts.new_messages += new_messages;
                         }
                         SyncStatus::UpToDate => {
-                            output.up_to_date(&filename, verbose)?;
-                            provider_uptodate += 1;
+                            output.up_to_date(&filename, verbose > 1)?;
+                            counts.up_to_date += 1;
                         }
                         SyncStatus::Failed(e) => {
                             output.failed(&filename, &e.to_string())?;
-                            _provider_failed += 1;
+                            counts.failed += 1;
                         }
-                        SyncStatus::Skipped => {
-                            output.skipped(&filename, verbose)?;
-                            provider_skipped += 1;
+                        SyncStatus::Skipped(reason) => {
+                            output.skipped(&filename, &reason, verbose > 0)?;
+                            counts.skipped += 1;
                         }
                     }
                 }
 
-                if !verbose {
-                    output.summary_compact(provider_synced, provider_uptodate)?;
-                }
-                if verbose && provider_skipped > 0 {
-                    output.skipped(&format!("{} sessions", provider_skipped), verbose)?;
+                let state = tracker.get_state().await;
+                if let Some(report) = &mut report {
+                    report.link_history(provider.name(), |file| {
+                        state
+                            .sessions
+                            .values()
+                            .find(|s| s.file_path == file)
+                            .map(|s| s.markdown_path.clone())
+                    });
                 }
-
-                total_synced += provider_synced;
-                total_uptodate += provider_uptodate;
+                written.extend(
+                    state
+                        .sessions
+                        .values()
+                        .filter(|s| synced_files.contains(&s.file_path))
+                        .map(|s| s.markdown_path.clone()),
+                );
+                table.push(counts);
+                listed = true;
             }
             Err(e) => {
                 tracing::error!("Failed to scan {}: {}", provider.name(), e);
+                if let Some(report) = &mut report {
+                    report.provider_failed(provider.name(), e.to_string());
+                }
             }
         }
 
         // Save state after each provider
-        tracker.save_state().await?;
+        let saving = Instant::now();
+        synchronizer.save_progress().await;
+        if !dry_run {
+            tracker.save_state().await?;
+        }
+        provider_timings.save = saving.elapsed();
+        if timings && listed {
+            all_timings.push(provider_timings);
+        }
     }
 
-    output.summary(total_synced, total_uptodate)?;
+    if let Some(report) = &mut report {
+        report.finish();
+        if write_report {
+            report.save(&project_path).await?;
+        }
+    }
 
+    let totals = ProviderCounts::total(&table);
+    output.pull_table(&table, &totals, dry_run)?;
+    if timings {
+        output.pull_timings(&all_timings)?;
+    }
+    if dry_run {
+        output.dry_run_summary(
+            would_create,
+            would_append,
+            totals.new_messages,
+            totals.up_to_date,
+        )?;
+    }
+
+    if !dry_run && crate::config::get().git.auto_commit {
+        match git::commit_history(&project_path, &written).await {
+            Ok(outcome) => output.history_commit(&outcome)?,
+            Err(e) => output.history_commit_failed(&e)?,
+        }
+    }
+
+    if !dry_run && !written.is_empty() {
+        if let Err(e) = notify::sessions_written(&project_path, Trigger::Pull, &written).await {
+            output.webhook_failed(&e)?;
+        }
+    }
+
+    if let Some(report) = &report {
+        output.pull_document(&report.document(&project_path))?;
+    }
+    if totals.failed > 0 {
+        return Err(WaylogError::SyncFailed(totals.failed, totals.found));
+    }
     Ok(())
 }
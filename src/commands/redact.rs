@@ -0,0 +1,59 @@
+use crate::error::Result;
+use crate::exporter::encryption;
+use crate::exporter::redact::Redactor;
+use crate::output::Output;
+use crate::utils::path;
+use std::path::PathBuf;
+
+pub async fn handle_redact(
+    dry_run: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let history_dir = path::get_waylog_dir(&project_path);
+    let redactor = Redactor::from_config();
+
+    let mut files = Vec::new();
+    if history_dir.exists() {
+        let mut entries = tokio::fs::read_dir(&history_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            files.extend(encryption::history_path(&entry.path()));
+        }
+    }
+    files.sort();
+
+    let mut redacted = 0;
+    for file in &files {
+        let content = encryption::read(file).await?;
+        let (frontmatter, body) = split_frontmatter(&content);
+
+        let scrubbed = redactor.redact(body);
+        if scrubbed == body {
+            continue;
+        }
+
+        if !dry_run {
+            encryption::write(file, format!("{}{}", frontmatter, scrubbed)).await?;
+            crate::exporter::mirror(file).await?;
+            crate::session::edits::ExportedFiles::refresh(&project_path, file)?;
+        }
+        let filename = file.file_name().unwrap_or_default().to_string_lossy();
+        output.redacted(&filename, dry_run)?;
+        redacted += 1;
+    }
+
+    output.redact_summary(redacted, files.len(), dry_run)?;
+
+    Ok(())
+}
+
+/// A history file's frontmatter and the rest. Only the rest is redacted;
+/// session ids and paths are not secrets.
+pub fn split_frontmatter(content: &str) -> (&str, &str) {
+    let body_start = content
+        .strip_prefix("---")
+        .and_then(|rest| rest.find("\n---"))
+        .map(|end| end + 3 + 4)
+        .unwrap_or(0);
+    content.split_at(body_start.min(content.len()))
+}
@@ -0,0 +1,250 @@
+use crate::error::Result;
+use crate::exporter::encryption;
+use crate::exporter::frontmatter::{parse_frontmatter, Frontmatter};
+use crate::exporter::html::{self, IndexEntry};
+use crate::exporter::markdown::labels::Labels;
+use crate::output::Output;
+use crate::providers;
+use crate::providers::base::{ChatSession, TimeRange};
+use crate::session::access::{self, Target};
+use crate::session::merge;
+use crate::utils::path;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub use crate::cli::ReexportFormat;
+
+/// Options for `waylog reexport`
+#[derive(Debug, Clone)]
+pub struct ReexportOptions {
+    pub format: ReexportFormat,
+    pub out: PathBuf,
+    /// Only regenerate files updated at or after this time
+    pub changed_since: Option<DateTime<Utc>>,
+}
+
+/// A markdown history file and the derived page it maps to
+struct HistoryFile {
+    markdown: PathBuf,
+    frontmatter: Frontmatter,
+    page: String,
+}
+
+pub async fn handle_reexport(
+    options: ReexportOptions,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let ReexportFormat::Html = options.format;
+    std::fs::create_dir_all(&options.out)?;
+
+    let history = read_history(&project_path).await?;
+    let (changed, unchanged): (Vec<_>, Vec<_>) = history
+        .iter()
+        .partition(|file| needs_page(file, &options.out, options.changed_since));
+
+    // Sources are only parsed when something needs regenerating
+    let sessions = if changed.is_empty() {
+        HashMap::new()
+    } else {
+        load_sessions(&project_path).await?
+    };
+
+    let read: Vec<_> = changed
+        .iter()
+        .filter_map(|file| file.frontmatter.session_id.as_deref())
+        .filter(|id| sessions.contains_key(*id))
+        .collect();
+    access::record(
+        &project_path,
+        "reexport",
+        &read,
+        Some(Target::File(&options.out)),
+    )?;
+
+    let labels = Labels::from_config();
+    let mut entries = Vec::new();
+    let mut regenerated = 0;
+    for file in changed {
+        match source_session(file, &sessions) {
+            Some(session) => {
+                let page = options.out.join(&file.page);
+                std::fs::write(&page, html::render_session(&session, &labels))?;
+                output.reexported(&page)?;
+                entries.push(IndexEntry::for_session(
+                    file.page.clone(),
+                    &session,
+                    &labels,
+                ));
+                regenerated += 1;
+            }
+            None => {
+                output.warn(format!(
+                    "Source of {} is no longer available; page not regenerated",
+                    file.markdown.display()
+                ))?;
+                if let Some(entry) = index_entry(file, &options.out).await {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+
+    for file in &unchanged {
+        if let Some(entry) = index_entry(file, &options.out).await {
+            entries.push(entry);
+        }
+    }
+    std::fs::write(
+        options.out.join(html::INDEX_FILE),
+        html::render_index(&entries),
+    )?;
+
+    output.reexport_summary(regenerated, unchanged.len(), &options.out)?;
+    Ok(())
+}
+
+/// Markdown files of the history that belong to a session, in name order
+async fn read_history(project_path: &Path) -> Result<Vec<HistoryFile>> {
+    let history_dir = path::get_waylog_dir(project_path);
+    let mut files = Vec::new();
+    if !history_dir.exists() {
+        return Ok(files);
+    }
+
+    let mut entries = tokio::fs::read_dir(&history_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(markdown) = encryption::history_path(&entry.path()) else {
+            continue;
+        };
+        let frontmatter = parse_frontmatter(&markdown).await?;
+        if frontmatter.session_id.is_none() {
+            continue;
+        }
+        let stem = markdown
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        files.push(HistoryFile {
+            markdown,
+            frontmatter,
+            page: format!("{}.html", stem),
+        });
+    }
+    files.sort_by(|a, b| a.markdown.cmp(&b.markdown));
+    Ok(files)
+}
+
+/// Whether the page of `file` in `out` is written: pages that were never
+/// generated always are, the others if the session changed since `since`
+fn needs_page(file: &HistoryFile, out: &Path, since: Option<DateTime<Utc>>) -> bool {
+    !out.join(&file.page).exists() || since.is_none_or(|since| updated_at(file) >= since)
+}
+
+/// When a history file last changed: its `updated_at`, or the file's mtime
+/// for files written before the field was kept current
+fn updated_at(file: &HistoryFile) -> DateTime<Utc> {
+    file.frontmatter.updated_at.unwrap_or_else(|| {
+        std::fs::metadata(encryption::stored_path(&file.markdown))
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now())
+    })
+}
+
+/// Parse the sources of every configured provider, by session id
+async fn load_sessions(project_path: &Path) -> Result<HashMap<String, ChatSession>> {
+    let mut sessions = HashMap::new();
+    for name in crate::config::get().providers.default_providers() {
+        let provider = providers::get_provider(name)?;
+        if !provider.is_installed() {
+            continue;
+        }
+        for session in
+            super::export::load_sessions(&provider, project_path, &TimeRange::default()).await?
+        {
+            sessions.insert(session.session_id.clone(), session);
+        }
+    }
+    Ok(sessions)
+}
+
+/// The session a history file was written from, combining the sources of a merged file
+fn source_session(
+    file: &HistoryFile,
+    sessions: &HashMap<String, ChatSession>,
+) -> Option<ChatSession> {
+    if file.frontmatter.merged_sessions.is_empty() {
+        return sessions
+            .get(file.frontmatter.session_id.as_deref()?)
+            .cloned();
+    }
+
+    let sources: Option<Vec<ChatSession>> = file
+        .frontmatter
+        .merged_sessions
+        .iter()
+        .map(|(id, _)| sessions.get(id).cloned())
+        .collect();
+    merge::merge_sessions(&sources?)
+}
+
+/// Index row for a page that was not regenerated, from the markdown file
+async fn index_entry(file: &HistoryFile, out: &Path) -> Option<IndexEntry> {
+    if !out.join(&file.page).exists() {
+        return None;
+    }
+    let content = encryption::read(&file.markdown).await.ok()?;
+    let title = content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .unwrap_or_default()
+        .to_string();
+
+    Some(IndexEntry {
+        filename: file.page.clone(),
+        title,
+        provider: file.frontmatter.provider.clone().unwrap_or_default(),
+        started_at: file
+            .frontmatter
+            .started_at
+            .unwrap_or_else(|| updated_at(file)),
+        messages: file.frontmatter.message_count.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_only_pages_of_changed_sessions_are_written() {
+        let project = TempDir::new().unwrap();
+        let history = path::get_waylog_dir(project.path());
+        std::fs::create_dir_all(&history).unwrap();
+        std::fs::write(
+            history.join("a.md"),
+            "---\nsession_id: a\nupdated_at: 2025-01-15T09:30:00Z\n---\n# A\n",
+        )
+        .unwrap();
+        std::fs::write(history.join("notes.md"), "# Not a session\n").unwrap();
+
+        let files = read_history(project.path()).await.unwrap();
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.page, "a.html");
+
+        let out = project.path().join("site");
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        // Never generated
+        assert!(needs_page(file, &out, Some(at("2025-02-01T00:00:00Z"))));
+
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("a.html"), "").unwrap();
+        assert!(!needs_page(file, &out, Some(at("2025-02-01T00:00:00Z"))));
+        assert!(needs_page(file, &out, Some(at("2025-01-01T00:00:00Z"))));
+        assert!(needs_page(file, &out, None));
+    }
+}
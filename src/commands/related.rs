@@ -0,0 +1,153 @@
+use crate::error::{Result, WaylogError};
+use crate::exporter;
+use crate::init::WAYLOG_DIR;
+use crate::output::Output;
+use crate::session::SessionTracker;
+use crate::utils::path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the generated file -> sessions index inside `.waylog`
+const INDEX_FILE: &str = "index.md";
+
+pub async fn handle_related(
+    query: Option<String>,
+    index: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let sessions = load_edited_files(&path::get_waylog_dir(&project_path)).await?;
+
+    if index {
+        let index_path = project_path.join(WAYLOG_DIR).join(INDEX_FILE);
+        tokio::fs::write(&index_path, build_index(&sessions)).await?;
+        output.index_written(&index_path)?;
+        return Ok(());
+    }
+
+    let query = query.unwrap_or_default();
+    let state = SessionTracker::load_project_state(&project_path).await?;
+    let target = state
+        .find_session(&query)
+        .ok_or_else(|| WaylogError::SessionNotFound(query.clone()))?;
+
+    let related = related_sessions(&target.markdown_path, &sessions);
+    output.related_header(&target.markdown_path, related.len())?;
+    for (markdown, shared) in &related {
+        output.related_session(markdown, shared)?;
+    }
+
+    Ok(())
+}
+
+/// Read the `files_edited` frontmatter of every history file
+async fn load_edited_files(history_dir: &Path) -> Result<Vec<(PathBuf, Vec<String>)>> {
+    let mut sessions = Vec::new();
+    if !history_dir.exists() {
+        return Ok(sessions);
+    }
+
+    let mut entries = tokio::fs::read_dir(history_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(path) = exporter::encryption::history_path(&entry.path()) else {
+            continue;
+        };
+        if let Ok(fm) = exporter::parse_frontmatter(&path).await {
+            if !fm.files_edited.is_empty() {
+                sessions.push((path, fm.files_edited));
+            }
+        }
+    }
+    sessions.sort();
+    Ok(sessions)
+}
+
+/// Sessions that edited at least one of the files `target` edited, with the
+/// shared files; most overlap first
+fn related_sessions(
+    target: &Path,
+    sessions: &[(PathBuf, Vec<String>)],
+) -> Vec<(PathBuf, Vec<String>)> {
+    let Some((_, target_files)) = sessions.iter().find(|(p, _)| p == target) else {
+        return Vec::new();
+    };
+
+    let mut related: Vec<_> = sessions
+        .iter()
+        .filter(|(p, _)| p != target)
+        .filter_map(|(p, files)| {
+            let shared: Vec<String> = files
+                .iter()
+                .filter(|f| target_files.contains(f))
+                .cloned()
+                .collect();
+            (!shared.is_empty()).then(|| (p.clone(), shared))
+        })
+        .collect();
+    related.sort_by_key(|(_, shared)| std::cmp::Reverse(shared.len()));
+    related
+}
+
+/// Markdown index mapping each edited file to the sessions that touched it
+fn build_index(sessions: &[(PathBuf, Vec<String>)]) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+    for (markdown, files) in sessions {
+        for file in files {
+            by_file.entry(file).or_default().push(markdown);
+        }
+    }
+
+    let mut md = String::from(
+        "# Files\n\nSessions that edited each file, generated by `waylog related --index`.\n",
+    );
+    for (file, markdowns) in by_file {
+        md.push_str(&format!("\n## `{}`\n\n", file));
+        for markdown in markdowns {
+            let name = markdown.file_name().unwrap_or_default().to_string_lossy();
+            md.push_str(&format!(
+                "- [{}]({}/{})\n",
+                name.trim_end_matches(".md"),
+                crate::init::subdirs::HISTORY,
+                name.replace(' ', "%20")
+            ));
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions() -> Vec<(PathBuf, Vec<String>)> {
+        vec![
+            (
+                PathBuf::from("a.md"),
+                vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            ),
+            (PathBuf::from("b.md"), vec!["src/main.rs".to_string()]),
+            (
+                PathBuf::from("c.md"),
+                vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            ),
+            (PathBuf::from("d.md"), vec!["README.md".to_string()]),
+        ]
+    }
+
+    #[test]
+    fn test_related_sessions() {
+        let related = related_sessions(Path::new("a.md"), &sessions());
+        let names: Vec<_> = related.iter().map(|(p, _)| p.to_str().unwrap()).collect();
+        assert_eq!(names, ["c.md", "b.md"]);
+        assert_eq!(related[0].1, ["src/main.rs", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_build_index() {
+        let index = build_index(&sessions());
+        assert!(index.contains(
+            "## `src/main.rs`\n\n- [a](history/a.md)\n- [b](history/b.md)\n- [c](history/c.md)\n"
+        ));
+        assert!(index.contains("## `README.md`\n\n- [d](history/d.md)\n"));
+    }
+}
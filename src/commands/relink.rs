@@ -0,0 +1,147 @@
+use crate::config::Config;
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::providers;
+use std::path::PathBuf;
+
+/// Record the paths a project had before it was moved or renamed in
+/// `[project] previous_paths`, so sessions the agents filed under those
+/// paths are synced again
+pub async fn handle_relink(
+    old_paths: Vec<PathBuf>,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let mut previous = crate::config::get().project.previous_paths.clone();
+    let mut added = Vec::new();
+
+    for old in old_paths {
+        // The old path no longer exists, so it can only be made absolute, not canonical
+        let old = if old.is_absolute() {
+            old
+        } else {
+            current_dir.join(old)
+        };
+        if old == project_path {
+            return Err(WaylogError::Config(format!(
+                "{} is the project's current path",
+                old.display()
+            )));
+        }
+        if !previous.contains(&old) {
+            previous.push(old.clone());
+        }
+        added.push(old);
+    }
+
+    let config_path = Config::path(&project_path);
+    let content = match std::fs::read_to_string(&config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let updated = set_previous_paths(&content, &previous)
+        .map_err(|e| WaylogError::Config(format!("{}: {}", config_path.display(), e)))?;
+    crate::utils::atomic::write(&config_path, updated).await?;
+
+    for old in &added {
+        let mut sessions = 0;
+        for provider in providers::all_providers() {
+            if provider.is_installed() {
+                sessions += provider.get_all_sessions(old).await?.len();
+            }
+        }
+        output.relinked(old, sessions)?;
+    }
+    Ok(())
+}
+
+/// `content` with `previous_paths` under `[project]` replaced by `paths`,
+/// keeping the rest of the file (and its comments) as it was
+fn set_previous_paths(content: &str, paths: &[PathBuf]) -> std::result::Result<String, String> {
+    let line = format!("previous_paths = {}", toml_array(paths));
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let section = lines.iter().position(|l| l.trim() == "[project]");
+    match section {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            let existing = (start + 1..end).find(|&i| {
+                let l = lines[i].trim_start();
+                l.starts_with("previous_paths")
+                    && l["previous_paths".len()..].trim_start().starts_with('=')
+            });
+            match existing {
+                Some(i) => {
+                    if toml::from_str::<toml::Table>(&lines[i]).is_err() {
+                        return Err(
+                            "previous_paths spans several lines; edit it by hand".to_string()
+                        );
+                    }
+                    lines[i] = line;
+                }
+                None => lines.insert(start + 1, line),
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[project]".to_string());
+            lines
+                .push("# Paths this project had before it was moved (`waylog relink`)".to_string());
+            lines.push(line);
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    toml::from_str::<toml::Table>(&updated).map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+fn toml_array(paths: &[PathBuf]) -> String {
+    let items: Vec<String> = paths
+        .iter()
+        .map(|p| toml::Value::String(p.to_string_lossy().into_owned()).to_string())
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(list: &[&str]) -> Vec<PathBuf> {
+        list.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_set_previous_paths_appends_section() {
+        let updated =
+            set_previous_paths("[run]\ndefault_agent = \"claude\"\n", &paths(&["/old"])).unwrap();
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.project.previous_paths, paths(&["/old"]));
+        assert_eq!(config.run.default_agent.as_deref(), Some("claude"));
+    }
+
+    #[test]
+    fn test_set_previous_paths_replaces_existing() {
+        let content = "# keep me\n[project]\nprevious_paths = [\"/a\"]\n\n[export]\nfilename_template = \"{slug}\"\n";
+        let updated = set_previous_paths(content, &paths(&["/a", "/b"])).unwrap();
+        assert!(updated.starts_with("# keep me\n"));
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.project.previous_paths, paths(&["/a", "/b"]));
+        assert_eq!(config.export.filename_template, "{slug}");
+    }
+
+    #[test]
+    fn test_set_previous_paths_refuses_multiline_array() {
+        let content = "[project]\nprevious_paths = [\n  \"/a\",\n]\n";
+        assert!(set_previous_paths(content, &paths(&["/a", "/b"])).is_err());
+    }
+}
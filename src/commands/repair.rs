@@ -0,0 +1,103 @@
+use crate::error::Result;
+use crate::exporter::{encryption, frontmatter};
+use crate::output::Output;
+use crate::session::{edits::ExportedFiles, repair, trash};
+use crate::utils::path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// What was wrong with a history file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// Git conflict markers, resolved by keeping the messages of both sides
+    ConflictMarkers,
+    /// Several files for one session (conflict copies included), merged into one
+    Duplicates,
+}
+
+pub async fn handle_repair(
+    dry_run: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let history_dir = path::get_waylog_dir(&project_path);
+    let level = crate::config::get().export.headers.level;
+
+    let mut files = Vec::new();
+    if history_dir.exists() {
+        let mut entries = tokio::fs::read_dir(&history_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            files.extend(encryption::history_path(&entry.path()));
+        }
+    }
+    files.sort();
+
+    // Conflict markers first, so the files are whole again before they are compared
+    let mut repaired = 0;
+    let mut contents = BTreeMap::new();
+    for file in &files {
+        let content = encryption::read(file).await?;
+        let content = match repair::conflict_sides(&content) {
+            Some((ours, theirs)) => {
+                let merged = repair::merge_versions(&ours, &[&theirs], level);
+                if !dry_run {
+                    save(&project_path, file, &merged.content).await?;
+                }
+                output.repaired(file, Problem::ConflictMarkers, &[], merged.added, dry_run)?;
+                repaired += 1;
+                merged.content
+            }
+            None => content,
+        };
+        contents.insert(file.clone(), content);
+    }
+
+    // Parts and hand-edit revisions of a session are files of their own
+    let mut sessions: BTreeMap<(String, usize, usize), Vec<PathBuf>> = BTreeMap::new();
+    for (file, content) in &contents {
+        let fm = frontmatter::parse_frontmatter_str(content);
+        if let Some(session_id) = fm.session_id {
+            let key = (session_id, fm.part.unwrap_or(1), fm.revision.unwrap_or(1));
+            sessions.entry(key).or_default().push(file.clone());
+        }
+    }
+
+    for mut copies in sessions.into_values().filter(|copies| copies.len() > 1) {
+        copies.sort_by_key(|file| primary_rank(file));
+        let (primary, others) = copies.split_first().expect("more than one copy");
+        let other_contents: Vec<&str> = others.iter().map(|file| contents[file].as_str()).collect();
+        let merged = repair::merge_versions(&contents[primary], &other_contents, level);
+
+        if !dry_run {
+            save(&project_path, primary, &merged.content).await?;
+            for other in others {
+                trash::move_to_trash(&project_path, other)?;
+                crate::exporter::public::remove(other).await?;
+            }
+        }
+        output.repaired(primary, Problem::Duplicates, others, merged.added, dry_run)?;
+        repaired += 1;
+    }
+
+    output.repair_summary(repaired, files.len(), dry_run)?;
+    Ok(())
+}
+
+/// Which copy of a session the others are merged into: not a conflict copy,
+/// then the shortest name (a `-2` suffix was added later), then by name, so
+/// that every machine picks the same one
+fn primary_rank(file: &Path) -> (bool, usize, PathBuf) {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    (
+        repair::is_conflict_copy(&name),
+        name.len(),
+        file.to_path_buf(),
+    )
+}
+
+/// Write a repaired history file and bring its copies up to date
+async fn save(project_path: &Path, file: &Path, content: &str) -> Result<()> {
+    encryption::write(file, content).await?;
+    crate::exporter::mirror(file).await?;
+    ExportedFiles::refresh(project_path, file)
+}
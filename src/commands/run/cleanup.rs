@@ -1,10 +1,31 @@
 use crate::error::Result;
-use crate::{exporter, providers, session};
+use crate::exporter::derived::DerivedRenderer;
+use crate::notify::{self, Trigger};
+use crate::synchronizer::Echo;
+use crate::{exporter, git, hooks, providers, session};
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::process::Child;
+use std::time::Duration;
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tracing;
 
+/// What the cleanup after the agent exits works with, gathered once per run
+pub(crate) struct CleanupContext<'a> {
+    pub(crate) watcher_handle: &'a JoinHandle<()>,
+    pub(crate) tracker: &'a Arc<session::SessionTracker>,
+    pub(crate) provider: &'a Arc<dyn providers::base::Provider>,
+    pub(crate) project_path: &'a Path,
+    pub(crate) waylog_dir: &'a Path,
+    pub(crate) frontmatter: &'a [(&'a str, String)],
+    pub(crate) renderer: &'a DerivedRenderer,
+    pub(crate) echo: Option<&'a Echo>,
+    pub(crate) console: &'a Echo,
+    /// HEAD when the agent started; the commits made since are listed
+    pub(crate) head: Option<&'a git::Head>,
+}
+
 /// Perform cleanup and final sync
 ///
 /// This function handles:
@@ -14,25 +35,129 @@ use tracing;
 ///
 /// Errors during cleanup are logged but don't prevent the function from completing.
 pub(crate) async fn cleanup_and_sync(
-    watcher_handle: &JoinHandle<()>,
-    _child: &mut Child,
-    tracker: &Arc<session::SessionTracker>,
-    provider: &Arc<dyn providers::base::Provider>,
-    project_path: &std::path::Path,
-    waylog_dir: &std::path::Path,
-    _exit_status: Option<std::process::ExitStatus>,
+    context: &CleanupContext<'_>,
+    exit_status: Option<std::process::ExitStatus>,
+    ask_name: bool,
 ) -> Result<()> {
-    // Stop the file watcher
-    watcher_handle.abort();
-    // Wait a bit for the watcher to stop (non-blocking, ignore result)
-    // Note: JoinHandle is not Copy, so we can't await the reference directly
-    // Just abort is sufficient, the task will be cleaned up
+    let CleanupContext {
+        watcher_handle,
+        tracker,
+        provider,
+        project_path,
+        console,
+        head,
+        ..
+    } = *context;
+
+    // Stop the file watcher, though not halfway through writing a session:
+    // a file written but not yet recorded would get a `-2` twin from the
+    // final sync. A watcher waiting for the lock never gets it.
+    {
+        let _writing = tracker.lock_writes().await;
+        watcher_handle.abort();
+    }
+
+    let session_file = final_sync(context).await?;
+
+    if let Some(head) = head {
+        if let Err(e) = link_commits(tracker, project_path, session_file.as_deref(), head).await {
+            if let Ok(mut console) = console.lock() {
+                let _ = console.commits_not_listed(&e);
+            }
+        }
+    }
 
-    // Do a final sync
+    if ask_name {
+        if let Err(e) =
+            super::name::ask_name(tracker, provider, session_file.as_deref(), console).await
+        {
+            if let Ok(mut console) = console.lock() {
+                let _ = console.naming_failed(&e);
+            }
+        }
+    }
+
+    if crate::config::get().git.auto_commit {
+        commit_history(tracker, project_path, session_file.as_deref(), console).await;
+    }
+    notify(tracker, project_path, session_file.as_deref(), console).await;
+
+    post_run(
+        tracker,
+        provider,
+        project_path,
+        session_file.as_deref(),
+        exit_status,
+        console,
+    )
+    .await;
+    Ok(())
+}
+
+/// Sync the messages written since the watcher last synced. Returns the
+/// session file that was synced, if one was found.
+async fn final_sync(context: &CleanupContext<'_>) -> Result<Option<PathBuf>> {
+    let CleanupContext {
+        tracker,
+        provider,
+        project_path,
+        waylog_dir,
+        frontmatter,
+        renderer,
+        echo,
+        console,
+        ..
+    } = *context;
+    // Do a final sync. Finding and parsing the session is what takes long on
+    // huge sessions, and it writes nothing, so that part is given up on after
+    // `[run] final_sync_timeout` and handed off to a background pull.
     tracing::info!("Session ended, performing final sync...");
+    let config = &crate::config::get().run;
+    let deadline = (config.final_sync_timeout > 0)
+        .then(|| Instant::now() + Duration::from_secs(config.final_sync_timeout));
+
+    let session_file = match before(deadline, provider.find_latest_session(project_path)).await {
+        Some(Ok(Some(file))) => Some(file),
+        Some(_) => None,
+        None => {
+            return detach_final_sync(tracker, provider, project_path, None, renderer, console)
+                .await
+                .map(|()| None);
+        }
+    };
+    let found = session_file.clone();
+
+    if let Some(session_file) = session_file {
+        if config.detach_final_sync {
+            return detach_final_sync(
+                tracker,
+                provider,
+                project_path,
+                Some(&session_file),
+                renderer,
+                console,
+            )
+            .await
+            .map(|()| found);
+        }
 
-    if let Ok(Some(session_file)) = provider.find_latest_session(project_path).await {
-        if let Ok((session, new_messages)) = tracker.get_new_messages(&session_file).await {
+        let parsed = match before(deadline, tracker.get_new_messages(&session_file)).await {
+            Some(parsed) => parsed,
+            None => {
+                return detach_final_sync(
+                    tracker,
+                    provider,
+                    project_path,
+                    Some(&session_file),
+                    renderer,
+                    console,
+                )
+                .await
+                .map(|()| found);
+            }
+        };
+
+        if let Ok((session, new_messages)) = parsed {
             if !new_messages.is_empty() {
                 tracing::info!("Syncing {} final messages", new_messages.len());
 
@@ -40,55 +165,271 @@ pub(crate) async fn cleanup_and_sync(
                     if let Some(existing) = tracker.get_markdown_path(&session.session_id).await {
                         existing
                     } else {
-                        let slug = session
-                            .messages
-                            .iter()
-                            .find(|m| m.role == crate::providers::base::MessageRole::User)
-                            .map(|m| crate::utils::string::slugify(&m.content))
-                            .unwrap_or_else(|| session.session_id.clone());
-
-                        let timestamp = session.started_at.format("%Y-%m-%d_%H-%M-%SZ");
-                        let filename = format!("{}-{}-{}.md", timestamp, provider.name(), slug);
-                        waylog_dir.join(filename)
+                        exporter::markdown::named_session_path(
+                            waylog_dir,
+                            &session,
+                            exporter::markdown::given_title(frontmatter).as_deref(),
+                        )
                     };
 
                 let synced_count = tracker.get_synced_count(&session.session_id).await;
 
-                // Perform sync - errors are logged but don't stop cleanup
-                match (synced_count == 0, &markdown_path) {
-                    (true, path) => {
-                        if let Err(e) = exporter::create_markdown_file(path, &session).await {
-                            tracing::error!("Failed to create markdown file: {}", e);
-                        }
+                // A hand-edited file is left to `waylog pull` and `[sync] on_conflict`
+                if synced_count > 0 && tracker.is_edited(&markdown_path).await {
+                    tracing::warn!(
+                        "{} was edited by hand; run `waylog pull` to sync the last {} messages",
+                        markdown_path.display(),
+                        new_messages.len()
+                    );
+                } else {
+                    // Perform sync - errors are logged but don't stop cleanup
+                    if let Err(e) = exporter::markdown::parts::write_session(
+                        &markdown_path,
+                        &session,
+                        synced_count,
+                        &new_messages,
+                        frontmatter,
+                    )
+                    .await
+                    {
+                        tracing::error!("Failed to write messages: {}", e);
                     }
-                    (false, path) => {
-                        if let Err(e) = exporter::append_messages(path, &new_messages).await {
-                            tracing::error!("Failed to append messages: {}", e);
+
+                    if let Some(echo) = echo {
+                        if let Ok(mut output) = echo.lock() {
+                            let _ = output.echo_messages(&new_messages);
                         }
                     }
-                }
 
-                if let Err(e) = tracker
-                    .update_session(
-                        session.session_id.clone(),
-                        session_file,
-                        markdown_path.clone(),
-                        session.messages.len(),
-                    )
-                    .await
-                {
-                    tracing::error!("Failed to update session: {}", e);
-                } else {
-                    tracing::info!("✓ Final sync complete: {}", markdown_path.display());
+                    tracker.record_file(&markdown_path).await;
+                    tracker
+                        .record_messages(&session.session_id, synced_count, &new_messages)
+                        .await;
+                    if let Err(e) = tracker
+                        .update_session(
+                            session.session_id.clone(),
+                            session_file,
+                            markdown_path.clone(),
+                            synced_count + new_messages.len(),
+                        )
+                        .await
+                    {
+                        tracing::error!("Failed to update session: {}", e);
+                    } else {
+                        tracing::info!("Final sync complete: {}", markdown_path.display());
+                    }
+
+                    if let Err(e) = renderer.schedule(&markdown_path, &session).await {
+                        tracing::error!("{}", e);
+                    }
                 }
             }
         }
     }
 
+    // Renders the watcher held back
+    if let Err(e) = renderer.flush().await {
+        tracing::error!("{}", e);
+    }
+
     // Save final state - errors are logged but don't stop cleanup
     if let Err(e) = tracker.save_state().await {
         tracing::warn!("Failed to save state: {}", e);
     }
 
+    Ok(found)
+}
+
+/// List the commits made since `head` (while the agent ran) in a section at
+/// the end of the session's history file
+async fn link_commits(
+    tracker: &session::SessionTracker,
+    project_path: &Path,
+    session_file: Option<&Path>,
+    head: &git::Head,
+) -> Result<()> {
+    let state = tracker.get_state().await;
+    let Some(markdown_path) = session_file
+        .and_then(|file| state.sessions.values().find(|s| s.file_path == file))
+        .map(|s| s.markdown_path.clone())
+        .filter(|path| exporter::encryption::exists(path))
+    else {
+        return Ok(());
+    };
+    let commits = git::commits_since(project_path, head).await?;
+    if commits.is_empty() {
+        return Ok(());
+    }
+
+    let _writing = tracker.lock_writes().await;
+    let edited = tracker.is_edited(&markdown_path).await;
+    let last = exporter::markdown::parts::existing_parts(&markdown_path)
+        .pop()
+        .unwrap_or_else(|| markdown_path.clone());
+    let commits: Vec<_> = commits
+        .into_iter()
+        .map(|commit| (commit.hash, commit.subject))
+        .collect();
+    exporter::markdown::append_commits(&last, &commits).await?;
+    if !edited {
+        tracker.record_file(&markdown_path).await;
+    }
+    exporter::mirror(&last).await
+}
+
+/// Commit the history with the session the agent wrote (`[git] auto_commit`).
+/// A failure is reported but fails nothing.
+async fn commit_history(
+    tracker: &session::SessionTracker,
+    project_path: &Path,
+    session_file: Option<&Path>,
+    console: &Echo,
+) {
+    let state = tracker.get_state().await;
+    let sessions: Vec<PathBuf> = session_file
+        .and_then(|file| state.sessions.values().find(|s| s.file_path == file))
+        .map(|s| s.markdown_path.clone())
+        .into_iter()
+        .collect();
+    let committed = git::commit_history(project_path, &sessions).await;
+    if let Ok(mut console) = console.lock() {
+        let _ = match committed {
+            Ok(outcome) => console.history_commit(&outcome),
+            Err(e) => console.history_commit_failed(&e),
+        };
+    }
+}
+
+/// Post the session the agent wrote to the `[notify]` webhook. A failure
+/// is reported but fails nothing.
+async fn notify(
+    tracker: &session::SessionTracker,
+    project_path: &Path,
+    session_file: Option<&Path>,
+    console: &Echo,
+) {
+    let state = tracker.get_state().await;
+    let sessions: Vec<PathBuf> = session_file
+        .and_then(|file| state.sessions.values().find(|s| s.file_path == file))
+        .map(|s| s.markdown_path.clone())
+        .into_iter()
+        .collect();
+    if let Err(e) = notify::sessions_written(project_path, Trigger::Run, &sessions).await {
+        if let Ok(mut console) = console.lock() {
+            let _ = console.webhook_failed(&e);
+        }
+    }
+}
+
+/// Run the `[hooks] post_run` commands with the session the agent wrote
+/// and how it exited. A failing command is reported but fails nothing.
+async fn post_run(
+    tracker: &session::SessionTracker,
+    provider: &Arc<dyn providers::base::Provider>,
+    project_path: &Path,
+    session_file: Option<&Path>,
+    exit_status: Option<std::process::ExitStatus>,
+    console: &Echo,
+) {
+    let commands = &crate::config::get().hooks.post_run;
+    if commands.is_empty() {
+        return;
+    }
+
+    let state = tracker.get_state().await;
+    let session =
+        session_file.and_then(|file| state.sessions.values().find(|s| s.file_path == file));
+    let env = [
+        ("WAYLOG_PROVIDER", provider.name().to_string()),
+        (
+            "WAYLOG_SESSION_ID",
+            session.map(|s| s.session_id.clone()).unwrap_or_default(),
+        ),
+        (
+            "WAYLOG_MARKDOWN_PATH",
+            session
+                .map(|s| s.markdown_path.display().to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "WAYLOG_EXIT_CODE",
+            exit_status
+                .and_then(|status| status.code())
+                .map(|code| code.to_string())
+                .unwrap_or_default(),
+        ),
+    ];
+    if let Err(e) = hooks::run_commands("post_run", commands, project_path, &env).await {
+        if let Ok(mut console) = console.lock() {
+            let _ = console.warn(e.to_string());
+        }
+    }
+}
+
+/// Run `future` to completion, or give up on it at `deadline`
+async fn before<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Save the state synced so far and leave the rest of the final sync to a
+/// detached `waylog pull`, which keeps running after the shell returns
+async fn detach_final_sync(
+    tracker: &Arc<session::SessionTracker>,
+    provider: &Arc<dyn providers::base::Provider>,
+    project_path: &Path,
+    session_file: Option<&Path>,
+    renderer: &DerivedRenderer,
+    console: &Echo,
+) -> Result<()> {
+    // The background pull reads the state from disk
+    if let Err(e) = tracker.save_state().await {
+        tracing::warn!("Failed to save state: {}", e);
+    }
+    // and only renders the sessions it syncs itself
+    if let Err(e) = renderer.flush().await {
+        tracing::error!("{}", e);
+    }
+
+    // This process holds the project lock until it exits, after the hooks
+    // and the commit, so the pull waits for it rather than giving up
+    let mut args = vec![
+        "pull".to_string(),
+        "--quiet".to_string(),
+        "--wait".to_string(),
+        "--provider".to_string(),
+        provider.name().to_string(),
+    ];
+    if let Some(file) = session_file {
+        args.push("--session".to_string());
+        args.push(file.to_string_lossy().to_string());
+    }
+
+    match crate::utils::process::spawn_detached(&args, project_path) {
+        Ok(()) => {
+            if let Ok(mut output) = console.lock() {
+                output.final_sync_detached(&args)?;
+            }
+        }
+        Err(e) => tracing::error!("Could not start the background sync: {}", e),
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_before_gives_up_at_deadline() {
+        let soon = Some(Instant::now() + Duration::from_millis(20));
+        let slow = tokio::time::sleep(Duration::from_secs(5));
+        assert!(before(soon, slow).await.is_none());
+
+        let later = Some(Instant::now() + Duration::from_secs(5));
+        assert_eq!(before(later, async { 1 }).await, Some(1));
+        assert_eq!(before(None, async { 2 }).await, Some(2));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::config::RunConfig;
+use std::ffi::OsString;
+
+/// Variables kept by `clean_env` so agents can still find their binaries,
+/// config and terminal
+const BASE_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "COLORTERM",
+    "LANG",
+    "LC_ALL",
+    "TMPDIR",
+    "TZ",
+    // Windows
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "SYSTEMROOT",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+];
+
+/// Environment flags given on the `waylog run` command line
+#[derive(Debug, Default, Clone)]
+pub struct EnvOptions {
+    pub clean: bool,
+    pub set: Vec<(String, String)>,
+    pub unset: Vec<String>,
+}
+
+/// Build the agent's environment from the inherited one, the `[run]` config
+/// and command-line flags (flags win over config)
+pub(crate) fn child_env(
+    inherited: impl IntoIterator<Item = (OsString, OsString)>,
+    config: &RunConfig,
+    options: &EnvOptions,
+) -> Vec<(OsString, OsString)> {
+    let clean = config.clean_env || options.clean;
+
+    let mut env: Vec<(OsString, OsString)> = inherited
+        .into_iter()
+        .filter(|(key, _)| {
+            let key = key.to_string_lossy();
+            if clean
+                && !BASE_ENV.iter().any(|k| matches_pattern(k, &key))
+                && !config.keep_env.iter().any(|p| matches_pattern(p, &key))
+            {
+                return false;
+            }
+            !config.strip_env.iter().any(|p| matches_pattern(p, &key))
+                && !options.unset.iter().any(|p| matches_pattern(p, &key))
+        })
+        .collect();
+
+    let set = config
+        .env
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .chain(options.set.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    for (key, value) in set {
+        env.retain(|(k, _)| k != key);
+        env.push((key.into(), value.into()));
+    }
+
+    env
+}
+
+/// Match a variable name against `NAME` or a `PREFIX*` pattern.
+/// Windows variable names are case-insensitive.
+fn matches_pattern(pattern: &str, key: &str) -> bool {
+    let (pattern, key) = if cfg!(windows) {
+        (pattern.to_ascii_uppercase(), key.to_ascii_uppercase())
+    } else {
+        (pattern.to_string(), key.to_string())
+    };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inherited() -> Vec<(OsString, OsString)> {
+        [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/me"),
+            ("AWS_ACCESS_KEY_ID", "AKIA"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("ANTHROPIC_MODEL", "sonnet"),
+            ("EDITOR", "vim"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.into(), v.into()))
+        .collect()
+    }
+
+    fn get<'a>(env: &'a [(OsString, OsString)], key: &str) -> Option<&'a str> {
+        env.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_child_env_default_inherits_everything() {
+        let env = child_env(inherited(), &RunConfig::default(), &EnvOptions::default());
+        assert_eq!(env.len(), 6);
+    }
+
+    #[test]
+    fn test_child_env_strip_and_set() {
+        let config = RunConfig {
+            strip_env: vec!["AWS_*".to_string()],
+            env: [("ANTHROPIC_MODEL".to_string(), "opus".to_string())].into(),
+            ..Default::default()
+        };
+        let options = EnvOptions {
+            unset: vec!["EDITOR".to_string()],
+            set: vec![("DEBUG".to_string(), "1".to_string())],
+            ..Default::default()
+        };
+        let env = child_env(inherited(), &config, &options);
+
+        assert_eq!(get(&env, "AWS_ACCESS_KEY_ID"), None);
+        assert_eq!(get(&env, "AWS_SECRET_ACCESS_KEY"), None);
+        assert_eq!(get(&env, "EDITOR"), None);
+        assert_eq!(get(&env, "ANTHROPIC_MODEL"), Some("opus"));
+        assert_eq!(get(&env, "DEBUG"), Some("1"));
+        assert_eq!(get(&env, "PATH"), Some("/usr/bin"));
+    }
+
+    #[test]
+    fn test_child_env_clean() {
+        let config = RunConfig {
+            keep_env: vec!["ANTHROPIC_*".to_string()],
+            ..Default::default()
+        };
+        let options = EnvOptions {
+            clean: true,
+            set: vec![("ANTHROPIC_MODEL".to_string(), "haiku".to_string())],
+            ..Default::default()
+        };
+        let env = child_env(inherited(), &config, &options);
+
+        let keys: Vec<_> = env.iter().map(|(k, _)| k.to_str().unwrap()).collect();
+        assert_eq!(keys, ["PATH", "HOME", "ANTHROPIC_MODEL"]);
+        assert_eq!(get(&env, "ANTHROPIC_MODEL"), Some("haiku"));
+    }
+}
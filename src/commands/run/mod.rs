@@ -1,22 +1,77 @@
 mod cleanup;
+mod env;
+mod name;
+mod notes;
 mod process;
+mod pty;
+mod record;
 
+pub(crate) use env::child_env;
+pub use env::EnvOptions;
+
+use crate::config::Recording;
 use crate::error::{Result, WaylogError};
+use crate::exporter::derived::DerivedRenderer;
 use crate::output::Output;
-use crate::{providers, session, utils, watcher};
+use crate::synchronizer::Echo;
+use crate::{git, hooks, providers, session, utils, watcher};
+use process::AgentChild;
+use pty::PtyChild;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::process::Command;
 use tokio::task::JoinHandle;
 
+/// Options for `waylog run` besides the agent and its arguments
+#[derive(Debug, Default, Clone)]
+pub struct RunOptions {
+    pub env: EnvOptions,
+    /// Print each message to stderr as soon as it is recorded
+    pub echo_sync: bool,
+    /// Stop the agent after this long
+    pub timeout: Option<Duration>,
+    /// Also sync the sessions of the other installed providers
+    pub all_providers: bool,
+    /// How eagerly the session files are synced
+    pub watch: watcher::WatchTuning,
+    /// Run the agent in a pseudo-terminal when waylog runs in one
+    pub pty: bool,
+    /// Record the agent's terminal (needs the pseudo-terminal)
+    pub record: Recording,
+    /// Ask for a title and tags for the session when the agent exits
+    pub ask_name: bool,
+    /// Name of the session, in place of the first message (`--session-name`)
+    pub session_name: Option<String>,
+}
+
 pub async fn handle_run(
     agent: Option<String>,
     args: Vec<String>,
+    options: RunOptions,
     project_path: PathBuf,
     output: &mut Output,
 ) -> Result<()> {
-    let agent_name = match agent {
+    let (provider, command) = resolve_agent(agent, output)?;
+
+    // A separate handle, since the watcher and the final sync write from their own tasks
+    let console = Arc::new(Mutex::new(output.new_handle()));
+
+    // Now run_agent can focus on execution without validation
+    run_agent(command, args, options, console, project_path, provider).await?;
+
+    Ok(())
+}
+
+/// The provider and launch command of an agent given on the command line
+/// (or `[run] default_agent`): a built-in one, or an alias from `[agents]`
+pub(crate) fn resolve_agent(
+    agent: Option<String>,
+    output: &mut Output,
+) -> Result<(Arc<dyn providers::base::Provider>, Vec<String>)> {
+    let agent_name = match agent.or_else(|| crate::config::get().run.default_agent.clone()) {
         Some(name) => name,
         None => {
             output.missing_agent()?;
@@ -24,35 +79,65 @@ pub async fn handle_run(
         }
     };
 
-    // Get and validate provider before calling run_agent
-    let provider = match providers::get_provider(&agent_name) {
-        Ok(p) => p,
-        Err(WaylogError::ProviderNotFound(name)) => {
-            output.unknown_agent(&name)?;
-            return Err(WaylogError::ProviderNotFound(name));
+    // Aliases from config launch a custom command but keep a built-in provider's parser
+    Ok(match crate::config::get().agents.get(&agent_name) {
+        Some(alias) => {
+            let provider = providers::get_provider(&alias.provider)?;
+            let command = utils::string::split_command(&alias.command);
+            if which::which(&command[0]).is_err() {
+                output.agent_not_installed(&command[0])?;
+                return Err(WaylogError::AgentNotInstalled(command[0].clone()));
+            }
+            (provider, command)
         }
-        Err(e) => return Err(e),
-    };
-
-    // Check if the tool is installed
-    if !provider.is_installed() {
-        output.agent_not_installed(provider.command())?;
-        return Err(WaylogError::AgentNotInstalled(
-            provider.command().to_string(),
-        ));
-    }
-
-    // Now run_agent can focus on execution without validation
-    run_agent(args, project_path, provider).await?;
+        None => {
+            // Get and validate provider before calling run_agent
+            let provider = match providers::get_provider(&agent_name) {
+                Ok(p) => p,
+                Err(WaylogError::ProviderNotFound(name)) => {
+                    output.unknown_agent(&name)?;
+                    return Err(WaylogError::ProviderNotFound(name));
+                }
+                Err(e) => return Err(e),
+            };
+
+            // Check if the tool is installed
+            if !provider.is_installed() {
+                output.agent_not_installed(provider.command())?;
+                return Err(WaylogError::AgentNotInstalled(
+                    provider.command().to_string(),
+                ));
+            }
 
-    Ok(())
+            let command = vec![provider.command().to_string()];
+            (provider, command)
+        }
+    })
 }
 
+/// `command` is the program plus any fixed leading arguments; `args` are the
+/// extra arguments given on the `waylog run` command line
 async fn run_agent(
+    command: Vec<String>,
     args: Vec<String>,
+    options: RunOptions,
+    console: Echo,
     project_path: PathBuf,
     provider: Arc<dyn providers::base::Provider>,
 ) -> Result<()> {
+    let RunOptions {
+        env,
+        echo_sync,
+        timeout,
+        all_providers,
+        watch,
+        pty,
+        record,
+        ask_name,
+        session_name,
+    } = options;
+    let echo = echo_sync.then(|| console.clone());
+
     // Provider is already validated in handle_run, so we can focus on execution
     tracing::info!("Starting {} in {}", provider.name(), project_path.display());
 
@@ -66,25 +151,128 @@ async fn run_agent(
     let tracker =
         Arc::new(session::SessionTracker::new(project_path.clone(), provider.clone()).await?);
 
+    hooks::run_commands(
+        "pre_run",
+        &crate::config::get().hooks.pre_run,
+        &project_path,
+        &[("WAYLOG_PROVIDER", provider.name().to_string())],
+    )
+    .await?;
+
+    // Record how the agent was launched, since flags like the model change how
+    // the transcript should be read
+    let invocation =
+        utils::string::shell_join(command.iter().chain(args.iter()).map(String::as_str));
+    // Quoted so that `:` or `#` in prompts can't break the YAML frontmatter
+    let invocation = serde_json::to_string(&invocation)?;
+    let mut frontmatter = vec![("invocation", invocation)];
+    // The session's title, and its history file is named after it
+    if let Some(name) = &session_name {
+        frontmatter.push(("title", serde_json::to_string(name)?));
+    }
+
+    // The title is asked for at the terminal, after an agent that exited by itself
+    let ask_name = ask_name
+        && std::io::stdin().is_terminal()
+        && console.lock().is_ok_and(|console| !console.json());
+
+    // The terminal is recorded as it passes through the pseudo-terminal,
+    // and the recording is linked from the sessions of this run. On Windows
+    // the pseudo-terminal would take the first key of the answer to the title
+    // question, so with `--ask-name` the agent gets our terminal instead.
+    let pty = pty
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+        && !(cfg!(windows) && ask_name);
+    let recorder = match record {
+        Recording::Off => None,
+        _ if crate::exporter::encryption::enabled() => {
+            return Err(WaylogError::Config(
+                "--record can't be used with [encryption]: terminal recordings are not encrypted"
+                    .to_string(),
+            ));
+        }
+        _ if !pty => {
+            if let Ok(mut console) = console.lock() {
+                console.recording_needs_pty()?;
+            }
+            None
+        }
+        format => {
+            let path = record::recording_path(&project_path, provider.name(), format);
+            let size = pty::terminal_size();
+            let recorder = record::Recorder::create(&path, format, size.cols, size.rows)?;
+            if let Ok(mut console) = console.lock() {
+                console.recording_to(&path)?;
+            }
+            let relative = path.strip_prefix(&project_path).unwrap_or(&path);
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            frontmatter.push(("recording", serde_json::to_string(&relative)?));
+            Some(recorder)
+        }
+    };
+
+    // Derived artifacts are rebuilt at most every `render_interval` while
+    // the agent runs, and once more at the end
+    let renderer = Arc::new(DerivedRenderer::from_config(project_path.clone()));
+
     // Create file watcher
-    let watcher =
-        watcher::FileWatcher::new(provider.clone(), project_path.clone(), tracker.clone());
+    let mut watcher =
+        watcher::FileWatcher::new(provider.clone(), project_path.clone(), tracker.clone())
+            .with_renderer(renderer.clone())
+            .with_tuning(watch);
+    for (key, value) in &frontmatter {
+        watcher = watcher.with_frontmatter(key, value.clone());
+    }
+    if let Some(echo) = &echo {
+        watcher = watcher.with_echo(echo.clone());
+    }
+    if all_providers {
+        for other in providers::all_providers() {
+            if other.name() != provider.name() && other.is_installed() {
+                watcher = watcher.also_watch(other);
+            }
+        }
+    }
 
-    // Start file watcher in background
+    // Start file watcher in background, restarting it if it fails so live
+    // sync doesn't quietly stop for the rest of the session. Notes from
+    // `waylog note` are taken alongside, and stop with it.
+    let watcher_console = console.clone();
+    let (notes_tracker, notes_provider, notes_project) =
+        (tracker.clone(), provider.clone(), project_path.clone());
     let watcher_handle: JoinHandle<()> = tokio::spawn(async move {
-        if let Err(e) = watcher.watch().await {
+        let watched = watcher.watch_supervised(|e, retry_in| {
             tracing::error!("File watcher error: {}", e);
-        }
+            if let Ok(mut console) = watcher_console.lock() {
+                let _ = console.live_sync_degraded(e, retry_in);
+            }
+        });
+        let notes = notes::serve(notes_tracker, notes_provider, &notes_project);
+        tokio::join!(watched, notes);
     });
 
+    // Commits made from here on are listed in the session's history file
+    let head = git::head(&project_path).await;
+
     // Start the AI CLI tool as a child process
-    tracing::info!("Launching {}...", provider.command());
-    let mut child = Command::new(provider.command())
-        .args(&args)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()?;
+    tracing::info!("Launching {}...", command[0]);
+    let child_env = env::child_env(std::env::vars_os(), &crate::config::get().run, &env);
+    let mut child = if pty {
+        AgentChild::Pty(PtyChild::spawn(&command, &args, child_env, recorder)?)
+    } else {
+        AgentChild::Inherited(
+            Command::new(&command[0])
+                .args(&command[1..])
+                .args(&args)
+                .env_clear()
+                .envs(child_env)
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .spawn()?,
+        )
+    };
 
     // Setup cross-platform signal handling using tokio::signal
     #[cfg(unix)]
@@ -132,9 +320,22 @@ async fn run_agent(
         }
     };
 
-    // Unified signal handling logic using tokio::select!
+    let cleanup = cleanup::CleanupContext {
+        watcher_handle: &watcher_handle,
+        tracker: &tracker,
+        provider: &provider,
+        project_path: &project_path,
+        waylog_dir: &waylog_dir,
+        frontmatter: &frontmatter,
+        renderer: &renderer,
+        echo: echo.as_ref(),
+        console: &console,
+        head: head.as_ref(),
+    };
+
+    // A signal to stop, resolving to the exit code it is reported with
     #[cfg(unix)]
-    let exit_status = {
+    let signalled = async {
         // Unix: Handle SIGINT and SIGTERM
         tokio::select! {
             // SIGINT (Ctrl+C)
@@ -146,20 +347,8 @@ async fn run_agent(
                 }
             } => {
                 tracing::info!("Received SIGINT (Ctrl+C), cleaning up...");
-                process::terminate_child(&mut child).await;
-                let status = child.wait().await?;
-                cleanup::cleanup_and_sync(
-                    &watcher_handle,
-                    &mut child,
-                    &tracker,
-                    &provider,
-                    &project_path,
-                    &waylog_dir,
-                    Some(status),
-                )
-                .await?;
                 // Standard exit code for SIGINT: 130
-                return Err(WaylogError::ChildProcessFailed(130));
+                130
             }
             // SIGTERM
             _ = async {
@@ -170,107 +359,51 @@ async fn run_agent(
                 }
             } => {
                 tracing::info!("Received SIGTERM, cleaning up...");
-                process::terminate_child(&mut child).await;
-                let status = child.wait().await?;
-                cleanup::cleanup_and_sync(
-                    &watcher_handle,
-                    &mut child,
-                    &tracker,
-                    &provider,
-                    &project_path,
-                    &waylog_dir,
-                    Some(status),
-                )
-                .await?;
                 // Standard exit code for SIGTERM: 143
-                return Err(WaylogError::ChildProcessFailed(143));
-            }
-            // Child process exited normally
-            status_result = child.wait() => {
-                let status = status_result?;
-                watcher_handle.abort();
-                cleanup::cleanup_and_sync(
-                    &watcher_handle,
-                    &mut child,
-                    &tracker,
-                    &provider,
-                    &project_path,
-                    &waylog_dir,
-                    Some(status),
-                )
-                .await?;
-                Some(status)
+                143
             }
         }
     };
 
     #[cfg(windows)]
-    let exit_status = {
-        // Windows: Handle Ctrl+C
-        tokio::select! {
-            // Ctrl+C
-            result = async {
-                if let Some(ref mut ctrl_c_stream) = ctrl_c {
-                    // recv() returns Option<()>, Some(()) when signal received, None when stream closed
-                    ctrl_c_stream.recv().await
-                } else {
-                    std::future::pending().await
-                }
-            } => {
-                // Only process if signal was actually received (Some(()))
-                if result.is_none() {
-                    // Stream closed, wait for child process to exit normally
-                    let status = child.wait().await?;
-                    watcher_handle.abort();
-                    cleanup::cleanup_and_sync(
-                        &watcher_handle,
-                        &mut child,
-                        &tracker,
-                        &provider,
-                        &project_path,
-                        &waylog_dir,
-                        Some(status),
-                    )
-                    .await?;
-                    if !status.success() {
-                        let exit_code = status.code().unwrap_or(1);
-                        return Err(WaylogError::ChildProcessFailed(exit_code));
-                    }
-                    return Ok(());
-                }
+    let signalled = async {
+        // Windows: Handle Ctrl+C. recv() returns None once the stream is
+        // closed; the agent is then left to exit by itself.
+        let received = match ctrl_c.as_mut() {
+            Some(ctrl_c_stream) => ctrl_c_stream.recv().await.is_some(),
+            None => false,
+        };
+        if !received {
+            return std::future::pending().await;
+        }
+        tracing::info!("Received Ctrl+C, cleaning up...");
+        // Standard exit code for Ctrl+C: 130 (same as Unix SIGINT)
+        130
+    };
 
-                tracing::info!("Received Ctrl+C, cleaning up...");
-                process::terminate_child(&mut child).await;
-                let status = child.wait().await?;
-                cleanup::cleanup_and_sync(
-                    &watcher_handle,
-                    &mut child,
-                    &tracker,
-                    &provider,
-                    &project_path,
-                    &waylog_dir,
-                    Some(status),
-                )
-                .await?;
-                // Standard exit code for Ctrl+C: 130 (same as Unix SIGINT)
-                return Err(WaylogError::ChildProcessFailed(130));
-            }
-            // Child process exited normally
-            status_result = child.wait() => {
-                let status = status_result?;
-                watcher_handle.abort();
-                cleanup::cleanup_and_sync(
-                    &watcher_handle,
-                    &mut child,
-                    &tracker,
-                    &provider,
-                    &project_path,
-                    &waylog_dir,
-                    Some(status),
-                )
-                .await?;
-                Some(status)
+    let exit_status = tokio::select! {
+        code = signalled => {
+            process::terminate_child(&mut child).await;
+            let status = child.wait().await?;
+            cleanup::cleanup_and_sync(&cleanup, Some(status), false).await?;
+            return Err(WaylogError::ChildProcessFailed(code));
+        }
+        // --timeout ran out
+        _ = process::deadline(timeout) => {
+            tracing::info!("Timeout reached, stopping {}...", provider.name());
+            if let Ok(mut console) = console.lock() {
+                console.run_timed_out(provider.name())?;
             }
+            process::stop_child(&mut child).await;
+            let status = child.wait().await?;
+            cleanup::cleanup_and_sync(&cleanup, Some(status), false).await?;
+            return Err(WaylogError::TimedOut(provider.name().to_string()));
+        }
+        // Child process exited normally
+        status_result = child.wait() => {
+            let status = status_result?;
+            cleanup::cleanup_and_sync(&cleanup, Some(status), ask_name).await?;
+            Some(status)
         }
     };
 
@@ -300,7 +433,6 @@ mod tests {
     use std::collections::HashMap;
     use std::path::Path;
     use tempfile::TempDir;
-    use tokio::process::Command as TokioCommand;
 
     // Mock Provider for testing
     struct MockProvider {
@@ -355,6 +487,10 @@ mod tests {
             Ok(self.sessions.keys().cloned().collect())
         }
 
+        async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+            Ok(self.sessions.keys().cloned().collect())
+        }
+
         fn is_installed(&self) -> bool {
             true
         }
@@ -364,6 +500,14 @@ mod tests {
         }
     }
 
+    fn console() -> Echo {
+        Arc::new(Mutex::new(Output::new(
+            true,
+            false,
+            crate::output::ColorMode::Never,
+        )))
+    }
+
     fn create_test_session(session_id: &str, message_count: usize) -> ChatSession {
         let now = Utc::now();
         let mut messages = Vec::new();
@@ -417,28 +561,22 @@ mod tests {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         });
 
-        // Create a simple child process that exits immediately
-        // On Windows, echo is a shell built-in, so use cmd /C
-        #[cfg(windows)]
-        let mut child = TokioCommand::new("cmd")
-            .args(["/C", "echo", "test"])
-            .spawn()
-            .unwrap();
-        #[cfg(not(windows))]
-        let mut child = TokioCommand::new("echo").arg("test").spawn().unwrap();
-
-        // Wait for child to exit
-        let _ = child.wait().await;
-
         // Call cleanup_and_sync
         let result = cleanup::cleanup_and_sync(
-            &watcher_handle,
-            &mut child,
-            &tracker,
-            &provider,
-            &project_path,
-            &waylog_dir,
+            &cleanup::CleanupContext {
+                watcher_handle: &watcher_handle,
+                tracker: &tracker,
+                provider: &provider,
+                project_path: &project_path,
+                waylog_dir: &waylog_dir,
+                frontmatter: &[],
+                renderer: &DerivedRenderer::immediate(project_path.clone()),
+                echo: None,
+                console: &console(),
+                head: None,
+            },
             None,
+            false,
         )
         .await;
 
@@ -478,25 +616,22 @@ mod tests {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         });
 
-        // Create child process (cross-platform)
-        #[cfg(windows)]
-        let mut child = TokioCommand::new("cmd")
-            .args(["/C", "echo", "test"])
-            .spawn()
-            .unwrap();
-        #[cfg(not(windows))]
-        let mut child = TokioCommand::new("echo").arg("test").spawn().unwrap();
-        let _ = child.wait().await;
-
         // Call cleanup_and_sync - should succeed even with no messages
         let result = cleanup::cleanup_and_sync(
-            &watcher_handle,
-            &mut child,
-            &tracker,
-            &provider,
-            &project_path,
-            &waylog_dir,
+            &cleanup::CleanupContext {
+                watcher_handle: &watcher_handle,
+                tracker: &tracker,
+                provider: &provider,
+                project_path: &project_path,
+                waylog_dir: &waylog_dir,
+                frontmatter: &[],
+                renderer: &DerivedRenderer::immediate(project_path.clone()),
+                echo: None,
+                console: &console(),
+                head: None,
+            },
             None,
+            false,
         )
         .await;
 
@@ -542,6 +677,10 @@ mod tests {
                 Ok(vec![])
             }
 
+            async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+                Ok(vec![])
+            }
+
             fn is_installed(&self) -> bool {
                 true
             }
@@ -562,24 +701,22 @@ mod tests {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         });
 
-        #[cfg(windows)]
-        let mut child = TokioCommand::new("cmd")
-            .args(["/C", "echo", "test"])
-            .spawn()
-            .unwrap();
-        #[cfg(not(windows))]
-        let mut child = TokioCommand::new("echo").arg("test").spawn().unwrap();
-        let _ = child.wait().await;
-
         // Should not panic even when provider returns error
         let result = cleanup::cleanup_and_sync(
-            &watcher_handle,
-            &mut child,
-            &tracker,
-            &provider,
-            &project_path,
-            &waylog_dir,
+            &cleanup::CleanupContext {
+                watcher_handle: &watcher_handle,
+                tracker: &tracker,
+                provider: &provider,
+                project_path: &project_path,
+                waylog_dir: &waylog_dir,
+                frontmatter: &[],
+                renderer: &DerivedRenderer::immediate(project_path.clone()),
+                echo: None,
+                console: &console(),
+                head: None,
+            },
             None,
+            false,
         )
         .await;
 
@@ -0,0 +1,145 @@
+//! Naming the session when the agent exits (`run --ask-name`, `[run]
+//! ask_name`). The first message rarely makes a good title ("hi",
+//! "continue"), so the user is asked for one and for tags; they go into the
+//! frontmatter and heading, and the file is renamed after the title.
+use crate::error::Result;
+use crate::exporter::{self, frontmatter};
+use crate::i18n::t;
+use crate::synchronizer::Echo;
+use crate::{providers, session};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Ask for a title and tags for the session the agent wrote to
+/// `session_file` and apply them to its history file. Nothing is asked if
+/// the session was never synced; empty answers change nothing.
+pub(crate) async fn ask_name(
+    tracker: &session::SessionTracker,
+    provider: &Arc<dyn providers::base::Provider>,
+    session_file: Option<&Path>,
+    console: &Echo,
+) -> Result<()> {
+    let Some(session_file) = session_file else {
+        return Ok(());
+    };
+    let state = tracker.get_state().await;
+    let Some(synced) = state
+        .sessions
+        .values()
+        .find(|s| s.file_path == session_file && exporter::encryption::exists(&s.markdown_path))
+    else {
+        return Ok(());
+    };
+
+    let title = prompt(&t!("run-name-prompt"));
+    let tags = prompt(&t!("run-tags-prompt"));
+    let tags: Vec<String> = tags
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect();
+    if title.is_empty() && tags.is_empty() {
+        return Ok(());
+    }
+
+    let content = exporter::encryption::read(&synced.markdown_path).await?;
+    let content = apply_name(&content, &title, &tags)?;
+
+    // A split session keeps its names, since its parts link to each other by name
+    let mut markdown_path = synced.markdown_path.clone();
+    if !title.is_empty() && exporter::markdown::parts::existing_parts(&markdown_path).len() == 1 {
+        let session = provider.parse_session(session_file).await?;
+        let renamed = markdown_path.with_file_name(exporter::markdown::named_session_filename(
+            &session,
+            Some(&title),
+        ));
+        if !exporter::encryption::exists(&renamed) {
+            markdown_path = renamed;
+        }
+    }
+
+    exporter::encryption::write(&markdown_path, &content).await?;
+    if markdown_path != synced.markdown_path {
+        tokio::fs::remove_file(exporter::encryption::stored_path(&synced.markdown_path)).await?;
+        exporter::public::remove(&synced.markdown_path).await?;
+        exporter::command::rename(&synced.markdown_path, &markdown_path).await?;
+    }
+    exporter::mirror(&markdown_path).await?;
+
+    tracker.record_file(&markdown_path).await;
+    tracker
+        .update_session(
+            synced.session_id.clone(),
+            synced.file_path.clone(),
+            markdown_path.clone(),
+            synced.synced_message_count,
+        )
+        .await?;
+    tracker.save_state().await?;
+
+    if let Ok(mut console) = console.lock() {
+        console.session_named(&markdown_path)?;
+    }
+    Ok(())
+}
+
+/// One line typed at the terminal, or empty if there is none
+fn prompt(question: &str) -> String {
+    dialoguer::Input::<String>::new()
+        .with_prompt(question)
+        .allow_empty(true)
+        .interact_text()
+        .map(|answer| answer.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// `content` of a history file with `title` (if not empty) in its
+/// frontmatter and heading, and `tags` (if any) in its frontmatter
+fn apply_name(content: &str, title: &str, tags: &[String]) -> Result<String> {
+    let mut content = content.to_string();
+    if !title.is_empty() {
+        // Quoted so that `:` or `#` in the title can't break the YAML frontmatter
+        content = frontmatter::set_field(&content, "title", &serde_json::to_string(title)?);
+        if let Some(start) = content.find("\n# ") {
+            let end = content[start + 1..]
+                .find('\n')
+                .map_or(content.len(), |end| start + 1 + end);
+            content.replace_range(start + 1..end, &format!("# {}", title));
+        }
+    }
+    if !tags.is_empty() {
+        content = frontmatter::set_field(&content, "tags", &frontmatter::format_list(tags));
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_name_sets_title_heading_and_tags() {
+        let content = "---\nprovider: claude\nmessage_count: 1\n---\n\n# hi\n\n## User\n\nhi\n";
+        let named = apply_name(
+            content,
+            "Fix the login: redirect loop",
+            &["auth".to_string(), "bug".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            named,
+            "---\nprovider: claude\nmessage_count: 1\ntitle: \"Fix the login: redirect loop\"\ntags: auth, bug\n---\n\n# Fix the login: redirect loop\n\n## User\n\nhi\n"
+        );
+        let fm = frontmatter::parse_frontmatter_str(&named);
+        assert_eq!(fm.title.as_deref(), Some("Fix the login: redirect loop"));
+        assert_eq!(fm.tags, vec!["auth", "bug"]);
+
+        // Tags alone leave the title as it was
+        let tagged = apply_name(content, "", &["auth".to_string()]).unwrap();
+        assert!(tagged.contains("\n# hi\n"));
+        assert!(tagged.contains("tags: auth\n"));
+        assert!(!tagged.contains("title:"));
+    }
+}
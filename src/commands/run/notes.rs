@@ -0,0 +1,75 @@
+//! Notes added from another terminal while the agent runs (`waylog note`).
+//! They arrive over the control channel and are appended to the history
+//! file of the session the agent is writing, between its messages.
+use crate::exporter;
+use crate::ipc::{self, Reply, Request};
+use crate::{providers, session};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Take notes for the session `provider` writes in `project` until dropped
+pub(crate) async fn serve(
+    tracker: Arc<session::SessionTracker>,
+    provider: Arc<dyn providers::base::Provider>,
+    project: &Path,
+) {
+    let served = ipc::serve(project, |request| {
+        let tracker = tracker.clone();
+        let provider = provider.clone();
+        async move {
+            match request {
+                Request::Note { text } => match add_note(&tracker, &provider, project, &text).await
+                {
+                    Ok(reply) => reply,
+                    Err(e) => Reply::Failed {
+                        error: e.to_string(),
+                    },
+                },
+            }
+        }
+    })
+    .await;
+    if let Err(e) = served {
+        tracing::warn!("`waylog note` is unavailable during this run: {}", e);
+    }
+}
+
+/// Append `text` to the last file of the agent's current session
+async fn add_note(
+    tracker: &session::SessionTracker,
+    provider: &Arc<dyn providers::base::Provider>,
+    project: &Path,
+    text: &str,
+) -> crate::error::Result<Reply> {
+    let not_yet = || Reply::Failed {
+        error: format!(
+            "{} has not written a message yet; try again after the first one",
+            provider.name()
+        ),
+    };
+    let Some(session_file) = provider.find_latest_session(project).await? else {
+        return Ok(not_yet());
+    };
+    let state = tracker.get_state().await;
+    let Some(markdown_path) = state
+        .sessions
+        .values()
+        .find(|s| s.file_path == session_file)
+        .map(|s| s.markdown_path.clone())
+    else {
+        return Ok(not_yet());
+    };
+
+    let _writing = tracker.lock_writes().await;
+    // A file edited by hand stays marked as such
+    let edited = tracker.is_edited(&markdown_path).await;
+    let last = exporter::markdown::parts::existing_parts(&markdown_path)
+        .pop()
+        .unwrap_or_else(|| markdown_path.clone());
+    exporter::markdown::append_note(&last, text, &chrono::Utc::now()).await?;
+    if !edited {
+        tracker.record_file(&markdown_path).await;
+    }
+
+    Ok(Reply::Noted { markdown_path })
+}
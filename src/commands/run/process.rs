@@ -1,9 +1,72 @@
+use super::pty::PtyChild;
+use std::io;
+use std::process::ExitStatus;
 use tokio::process::Child;
 use tokio::time::{timeout, Duration};
 use tracing;
 
+/// The running agent, sharing our terminal or in a pseudo-terminal of its own
+pub(crate) enum AgentChild {
+    Inherited(Child),
+    Pty(PtyChild),
+}
+
+impl AgentChild {
+    pub(crate) fn id(&self) -> Option<u32> {
+        match self {
+            AgentChild::Inherited(child) => child.id(),
+            AgentChild::Pty(child) => child.id(),
+        }
+    }
+
+    pub(crate) async fn wait(&mut self) -> io::Result<ExitStatus> {
+        match self {
+            AgentChild::Inherited(child) => child.wait().await,
+            AgentChild::Pty(child) => child.wait().await,
+        }
+    }
+
+    /// Kill the agent and wait for it to exit
+    async fn kill(&mut self) -> io::Result<()> {
+        match self {
+            AgentChild::Inherited(child) => child.kill().await,
+            AgentChild::Pty(child) => {
+                child.kill()?;
+                child.wait().await.map(|_| ())
+            }
+        }
+    }
+}
+
+/// How long an agent asked to stop by `--timeout` gets to save its state
+const STOP_GRACE: Duration = Duration::from_secs(10);
+
+/// Resolves when `timeout` has passed; never without one
+pub(crate) async fn deadline(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Ask the child to exit (SIGTERM on Unix) so it can flush its transcript,
+/// and kill it if it is still running after a grace period
+pub(crate) async fn stop_child(child: &mut AgentChild) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: kill() only sends a signal; the pid is our own child, which
+        // has not been reaped yet since `id()` still returns it
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } == 0
+            && timeout(STOP_GRACE, child.wait()).await.is_ok()
+        {
+            return;
+        }
+    }
+    terminate_child(child).await;
+}
+
 /// Terminate child process with timeout
-pub(crate) async fn terminate_child(child: &mut Child) {
+pub(crate) async fn terminate_child(child: &mut AgentChild) {
     // Try to kill the child process
     if let Err(e) = child.kill().await {
         // Child might have already exited
@@ -0,0 +1,315 @@
+//! Running the agent in a pseudo-terminal. The agent sees a real terminal of
+//! the same size as ours, our terminal is put in raw mode so every key
+//! (Ctrl+C included) reaches the agent as typed, and the agent's output is
+//! copied back to our stdout.
+use super::record::Recorder;
+use portable_pty::{ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// A recording shared by the output and resize forwarding
+type SharedRecorder = Arc<Mutex<Recorder>>;
+
+/// An agent started in a pseudo-terminal
+pub(crate) struct PtyChild {
+    pid: Option<u32>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    /// Set once the agent has exited
+    status: watch::Receiver<Option<Result<ExitStatus, String>>>,
+    /// Restores the terminal when dropped
+    raw_mode: Option<RawMode>,
+    /// Forwards size changes of our terminal; holds the pty open
+    resizer: JoinHandle<()>,
+}
+
+impl PtyChild {
+    /// Start `command` with `args` and `env` in a new pseudo-terminal,
+    /// recording its output on `recorder`
+    pub(crate) fn spawn(
+        command: &[String],
+        args: &[String],
+        env: Vec<(OsString, OsString)>,
+        recorder: Option<Recorder>,
+    ) -> io::Result<Self> {
+        let pair = portable_pty::native_pty_system()
+            .openpty(terminal_size())
+            .map_err(io::Error::other)?;
+
+        let mut builder = CommandBuilder::new(&command[0]);
+        builder.args(&command[1..]);
+        builder.args(args);
+        builder.env_clear();
+        for (key, value) in env {
+            builder.env(key, value);
+        }
+        builder.cwd(std::env::current_dir()?);
+
+        let mut child = pair
+            .slave
+            .spawn_command(builder)
+            .map_err(io::Error::other)?;
+        // Only the agent holds the terminal side open, so reading the master
+        // ends when the agent and anything it started have exited
+        drop(pair.slave);
+
+        let reader = pair.master.try_clone_reader().map_err(io::Error::other)?;
+        let writer = pair.master.take_writer().map_err(io::Error::other)?;
+        let raw_mode = RawMode::enable();
+        let recorder = recorder.map(|recorder| Arc::new(Mutex::new(recorder)));
+
+        let output_recorder = recorder.clone();
+        std::thread::spawn(move || copy_output(reader, output_recorder));
+        let exited = Arc::new(AtomicBool::new(false));
+        let input_exited = exited.clone();
+        std::thread::spawn(move || copy_input(writer, &input_exited));
+
+        let pid = child.process_id();
+        let killer = child.clone_killer();
+        let (tx, status) = watch::channel(None);
+        std::thread::spawn(move || {
+            let status = child.wait().map(exit_status).map_err(|e| e.to_string());
+            exited.store(true, Ordering::Relaxed);
+            let _ = tx.send(Some(status));
+        });
+
+        Ok(Self {
+            pid,
+            killer,
+            status,
+            raw_mode,
+            resizer: tokio::spawn(forward_resizes(pair.master, recorder)),
+        })
+    }
+
+    pub(crate) fn id(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Wait for the agent to exit, then give the terminal back
+    pub(crate) async fn wait(&mut self) -> io::Result<ExitStatus> {
+        let status = self
+            .status
+            .wait_for(Option::is_some)
+            .await
+            .map_err(|_| io::Error::other("lost track of the agent"))?
+            .clone();
+        self.raw_mode.take();
+        self.resizer.abort();
+        status
+            .unwrap_or_else(|| Err("no exit status".to_string()))
+            .map_err(io::Error::other)
+    }
+
+    pub(crate) fn kill(&mut self) -> io::Result<()> {
+        self.killer.kill()
+    }
+}
+
+impl Drop for PtyChild {
+    fn drop(&mut self) {
+        self.resizer.abort();
+    }
+}
+
+/// Our terminal in raw mode, until dropped
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        match crossterm::terminal::enable_raw_mode() {
+            Ok(()) => {
+                crate::output::set_raw_terminal(true);
+                Some(Self)
+            }
+            Err(e) => {
+                tracing::warn!("Could not put the terminal in raw mode: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        crate::output::set_raw_terminal(false);
+        if let Err(e) = crossterm::terminal::disable_raw_mode() {
+            tracing::warn!("Could not restore the terminal: {}", e);
+        }
+    }
+}
+
+/// Size of our terminal, or 80x24 if it can't be told
+pub(crate) fn terminal_size() -> PtySize {
+    match crossterm::terminal::size() {
+        Ok((cols, rows)) if cols > 0 && rows > 0 => PtySize {
+            rows,
+            cols,
+            ..PtySize::default()
+        },
+        _ => PtySize::default(),
+    }
+}
+
+/// Resize the pty whenever our terminal is resized
+async fn forward_resizes(master: Box<dyn MasterPty + Send>, recorder: Option<SharedRecorder>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut resized) = signal(SignalKind::window_change()) else {
+            return std::future::pending().await;
+        };
+        while resized.recv().await.is_some() {
+            resize(&*master, recorder.as_ref(), terminal_size());
+        }
+    }
+
+    // No resize signal on Windows; check the size a few times a second
+    #[cfg(windows)]
+    {
+        let mut size = terminal_size();
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(250));
+        loop {
+            interval.tick().await;
+            let current = terminal_size();
+            if current != size {
+                size = current;
+                resize(&*master, recorder.as_ref(), size);
+            }
+        }
+    }
+}
+
+/// Resize the pty to `size`, and note it in the recording
+fn resize(master: &(dyn MasterPty + Send), recorder: Option<&SharedRecorder>, size: PtySize) {
+    if let Err(e) = master.resize(size) {
+        tracing::debug!("Could not resize the agent's terminal: {}", e);
+    }
+    if let Some(recorder) = recorder {
+        if let Ok(mut recorder) = recorder.lock() {
+            if let Err(e) = recorder.resize(size.cols, size.rows) {
+                tracing::debug!("Could not record a resize: {}", e);
+            }
+        }
+    }
+}
+
+/// Copy the agent's output to our stdout (and the recording) as it comes
+fn copy_output(mut reader: Box<dyn Read + Send>, recorder: Option<SharedRecorder>) {
+    let mut stdout = io::stdout();
+    let mut buf = [0; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            // EIO on Linux once the agent side is closed
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if let Some(recorder) = &recorder {
+                    if let Ok(mut recorder) = recorder.lock() {
+                        if let Err(e) = recorder.output(&buf[..n]) {
+                            tracing::debug!("Could not record the agent's output: {}", e);
+                        }
+                    }
+                }
+                if stdout
+                    .write_all(&buf[..n])
+                    .and_then(|_| stdout.flush())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Copy our stdin to the agent as it is typed, until it exits
+fn copy_input(mut writer: Box<dyn Write + Send>, exited: &AtomicBool) {
+    let mut stdin = io::stdin();
+    let mut buf = [0; 1024];
+    loop {
+        if !stdin_ready(exited) {
+            return;
+        }
+        match stdin.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if writer
+                    .write_all(&buf[..n])
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Wait until our stdin has input, or return false once the agent has
+/// exited, so that what is typed after it (e.g. the answer to `--ask-name`)
+/// stays for waylog to read
+#[cfg(unix)]
+fn stdin_ready(exited: &AtomicBool) -> bool {
+    const POLL_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    while !exited.load(Ordering::Relaxed) {
+        // SAFETY: `fd` is one valid pollfd for the duration of the call
+        match unsafe { libc::poll(&mut fd, 1, POLL_TIMEOUT.as_millis() as libc::c_int) } {
+            0 => continue,
+            n if n < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {
+                continue
+            }
+            _ => return true,
+        }
+    }
+    false
+}
+
+/// No way to wait on the console with a timeout here; the read blocks until
+/// the next key after the agent exits, and that key is lost, which is why
+/// `--ask-name` runs the agent without a pseudo-terminal on Windows
+#[cfg(windows)]
+fn stdin_ready(exited: &AtomicBool) -> bool {
+    !exited.load(Ordering::Relaxed)
+}
+
+/// The std exit status for the agent's exit code
+fn exit_status(status: portable_pty::ExitStatus) -> ExitStatus {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(((status.exit_code() & 0xff) as i32) << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(status.exit_code())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_exit_code_of_an_agent_in_a_pty() {
+        let command = vec!["sh".to_string(), "-c".to_string()];
+        let mut child = PtyChild::spawn(
+            &command,
+            &["test -t 0 && exit 3".to_string()],
+            std::env::vars_os().collect(),
+            None,
+        )
+        .unwrap();
+        assert!(child.id().is_some());
+        assert_eq!(child.wait().await.unwrap().code(), Some(3));
+    }
+}
@@ -0,0 +1,143 @@
+//! Recordings of the agent's terminal (`run --record`, `[run] record`),
+//! written to `.waylog/recordings/` as the output passes through the
+//! pseudo-terminal: an asciinema v2 cast, or the raw output as a plain log.
+use crate::config::Recording;
+use crate::init::{subdirs, WAYLOG_DIR};
+use chrono::Utc;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Where a new recording of `provider`'s terminal is written
+pub(crate) fn recording_path(project: &Path, provider: &str, format: Recording) -> PathBuf {
+    let extension = match format {
+        Recording::Log => "log",
+        Recording::Off | Recording::Cast => "cast",
+    };
+    project
+        .join(WAYLOG_DIR)
+        .join(subdirs::RECORDINGS)
+        .join(format!(
+            "{}-{}.{}",
+            Utc::now().format("%Y-%m-%d_%H-%M-%SZ"),
+            provider,
+            extension
+        ))
+}
+
+/// A terminal recording being written
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+    format: Recording,
+    started: Instant,
+    /// The start of a UTF-8 character cut off at the end of the last chunk
+    pending: Vec<u8>,
+}
+
+impl Recorder {
+    /// Start a recording of a `cols` x `rows` terminal at `path`
+    pub(crate) fn create(path: &Path, format: Recording, cols: u16, rows: u16) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = BufWriter::new(File::create(path)?);
+        if format == Recording::Cast {
+            let header = serde_json::json!({
+                "version": 2,
+                "width": cols,
+                "height": rows,
+                "timestamp": Utc::now().timestamp(),
+                "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+            });
+            writeln!(out, "{}", header)?;
+            out.flush()?;
+        }
+        Ok(Self {
+            out,
+            format,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Record output of the agent
+    pub(crate) fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.format != Recording::Cast {
+            self.out.write_all(data)?;
+            return self.out.flush();
+        }
+
+        // Cast events are text, so a character split between two reads
+        // waits for the rest of it
+        self.pending.extend_from_slice(data);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.event("o", &text)
+    }
+
+    /// Record that the terminal was resized
+    pub(crate) fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        if self.format != Recording::Cast {
+            return Ok(());
+        }
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    fn event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        writeln!(self.out, "{}", serde_json::json!([time, kind, data]))?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cast_keeps_split_characters_whole() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.cast");
+        let mut recorder = Recorder::create(&path, Recording::Cast, 100, 30).unwrap();
+        let text = "héllo\r\n".as_bytes();
+        recorder.output(&text[..2]).unwrap();
+        recorder.output(&text[2..]).unwrap();
+        recorder.resize(120, 40).unwrap();
+        drop(recorder);
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 100);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "h");
+        assert_eq!(lines[2][2], "éllo\r\n");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "120x40");
+    }
+
+    #[test]
+    fn test_log_is_the_raw_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.log");
+        let mut recorder = Recorder::create(&path, Recording::Log, 80, 24).unwrap();
+        recorder.output(b"\x1b[1mbold\x1b[0m\r\n").unwrap();
+        recorder.resize(100, 30).unwrap();
+        drop(recorder);
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"\x1b[1mbold\x1b[0m\r\n");
+    }
+}
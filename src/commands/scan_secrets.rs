@@ -0,0 +1,91 @@
+use super::redact::split_frontmatter;
+use crate::error::{Result, WaylogError};
+use crate::exporter::encryption;
+use crate::exporter::redact::Redactor;
+use crate::output::Output;
+use crate::utils::path;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub async fn handle_scan_secrets(
+    paths: Vec<PathBuf>,
+    fix: bool,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let history_dir = path::get_waylog_dir(&project_path);
+    let roots = if !paths.is_empty() {
+        paths
+    } else if history_dir.exists() {
+        vec![history_dir.clone()]
+    } else {
+        Vec::new()
+    };
+    let files = markdown_files(&roots)?;
+
+    // The audit is for history written without redaction, so it looks for
+    // secrets even where `[redaction]` is turned off
+    let mut config = crate::config::get().redaction.clone();
+    config.enabled = true;
+    config.builtin = true;
+    let redactor = Redactor::new(&config);
+
+    let mut found = 0;
+    let mut affected = 0;
+    for file in &files {
+        let content = encryption::read(file).await?;
+        let (frontmatter, body) = split_frontmatter(&content);
+        let findings = redactor.scan(body);
+        if findings.is_empty() {
+            continue;
+        }
+
+        let shown = file.strip_prefix(&project_path).unwrap_or(file);
+        let lines_before = frontmatter.matches('\n').count();
+        for mut finding in findings {
+            finding.line += lines_before;
+            output.secret_found(shown, &finding)?;
+            found += 1;
+        }
+        affected += 1;
+
+        if fix {
+            encryption::write(file, format!("{}{}", frontmatter, redactor.redact(body))).await?;
+            if is_within(file, &history_dir) {
+                crate::exporter::mirror(file).await?;
+                crate::session::edits::ExportedFiles::refresh(&project_path, file)?;
+            }
+        }
+    }
+
+    output.scan_summary(found, affected, files.len(), fix)?;
+    if found > 0 && !fix {
+        return Err(WaylogError::SecretsFound(found));
+    }
+    Ok(())
+}
+
+/// The markdown files (encrypted ones by their `.md` name) among `paths` and
+/// in the directories among them, sorted
+fn markdown_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        for entry in WalkDir::new(path) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.file_type().is_file() {
+                files.extend(encryption::history_path(entry.path()));
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Whether `file` is in `dir`, however either was spelled
+fn is_within(file: &Path, dir: &Path) -> bool {
+    match (file.canonicalize(), dir.canonicalize()) {
+        (Ok(file), Ok(dir)) => file.starts_with(dir),
+        _ => false,
+    }
+}
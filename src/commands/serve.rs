@@ -0,0 +1,316 @@
+//! `waylog serve`: the history as JSON over HTTP, for dashboards, editor
+//! plugins and scripts. Only `GET` is answered, one request per connection.
+//!
+//! - `/sessions`: the synced sessions, most recently updated first
+//! - `/sessions/{id}`: one session with its markdown
+//! - `/search?q=`: sessions containing all the words, with a snippet
+//!
+//! The lists take `provider`, `since` and `until` (YYYY-MM-DD or RFC 3339,
+//! compared with the session's last update), `page` (from 1) and `per_page`.
+use crate::error::Result;
+use crate::output::Output;
+use crate::session::{access, catalog};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Sessions per page unless `per_page` says otherwise, and the most it may say
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
+
+/// Longest request head read; anything longer is refused with 431
+const MAX_REQUEST: usize = 16 * 1024;
+
+/// How long a connection may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn handle_serve(
+    bind: String,
+    port: u16,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let listener = TcpListener::bind((bind.as_str(), port)).await?;
+    let local = listener.local_addr()?;
+    output.serving(&local)?;
+
+    // Only names of this host are answered, so a web page that rebinds its
+    // own name to the loopback address can't read the history
+    let hosts: Arc<[String]> = [bind, local.ip().to_string()]
+        .into_iter()
+        .chain(["localhost".to_string(), "127.0.0.1".to_string()])
+        .collect();
+    let project = Arc::new(project_path);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let (project, hosts) = (project.clone(), hosts.clone());
+        tokio::spawn(async move {
+            if let Err(e) = connection(stream, peer, &project, &hosts).await {
+                tracing::debug!("Answering {}: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Read one request from `stream` and write back the reply
+async fn connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    project: &Path,
+    hosts: &[String],
+) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0; 4096];
+    let read = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        Ok::<_, std::io::Error>(())
+    })
+    .await;
+    if !matches!(read, Ok(Ok(()))) {
+        return Ok(());
+    }
+
+    let complete = head.windows(4).any(|w| w == b"\r\n\r\n");
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (
+        request_line.next().unwrap_or(""),
+        request_line.next().unwrap_or("/"),
+    );
+    let (status, body) = if !complete && head.len() >= MAX_REQUEST {
+        (431, error("request head too large"))
+    } else if !host_allowed(&head, hosts) {
+        (
+            403,
+            error("host not allowed; use localhost or the bound address"),
+        )
+    } else {
+        respond(project, method, target).await
+    };
+    tracing::debug!("{} {} {} -> {}", peer, method, target, status);
+
+    let body = serde_json::to_vec_pretty(&body)?;
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    };
+    let mut reply = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n{}\r\n",
+        status,
+        reason,
+        body.len(),
+        if status == 405 { "allow: GET\r\n" } else { "" }
+    )
+    .into_bytes();
+    reply.extend_from_slice(&body);
+    stream.write_all(&reply).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Status and JSON body of the reply to `method target`
+async fn respond(project: &Path, method: &str, target: &str) -> (u16, Value) {
+    if method != "GET" {
+        return (405, error("only GET is supported"));
+    }
+    let Ok(url) = reqwest::Url::parse(&format!("http://localhost{}", target)) else {
+        return (400, error("malformed request target"));
+    };
+    let query = match Query::parse(&url) {
+        Ok(query) => query,
+        Err(message) => return (400, error(&message)),
+    };
+
+    let segments: Vec<_> = url.path_segments().into_iter().flatten().collect();
+    let reply = match segments.as_slice() {
+        ["sessions"] => catalog::list(project).await.map(|sessions| {
+            let sessions: Vec<_> = sessions
+                .into_iter()
+                .filter(|s| query.matches(&s.provider, s.updated_at))
+                .collect();
+            query.page("sessions", sessions)
+        }),
+        ["sessions", id] if !id.is_empty() => match catalog::read(project, id).await {
+            Ok((session, markdown)) => {
+                if let Err(e) = access::record(project, "serve", &[&session.session_id], None) {
+                    return (500, error(&e.to_string()));
+                }
+                let mut body = serde_json::to_value(&session).unwrap_or_default();
+                body["markdown"] = Value::String(markdown);
+                Ok(body)
+            }
+            Err(crate::error::WaylogError::SessionNotFound(id)) => {
+                return (404, error(&format!("no synced session matches '{}'", id)))
+            }
+            Err(e) => Err(e),
+        },
+        ["search"] => {
+            let Some(q) = &query.q else {
+                return (400, error("missing q"));
+            };
+            catalog::search(project, q).await.map(|hits| {
+                let hits: Vec<_> = hits
+                    .into_iter()
+                    .filter(|h| query.matches(&h.session.provider, h.session.updated_at))
+                    .collect();
+                query.page("results", hits)
+            })
+        }
+        _ => {
+            return (
+                404,
+                error("not found; try /sessions, /sessions/{id} or /search?q="),
+            )
+        }
+    };
+
+    match reply {
+        Ok(body) => (200, body),
+        Err(e) => (500, error(&e.to_string())),
+    }
+}
+
+/// Whether the `Host` header of the request `head` names one of `hosts`,
+/// with or without a port
+fn host_allowed(head: &str, hosts: &[String]) -> bool {
+    let Some(host) = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then(|| value.trim())
+    }) else {
+        return false;
+    };
+    let name = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(""),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    hosts.iter().any(|h| h.eq_ignore_ascii_case(name))
+}
+
+fn error(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// Filters and page of a list request
+#[derive(Debug, Default)]
+struct Query {
+    q: Option<String>,
+    provider: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    page: usize,
+    per_page: usize,
+}
+
+impl Query {
+    fn parse(url: &reqwest::Url) -> std::result::Result<Self, String> {
+        let mut query = Self {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+            ..Self::default()
+        };
+        for (key, value) in url.query_pairs() {
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("{} must be a positive number, got '{}'", key, value))
+            };
+            match key.as_ref() {
+                "q" => query.q = Some(value.to_string()),
+                "provider" => query.provider = Some(value.to_string()),
+                "since" => query.since = Some(crate::cli::parse_date(&value)?),
+                "until" => query.until = Some(crate::cli::parse_date(&value)?),
+                "page" => query.page = number()?,
+                "per_page" => query.per_page = number()?.min(MAX_PER_PAGE),
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+
+    /// Whether a session of `provider` last updated at `updated_at` passes the filters
+    fn matches(&self, provider: &str, updated_at: Option<DateTime<Utc>>) -> bool {
+        self.provider.as_deref().is_none_or(|p| p == provider)
+            && self
+                .since
+                .is_none_or(|since| updated_at.is_some_and(|at| at >= since))
+            && self
+                .until
+                .is_none_or(|until| updated_at.is_some_and(|at| at < until))
+    }
+
+    /// The requested page of `items`, under `key`, with the paging details
+    fn page<T: Serialize>(&self, key: &str, items: Vec<T>) -> Value {
+        let total = items.len();
+        let page: Vec<T> = items
+            .into_iter()
+            .skip((self.page - 1).saturating_mul(self.per_page))
+            .take(self.per_page)
+            .collect();
+        let mut body = json!({
+            "total": total,
+            "page": self.page,
+            "per_page": self.per_page,
+        });
+        body[key] = serde_json::to_value(page).unwrap_or_default();
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_filters_and_pages() {
+        let url = reqwest::Url::parse(
+            "http://localhost/sessions?provider=claude&since=2025-01-01&page=2&per_page=2",
+        )
+        .unwrap();
+        let query = Query::parse(&url).unwrap();
+        let at = |s: &str| Some(DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc));
+        assert!(query.matches("claude", at("2025-01-02T00:00:00Z")));
+        assert!(!query.matches("codex", at("2025-01-02T00:00:00Z")));
+        assert!(!query.matches("claude", at("2024-12-31T23:59:59Z")));
+        assert!(!query.matches("claude", None));
+
+        let body = query.page("sessions", vec![1, 2, 3, 4, 5]);
+        assert_eq!(body["sessions"], json!([3, 4]));
+        assert_eq!(body["total"], 5);
+
+        let url = reqwest::Url::parse("http://localhost/sessions?page=0").unwrap();
+        assert!(Query::parse(&url).is_err());
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let hosts = ["::1".to_string(), "localhost".to_string()];
+        let head = |host: &str| format!("GET / HTTP/1.1\r\n{}\r\n\r\n", host);
+        assert!(host_allowed(&head("Host: localhost:8080"), &hosts));
+        assert!(host_allowed(&head("host: LOCALHOST"), &hosts));
+        assert!(host_allowed(&head("Host: [::1]:8080"), &hosts));
+        assert!(!host_allowed(&head("Host: evil.example:8080"), &hosts));
+        assert!(!host_allowed(&head("Host: localhost.evil.example"), &hosts));
+        assert!(!host_allowed(&head("Accept: */*"), &hosts));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::Result;
+use crate::exporter::markdown::parts;
+use crate::exporter::site::{self, SiteSession};
+use crate::output::Output;
+use crate::session::access::{self, Target};
+use crate::session::catalog;
+use std::path::PathBuf;
+
+pub async fn handle_site(
+    out: Option<PathBuf>,
+    title: Option<String>,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let out = out.unwrap_or_else(|| project_path.join("site"));
+    let title = title.unwrap_or_else(|| {
+        project_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Sessions".to_string())
+    });
+
+    let mut sessions = Vec::new();
+    for summary in catalog::list(&project_path).await? {
+        let mut markdown = String::new();
+        for part in parts::existing_parts(&project_path.join(&summary.markdown_path)) {
+            let content = crate::exporter::encryption::read(&part).await?;
+            markdown.push_str(site::strip_frontmatter(&content));
+        }
+        sessions.push(SiteSession { summary, markdown });
+    }
+    let ids: Vec<&str> = sessions
+        .iter()
+        .map(|s| s.summary.session_id.as_str())
+        .collect();
+    access::record(&project_path, "site", &ids, Some(Target::File(&out)))?;
+
+    let pages = site::build(&title, &sessions);
+    for (page, html) in &pages {
+        let path = out.join(page);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, html)?;
+    }
+    // GitHub Pages would otherwise run the site through Jekyll
+    std::fs::write(out.join(".nojekyll"), "")?;
+
+    output.site_built(&out, sessions.len(), pages.len())?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::session::snapshot::Snapshot;
+use crate::session::SessionTracker;
+use std::path::{Path, PathBuf};
+
+pub use crate::cli::SnapshotAction;
+
+pub async fn handle_snapshot(
+    action: SnapshotAction,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    match action {
+        SnapshotAction::Create { name } => {
+            let snapshot = current(&name, &project_path).await?;
+            snapshot.save(&project_path)?;
+            output.snapshot_created(&snapshot)?;
+        }
+        SnapshotAction::List => {
+            let snapshots = Snapshot::list(&project_path)?;
+            for snapshot in &snapshots {
+                output.snapshot_entry(snapshot)?;
+            }
+            if snapshots.is_empty() {
+                output.no_snapshots()?;
+            }
+        }
+        SnapshotAction::Diff { from, to } => {
+            let from = Snapshot::load(&project_path, &from)?;
+            let to = match to {
+                Some(name) => Some(Snapshot::load(&project_path, &name)?),
+                None => None,
+            };
+            report_changes(&from, to.as_ref(), &project_path, output).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The history as it is now
+async fn current(name: &str, project_path: &Path) -> Result<Snapshot> {
+    let state = SessionTracker::load_project_state(project_path).await?;
+    Ok(Snapshot::capture(name, &state))
+}
+
+/// Print how the history changed from `from` to `to`, or to now
+pub(crate) async fn report_changes(
+    from: &Snapshot,
+    to: Option<&Snapshot>,
+    project_path: &Path,
+    output: &mut Output,
+) -> Result<()> {
+    let changes = match to {
+        Some(to) => from.changes_to(to),
+        None => from.changes_to(&current("current", project_path).await?),
+    };
+    for change in &changes {
+        output.diff_change(change)?;
+    }
+    output.diff_summary(from, to, &changes)?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::providers::{self, base::ChatMessage, base::ParseCursor, base::Provider};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// How often the followed session file is checked for new messages
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Look for a newer session to switch to every this many polls
+const SWITCH_CHECK_POLLS: u32 = 4;
+
+/// Follow the project's latest session and print its messages as they are
+/// written, switching to a newer session when one starts. Runs until
+/// interrupted.
+pub async fn handle_tail(
+    provider: Option<String>,
+    lines: usize,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let providers = match provider {
+        Some(name) => vec![providers::get_provider(&name)?],
+        None => providers::enabled_providers(),
+    };
+
+    let mut followed: Option<Followed> = None;
+    let mut waiting = false;
+    let mut poll: u32 = 0;
+    loop {
+        if followed.is_none() || poll.is_multiple_of(SWITCH_CHECK_POLLS) {
+            match latest_session(&providers, &project_path).await {
+                Some((provider, file)) if followed.as_ref().is_none_or(|f| f.file != file) => {
+                    output.tail_following(provider.name(), &file)?;
+                    let mut next = Followed::new(provider, file);
+                    // Only the end of the session that was already there;
+                    // all of one that starts while following
+                    let messages = next.read_new().await;
+                    let skip = match followed {
+                        None => messages.len().saturating_sub(lines),
+                        Some(_) => 0,
+                    };
+                    output.tail_messages(&messages[skip..])?;
+                    followed = Some(next);
+                }
+                Some(_) => {}
+                None if !waiting => {
+                    output.tail_waiting(&project_path)?;
+                    waiting = true;
+                }
+                None => {}
+            }
+        }
+
+        if let Some(followed) = &mut followed {
+            let messages = followed.read_new().await;
+            output.tail_messages(&messages)?;
+        }
+        poll = poll.wrapping_add(1);
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// The most recently modified latest session of `providers`
+async fn latest_session(
+    providers: &[Arc<dyn Provider>],
+    project_path: &Path,
+) -> Option<(Arc<dyn Provider>, PathBuf)> {
+    let mut latest = None;
+    for provider in providers {
+        let file = match provider.find_latest_session(project_path).await {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::debug!("No {} sessions: {}", provider.name(), e);
+                continue;
+            }
+        };
+        let modified = modified(&file);
+        if latest
+            .as_ref()
+            .is_none_or(|(_, _, newest)| modified > *newest)
+        {
+            latest = Some((provider.clone(), file, modified));
+        }
+    }
+    latest.map(|(provider, file, _)| (provider, file))
+}
+
+fn modified(file: &Path) -> Option<SystemTime> {
+    std::fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+/// A session file being followed and how far it has been read
+struct Followed {
+    provider: Arc<dyn Provider>,
+    file: PathBuf,
+    cursor: Option<ParseCursor>,
+    /// Messages read so far, for providers that can't resume from a cursor
+    seen: usize,
+    /// Size and modification time when last read in full
+    stamp: Option<(u64, Option<SystemTime>)>,
+}
+
+impl Followed {
+    fn new(provider: Arc<dyn Provider>, file: PathBuf) -> Self {
+        Self {
+            provider,
+            file,
+            cursor: None,
+            seen: 0,
+            stamp: None,
+        }
+    }
+
+    /// Messages written since the last read. A file caught mid-write is
+    /// read again on the next poll.
+    async fn read_new(&mut self) -> Vec<ChatMessage> {
+        let stamp = std::fs::metadata(&self.file)
+            .map(|m| (m.len(), m.modified().ok()))
+            .ok();
+        if stamp.is_some() && stamp == self.stamp {
+            return Vec::new();
+        }
+
+        match self.parse().await {
+            Ok(messages) => {
+                self.stamp = stamp;
+                messages
+            }
+            Err(e) => {
+                tracing::debug!("Reading {}: {}", self.file.display(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn parse(&mut self) -> Result<Vec<ChatMessage>> {
+        let parsed = self
+            .provider
+            .parse_session_from(&self.file, self.cursor.as_ref(), None)
+            .await?;
+        if let Some((session, cursor)) = parsed {
+            self.cursor = Some(cursor);
+            return Ok(session.messages);
+        }
+
+        let session = self.provider.parse_session(&self.file).await?;
+        let messages: Vec<_> = session.messages.into_iter().skip(self.seen).collect();
+        self.seen += messages.len();
+        Ok(messages)
+    }
+}
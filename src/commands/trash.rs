@@ -0,0 +1,33 @@
+use crate::error::Result;
+use crate::exporter;
+use crate::output::Output;
+use crate::session::trash;
+use std::path::PathBuf;
+
+pub use crate::cli::TrashAction;
+
+pub async fn handle_trash(
+    action: TrashAction,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    trash::purge_expired(&project_path, crate::config::get().trash.retention_days)?;
+
+    match action {
+        TrashAction::List => {
+            let entries = trash::list(&project_path)?;
+            for entry in &entries {
+                output.trash_entry(&entry.id(&project_path), entry.deleted_at)?;
+            }
+            output.trash_summary(entries.len(), crate::config::get().trash.retention_days)?;
+        }
+        TrashAction::Restore { entry } => {
+            let entry = trash::find(&project_path, &entry)?;
+            trash::restore(&project_path, &entry)?;
+            exporter::mirror(&entry.original).await?;
+            output.trash_restored(&entry.original)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use crate::error::Result;
+use crate::output::Output;
+use crate::providers;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// Session files of one provider, summed up
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProviderUsage {
+    pub sessions: usize,
+    pub bytes: u64,
+    /// Oldest and newest last-modified time of the session files
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl ProviderUsage {
+    pub fn add(&mut self, bytes: u64, modified: Option<DateTime<Utc>>) {
+        self.sessions += 1;
+        self.bytes += bytes;
+        if let Some(modified) = modified {
+            self.first = Some(self.first.map_or(modified, |first| first.min(modified)));
+            self.last = Some(self.last.map_or(modified, |last| last.max(modified)));
+        }
+    }
+
+    pub fn merge(&mut self, other: &ProviderUsage) {
+        self.sessions += other.sessions;
+        self.bytes += other.bytes;
+        for date in [other.first, other.last].into_iter().flatten() {
+            self.first = Some(self.first.map_or(date, |first| first.min(date)));
+            self.last = Some(self.last.map_or(date, |last| last.max(date)));
+        }
+    }
+
+    fn from_files(files: &[PathBuf]) -> Self {
+        let mut usage = Self::default();
+        for file in files {
+            let Ok(metadata) = std::fs::metadata(file) else {
+                continue;
+            };
+            let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+            usage.add(metadata.len(), modified);
+        }
+        usage
+    }
+}
+
+/// Report session counts, disk usage and date ranges per provider, for the
+/// current project or (`global`) for every project on this machine
+pub async fn handle_usage(global: bool, project_path: PathBuf, output: &mut Output) -> Result<()> {
+    let scope = if global {
+        None
+    } else {
+        Some(project_path.as_path())
+    };
+
+    let mut total = ProviderUsage::default();
+    for name in providers::list_providers() {
+        let provider = providers::get_provider(name)?;
+        let files = match list_sessions(provider.as_ref(), scope).await {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::debug!("Could not list {} sessions: {}", name, e);
+                Vec::new()
+            }
+        };
+        let usage = ProviderUsage::from_files(&files);
+        output.usage_provider(name, &usage)?;
+        total.merge(&usage);
+    }
+    output.usage_total(&total, global)?;
+
+    Ok(())
+}
+
+async fn list_sessions(
+    provider: &dyn providers::base::Provider,
+    project_path: Option<&Path>,
+) -> Result<Vec<PathBuf>> {
+    match project_path {
+        Some(project_path) => provider.get_project_sessions(project_path).await,
+        None => provider.get_all_sessions_global().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_usage_tracks_date_range() {
+        let jan = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let mar = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let jun = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+
+        let mut claude = ProviderUsage::default();
+        claude.add(100, Some(mar));
+        claude.add(50, Some(jan));
+        claude.add(10, None);
+        assert_eq!(claude.sessions, 3);
+        assert_eq!(claude.bytes, 160);
+        assert_eq!((claude.first, claude.last), (Some(jan), Some(mar)));
+
+        let mut codex = ProviderUsage::default();
+        codex.add(40, Some(jun));
+
+        let mut total = ProviderUsage::default();
+        total.merge(&claude);
+        total.merge(&codex);
+        total.merge(&ProviderUsage::default());
+        assert_eq!(total.sessions, 4);
+        assert_eq!(total.bytes, 200);
+        assert_eq!((total.first, total.last), (Some(jan), Some(jun)));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::{Result, WaylogError};
+use crate::output::Output;
+use crate::providers;
+use crate::providers::validate::ValidationReport;
+use std::path::PathBuf;
+
+pub async fn handle_validate(
+    provider_name: Option<String>,
+    project_path: PathBuf,
+    output: &mut Output,
+) -> Result<()> {
+    let names = match provider_name {
+        Some(name) => vec![name],
+        None => crate::config::get()
+            .providers
+            .default_providers()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    };
+
+    let mut problems = 0;
+    for name in names {
+        let provider = match providers::get_provider(&name) {
+            Ok(p) => p,
+            Err(WaylogError::ProviderNotFound(invalid)) => {
+                output.unknown_provider(&invalid)?;
+                return Err(WaylogError::ProviderNotFound(invalid));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut report = ValidationReport::default();
+        for path in provider.get_project_sessions(&project_path).await? {
+            if let Err(e) = provider.validate_session(&path, &mut report).await {
+                report.error(&path, None, e.to_string());
+            }
+        }
+
+        problems += report.errors.len()
+            + report.unknown_event_types.len()
+            + report.unknown_fields.len()
+            + report.unknown_content_types.len();
+        output.validation_report(provider.name(), &report)?;
+    }
+
+    if problems > 0 {
+        return Err(WaylogError::ValidationFailed(problems));
+    }
+    Ok(())
+}
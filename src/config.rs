@@ -0,0 +1,1144 @@
+use crate::error::{Result, WaylogError};
+use crate::init::{WAYLOG_CONFIG_FILE, WAYLOG_DIR};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Project configuration, read from `.waylog/config.toml`.
+/// Every field has a default, so a missing file or missing keys are fine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub export: ExportConfig,
+    pub redaction: RedactionConfig,
+    pub encryption: EncryptionConfig,
+    pub providers: ProvidersConfig,
+    pub exclude: ExcludeConfig,
+    pub trash: TrashConfig,
+    pub audit: AuditConfig,
+    pub archive: ArchiveConfig,
+    pub sync: SyncConfig,
+    pub watch: WatchConfig,
+    pub project: ProjectConfig,
+    pub hooks: HooksConfig,
+    pub git: GitConfig,
+    pub notify: NotifyConfig,
+    pub share: ShareConfig,
+    pub ui: UiConfig,
+
+    /// Child process settings for `waylog run`
+    pub run: RunConfig,
+
+    /// Custom launchers for `waylog run <alias>` (`[agents.<alias>]`)
+    pub agents: BTreeMap<String, AgentAlias>,
+}
+
+/// Sessions that are never exported (`[exclude]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcludeConfig {
+    /// Skip sessions with fewer messages than this
+    pub min_messages: usize,
+
+    /// Skip sessions where any message matches one of these regular expressions
+    pub patterns: Vec<String>,
+
+    /// Skip these session ids
+    pub session_ids: Vec<String>,
+}
+
+/// Integrations notified as sessions are synced (`[hooks]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Fired after new messages are written to a history file (`[[hooks.message]]`)
+    pub message: Vec<Hook>,
+
+    /// Commands run before `waylog run` launches the agent; if one fails,
+    /// the agent is not started
+    pub pre_run: Vec<String>,
+
+    /// Commands run after the agent exited and its session was synced
+    pub post_run: Vec<String>,
+}
+
+/// A script or webhook that gets each event as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    /// Program run with the event on stdin
+    pub command: Option<String>,
+
+    /// URL the event is POSTed to
+    pub url: Option<String>,
+
+    /// Seconds the hook may take before it is abandoned
+    pub timeout: u64,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            command: None,
+            url: None,
+            timeout: 10,
+        }
+    }
+}
+
+/// Committing the history to the project's git repository (`[git]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit `.waylog/history` when `waylog run` ends and when `pull` writes to it
+    pub auto_commit: bool,
+
+    /// Message of a commit of one session; `{provider}`, `{title}` and
+    /// `{messages}` are filled in
+    pub message: String,
+
+    /// Message of a commit of several sessions; `{sessions}` and
+    /// `{messages}` are filled in
+    pub message_many: String,
+
+    /// What to do while the work tree has other uncommitted changes
+    pub on_dirty: DirtyPolicy,
+
+    /// What the project's `.gitignore` keeps out of git, applied by `waylog init`
+    pub gitignore: GitignorePolicy,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            auto_commit: false,
+            message: "waylog: {provider} session '{title}', {messages} msgs".to_string(),
+            message_many: "waylog: {sessions} sessions, {messages} msgs".to_string(),
+            on_dirty: DirtyPolicy::default(),
+            gitignore: GitignorePolicy::default(),
+        }
+    }
+}
+
+/// A chat webhook told about new sessions (`[notify]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Slack or Discord incoming webhook; nothing is posted without one
+    pub webhook: Option<String>,
+
+    /// Post when `waylog run` ends
+    pub on_run: bool,
+
+    /// Post when `pull` writes new messages
+    pub on_pull: bool,
+
+    /// Where the history can be read, e.g. the repository's web view of the
+    /// project; the history file's path is appended. Without it the path is posted.
+    pub link: Option<String>,
+
+    /// Price of a million tokens, to show what a session cost
+    pub price_per_million_tokens: Option<f64>,
+
+    /// Seconds the webhook may take before it is abandoned
+    pub timeout: u64,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            on_run: true,
+            on_pull: true,
+            link: None,
+            price_per_million_tokens: None,
+            timeout: 10,
+        }
+    }
+}
+
+/// How waylog talks to you (`[ui]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Language of the messages, e.g. `zh-CN`; the locale (`LANG`) otherwise
+    pub language: Option<String>,
+}
+
+/// Where `waylog gist` uploads sessions (`[share]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    /// GitHub API the gists are created with (GitHub Enterprise has its own)
+    pub github_api: String,
+
+    /// Make gists public (listed on the profile) rather than secret
+    pub public: bool,
+
+    /// Paste service used by `--paste`: the session is POSTed as the request
+    /// body and the service answers with the URL
+    pub paste_url: String,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            github_api: "https://api.github.com".to_string(),
+            public: false,
+            paste_url: "https://paste.rs".to_string(),
+        }
+    }
+}
+
+/// Auto-commits in a work tree with other changes (`[git] on_dirty`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DirtyPolicy {
+    /// Commit only the history; other changes, staged or not, stay as they are
+    #[default]
+    Commit,
+    /// Don't commit until the other changes are committed or stashed
+    Skip,
+}
+
+/// What the project's `.gitignore` keeps out of git (`[git] gitignore`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitignorePolicy {
+    /// Commit all of `.waylog` with the code (nothing is added)
+    #[default]
+    Commit,
+    /// Keep all of `.waylog/` out of git
+    Ignore,
+    /// Commit the history, but keep this machine's logs and sync state out of git
+    Local,
+}
+
+/// Where the project was before (`[project]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Paths the project had before it was moved or renamed; providers that
+    /// file sessions by project path are searched under these too
+    pub previous_paths: Vec<PathBuf>,
+}
+
+/// Syncing into existing history files (`[sync]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// What to do with new messages of a session whose file was edited by hand
+    pub on_conflict: ConflictPolicy,
+
+    /// Messages read from a session file before they are written out, so a
+    /// huge session is synced with flat memory (0 reads the whole file at once)
+    pub batch_messages: usize,
+
+    /// The history is shared between machines (git, Dropbox): new files are
+    /// named the same on every machine, in UTC and with the session id on a
+    /// name collision
+    pub shared: bool,
+
+    /// Name of this machine in the sync state (default: the host name)
+    pub machine: Option<String>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            on_conflict: ConflictPolicy::default(),
+            batch_messages: 2000,
+            shared: false,
+            machine: None,
+        }
+    }
+}
+
+/// Handling of hand-edited history files (`on_conflict`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Leave the edited file alone and write the whole session to `<name>-rev2.md`
+    #[default]
+    Revision,
+    /// Leave the edited file alone and don't sync the session, with a warning
+    Skip,
+}
+
+/// Live syncing while `waylog run` is active (`[watch]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// How changes to session files are noticed
+    pub mode: WatchMode,
+
+    /// Also sync every other installed agent's sessions of the project (`run --all-providers`)
+    pub all_providers: bool,
+
+    /// Milliseconds without file events before syncing, so a burst of writes
+    /// is synced once (`run --debounce`)
+    pub debounce_ms: u64,
+
+    /// Least milliseconds between two syncs of the same session; changes
+    /// in between wait for the next one (`run --min-sync-interval`)
+    pub min_sync_interval_ms: u64,
+
+    /// Seconds between full rescans of every watched session, in case an
+    /// event was missed, and the timer in `poll` mode (`run --rescan-interval`)
+    pub rescan_interval: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            mode: WatchMode::default(),
+            all_providers: false,
+            debounce_ms: 200,
+            min_sync_interval_ms: 1000,
+            rescan_interval: 30,
+        }
+    }
+}
+
+/// How the watcher notices new messages (`mode`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchMode {
+    /// File system events (inotify, FSEvents, ReadDirectoryChangesW)
+    #[default]
+    Events,
+    /// Sync on a timer, for file systems that deliver no events (network mounts)
+    Poll,
+}
+
+/// A copy of every project's history in one place (`[archive]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Also copy history files to `<dir>/<project slug>/` (`~` is the home
+    /// directory); `$WAYLOG_ARCHIVE` is used when not set
+    pub dir: Option<PathBuf>,
+}
+
+/// Who read the exported transcripts (`[audit]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Append a line to `.waylog/access.log` whenever sessions are opened or exported
+    pub access_log: bool,
+}
+
+/// Soft-deleted files in `.waylog/trash` (`[trash]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashConfig {
+    /// Days a deleted file is kept before it is purged (0 keeps it forever)
+    pub retention_days: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+/// Environment of the agent started by `waylog run`, and the sync after it exits (`[run]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// Start from a minimal environment instead of inheriting the shell's
+    pub clean_env: bool,
+
+    /// Extra variables passed through when `clean_env` is set (`*` suffix matches a prefix)
+    pub keep_env: Vec<String>,
+
+    /// Variables removed from the environment (`*` suffix matches a prefix, e.g. `AWS_*`)
+    pub strip_env: Vec<String>,
+
+    /// Variables set for the agent (`[run.env]`)
+    pub env: BTreeMap<String, String>,
+
+    /// Seconds to wait for the final sync after the agent exits before it is
+    /// handed off to a background `waylog pull` (0 waits as long as it takes)
+    pub final_sync_timeout: u64,
+
+    /// Always hand the final sync off to the background, so the shell returns at once
+    pub detach_final_sync: bool,
+
+    /// Agent (or `[agents]` alias) started by `waylog run` without one
+    pub default_agent: Option<String>,
+
+    /// Run the agent in a pseudo-terminal of its own when waylog runs in a
+    /// terminal (`run --no-pty` turns it off once)
+    pub pty: bool,
+
+    /// Record the agent's terminal to `.waylog/recordings/` (`run --record`)
+    pub record: Recording,
+
+    /// Ask for a title and tags for the session when the agent exits, to
+    /// name its history file by (`run --ask-name`)
+    pub ask_name: bool,
+}
+
+/// How `waylog run` records the agent's terminal (`[run] record`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Recording {
+    /// No recording
+    #[default]
+    Off,
+    /// An asciinema v2 cast, with timing, for `asciinema play`
+    Cast,
+    /// The raw output, escape sequences included (`less -R`)
+    Log,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            clean_env: false,
+            keep_env: Vec::new(),
+            strip_env: Vec::new(),
+            env: BTreeMap::new(),
+            final_sync_timeout: 10,
+            detach_final_sync: false,
+            default_agent: None,
+            pty: true,
+            record: Recording::Off,
+            ask_name: false,
+        }
+    }
+}
+
+/// A custom agent launch command tracked with a built-in provider's parser
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentAlias {
+    /// Command line to launch, e.g. `claude --dangerously-skip-permissions`
+    pub command: String,
+
+    /// Provider whose session files the command writes (e.g. `claude`)
+    pub provider: String,
+}
+
+/// Settings that control how sessions are written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Filename template for new sessions (without extension).
+    /// Placeholders: `{date}`, `{time}`, `{offset}`, `{provider}`, `{slug}`, `{session_id}`, `{title}`
+    pub filename_template: String,
+
+    /// Time zone for timestamps in filenames, frontmatter and message headers:
+    /// `"UTC"`, `"local"` or an IANA name such as `"Europe/Berlin"`
+    pub timezone: String,
+
+    /// What to do when a new session renders to a filename that already exists
+    pub on_collision: CollisionPolicy,
+
+    /// Maximum length in bytes of a generated markdown filename (including extension)
+    pub max_filename_length: usize,
+
+    /// Language of the labels written into markdown ("en", "zh", "ja", "de", "fr", "es")
+    pub language: String,
+
+    /// Individual label overrides, applied on top of `language`
+    pub labels: LabelOverrides,
+
+    /// Style of the per-message role headers (`[export.headers]`)
+    pub headers: HeaderConfig,
+
+    /// Shareable copy of the history (`[export.public]`)
+    pub public: PublicConfig,
+
+    /// Pace of filling the SQLite archive (`[export.sqlite]`)
+    pub sqlite: SqliteConfig,
+
+    /// External exporter: receives each synced session as JSON on stdin, and
+    /// its stdout is written next to the markdown file
+    pub command: Option<String>,
+
+    /// Extension of the files written by `command` (e.g. "adoc")
+    pub command_extension: String,
+
+    /// Start a new `-partN.md` file once a session file has this many messages (0 = no limit)
+    pub max_messages_per_file: usize,
+
+    /// Start a new `-partN.md` file once a session file's messages take about this many bytes (0 = no limit)
+    pub max_bytes_per_file: u64,
+
+    /// While `waylog run` watches a session, rebuild derived artifacts (the
+    /// SQLite archive, `command` output) at most this often in seconds; they
+    /// are always brought up to date when the session ends (0 = every sync)
+    pub render_interval: u64,
+}
+
+/// A redacted mirror of `history/` in `history-public/`, kept in sync automatically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublicConfig {
+    /// Maintain `.waylog/history-public/`
+    pub enabled: bool,
+
+    /// Replace the project path with `.` and the home directory with `~`
+    pub anonymize_paths: bool,
+}
+
+impl Default for PublicConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            anonymize_paths: true,
+        }
+    }
+}
+
+/// How `export --format sqlite` fills the archive, so a first backfill of a
+/// long history doesn't keep the CPU and disk busy in one go
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqliteConfig {
+    /// Messages written per transaction
+    pub batch_size: usize,
+
+    /// Pause between transactions, in milliseconds
+    pub pause_ms: u64,
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            pause_ms: 20,
+        }
+    }
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            timezone: "UTC".to_string(),
+            on_collision: CollisionPolicy::default(),
+            max_filename_length: 128,
+            language: "en".to_string(),
+            labels: LabelOverrides::default(),
+            headers: HeaderConfig::default(),
+            public: PublicConfig::default(),
+            sqlite: SqliteConfig::default(),
+            command: None,
+            command_extension: "txt".to_string(),
+            max_messages_per_file: 0,
+            max_bytes_per_file: 0,
+            render_interval: 60,
+        }
+    }
+}
+
+/// Which providers `waylog pull` syncs when `--provider` is not given (`[providers]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProvidersConfig {
+    /// Providers synced by default, in this order (every known provider if empty)
+    pub enabled: Vec<String>,
+
+    /// Providers to skip even if listed in `enabled`
+    pub disabled: Vec<String>,
+}
+
+impl ProvidersConfig {
+    /// Provider names to sync by default
+    pub fn default_providers(&self) -> Vec<&str> {
+        if self.enabled.is_empty() {
+            return crate::providers::list_providers()
+                .into_iter()
+                .filter(|name| self.is_enabled(name))
+                .collect();
+        }
+        self.enabled
+            .iter()
+            .map(String::as_str)
+            .filter(|name| self.is_enabled(name))
+            .collect()
+    }
+
+    /// Whether a provider is synced by default
+    pub fn is_enabled(&self, name: &str) -> bool {
+        (self.enabled.is_empty() || self.enabled.iter().any(|e| e.eq_ignore_ascii_case(name)))
+            && !self.disabled.iter().any(|d| d.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Secret redaction applied to message text before it is exported (`[redaction]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Redact secrets in exported history; set to false to turn redaction off
+    pub enabled: bool,
+
+    /// Use the built-in detectors for common API keys, tokens and passwords
+    pub builtin: bool,
+
+    /// Additional regular expressions; every match is redacted
+    pub patterns: Vec<String>,
+
+    /// A gitleaks config whose `[[rules]]` are applied as well (relative to
+    /// the project root)
+    pub gitleaks_config: Option<PathBuf>,
+
+    /// External program that gets each message's text on stdin and prints
+    /// the redacted text on stdout, after the rules above ran
+    pub command: Option<String>,
+
+    /// Text that replaces a redacted secret
+    pub replacement: String,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            builtin: true,
+            patterns: Vec::new(),
+            gitleaks_config: None,
+            command: None,
+            replacement: "[REDACTED]".to_string(),
+        }
+    }
+}
+
+/// Encryption of the history files at rest (`[encryption]`). Set either
+/// `recipients` or `passphrase`; history files are then stored as `.md.age`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// age public keys (`age1...`) the history is encrypted to
+    pub recipients: Vec<String>,
+
+    /// Encrypt with a passphrase, read from `$WAYLOG_PASSPHRASE` or asked for
+    pub passphrase: bool,
+
+    /// age identity file that decrypts the history (relative to the project
+    /// root, or under `~`); `$WAYLOG_IDENTITY` takes precedence
+    pub identity: Option<PathBuf>,
+}
+
+impl EncryptionConfig {
+    /// Whether history files are written encrypted
+    pub fn enabled(&self) -> bool {
+        !self.recipients.is_empty() || self.passphrase
+    }
+}
+
+/// Default filename template, e.g. `2024-01-01_12-00-00Z-claude-fix-the-build`
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{date}_{time}{offset}-{provider}-{slug}";
+
+/// Placeholders understood by `filename_template`
+pub const FILENAME_PLACEHOLDERS: &[&str] = &[
+    "date",
+    "time",
+    "offset",
+    "provider",
+    "slug",
+    "session_id",
+    "title",
+];
+
+/// Collision handling for generated filenames (`on_collision`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollisionPolicy {
+    /// Append `-2`, `-3`, ... until the name is free
+    #[default]
+    Suffix,
+    /// Append the first 8 characters of the session id
+    SessionId,
+    /// Replace the existing file
+    Overwrite,
+}
+
+/// Role header style (`[export.headers]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeaderConfig {
+    /// Prefix role names with 👤 / 🤖 / ⚙️, notes with 📝 and thoughts with 💭;
+    /// false keeps the history free of emoji
+    pub emoji: bool,
+
+    /// Markdown heading level of each message header (1-6)
+    pub level: u8,
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            emoji: true,
+            level: 2,
+        }
+    }
+}
+
+/// Custom markdown labels (`[export.labels]`).
+/// `{model}` in a role label is replaced with the message's model name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelOverrides {
+    pub user: Option<String>,
+    pub assistant: Option<String>,
+    pub system: Option<String>,
+    pub tools_used: Option<String>,
+    pub thoughts: Option<String>,
+    pub untitled: Option<String>,
+    pub part: Option<String>,
+    pub note: Option<String>,
+    pub commits: Option<String>,
+}
+
+impl Config {
+    /// Path of the config file for a project
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(WAYLOG_DIR).join(WAYLOG_CONFIG_FILE)
+    }
+
+    /// Load the project config, falling back to defaults if the file does not exist
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|e| WaylogError::Config(format!("{}: {}", path.display(), e)))?;
+        if let Some(rules) = &mut config.redaction.gitleaks_config {
+            *rules = project_root.join(&*rules);
+        }
+        if let Some(identity) = &mut config.encryption.identity {
+            *identity = match identity.strip_prefix("~") {
+                Ok(rest) => crate::utils::path::home_dir()?.join(rest),
+                Err(_) => project_root.join(&*identity),
+            };
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (alias, agent) in &self.agents {
+            if crate::providers::get_provider(&agent.provider).is_err() {
+                return Err(WaylogError::Config(format!(
+                    "unknown provider '{}' for agent '{}' (expected one of: {})",
+                    agent.provider,
+                    alias,
+                    crate::providers::list_providers().join(", ")
+                )));
+            }
+            if crate::utils::string::split_command(&agent.command).is_empty() {
+                return Err(WaylogError::Config(format!(
+                    "agent '{}' has an empty command",
+                    alias
+                )));
+            }
+        }
+
+        for name in self
+            .providers
+            .enabled
+            .iter()
+            .chain(&self.providers.disabled)
+        {
+            if crate::providers::get_provider(name).is_err() {
+                return Err(WaylogError::Config(format!(
+                    "unknown provider '{}' in [providers] (expected one of: {})",
+                    name,
+                    crate::providers::list_providers().join(", ")
+                )));
+            }
+        }
+
+        for pattern in &self.exclude.patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(WaylogError::Config(format!(
+                    "invalid exclude pattern '{}': {}",
+                    pattern, e
+                )));
+            }
+        }
+
+        for pattern in &self.redaction.patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(WaylogError::Config(format!(
+                    "invalid redaction pattern '{}': {}",
+                    pattern, e
+                )));
+            }
+        }
+
+        for hook in &self.hooks.message {
+            match (&hook.command, &hook.url) {
+                (Some(command), None)
+                    if !crate::utils::string::split_command(command).is_empty() => {}
+                (None, Some(url)) if url.starts_with("http://") || url.starts_with("https://") => {}
+                (None, Some(url)) => {
+                    return Err(WaylogError::Config(format!(
+                        "hook url '{}' must start with http:// or https://",
+                        url
+                    )));
+                }
+                _ => {
+                    return Err(WaylogError::Config(
+                        "each [[hooks.message]] needs either a non-empty command or a url"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+
+        for command in self.hooks.pre_run.iter().chain(&self.hooks.post_run) {
+            if crate::utils::string::split_command(command).is_empty() {
+                return Err(WaylogError::Config(
+                    "hooks.pre_run and hooks.post_run can't hold empty commands".to_string(),
+                ));
+            }
+        }
+
+        if self.watch.rescan_interval == 0 {
+            return Err(WaylogError::Config(
+                "watch.rescan_interval must be at least 1 second".to_string(),
+            ));
+        }
+
+        if let Some(path) = &self.redaction.gitleaks_config {
+            crate::exporter::redact::load_gitleaks_rules(path, &self.redaction.replacement)?;
+        }
+
+        if !self.encryption.recipients.is_empty() && self.encryption.passphrase {
+            return Err(WaylogError::Config(
+                "set either encryption.recipients or encryption.passphrase, not both".to_string(),
+            ));
+        }
+        crate::exporter::encryption::recipients(&self.encryption)?;
+        if self.encryption.enabled() && self.run.record != Recording::Off {
+            return Err(WaylogError::Config(
+                "run.record can't be used with [encryption]: terminal recordings are not encrypted"
+                    .to_string(),
+            ));
+        }
+
+        if let Some(command) = &self.redaction.command {
+            if crate::utils::string::split_command(command).is_empty() {
+                return Err(WaylogError::Config(
+                    "redaction.command is empty".to_string(),
+                ));
+            }
+        }
+
+        if crate::utils::time::Timezone::parse(&self.export.timezone).is_none() {
+            return Err(WaylogError::Config(format!(
+                "unknown export.timezone '{}' (expected \"UTC\", \"local\" or an IANA name such as \"Europe/Berlin\")",
+                self.export.timezone
+            )));
+        }
+
+        if !(1..=6).contains(&self.export.headers.level) {
+            return Err(WaylogError::Config(format!(
+                "export.headers.level must be between 1 and 6, got {}",
+                self.export.headers.level
+            )));
+        }
+
+        if self.export.sqlite.batch_size == 0 {
+            return Err(WaylogError::Config(
+                "export.sqlite.batch_size must be at least 1".to_string(),
+            ));
+        }
+
+        if let Some(command) = &self.export.command {
+            if crate::utils::string::split_command(command).is_empty() {
+                return Err(WaylogError::Config("export.command is empty".to_string()));
+            }
+            let extension = &self.export.command_extension;
+            if extension.is_empty()
+                || extension.contains(['/', '\\', '.'])
+                || extension.eq_ignore_ascii_case("md")
+            {
+                return Err(WaylogError::Config(format!(
+                    "export.command_extension must be a plain extension other than \"md\", got '{}'",
+                    extension
+                )));
+            }
+        }
+
+        let template = &self.export.filename_template;
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| {
+                WaylogError::Config(format!("unclosed '{{' in filename_template '{}'", template))
+            })?;
+            let name = &rest[start + 1..start + end];
+            if !FILENAME_PLACEHOLDERS.contains(&name) {
+                return Err(WaylogError::Config(format!(
+                    "unknown placeholder {{{}}} in filename_template (expected one of: {})",
+                    name,
+                    FILENAME_PLACEHOLDERS.join(", ")
+                )));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        if template.contains(['/', '\\']) {
+            return Err(WaylogError::Config(
+                "filename_template must not contain path separators".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Install the loaded config for the rest of the process
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        tracing::debug!("Config already initialized, ignoring");
+    }
+}
+
+/// Get the active config (defaults if `init` was never called, e.g. in tests)
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_config_uses_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.export.max_filename_length, 128);
+    }
+
+    #[test]
+    fn test_load_partial_config() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[export]\nmax_filename_length = 64\n\n[export.labels]\nuser = \"Me\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.export.max_filename_length, 64);
+        assert_eq!(config.export.language, "en");
+        assert_eq!(config.export.labels.user.as_deref(), Some("Me"));
+    }
+
+    #[test]
+    fn test_load_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(Config::path(temp_dir.path()), "[export\n").unwrap();
+
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_filename_template() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[export]\nfilename_template = \"{date}-{title}\"\non_collision = \"session-id\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.export.filename_template, "{date}-{title}");
+        assert_eq!(config.export.on_collision, CollisionPolicy::SessionId);
+    }
+
+    #[test]
+    fn test_load_watch_tuning() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(Config::path(temp_dir.path()), "[watch]\ndebounce_ms = 50\n").unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.watch.debounce_ms, 50);
+        assert_eq!(config.watch.min_sync_interval_ms, 1000);
+        assert_eq!(config.watch.rescan_interval, 30);
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[watch]\nrescan_interval = 0\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_invalid_redaction_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[redaction]\npatterns = [\"internal-(\"]\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_encryption() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        let recipient = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            format!("[encryption]\nrecipients = [\"{recipient}\"]\nidentity = \"key.txt\"\n"),
+        )
+        .unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert!(config.encryption.enabled());
+        assert_eq!(
+            config.encryption.identity,
+            Some(temp_dir.path().join("key.txt"))
+        );
+
+        for content in [
+            "[encryption]\nrecipients = [\"age1nope\"]\n".to_string(),
+            format!("[encryption]\nrecipients = [\"{recipient}\"]\npassphrase = true\n"),
+            // Recordings and the SQLite archive would keep plaintext copies
+            format!("[encryption]\nrecipients = [\"{recipient}\"]\n[run]\nrecord = \"cast\"\n"),
+        ] {
+            std::fs::write(Config::path(temp_dir.path()), content).unwrap();
+            assert!(matches!(
+                Config::load(temp_dir.path()),
+                Err(WaylogError::Config(_))
+            ));
+        }
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            format!("[encryption]\nrecipients = [\"{recipient}\"]\n"),
+        )
+        .unwrap();
+        // A plaintext archive is refused only when it is written to
+        std::fs::write(crate::exporter::sqlite::database_path(temp_dir.path()), "").unwrap();
+        assert!(Config::load(temp_dir.path()).unwrap().encryption.enabled());
+    }
+
+    #[test]
+    fn test_load_providers() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[providers]\ndisabled = [\"codex\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.providers.default_providers(), ["claude", "gemini"]);
+        assert!(config.providers.is_enabled("Claude"));
+        assert!(!config.providers.is_enabled("codex"));
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[providers]\nenabled = [\"codex\", \"claude\"]\n",
+        )
+        .unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.providers.default_providers(), ["codex", "claude"]);
+        assert!(!config.providers.is_enabled("gemini"));
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[providers]\nenabled = [\"cursor\"]\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_agent_aliases() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[agents.cc]\ncommand = \"claude --dangerously-skip-permissions\"\nprovider = \"claude\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.agents["cc"].provider, "claude");
+
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[agents.cc]\ncommand = \"cursor-agent\"\nprovider = \"cursor\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_unknown_placeholder() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[export]\nfilename_template = \"{date}-{model}\"\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_export_command() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[export]\ncommand = \"md2adoc --toc\"\ncommand_extension = \"adoc\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.export.command.as_deref(), Some("md2adoc --toc"));
+
+        // Would overwrite the markdown history files
+        std::fs::write(
+            Config::path(temp_dir.path()),
+            "[export]\ncommand = \"md2adoc\"\ncommand_extension = \"md\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(WaylogError::Config(_))
+        ));
+    }
+}
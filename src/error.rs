@@ -1,5 +1,18 @@
 use thiserror::Error;
 
+/// Exit codes for failures scripts commonly branch on that have no sysexits
+/// code of their own. The rest follow sysexits(3); see the README for the table.
+pub mod code {
+    /// The named provider is not one waylog knows
+    pub const PROVIDER_NOT_FOUND: i32 = 79;
+    /// Some sessions or projects were synced and others failed
+    pub const PARTIAL_SYNC: i32 = 80;
+    /// Every session (or project) there was to sync failed
+    pub const SYNC_FAILED: i32 = 81;
+    /// The agent's command is not on PATH; the same code a shell uses
+    pub const AGENT_NOT_INSTALLED: i32 = 127;
+}
+
 #[derive(Error, Debug)]
 pub enum WaylogError {
     #[error("IO error: {0}")]
@@ -8,6 +21,9 @@ pub enum WaylogError {
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("Invalid config: {0}")]
+    Config(String),
+
     #[error("Provider not found: {0}")]
     ProviderNotFound(String),
 
@@ -17,15 +33,90 @@ pub enum WaylogError {
     #[error("No waylog project found.\nTo start a new session, use `waylog run <AGENT>`.\nTo sync history, please run this command inside an active waylog project (.waylog folder found).")]
     ProjectNotFound,
 
+    #[error("Refusing to track the home directory {0} as a project.\nRun waylog inside a project directory, or pass --here to track it anyway.")]
+    HomeDirectoryProject(String),
+
+    #[error("Could not tell which shell you use from $SHELL; pass --shell")]
+    UnknownShell,
+
     #[error("Missing required argument <AGENT>")]
     MissingAgent,
 
     #[error("{0} is not installed or not in PATH")]
     AgentNotInstalled(String),
 
+    #[error("No synced session matches '{0}'")]
+    SessionNotFound(String),
+
+    #[error("Source file for session {0} is no longer available")]
+    SourceNotFound(String),
+
+    #[error("Nothing in the trash matches '{0}'")]
+    TrashEntryNotFound(String),
+
+    #[error("No snapshot named '{0}' (create one with `waylog snapshot create {0}`)")]
+    SnapshotNotFound(String),
+
+    #[error("A snapshot named '{0}' already exists")]
+    SnapshotExists(String),
+
+    #[error("Found {0} unexpected items in provider session files")]
+    ValidationFailed(usize),
+
+    #[error("Found {0} secrets in history files (`--fix` redacts them)")]
+    SecretsFound(usize),
+
+    #[error("Export command failed: {0}")]
+    ExportCommandFailed(String),
+
+    #[error("Hook failed: {0}")]
+    HookFailed(String),
+
+    #[error("git failed: {0}")]
+    Git(String),
+
+    #[error("Could not decrypt or encrypt the history: {0}")]
+    Encryption(String),
+
+    #[error("Could not share the session: {0}")]
+    ShareFailed(String),
+
+    #[error("{0} of {1} sessions failed to sync")]
+    SyncFailed(usize, usize),
+
+    #[error("Pull failed in {0} of {1} projects")]
+    ProjectsFailed(usize, usize),
+
+    #[error("{0} ends in the middle of a write; run `waylog pull --force` to rewrite it")]
+    TruncatedFile(String),
+
+    #[error("{0} is already writing to this project; wait for it to finish, or pass --wait")]
+    ProjectLocked(String),
+
+    #[error("The agent failed for {0} of {1} prompts")]
+    BatchFailed(usize, usize),
+
+    #[error("{0} was stopped because --timeout ran out")]
+    TimedOut(String),
+
+    #[error("No `waylog run` is active in this project")]
+    NoActiveRun,
+
+    #[error("Could not add the note: {0}")]
+    NoteFailed(String),
+
     #[error("Child process exited with code {0}")]
     ChildProcessFailed(i32),
 
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("File watcher error: {0}")]
+    Watch(#[from] notify::Error),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -35,15 +126,53 @@ impl WaylogError {
     pub fn exit_code(&self) -> i32 {
         match self {
             // Command line usage errors
-            WaylogError::MissingAgent | WaylogError::ProviderNotFound(_) => exitcode::USAGE,
+            WaylogError::MissingAgent
+            | WaylogError::UnknownShell
+            | WaylogError::HomeDirectoryProject(_)
+            | WaylogError::SnapshotExists(_) => exitcode::USAGE,
+            // Configuration errors
+            WaylogError::Config(_) | WaylogError::Template(_) => exitcode::CONFIG,
             // Data format errors
-            WaylogError::Json(_) => exitcode::DATAERR,
+            WaylogError::Json(_)
+            | WaylogError::ValidationFailed(_)
+            | WaylogError::SecretsFound(_)
+            | WaylogError::TruncatedFile(_) => exitcode::DATAERR,
+            // I/O errors inside the SQLite archive, or from file events
+            WaylogError::Database(_) | WaylogError::Watch(_) => exitcode::IOERR,
             // Input file/resource errors
-            WaylogError::ProjectNotFound | WaylogError::Io(_) => exitcode::NOINPUT,
+            WaylogError::ProjectNotFound
+            | WaylogError::SessionNotFound(_)
+            | WaylogError::SourceNotFound(_)
+            | WaylogError::TrashEntryNotFound(_)
+            | WaylogError::SnapshotNotFound(_)
+            | WaylogError::Io(_) => exitcode::NOINPUT,
             // Service unavailable
-            WaylogError::AgentNotInstalled(_) => exitcode::UNAVAILABLE,
+            WaylogError::ExportCommandFailed(_)
+            | WaylogError::HookFailed(_)
+            | WaylogError::Git(_)
+            | WaylogError::ShareFailed(_)
+            | WaylogError::NoActiveRun => exitcode::UNAVAILABLE,
+            // No identity or passphrase that opens the history
+            WaylogError::Encryption(_) => exitcode::NOPERM,
             // Internal software errors
             WaylogError::PathError(_) | WaylogError::Internal(_) => exitcode::SOFTWARE,
+            WaylogError::ProviderNotFound(_) => code::PROVIDER_NOT_FOUND,
+            WaylogError::AgentNotInstalled(_) => code::AGENT_NOT_INSTALLED,
+            // Some sessions, or some of the per-project pulls, failed; each
+            // printed its own error. Nothing synced at all is told apart.
+            WaylogError::SyncFailed(failed, total) | WaylogError::ProjectsFailed(failed, total)
+                if failed >= total =>
+            {
+                code::SYNC_FAILED
+            }
+            WaylogError::SyncFailed(..) | WaylogError::ProjectsFailed(..) => code::PARTIAL_SYNC,
+            WaylogError::BatchFailed(..) => exitcode::TEMPFAIL,
+            // Another waylog holds the project lock; trying again later works
+            WaylogError::ProjectLocked(_) => exitcode::TEMPFAIL,
+            // e.g. the agent has not written its first message yet
+            WaylogError::NoteFailed(_) => exitcode::TEMPFAIL,
+            // The same code as timeout(1), so batch jobs can tell it apart
+            WaylogError::TimedOut(_) => 124,
             // Child process exit code (propagate directly)
             WaylogError::ChildProcessFailed(code) => *code,
         }
@@ -59,6 +188,8 @@ impl WaylogError {
             WaylogError::MissingAgent
                 | WaylogError::ProviderNotFound(_)
                 | WaylogError::AgentNotInstalled(_)
+                | WaylogError::ValidationFailed(_)
+                | WaylogError::SecretsFound(_)
         )
     }
 }
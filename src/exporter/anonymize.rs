@@ -0,0 +1,249 @@
+//! `--anonymize`: strip what identifies the machine and its user from
+//! sessions before they are written, so logs can be shared publicly.
+//!
+//! Placeholders are derived from a hash of the original value, so the same
+//! path or email always gets the same placeholder, across messages, files and
+//! runs.
+use crate::providers::base::{ChatMessage, ChatSession};
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const PROJECT_PLACEHOLDER: &str = "<project>";
+pub const HOME_PLACEHOLDER: &str = "<home>";
+pub const USER_PLACEHOLDER: &str = "<user>";
+pub const HOST_PLACEHOLDER: &str = "<host>";
+
+/// Names shorter than this are too likely to be ordinary words
+const MIN_NAME_LEN: usize = 3;
+
+/// Absolute POSIX or Windows paths with at least two components, preceded by
+/// the start of a line, whitespace, a quote or an opening bracket (so URLs and
+/// slash commands like `/init` are left alone). A `:line` suffix is kept.
+fn path_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        let segment = r#"[^\s/\\"'`<>()\[\]{},;:*?|]+"#;
+        Regex::new(&format!(
+            r#"(?m)(^|[\s"'`(\[=])((?:/|[A-Za-z]:\\){segment}(?:[/\\]{segment})+)"#
+        ))
+        .expect("valid path pattern")
+    })
+}
+
+fn email_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").expect("valid email pattern")
+    })
+}
+
+/// Name of the user running waylog
+pub fn user_name() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .or_else(|| {
+            crate::utils::path::home_dir()
+                .ok()?
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        })
+}
+
+/// Name of this machine
+pub fn host_name() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+}
+
+/// Replaces paths, usernames, hostnames and emails with placeholders
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    /// Directories replaced by a fixed placeholder, most specific first
+    dirs: Vec<(String, &'static str)>,
+    /// Whole-word names replaced by a fixed placeholder
+    names: Vec<(Regex, &'static str)>,
+}
+
+impl Anonymizer {
+    /// Anonymizer for a project on this machine
+    pub fn new(project: &Path) -> Self {
+        let home = crate::utils::path::home_dir().ok();
+        let user = user_name();
+        let host = host_name();
+
+        Self::with(
+            Some(project),
+            home.as_deref(),
+            user.as_deref(),
+            host.as_deref(),
+        )
+    }
+
+    pub fn with(
+        project: Option<&Path>,
+        home: Option<&Path>,
+        user: Option<&str>,
+        host: Option<&str>,
+    ) -> Self {
+        let mut dirs = Vec::new();
+        // Project first: it usually lives inside the home directory
+        for (dir, placeholder) in [(project, PROJECT_PLACEHOLDER), (home, HOME_PLACEHOLDER)] {
+            let Some(dir) = dir else { continue };
+            let dir = dir.to_string_lossy();
+            let dir = dir.trim_end_matches(['/', '\\']);
+            // Never rewrite "/" or an empty path
+            if dir.len() > 1 {
+                dirs.push((dir.to_string(), placeholder));
+            }
+        }
+
+        let mut names = Vec::new();
+        let mut hosts: Vec<&str> = host.into_iter().collect();
+        // Also the short name of a fully qualified hostname
+        if let Some(short) = host.and_then(|h| h.split_once('.')).map(|(short, _)| short) {
+            hosts.push(short);
+        }
+        for host in hosts {
+            names.extend(name_pattern(host, HOST_PLACEHOLDER));
+        }
+        if let Some(user) = user {
+            names.extend(name_pattern(user, USER_PLACEHOLDER));
+        }
+
+        Self { dirs, names }
+    }
+
+    /// Anonymize a piece of text
+    pub fn anonymize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (dir, placeholder) in &self.dirs {
+            text = text.replace(dir.as_str(), placeholder);
+        }
+
+        let text = path_pattern().replace_all(&text, |caps: &Captures| {
+            format!("{}{}", &caps[1], placeholder("path", &caps[2]))
+        });
+        let mut text = email_pattern()
+            .replace_all(&text, |caps: &Captures| placeholder("email", &caps[0]))
+            .into_owned();
+
+        for (pattern, placeholder) in &self.names {
+            text = pattern.replace_all(&text, *placeholder).into_owned();
+        }
+        text
+    }
+
+    /// Anonymize everything in a session that may identify the machine or user
+    pub fn anonymize_session(&self, session: &ChatSession) -> ChatSession {
+        ChatSession {
+            project_path: PathBuf::from(self.anonymize(&session.project_path.to_string_lossy())),
+            messages: session
+                .messages
+                .iter()
+                .map(|m| self.anonymize_message(m))
+                .collect(),
+            ..session.clone()
+        }
+    }
+
+    fn anonymize_message(&self, message: &ChatMessage) -> ChatMessage {
+        let mut message = message.clone();
+        message.content = self.anonymize(&message.content);
+        let metadata = &mut message.metadata;
+        for text in metadata
+            .tool_calls
+            .iter_mut()
+            .chain(metadata.thoughts.iter_mut())
+        {
+            *text = self.anonymize(text);
+        }
+        for access in &mut metadata.file_accesses {
+            access.path = self.anonymize(&access.path);
+        }
+        message
+    }
+}
+
+/// Case-insensitive whole-word match of `name`, unless it is too short to be safe
+fn name_pattern(name: &str, placeholder: &'static str) -> Option<(Regex, &'static str)> {
+    if name.chars().count() < MIN_NAME_LEN {
+        return None;
+    }
+    Regex::new(&format!(r"(?i)\b{}\b", regex::escape(name)))
+        .ok()
+        .map(|pattern| (pattern, placeholder))
+}
+
+/// `<kind-1a2b3c>`, the same for every occurrence of `value`
+fn placeholder(kind: &str, value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let short: String = digest[..3].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("<{}-{}>", kind, short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anonymizer() -> Anonymizer {
+        Anonymizer::with(
+            Some(Path::new("/home/alice/proj")),
+            Some(Path::new("/home/alice")),
+            Some("alice"),
+            Some("build-box.corp.example"),
+        )
+    }
+
+    #[test]
+    fn test_anonymize_paths() {
+        let text = anonymizer().anonymize(
+            "Edited /home/alice/proj/src/main.rs:12, read ~/x and /home/alice/.ssh/config",
+        );
+        assert_eq!(
+            text,
+            "Edited <project>/src/main.rs:12, read ~/x and <home>/.ssh/config"
+        );
+
+        // Other absolute paths get a stable placeholder
+        let a = anonymizer().anonymize("cat /etc/secret/token");
+        let b = anonymizer().anonymize("(/etc/secret/token)");
+        let placeholder = a.strip_prefix("cat ").unwrap();
+        assert!(placeholder.starts_with("<path-"), "{}", a);
+        assert_eq!(b, format!("({})", placeholder));
+        assert_ne!(anonymizer().anonymize("/etc/other/token"), placeholder);
+
+        // URLs, slash commands and relative paths are kept
+        let kept = "See https://example.com/a/b, run /init, open src/lib.rs";
+        assert_eq!(anonymizer().anonymize(kept), kept);
+
+        let windows = anonymizer().anonymize(r"at C:\Users\bob\repo");
+        assert!(windows.starts_with("at <path-"), "{}", windows);
+    }
+
+    #[test]
+    fn test_anonymize_names_and_emails() {
+        let text = anonymizer()
+            .anonymize("alice@example.com pushed from build-box as Alice; ping bob@example.org");
+        assert!(!text.contains("example"), "{}", text);
+        assert!(
+            text.contains(" pushed from <host> as <user>; ping <email-"),
+            "{}",
+            text
+        );
+        assert_eq!(
+            anonymizer().anonymize("Malice"),
+            "Malice",
+            "only whole words are names"
+        );
+
+        // Too short to replace safely
+        let short = Anonymizer::with(None, None, Some("me"), None);
+        assert_eq!(short.anonymize("tell me"), "tell me");
+    }
+}
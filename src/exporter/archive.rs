@@ -0,0 +1,132 @@
+//! Global archive: with `[archive] dir` (or `$WAYLOG_ARCHIVE`), every history
+//! file is also copied to `<dir>/<project slug>/`, so the sessions of all
+//! projects end up in one corpus that outlives the checkouts. The archive is
+//! only added to; files removed from a project's history stay archived.
+use crate::error::Result;
+use crate::exporter::encryption;
+use crate::init::WAYLOG_DIR;
+use crate::utils::path;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Environment variable naming the archive when the project config does not
+pub const ARCHIVE_ENV: &str = "WAYLOG_ARCHIVE";
+
+/// File in each project's archive directory holding the project path
+pub const PROJECT_FILE: &str = ".project";
+
+/// The archive directory, if one is configured
+pub fn archive_dir() -> Option<PathBuf> {
+    let dir = crate::config::get()
+        .archive
+        .dir
+        .clone()
+        .or_else(|| std::env::var_os(ARCHIVE_ENV).map(PathBuf::from))
+        .filter(|dir| !dir.as_os_str().is_empty())?;
+    match dir.strip_prefix("~") {
+        Ok(rest) => Some(path::home_dir().ok()?.join(rest)),
+        Err(_) => Some(dir),
+    }
+}
+
+/// Directory name of a project inside the archive: its folder name plus a
+/// hash of its full path, so same-named checkouts don't collide
+pub fn project_slug(project_dir: &Path) -> String {
+    let name = project_dir
+        .file_name()
+        .map(|n| crate::utils::string::slugify(&n.to_string_lossy()))
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "project".to_string());
+    format!("{}-{}", name, &path::encode_path_gemini(project_dir)[..8])
+}
+
+/// Copy a history file into the archive (no-op unless an archive is configured)
+pub async fn mirror(markdown_path: &Path) -> Result<()> {
+    match archive_dir() {
+        Some(archive) => mirror_to(&archive, markdown_path).await,
+        None => Ok(()),
+    }
+}
+
+async fn mirror_to(archive: &Path, markdown_path: &Path) -> Result<()> {
+    // <project>/.waylog/history/<file>
+    let Some(project_dir) = markdown_path
+        .parent()
+        .and_then(Path::parent)
+        .filter(|waylog| waylog.file_name().is_some_and(|n| n == WAYLOG_DIR))
+        .and_then(Path::parent)
+    else {
+        return Ok(());
+    };
+    // Encrypted history stays encrypted in the archive
+    let stored = encryption::stored_path(markdown_path);
+    let Some(file_name) = stored.file_name() else {
+        return Ok(());
+    };
+
+    let target_dir = archive.join(project_slug(project_dir));
+    path::ensure_dir_exists(&target_dir)?;
+    let project_file = target_dir.join(PROJECT_FILE);
+    if !project_file.exists() {
+        fs::write(&project_file, format!("{}\n", project_dir.display())).await?;
+    }
+    let target = target_dir.join(file_name);
+    fs::copy(
+        path::extended_length(&stored),
+        path::extended_length(&target),
+    )
+    .await?;
+
+    // Drop the copy under the other name, left from before encryption was
+    // turned on or off
+    let other = match encryption::history_path(&target) {
+        Some(plain) if plain != target => plain,
+        _ => encryption::encrypted_path(&target),
+    };
+    match fs::remove_file(path::extended_length(&other)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_slug() {
+        let a = project_slug(Path::new("/work/Waylog CLI"));
+        let b = project_slug(Path::new("/other/Waylog CLI"));
+        assert!(a.starts_with("waylog-cli-"), "{}", a);
+        assert_ne!(a, b);
+        assert_eq!(a, project_slug(Path::new("/work/Waylog CLI")));
+    }
+
+    #[tokio::test]
+    async fn test_mirror_copies_into_the_project_directory() {
+        let root = tempfile::TempDir::new().unwrap();
+        let project = root.path().join("app");
+        let history = path::get_waylog_dir(&project);
+        std::fs::create_dir_all(&history).unwrap();
+        let markdown = history.join("a.md");
+        std::fs::write(&markdown, "# A\n").unwrap();
+        let archive = root.path().join("archive");
+
+        mirror_to(&archive, &markdown).await.unwrap();
+        let target = archive.join(project_slug(&project));
+        assert_eq!(
+            std::fs::read_to_string(target.join(PROJECT_FILE)).unwrap(),
+            format!("{}\n", project.display())
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("a.md")).unwrap(),
+            "# A\n"
+        );
+
+        // Files outside a project's history are not archived
+        let stray = root.path().join("b.md");
+        std::fs::write(&stray, "# B\n").unwrap();
+        mirror_to(&archive, &stray).await.unwrap();
+        assert_eq!(std::fs::read_dir(&archive).unwrap().count(), 1);
+    }
+}
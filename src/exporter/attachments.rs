@@ -0,0 +1,214 @@
+use crate::error::Result;
+use crate::init::subdirs;
+use crate::providers::base::Attachment;
+use crate::utils::path;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::fs;
+use walkdir::WalkDir;
+
+/// Relative blob path for an attachment: `ab/cdef….ext` (sha256 of the content)
+pub fn blob_name(attachment: &Attachment) -> String {
+    let hash = format!("{:x}", Sha256::digest(&attachment.data));
+    format!(
+        "{}/{}.{}",
+        &hash[..2],
+        &hash[2..],
+        extension(&attachment.media_type)
+    )
+}
+
+/// Link to an attachment as seen from a markdown file in the history directory
+pub fn markdown_link(attachment: &Attachment) -> String {
+    format!("../{}/{}", subdirs::ATTACHMENTS, blob_name(attachment))
+}
+
+/// Attachment store that sits next to the history directory of `markdown_path`
+pub fn store_dir_for(markdown_path: &Path) -> Option<PathBuf> {
+    Some(markdown_path.parent()?.parent()?.join(subdirs::ATTACHMENTS))
+}
+
+/// Write attachments into the store. Blobs that already exist are left alone,
+/// so identical images pasted into several sessions are stored once.
+pub async fn store(store_dir: &Path, attachments: &[&Attachment]) -> Result<()> {
+    for attachment in attachments {
+        let blob = path::extended_length(&store_dir.join(blob_name(attachment)));
+        if fs::try_exists(&blob).await.unwrap_or(false) {
+            continue;
+        }
+        if let Some(parent) = blob.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&blob, &attachment.data).await?;
+    }
+    Ok(())
+}
+
+/// Count how many times each blob (`ab/cdef….ext`) is referenced from history files
+pub fn reference_counts(history_dir: &Path) -> Result<HashMap<String, usize>> {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    let re = LINK.get_or_init(|| {
+        Regex::new(r"attachments/([0-9a-f]{2}/[0-9a-f]{62}\.[a-z0-9]+)").expect("valid regex")
+    });
+
+    let mut counts = HashMap::new();
+    if !history_dir.exists() {
+        return Ok(counts);
+    }
+
+    for entry in WalkDir::new(history_dir).into_iter().filter_map(|e| e.ok()) {
+        let Some(file) = crate::exporter::encryption::history_path(entry.path()) else {
+            continue;
+        };
+        let content = crate::exporter::encryption::read_sync(&file)?;
+        for cap in re.captures_iter(&content) {
+            *counts.entry(cap[1].to_string()).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Result of a garbage collection pass
+#[derive(Debug, Default)]
+pub struct GcReport {
+    /// Blobs that were (or, in a dry run, would be) removed
+    pub removed: Vec<PathBuf>,
+    /// Bytes freed by removing them
+    pub freed_bytes: u64,
+    /// Blobs still referenced by at least one history file
+    pub kept: usize,
+}
+
+/// Remove blobs that no markdown file under `markdown_dirs` references any more
+pub fn collect_garbage(
+    store_dir: &Path,
+    markdown_dirs: &[PathBuf],
+    dry_run: bool,
+) -> Result<GcReport> {
+    let mut report = GcReport::default();
+    if !store_dir.exists() {
+        return Ok(report);
+    }
+
+    let mut counts = HashMap::new();
+    for dir in markdown_dirs {
+        for (blob, count) in reference_counts(dir)? {
+            *counts.entry(blob).or_insert(0) += count;
+        }
+    }
+
+    for entry in WalkDir::new(store_dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(store_dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if counts.get(&relative).copied().unwrap_or(0) > 0 {
+            report.kept += 1;
+            continue;
+        }
+
+        report.freed_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        if !dry_run {
+            std::fs::remove_file(entry.path())?;
+        }
+        report.removed.push(entry.path().to_path_buf());
+    }
+
+    // Drop shard directories left empty
+    if !dry_run {
+        for entry in std::fs::read_dir(store_dir)?.filter_map(|e| e.ok()) {
+            let dir = entry.path();
+            if dir.is_dir() && std::fs::read_dir(&dir)?.next().is_none() {
+                std::fs::remove_dir(&dir)?;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn extension(media_type: &str) -> &'static str {
+    match media_type {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn png(data: &[u8]) -> Attachment {
+        Attachment {
+            media_type: "image/png".to_string(),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_blob_name_is_content_addressed() {
+        let a = blob_name(&png(b"hello"));
+        assert_eq!(a, blob_name(&png(b"hello")));
+        assert_ne!(a, blob_name(&png(b"world")));
+        assert!(a.starts_with("2c/"));
+        assert!(a.ends_with(".png"));
+        assert_eq!(a.len(), 2 + 1 + 62 + 4);
+    }
+
+    #[tokio::test]
+    async fn test_store_dedups_identical_blobs() {
+        let dir = TempDir::new().unwrap();
+        let a = png(b"same");
+        let b = png(b"same");
+        store(dir.path(), &[&a, &b]).await.unwrap();
+
+        let files: Vec<_> = WalkDir::new(dir.path())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_removes_unreferenced() {
+        let dir = TempDir::new().unwrap();
+        let history = dir.path().join("history");
+        let attachments = dir.path().join("attachments");
+        std::fs::create_dir_all(&history).unwrap();
+
+        let kept = png(b"kept");
+        let orphan = png(b"orphan");
+        store(&attachments, &[&kept, &orphan]).await.unwrap();
+        std::fs::write(
+            history.join("s.md"),
+            format!("![image/png]({})\n", markdown_link(&kept)),
+        )
+        .unwrap();
+
+        let report = collect_garbage(&attachments, std::slice::from_ref(&history), true).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(attachments.join(blob_name(&orphan)).exists());
+
+        let report = collect_garbage(&attachments, std::slice::from_ref(&history), false).unwrap();
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.freed_bytes, 6);
+        assert!(!attachments.join(blob_name(&orphan)).exists());
+        assert!(attachments.join(blob_name(&kept)).exists());
+    }
+}
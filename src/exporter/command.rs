@@ -0,0 +1,115 @@
+//! Bespoke formats from an external program (`[export] command`). Every sync
+//! pipes the whole session as JSON to the command's stdin and writes its
+//! stdout next to the markdown history file, with `command_extension`.
+use super::redact::Redactor;
+use crate::error::{Result, WaylogError};
+use crate::providers::base::ChatSession;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Where the command's output for a history file is written
+pub fn output_path(markdown_path: &Path, extension: &str) -> PathBuf {
+    markdown_path.with_extension(extension)
+}
+
+/// Run the configured command for a synced session (no-op without `export.command`)
+pub async fn update(markdown_path: &Path, session: &ChatSession) -> Result<()> {
+    let export = &crate::config::get().export;
+    let Some(command) = &export.command else {
+        return Ok(());
+    };
+
+    let mut session = session.clone();
+    session.messages = Redactor::from_config()
+        .redact_messages(&session.messages)
+        .into_owned();
+    let output = run(command, &serde_json::to_vec(&session)?).await?;
+
+    // Stored like the history file it sits next to, encrypted with `[encryption]`
+    let target = output_path(markdown_path, &export.command_extension);
+    super::encryption::write(&target, output).await
+}
+
+/// Move the command's output along with a history file renamed from `from`
+/// to `to` (no-op without `export.command` or without output yet)
+pub async fn rename(from: &Path, to: &Path) -> Result<()> {
+    let export = &crate::config::get().export;
+    if export.command.is_none() {
+        return Ok(());
+    }
+    let source = super::encryption::stored_path(&output_path(from, &export.command_extension));
+    if !source.exists() {
+        return Ok(());
+    }
+    let mut target = output_path(to, &export.command_extension);
+    if source.extension().and_then(|e| e.to_str()) == Some(super::encryption::EXTENSION) {
+        target = super::encryption::encrypted_path(&target);
+    }
+    tokio::fs::rename(source, target).await?;
+    Ok(())
+}
+
+/// Run `command` with `input` on stdin and return its stdout
+async fn run(command: &str, input: &[u8]) -> Result<Vec<u8>> {
+    let words = crate::utils::string::split_command(command);
+    let Some((program, args)) = words.split_first() else {
+        return Err(WaylogError::Config("export.command is empty".to_string()));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| WaylogError::ExportCommandFailed(format!("{}: {}", program, e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that exits without reading all of its input is not an
+        // error in itself; its exit status decides
+        if let Err(e) = stdin.write_all(input).await {
+            tracing::debug!("{} did not read the whole session: {}", program, e);
+        }
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(WaylogError::ExportCommandFailed(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_pipes_stdin_to_stdout() {
+        let output = run("tr a-z A-Z", b"{\"provider\":\"claude\"}")
+            .await
+            .unwrap();
+        assert_eq!(output, b"{\"PROVIDER\":\"CLAUDE\"}");
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_failure() {
+        let error = run("sh -c 'echo broken >&2; exit 3'", b"{}")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WaylogError::ExportCommandFailed(_)));
+        assert!(error.to_string().contains("broken"), "{}", error);
+
+        assert!(matches!(
+            run("waylog-no-such-exporter", b"{}").await,
+            Err(WaylogError::ExportCommandFailed(_))
+        ));
+    }
+}
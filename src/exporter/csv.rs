@@ -0,0 +1,116 @@
+//! One row of message metadata per message, for spreadsheet analysis
+//! (`waylog export --format csv`). Message content is not included.
+use super::jsonl::Record;
+use crate::providers::base::MessageRole;
+use std::io::{self, Write};
+
+const HEADER: [&str; 12] = [
+    "provider",
+    "session_id",
+    "index",
+    "timestamp",
+    "role",
+    "model",
+    "input_tokens",
+    "output_tokens",
+    "cached_tokens",
+    "tool_call_count",
+    "tool_calls",
+    "chars",
+];
+
+/// Write a header row and one row per record
+pub fn write_rows<W: Write>(mut writer: W, records: &[Record]) -> io::Result<()> {
+    write_row(&mut writer, HEADER.iter().map(|h| h.to_string()))?;
+
+    for record in records {
+        let message = &record.message;
+        let tokens = message.metadata.tokens.as_ref();
+        let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+        write_row(
+            &mut writer,
+            [
+                record.provider.clone(),
+                record.session_id.clone(),
+                record.index.to_string(),
+                message.timestamp.to_rfc3339(),
+                match message.role {
+                    MessageRole::User => "user",
+                    MessageRole::Assistant => "assistant",
+                    MessageRole::System => "system",
+                }
+                .to_string(),
+                message.metadata.model.clone().unwrap_or_default(),
+                optional(tokens.map(|t| t.input)),
+                optional(tokens.map(|t| t.output)),
+                optional(tokens.map(|t| t.cached)),
+                message.metadata.tool_calls.len().to_string(),
+                message.metadata.tool_calls.join(";"),
+                message.content.chars().count().to_string(),
+            ],
+        )?;
+    }
+    writer.flush()
+}
+
+fn write_row<W: Write>(writer: &mut W, fields: impl IntoIterator<Item = String>) -> io::Result<()> {
+    let row: Vec<String> = fields.into_iter().map(|f| quote(&f)).collect();
+    writer.write_all(row.join(",").as_bytes())?;
+    writer.write_all(b"\r\n")
+}
+
+/// Quote a field if it contains a separator, quote or line break (RFC 4180)
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::jsonl::SCHEMA;
+    use crate::providers::base::{ChatMessage, MessageMetadata, TokenUsage};
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_write_rows() {
+        let record = Record {
+            schema: SCHEMA.to_string(),
+            provider: "claude".to_string(),
+            session_id: "abc".to_string(),
+            project: PathBuf::from("/project"),
+            index: 1,
+            message: ChatMessage {
+                id: "m1".to_string(),
+                timestamp: Utc.with_ymd_and_hms(2025, 1, 15, 9, 30, 0).unwrap(),
+                role: MessageRole::Assistant,
+                content: "Héllo, \"world\"".to_string(),
+                metadata: MessageMetadata {
+                    model: Some("claude-sonnet-4.5".to_string()),
+                    tokens: Some(TokenUsage {
+                        input: 10,
+                        output: 20,
+                        cached: 5,
+                    }),
+                    tool_calls: vec!["Bash".to_string(), "Edit, file".to_string()],
+                    ..MessageMetadata::default()
+                },
+            },
+        };
+
+        let mut buffer = Vec::new();
+        write_rows(&mut buffer, &[record]).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines: Vec<_> = csv.split("\r\n").collect();
+
+        assert_eq!(lines[0], HEADER.join(","));
+        assert_eq!(
+            lines[1],
+            "claude,abc,1,2025-01-15T09:30:00+00:00,assistant,claude-sonnet-4.5,10,20,5,2,\"Bash;Edit, file\",14"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Artifacts rebuilt from whole sessions after their markdown is written: the
+//! SQLite archive and the `[export] command` output. While a session is
+//! watched, re-rendering them on every sync would redo the same work over and
+//! over, so syncs queue their sessions here and the queue is rendered at most
+//! every `[export] render_interval` seconds, and once more when the session ends.
+use crate::error::Result;
+use crate::providers::base::ChatSession;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Coalesces the derived renders of synced sessions
+pub struct DerivedRenderer {
+    project_dir: PathBuf,
+    interval: Duration,
+    queue: Mutex<Queue>,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// session_id -> the session's markdown path and its latest parse
+    pending: BTreeMap<String, (PathBuf, ChatSession)>,
+    last_render: Option<Instant>,
+}
+
+impl DerivedRenderer {
+    pub fn new(project_dir: PathBuf, interval: Duration) -> Self {
+        Self {
+            project_dir,
+            interval,
+            queue: Mutex::new(Queue::default()),
+        }
+    }
+
+    /// Renders every synced session right away (`pull`, `export`)
+    pub fn immediate(project_dir: PathBuf) -> Self {
+        Self::new(project_dir, Duration::ZERO)
+    }
+
+    /// Renders at most every `[export] render_interval` seconds (`run`)
+    pub fn from_config(project_dir: PathBuf) -> Self {
+        let interval = Duration::from_secs(crate::config::get().export.render_interval);
+        Self::new(project_dir, interval)
+    }
+
+    /// Queue a synced session and render the queue if the interval has passed
+    pub async fn schedule(&self, markdown_path: &Path, session: &ChatSession) -> Result<()> {
+        self.queue.lock().await.pending.insert(
+            session.session_id.clone(),
+            (markdown_path.to_path_buf(), session.clone()),
+        );
+        self.render_due().await
+    }
+
+    /// Render the queue if the interval has passed since the last render
+    pub async fn render_due(&self) -> Result<()> {
+        let due = self
+            .queue
+            .lock()
+            .await
+            .last_render
+            .is_none_or(|last| last.elapsed() >= self.interval);
+        if due {
+            self.flush().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Render everything queued now. Every session is rendered even if one
+    /// fails; the first failure is returned.
+    pub async fn flush(&self) -> Result<()> {
+        let pending = {
+            let mut queue = self.queue.lock().await;
+            queue.last_render = Some(Instant::now());
+            std::mem::take(&mut queue.pending)
+        };
+
+        let mut result = Ok(());
+        for (markdown_path, session) in pending.values() {
+            if let Err(e) = super::sqlite::update(&self.project_dir, session) {
+                warn!("Could not update the SQLite archive: {}", e);
+            }
+            if let Err(e) = super::command::update(markdown_path, session).await {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Sessions waiting for their next render
+    #[cfg(test)]
+    async fn pending(&self) -> usize {
+        self.queue.lock().await.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn session(id: &str) -> ChatSession {
+        ChatSession {
+            session_id: id.to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/p"),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            messages: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_renders_are_coalesced() {
+        let dir = TempDir::new().unwrap();
+        let renderer = DerivedRenderer::new(dir.path().to_path_buf(), Duration::from_secs(3600));
+        let md = dir.path().join("a.md");
+
+        // The first sync renders, later ones wait for the interval
+        renderer.schedule(&md, &session("a")).await.unwrap();
+        assert_eq!(renderer.pending().await, 0);
+        renderer.schedule(&md, &session("a")).await.unwrap();
+        renderer.schedule(&md, &session("a")).await.unwrap();
+        renderer.schedule(&md, &session("b")).await.unwrap();
+        assert_eq!(renderer.pending().await, 2);
+
+        renderer.flush().await.unwrap();
+        assert_eq!(renderer.pending().await, 0);
+
+        let immediate = DerivedRenderer::immediate(dir.path().to_path_buf());
+        immediate.schedule(&md, &session("a")).await.unwrap();
+        immediate.schedule(&md, &session("a")).await.unwrap();
+        assert_eq!(immediate.pending().await, 0);
+    }
+}
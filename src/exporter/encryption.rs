@@ -0,0 +1,391 @@
+//! Encryption at rest (`[encryption]`): history files are stored as
+//! `<name>.md.age`, encrypted to age recipients or with a passphrase. The rest
+//! of waylog keeps using the `.md` path; reading and writing a history file
+//! through this module finds the file under either name and decrypts or
+//! encrypts it.
+//!
+//! With a passphrase, deriving a key from it (scrypt) takes about a second,
+//! too long to repeat on every live sync. So the history is encrypted to an
+//! age key kept in `.waylog/key.age`, itself encrypted with the passphrase,
+//! which each process unlocks once.
+use crate::config::EncryptionConfig;
+use crate::error::{Result, WaylogError};
+use crate::i18n::t;
+use crate::init::WAYLOG_DIR;
+use crate::utils::{atomic, path};
+use age::secrecy::{ExposeSecret, SecretString};
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+/// Extension added to the name of an encrypted history file
+pub const EXTENSION: &str = "age";
+
+/// Environment variable naming the age identity file that decrypts the history
+pub const IDENTITY_ENV: &str = "WAYLOG_IDENTITY";
+
+/// Environment variable holding the passphrase, so it need not be typed
+pub const PASSPHRASE_ENV: &str = "WAYLOG_PASSPHRASE";
+
+/// The project's history key, encrypted with the passphrase, inside `.waylog`
+pub const KEY_FILE: &str = "key.age";
+
+/// Whether new history files are written encrypted
+pub fn enabled() -> bool {
+    crate::config::get().encryption.enabled()
+}
+
+/// Where the history file `path` (a `.md` path) is encrypted
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(EXTENSION);
+    PathBuf::from(name)
+}
+
+/// The file holding the history file `path`: its encrypted copy if there is
+/// one, `path` otherwise (whether it exists or not)
+pub fn stored_path(path: &Path) -> PathBuf {
+    let encrypted = encrypted_path(path);
+    if encrypted.exists() {
+        encrypted
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Whether the history file `path` exists, encrypted or not
+pub fn exists(path: &Path) -> bool {
+    path.exists() || encrypted_path(path).exists()
+}
+
+/// The history file (`.md` path) a file found in the history directory
+/// holds, or None if it is no history file
+pub fn history_path(file: &Path) -> Option<PathBuf> {
+    match file.extension().and_then(|e| e.to_str()) {
+        Some("md") => Some(file.to_path_buf()),
+        Some(EXTENSION) => {
+            let plain = file.with_extension("");
+            (plain.extension().and_then(|e| e.to_str()) == Some("md")).then_some(plain)
+        }
+        _ => None,
+    }
+}
+
+/// The content of the history file `path`, decrypted if it is stored encrypted
+pub async fn read(path: &Path) -> Result<String> {
+    let stored = stored_path(path);
+    let bytes = tokio::fs::read(path::extended_length(&stored)).await?;
+    decode(&stored, bytes)
+}
+
+/// [`read`] for callers that are not async
+pub fn read_sync(path: &Path) -> Result<String> {
+    let stored = stored_path(path);
+    let bytes = std::fs::read(path::extended_length(&stored))?;
+    decode(&stored, bytes)
+}
+
+/// Replace the history file `path` (or a file derived from it, such as the
+/// `export.command` output) atomically: encrypted while `[encryption]` is
+/// set, in plaintext otherwise. The copy under the other
+/// name is removed, so turning encryption on or off converts files as they
+/// are written.
+pub async fn write(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let encrypted = encrypted_path(path);
+    let stale = if enabled() {
+        atomic::write(
+            &path::extended_length(&encrypted),
+            encrypt(path, content.as_ref())?,
+        )
+        .await?;
+        path.to_path_buf()
+    } else {
+        atomic::write(&path::extended_length(path), content).await?;
+        encrypted
+    };
+    match tokio::fs::remove_file(path::extended_length(&stale)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn decode(stored: &Path, bytes: Vec<u8>) -> Result<String> {
+    if stored.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+        return Ok(String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?);
+    }
+    decrypt(stored, &bytes)
+        .map_err(|e| WaylogError::Encryption(format!("{}: {}", stored.display(), e)))
+}
+
+fn encrypt(path: &Path, content: &[u8]) -> Result<Vec<u8>> {
+    let config = &crate::config::get().encryption;
+    match key_file(path) {
+        Some(key_file) if config.passphrase => {
+            encrypt_to(content, &[history_key(&key_file)?.to_public()])
+        }
+        _ => encrypt_with(content, config),
+    }
+}
+
+fn encrypt_with(content: &[u8], config: &EncryptionConfig) -> Result<Vec<u8>> {
+    if config.passphrase {
+        return seal(age::Encryptor::with_user_passphrase(passphrase()?), content);
+    }
+    encrypt_to(content, &recipients(config)?)
+}
+
+fn encrypt_to(content: &[u8], recipients: &[age::x25519::Recipient]) -> Result<Vec<u8>> {
+    let encryptor =
+        age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+            .map_err(|e| WaylogError::Encryption(e.to_string()))?;
+    seal(encryptor, content)
+}
+
+fn seal(encryptor: age::Encryptor, content: &[u8]) -> Result<Vec<u8>> {
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    writer.write_all(content)?;
+    writer.finish()?;
+    Ok(encrypted)
+}
+
+fn decrypt(stored: &Path, bytes: &[u8]) -> Result<String> {
+    let decryptor = age::Decryptor::new(bytes).map_err(failed)?;
+    if decryptor.is_scrypt() {
+        // Encrypted with the passphrase itself, before there was a history key
+        let identity = age::scrypt::Identity::new(passphrase()?);
+        return decrypt_with(decryptor, std::iter::once(&identity as &dyn age::Identity));
+    }
+    match key_file(stored) {
+        Some(key_file) if crate::config::get().encryption.passphrase => {
+            let key = history_key(&key_file)?;
+            decrypt_with(decryptor, std::iter::once(&key as &dyn age::Identity))
+        }
+        _ => {
+            let identities = identities()?;
+            decrypt_with(decryptor, identities.iter().map(|i| i.as_ref()))
+        }
+    }
+}
+
+fn decrypt_with<'a>(
+    decryptor: age::Decryptor<&[u8]>,
+    identities: impl Iterator<Item = &'a dyn age::Identity>,
+) -> Result<String> {
+    let mut reader = decryptor.decrypt(identities).map_err(failed)?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+fn failed(e: age::DecryptError) -> WaylogError {
+    WaylogError::Encryption(e.to_string())
+}
+
+/// The key file of the `.waylog` directory `path` is in, if it is in one
+fn key_file(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == WAYLOG_DIR))
+        .map(|dir| dir.join(KEY_FILE))
+}
+
+/// The history key in `key_file`, unlocked with the passphrase once per
+/// process, or created on first use
+fn history_key(key_file: &Path) -> Result<age::x25519::Identity> {
+    static KEYS: Mutex<BTreeMap<PathBuf, age::x25519::Identity>> = Mutex::new(BTreeMap::new());
+    let mut keys = KEYS
+        .lock()
+        .map_err(|_| WaylogError::Internal("history key cache poisoned".to_string()))?;
+    if let Some(key) = keys.get(key_file) {
+        return Ok(key.clone());
+    }
+    let key = match std::fs::read(key_file) {
+        Ok(sealed) => unlock_key(&sealed, passphrase()?)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => create_key(key_file)?,
+        Err(e) => return Err(e.into()),
+    };
+    keys.insert(key_file.to_path_buf(), key.clone());
+    Ok(key)
+}
+
+fn unlock_key(sealed: &[u8], passphrase: SecretString) -> Result<age::x25519::Identity> {
+    let decryptor = age::Decryptor::new(sealed).map_err(failed)?;
+    let identity = age::scrypt::Identity::new(passphrase);
+    let key = decrypt_with(decryptor, std::iter::once(&identity as &dyn age::Identity))?;
+    age::x25519::Identity::from_str(key.trim())
+        .map_err(|e| WaylogError::Encryption(format!("{}: {}", KEY_FILE, e)))
+}
+
+/// Make a new history key and save it in `key_file`. If another process
+/// saved one first, that one is used, so the project never has two.
+fn create_key(key_file: &Path) -> Result<age::x25519::Identity> {
+    let key = age::x25519::Identity::generate();
+    let sealed = seal(
+        age::Encryptor::with_user_passphrase(passphrase()?),
+        key.to_string().expose_secret().as_bytes(),
+    )?;
+    let temp = key_file.with_file_name(format!(".{}.{}.tmp", KEY_FILE, std::process::id()));
+    std::fs::write(&temp, sealed)?;
+    // Linking fails if the key file exists, unlike a rename
+    let linked = std::fs::hard_link(&temp, key_file);
+    let _ = std::fs::remove_file(&temp);
+    match linked {
+        Ok(()) => Ok(key),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            unlock_key(&std::fs::read(key_file)?, passphrase()?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The age public keys of `[encryption] recipients`
+pub fn recipients(config: &EncryptionConfig) -> Result<Vec<age::x25519::Recipient>> {
+    config
+        .recipients
+        .iter()
+        .map(|key| {
+            age::x25519::Recipient::from_str(key.trim())
+                .map_err(|e| WaylogError::Config(format!("encryption.recipients '{}': {}", key, e)))
+        })
+        .collect()
+}
+
+/// The identities in `$WAYLOG_IDENTITY` or `[encryption] identity`
+fn identities() -> Result<Vec<Box<dyn age::Identity>>> {
+    let file = std::env::var_os(IDENTITY_ENV)
+        .map(PathBuf::from)
+        .or_else(|| crate::config::get().encryption.identity.clone())
+        .filter(|file| !file.as_os_str().is_empty())
+        .ok_or_else(|| {
+            WaylogError::Encryption(format!(
+                "no identity to decrypt the history with; set [encryption] identity or {}",
+                IDENTITY_ENV
+            ))
+        })?;
+    let unreadable = |e: &dyn std::fmt::Display| {
+        WaylogError::Encryption(format!("identity {}: {}", file.display(), e))
+    };
+    age::IdentityFile::from_file(file.to_string_lossy().to_string())
+        .map_err(|e| unreadable(&e))?
+        .into_identities()
+        .map_err(|e| unreadable(&e))
+}
+
+/// The passphrase from `$WAYLOG_PASSPHRASE`, or asked for once per run
+fn passphrase() -> Result<SecretString> {
+    static PASSPHRASE: OnceLock<String> = OnceLock::new();
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(SecretString::from(passphrase.clone()));
+    }
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => passphrase,
+        _ if std::io::stdin().is_terminal() => dialoguer::Password::new()
+            .with_prompt(t!("encryption-passphrase"))
+            .interact()
+            .map_err(|e| WaylogError::Encryption(e.to_string()))?,
+        _ => {
+            return Err(WaylogError::Encryption(format!(
+                "the history is encrypted with a passphrase; set {}",
+                PASSPHRASE_ENV
+            )))
+        }
+    };
+    Ok(SecretString::from(
+        PASSPHRASE.get_or_init(|| passphrase).clone(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_path() {
+        assert_eq!(
+            history_path(Path::new("/h/a.md.age")),
+            Some(PathBuf::from("/h/a.md"))
+        );
+        assert_eq!(
+            history_path(Path::new("/h/a.md")),
+            Some(PathBuf::from("/h/a.md"))
+        );
+        assert_eq!(history_path(Path::new("/h/a.txt.age")), None);
+        assert_eq!(history_path(Path::new("/h/.a.md.123.tmp")), None);
+    }
+
+    #[test]
+    fn test_round_trip_to_recipient() {
+        let identity = age::x25519::Identity::generate();
+        let config = EncryptionConfig {
+            recipients: vec![identity.to_public().to_string()],
+            ..Default::default()
+        };
+        let encrypted = encrypt_with(b"# Session\n\nsecret plans\n", &config).unwrap();
+        assert!(!String::from_utf8_lossy(&encrypted).contains("secret plans"));
+
+        let decrypted = decrypt_with(
+            age::Decryptor::new(&encrypted[..]).unwrap(),
+            std::iter::once(&identity as &dyn age::Identity),
+        )
+        .unwrap();
+        assert_eq!(decrypted, "# Session\n\nsecret plans\n");
+
+        let stranger = age::x25519::Identity::generate();
+        assert!(matches!(
+            decrypt_with(
+                age::Decryptor::new(&encrypted[..]).unwrap(),
+                std::iter::once(&stranger as &dyn age::Identity),
+            ),
+            Err(WaylogError::Encryption(_))
+        ));
+    }
+
+    #[test]
+    fn test_history_key_is_sealed_with_the_passphrase() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let waylog = dir.path().join(WAYLOG_DIR);
+        std::fs::create_dir_all(waylog.join("history")).unwrap();
+        assert_eq!(
+            key_file(&waylog.join("history/a.md")),
+            Some(waylog.join(KEY_FILE))
+        );
+        assert_eq!(key_file(&dir.path().join("a.md")), None);
+
+        let key = age::x25519::Identity::generate();
+        let passphrase = || SecretString::from("correct horse".to_string());
+        let sealed = seal(
+            age::Encryptor::with_user_passphrase(passphrase()),
+            key.to_string().expose_secret().as_bytes(),
+        )
+        .unwrap();
+        let unlocked = unlock_key(&sealed, passphrase()).unwrap();
+        assert_eq!(unlocked.to_public(), key.to_public());
+        assert!(unlock_key(&sealed, SecretString::from("wrong".to_string())).is_err());
+
+        // History encrypted to the key opens with it, without the passphrase
+        let encrypted = encrypt_to(b"secret plans", &[unlocked.to_public()]).unwrap();
+        let decrypted = decrypt_with(
+            age::Decryptor::new(&encrypted[..]).unwrap(),
+            std::iter::once(&key as &dyn age::Identity),
+        )
+        .unwrap();
+        assert_eq!(decrypted, "secret plans");
+    }
+
+    #[test]
+    fn test_encrypted_path_and_stored_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("a.md");
+        assert_eq!(encrypted_path(&file), dir.path().join("a.md.age"));
+        assert_eq!(stored_path(&file), file);
+        assert!(!exists(&file));
+
+        std::fs::write(dir.path().join("a.md.age"), "x").unwrap();
+        assert_eq!(stored_path(&file), dir.path().join("a.md.age"));
+        assert!(exists(&file));
+    }
+}
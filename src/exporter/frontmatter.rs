@@ -1,5 +1,7 @@
 use crate::error::Result;
-use std::path::Path;
+use crate::exporter::encryption;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::io::AsyncReadExt;
 
@@ -7,22 +9,65 @@ use tokio::io::AsyncReadExt;
 pub struct Frontmatter {
     pub session_id: Option<String>,
     pub provider: Option<String>,
+    pub project: Option<PathBuf>,
     pub message_count: Option<usize>,
+    /// Input and output tokens of the messages in the file
+    pub total_tokens: Option<u64>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Time of the last synced message
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Source sessions combined into this file by `waylog merge`, with their synced message counts
+    pub merged_sessions: Vec<(String, usize)>,
+    /// Agent command line recorded by `waylog run` (kept as written, including quotes)
+    pub invocation: Option<String>,
+    /// Project files edited during the session, relative to the project
+    pub files_edited: Vec<String>,
+    /// Position of this file in a session split into several files (`-part2.md`, ...)
+    pub part: Option<usize>,
+    /// Revision of a session rewritten to a new file because the previous
+    /// one was edited by hand (`-rev2.md`, ...)
+    pub revision: Option<usize>,
+    /// Name the user gave the session when the agent exited
+    pub title: Option<String>,
+    /// Tags the user gave the session when the agent exited
+    pub tags: Vec<String>,
 }
 
 /// Parse minimal frontmatter from a markdown file
+/// (`.md`, or its encrypted `.md.age`)
 pub async fn parse_frontmatter(path: &Path) -> Result<Frontmatter> {
-    let mut file = fs::File::open(path).await?;
+    let path = encryption::history_path(path).unwrap_or_else(|| path.to_path_buf());
+    let stored = encryption::stored_path(&path);
+    if stored != path {
+        return Ok(parse_frontmatter_str(&encryption::read(&path).await?));
+    }
+    let mut file = fs::File::open(&path).await?;
 
-    // Read first 2KB which should cover the frontmatter
-    let mut buffer = [0u8; 2048];
+    // Read first 16KB which should cover the frontmatter (including long file lists)
+    let mut buffer = vec![0u8; 16 * 1024];
     let n = file.read(&mut buffer).await?;
-    let content = String::from_utf8_lossy(&buffer[..n]);
+    Ok(parse_frontmatter_str(&String::from_utf8_lossy(
+        &buffer[..n],
+    )))
+}
 
+/// Parse minimal frontmatter from the start of a markdown file's content
+pub fn parse_frontmatter_str(content: &str) -> Frontmatter {
     let mut fm = Frontmatter {
         session_id: None,
         provider: None,
+        project: None,
         message_count: None,
+        total_tokens: None,
+        started_at: None,
+        updated_at: None,
+        merged_sessions: Vec::new(),
+        invocation: None,
+        files_edited: Vec::new(),
+        part: None,
+        revision: None,
+        title: None,
+        tags: Vec::new(),
     };
 
     if let Some(stripped) = content.strip_prefix("---") {
@@ -36,16 +81,113 @@ pub async fn parse_frontmatter(path: &Path) -> Result<Frontmatter> {
                     fm.session_id = Some(val.trim().to_string());
                 } else if let Some(val) = line.strip_prefix("provider:") {
                     fm.provider = Some(val.trim().to_string());
+                } else if let Some(val) = line.strip_prefix("project:") {
+                    fm.project = Some(PathBuf::from(val.trim()));
                 } else if let Some(val) = line.strip_prefix("message_count:") {
                     if let Ok(count) = val.trim().parse() {
                         fm.message_count = Some(count);
                     }
+                } else if let Some(val) = line.strip_prefix("total_tokens:") {
+                    fm.total_tokens = val.trim().parse().ok();
+                } else if let Some(val) = line.strip_prefix("started_at:") {
+                    fm.started_at = parse_datetime(val);
+                } else if let Some(val) = line.strip_prefix("updated_at:") {
+                    fm.updated_at = parse_datetime(val);
+                } else if let Some(val) = line.strip_prefix("merged_sessions:") {
+                    fm.merged_sessions = parse_merged_sessions(val);
+                } else if let Some(val) = line.strip_prefix("invocation:") {
+                    fm.invocation = Some(val.trim().to_string());
+                } else if let Some(val) = line.strip_prefix("files_edited:") {
+                    fm.files_edited = parse_list(val);
+                } else if let Some(val) = line.strip_prefix("part:") {
+                    fm.part = val.trim().parse().ok();
+                } else if let Some(val) = line.strip_prefix("revision:") {
+                    fm.revision = val.trim().parse().ok();
+                } else if let Some(val) = line.strip_prefix("title:") {
+                    // Written JSON-quoted, but a hand-written one may not be
+                    let val = val.trim();
+                    fm.title = Some(serde_json::from_str(val).unwrap_or_else(|_| val.to_string()));
+                } else if let Some(val) = line.strip_prefix("tags:") {
+                    fm.tags = parse_list(val);
                 }
             }
         }
     }
 
-    Ok(fm)
+    fm
+}
+
+/// Format merged session counts as `id=count, id=count`
+pub fn format_merged_sessions(sessions: &[(String, usize)]) -> String {
+    sessions
+        .iter()
+        .map(|(id, count)| format!("{}={}", id, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format a list value as `a, b, c`
+pub fn format_list(items: &[String]) -> String {
+    items.join(", ")
+}
+
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(", ")
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Set `key: value` in the frontmatter of a markdown file's content,
+/// replacing the line in place if the key is already present. Content
+/// without frontmatter is returned unchanged.
+pub fn set_field(content: &str, key: &str, value: &str) -> String {
+    set_field_after(content, key, value, None)
+}
+
+/// Like `set_field`, but a key that is not present yet is inserted after
+/// the `after` key (if that one is present) rather than at the end
+pub fn set_field_after(content: &str, key: &str, value: &str, after: Option<&str>) -> String {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return content.to_string();
+    };
+    let Some(end) = rest.find("\n---") else {
+        return content.to_string();
+    };
+
+    let prefix = format!("{}:", key);
+    let field = format!("{}: {}", key, value);
+    let mut lines: Vec<String> = rest[..end].lines().map(String::from).collect();
+    match lines.iter().position(|line| line.starts_with(&prefix)) {
+        Some(i) => lines[i] = field,
+        None => {
+            let after = after.map(|after| format!("{}:", after));
+            let at = after
+                .and_then(|after| lines.iter().position(|line| line.starts_with(&after)))
+                .map_or(lines.len(), |i| i + 1);
+            lines.insert(at, field);
+        }
+    }
+
+    format!("---\n{}{}", lines.join("\n"), &rest[end..])
+}
+
+fn parse_merged_sessions(value: &str) -> Vec<(String, usize)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (id, count) = entry.trim().rsplit_once('=')?;
+            Some((id.trim().to_string(), count.trim().parse().ok()?))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -60,7 +202,9 @@ mod tests {
         let content = r#"---
 provider: claude
 session_id: test-session-123
+updated_at: 2025-01-15T10:30:00+01:00
 message_count: 5
+invocation: "claude --model opus"
 ---
 # Title
 Content here
@@ -71,6 +215,44 @@ Content here
         assert_eq!(fm.provider, Some("claude".to_string()));
         assert_eq!(fm.session_id, Some("test-session-123".to_string()));
         assert_eq!(fm.message_count, Some(5));
+        assert_eq!(
+            fm.updated_at.unwrap().to_rfc3339(),
+            "2025-01-15T09:30:00+00:00"
+        );
+        assert_eq!(fm.invocation.as_deref(), Some("\"claude --model opus\""));
+    }
+
+    #[test]
+    fn test_set_field() {
+        let content =
+            "---\nprovider: claude\nfiles_edited: a.rs\nmessage_count: 1\n---\n\n# Title\n";
+
+        let updated = set_field(content, "files_edited", "a.rs, b.rs");
+        let fm = parse_frontmatter_str(&updated);
+        assert_eq!(fm.files_edited, ["a.rs", "b.rs"]);
+        assert_eq!(fm.provider.as_deref(), Some("claude"));
+
+        assert!(updated.ends_with("\nmessage_count: 1\n---\n\n# Title\n"));
+        assert_eq!(updated.matches("files_edited").count(), 1);
+    }
+
+    #[test]
+    fn test_set_field_after_inserts_in_place() {
+        let content = "---
+message_count: 2
+files_edited: a.rs
+---
+
+# Title
+";
+        let updated = set_field_after(content, "total_tokens", "30", Some("message_count"));
+        assert!(updated.starts_with(
+            "---
+message_count: 2
+total_tokens: 30
+files_edited"
+        ));
+        assert_eq!(parse_frontmatter_str(&updated).total_tokens, Some(30));
     }
 
     #[tokio::test]
@@ -180,6 +362,27 @@ updated_at: 2024-01-01T01:00:00Z
         assert_eq!(fm.message_count, Some(3));
     }
 
+    #[tokio::test]
+    async fn test_parse_frontmatter_merged_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.md");
+        let content = r#"---
+provider: claude
+session_id: a
+message_count: 9
+merged_sessions: a=5, b=4
+---
+"#;
+        tokio::fs::write(&file_path, content).await.unwrap();
+        let fm = parse_frontmatter(&file_path).await.unwrap();
+
+        assert_eq!(
+            fm.merged_sessions,
+            vec![("a".to_string(), 5), ("b".to_string(), 4)]
+        );
+        assert_eq!(format_merged_sessions(&fm.merged_sessions), "a=5, b=4");
+    }
+
     #[tokio::test]
     async fn test_parse_frontmatter_missing_file() {
         let file_path = std::path::Path::new("/nonexistent/file.md");
@@ -0,0 +1,452 @@
+//! Standalone HTML pages for `waylog export --format html`: one page per
+//! session with highlighted code, plus an index linking them.
+use super::markdown::formatter::{extract_title, format_datetime, role_label};
+use super::markdown::labels::Labels;
+use crate::providers::base::{ChatMessage, ChatSession, MessageRole};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Name of the index page in an exported site
+pub const INDEX_FILE: &str = "index.html";
+
+/// Code blocks longer than this are collapsed
+const COLLAPSE_LINES: usize = 20;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; max-width: 52rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; line-height: 1.5; }
+a { color: #0969da; }
+header.session { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+header.session p { color: #656d76; margin-top: 0; }
+section.message { border: 1px solid #d0d7de; border-radius: 6px; padding: 0 1rem; margin: 1rem 0; }
+section.message.user { background: #f6f8fa; }
+section.message h2 { font-size: 1rem; margin: 0.75rem 0; }
+section.message h2 time { color: #656d76; font-weight: normal; font-size: 0.875rem; margin-left: 0.5rem; }
+pre { background: #ffffff; border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem; overflow-x: auto; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.875rem; }
+details { margin: 0.75rem 0; }
+summary { cursor: pointer; color: #656d76; }
+img { max-width: 100%; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.4rem 0.6rem; border-bottom: 1px solid #d0d7de; }
+"#;
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Highlighting classes for the code blocks, from syntect's GitHub-like theme
+fn highlight_css() -> &'static str {
+    static CSS: OnceLock<String> = OnceLock::new();
+    CSS.get_or_init(|| {
+        let themes = ThemeSet::load_defaults();
+        css_for_theme_with_class_style(&themes.themes["InspiredGitHub"], ClassStyle::Spaced)
+            .unwrap_or_default()
+    })
+}
+
+/// Escape text for HTML element content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Filename of a session's page: the markdown filename with an `.html` extension
+pub fn page_filename(session: &ChatSession) -> String {
+    let name = super::markdown::session_filename(session);
+    format!("{}.html", name.strip_suffix(".md").unwrap_or(&name))
+}
+
+/// Render a session as a complete HTML document
+pub fn render_session(session: &ChatSession, labels: &Labels) -> String {
+    let title = extract_title(&session.messages, labels);
+    let mut body = format!(
+        "<p><a href=\"index.html\">&larr; All sessions</a></p>\n\
+         <header class=\"session\">\n<h1>{}</h1>\n<p>{} &middot; {} &middot; {} messages</p>\n</header>\n",
+        escape(&title),
+        escape(&session.provider),
+        escape(&format_datetime(&session.started_at)),
+        session.messages.len()
+    );
+
+    for message in &session.messages {
+        body.push_str(&render_message(message, labels));
+    }
+
+    document(&title, &body)
+}
+
+/// A row of the index page
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    /// Page filename, relative to the index
+    pub filename: String,
+    pub title: String,
+    pub provider: String,
+    pub started_at: DateTime<Utc>,
+    pub messages: usize,
+}
+
+impl IndexEntry {
+    pub fn for_session(filename: String, session: &ChatSession, labels: &Labels) -> Self {
+        Self {
+            filename,
+            title: extract_title(&session.messages, labels),
+            provider: session.provider.clone(),
+            started_at: session.started_at,
+            messages: session.messages.len(),
+        }
+    }
+}
+
+/// Render the index page linking every exported session, newest first
+pub fn render_index(entries: &[IndexEntry]) -> String {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.started_at));
+
+    let mut body = String::from(
+        "<h1>Sessions</h1>\n<table>\n<thead><tr><th>Started</th><th>Provider</th><th>Session</th><th>Messages</th></tr></thead>\n<tbody>\n",
+    );
+    for entry in entries {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td><a href=\"{}\">{}</a></td><td>{}</td></tr>\n",
+            escape(&format_datetime(&entry.started_at)),
+            escape(&entry.provider),
+            escape(&entry.filename),
+            escape(&entry.title),
+            entry.messages
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+
+    document("Sessions", &body)
+}
+
+/// Render sessions as one document for `export --merge html`, in the given
+/// order after a table of contents
+pub fn render_combined(sessions: &[ChatSession], title: &str, labels: &Labels) -> String {
+    let mut body = format!(
+        "<h1>{}</h1>\n<nav>\n<h2>Contents</h2>\n<ol>\n",
+        escape(title)
+    );
+    for (i, session) in sessions.iter().enumerate() {
+        body.push_str(&format!(
+            "<li><a href=\"#session-{}\">{}</a> &middot; {} &middot; {}</li>\n",
+            i + 1,
+            escape(&extract_title(&session.messages, labels)),
+            escape(&session.provider),
+            escape(&format_datetime(&session.started_at))
+        ));
+    }
+    body.push_str("</ol>\n</nav>\n");
+
+    for (i, session) in sessions.iter().enumerate() {
+        body.push_str(&format!(
+            "<header class=\"session\" id=\"session-{}\">\n<h1>{}. {}</h1>\n<p>{} &middot; {} &middot; {} messages</p>\n</header>\n",
+            i + 1,
+            i + 1,
+            escape(&extract_title(&session.messages, labels)),
+            escape(&session.provider),
+            escape(&format_datetime(&session.started_at)),
+            session.messages.len()
+        ));
+        for message in &session.messages {
+            body.push_str(&render_message(message, labels));
+        }
+    }
+
+    document(title, &body)
+}
+
+pub(crate) fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        highlight_css(),
+        body
+    )
+}
+
+fn render_message(message: &ChatMessage, labels: &Labels) -> String {
+    let (role_emoji, role_name) = role_label(message, labels);
+    let class = match message.role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+    };
+    let role = if crate::config::get().export.headers.emoji {
+        format!("{} {}", role_emoji, role_name)
+    } else {
+        role_name
+    };
+
+    let mut html = format!(
+        "<section class=\"message {}\">\n<h2>{}<time datetime=\"{}\">{}</time></h2>\n",
+        class,
+        escape(&role),
+        message.timestamp.to_rfc3339(),
+        escape(&format_datetime(&message.timestamp))
+    );
+    html.push_str(&markdown_to_html(&message.content));
+
+    // Attachments are embedded so the page stands on its own
+    for attachment in &message.metadata.attachments {
+        let data = base64::engine::general_purpose::STANDARD.encode(&attachment.data);
+        let uri = format!("data:{};base64,{}", escape(&attachment.media_type), data);
+        if attachment.media_type.starts_with("image/") {
+            html.push_str(&format!(
+                "<p><img src=\"{}\" alt=\"{}\"></p>\n",
+                uri,
+                escape(&attachment.media_type)
+            ));
+        } else {
+            html.push_str(&format!(
+                "<p><a href=\"{}\" download>{}</a></p>\n",
+                uri,
+                escape(&attachment.media_type)
+            ));
+        }
+    }
+
+    if !message.metadata.tool_calls.is_empty() {
+        html.push_str(&format!(
+            "<details>\n<summary>🔧 {}</summary>\n<ul>\n",
+            escape(&labels.tools_used)
+        ));
+        for tool in &message.metadata.tool_calls {
+            html.push_str(&format!("<li><code>{}</code></li>\n", escape(tool)));
+        }
+        html.push_str("</ul>\n</details>\n");
+    }
+
+    if !message.metadata.thoughts.is_empty() {
+        html.push_str(&format!(
+            "<details>\n<summary>💭 {}</summary>\n<ul>\n",
+            escape(&labels.thoughts)
+        ));
+        for thought in &message.metadata.thoughts {
+            html.push_str(&format!("<li>{}</li>\n", escape(thought)));
+        }
+        html.push_str("</ul>\n</details>\n");
+    }
+
+    html.push_str("</section>\n");
+    html
+}
+
+/// Render message markdown, highlighting fenced code. Raw HTML in messages is
+/// shown as text rather than interpreted, and links and images go nowhere
+/// unless they are relative or http, https or mailto URLs.
+pub(crate) fn markdown_to_html(markdown: &str) -> String {
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    ) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, text)) = code.take() {
+                    events.push(Event::Html(highlight(&text, &lang).into()));
+                }
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buffer)) = code.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            })),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            })),
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// `url` if it is relative or an http, https or mailto URL, `#` otherwise, so
+/// a transcript can't smuggle `javascript:` or `data:` into the page
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // Browsers ignore whitespace and control characters in the scheme
+    let cleaned: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    let scheme = cleaned
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+    match scheme {
+        None => url,
+        Some(scheme)
+            if ["http", "https", "mailto"]
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(scheme)) =>
+        {
+            url
+        }
+        Some(_) => CowStr::Borrowed("#"),
+    }
+}
+
+/// Highlight a code block, collapsing it when it is long
+fn highlight(code: &str, lang: &str) -> String {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, ClassStyle::Spaced);
+    let highlighted = LinesWithEndings::from(code)
+        .try_for_each(|line| generator.parse_html_for_line_which_includes_newline(line))
+        .map(|_| generator.finalize())
+        .unwrap_or_else(|_| escape(code));
+
+    let block = format!("<pre class=\"code\"><code>{}</code></pre>\n", highlighted);
+    let lines = code.lines().count();
+    if lines > COLLAPSE_LINES {
+        format!(
+            "<details>\n<summary>{} ({} lines)</summary>\n{}</details>\n",
+            if lang.is_empty() { "code" } else { lang },
+            lines,
+            block
+        )
+    } else {
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::MessageMetadata;
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn session(content: &str) -> ChatSession {
+        let now = Utc::now();
+        ChatSession {
+            session_id: "abc".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: now,
+            updated_at: now,
+            messages: vec![ChatMessage {
+                id: "m0".to_string(),
+                timestamp: now,
+                role: MessageRole::User,
+                content: content.to_string(),
+                metadata: MessageMetadata::default(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_session_highlights_code() {
+        let session =
+            session("Fix <this>:\n\n```rust\nfn main() {}\n```\n\n<script>alert(1)</script>");
+        let html = render_session(&session, &Labels::default());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Fix &lt;this&gt;:</title>"));
+        assert!(html.contains("<section class=\"message user\">"));
+        assert!(html.contains("<span class=\"storage type function rust\">fn</span>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_unsafe_link_targets_are_dropped() {
+        let html = markdown_to_html(
+            "[a](javascript:alert(1)) [b](JaVa%0AScript:x) ![c](data:text/html,x) \
+             [d](<java script:alert(1)>) [e](https://example.com/?q=a:b) [f](docs/a.md#x:y) \
+             [g](mailto:me@example.com)",
+        );
+        assert!(!html.to_lowercase().contains("script:"), "{}", html);
+        assert!(!html.contains("data:"), "{}", html);
+        assert_eq!(html.matches("href=\"#\"").count(), 3, "{}", html);
+        assert!(html.contains("src=\"#\""));
+        assert!(html.contains("href=\"https://example.com/?q=a:b\""));
+        assert!(html.contains("href=\"docs/a.md#x:y\""));
+        assert!(html.contains("href=\"mailto:me@example.com\""));
+    }
+
+    #[test]
+    fn test_long_code_is_collapsed() {
+        let code = (0..30).map(|i| format!("echo {}\n", i)).collect::<String>();
+        assert!(highlight(&code, "sh").starts_with("<details>\n<summary>sh (30 lines)"));
+        assert!(highlight("echo 1\n", "sh").starts_with("<pre"));
+    }
+
+    #[test]
+    fn test_render_combined_links_each_session() {
+        let sessions = [session("First question"), session("Second question")];
+        let html = render_combined(&sessions, "Login work", &Labels::default());
+
+        assert!(html.contains("<title>Login work</title>"));
+        assert!(html.contains("<a href=\"#session-2\">Second question</a>"));
+        assert!(html.contains("id=\"session-2\""));
+        assert!(html.find("First question</h1>") < html.find("Second question</h1>"));
+    }
+
+    #[test]
+    fn test_render_index_links_pages() {
+        let session = session("Hello");
+        let entry = IndexEntry::for_session("a&b.html".to_string(), &session, &Labels::default());
+        let html = render_index(&[entry]);
+        assert!(html.contains("<a href=\"a&amp;b.html\">Hello</a>"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Provider-agnostic JSONL archive format: one message per line, with enough
+//! context (provider, session, project) to rebuild the sessions on import.
+use crate::error::Result;
+use crate::providers::base::{ChatMessage, ChatSession};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Schema tag written into every record; bumped on incompatible changes
+pub const SCHEMA: &str = "waylog.message.v1";
+
+/// One message in a JSONL export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub schema: String,
+    pub provider: String,
+    pub session_id: String,
+    pub project: PathBuf,
+    /// Position of the message within its session
+    pub index: usize,
+    #[serde(flatten)]
+    pub message: ChatMessage,
+}
+
+impl Record {
+    /// Stable identifier of the message, used for sampling
+    pub fn key(&self) -> String {
+        format!("{}#{}", self.session_id, self.index)
+    }
+}
+
+/// One record per message of `session`
+pub fn records(session: &ChatSession) -> impl Iterator<Item = Record> + '_ {
+    session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(index, message)| Record {
+            schema: SCHEMA.to_string(),
+            provider: session.provider.clone(),
+            session_id: session.session_id.clone(),
+            project: session.project_path.clone(),
+            index,
+            message: message.clone(),
+        })
+}
+
+/// Write records as newline-delimited JSON
+pub fn write_records<W: Write>(mut writer: W, records: &[Record]) -> io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Whether `content` looks like a waylog JSONL export
+pub fn is_jsonl(content: &str) -> bool {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .and_then(|value| value.get("schema")?.as_str().map(String::from))
+        .is_some_and(|schema| schema.starts_with("waylog.message."))
+}
+
+/// Rebuild sessions from JSONL text
+pub fn read_sessions(content: &str) -> Result<Vec<ChatSession>> {
+    let mut records = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(line).map_err(|e| {
+            <serde_json::Error as serde::de::Error>::custom(format!(
+                "line {}: {}",
+                line_number + 1,
+                e
+            ))
+        })?;
+        records.push(record);
+    }
+    Ok(into_sessions(records))
+}
+
+/// Group records back into sessions, in the order sessions first appear
+pub fn into_sessions(records: impl IntoIterator<Item = Record>) -> Vec<ChatSession> {
+    let mut sessions: Vec<(ChatSession, Vec<(usize, ChatMessage)>)> = Vec::new();
+
+    for record in records {
+        let existing = sessions
+            .iter_mut()
+            .find(|(s, _)| s.session_id == record.session_id && s.provider == record.provider);
+        let messages = match existing {
+            Some((_, messages)) => messages,
+            None => {
+                sessions.push((
+                    ChatSession {
+                        session_id: record.session_id,
+                        provider: record.provider,
+                        project_path: record.project,
+                        started_at: record.message.timestamp,
+                        updated_at: record.message.timestamp,
+                        messages: Vec::new(),
+                    },
+                    Vec::new(),
+                ));
+                &mut sessions.last_mut().unwrap().1
+            }
+        };
+        messages.push((record.index, record.message));
+    }
+
+    sessions
+        .into_iter()
+        .map(|(mut session, mut messages)| {
+            messages.sort_by_key(|(index, _)| *index);
+            session.messages = messages.into_iter().map(|(_, m)| m).collect();
+            if let (Some(first), Some(last)) = (
+                session.messages.iter().map(|m| m.timestamp).min(),
+                session.messages.iter().map(|m| m.timestamp).max(),
+            ) {
+                session.started_at = first;
+                session.updated_at = last;
+            }
+            session
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{Attachment, MessageMetadata, MessageRole};
+    use chrono::{Duration, Utc};
+
+    fn session() -> ChatSession {
+        let now = Utc::now();
+        let message = |role, content: &str, offset| ChatMessage {
+            id: format!("m{}", offset),
+            timestamp: now + Duration::seconds(offset),
+            role,
+            content: content.to_string(),
+            metadata: MessageMetadata::default(),
+        };
+        let mut reply = message(MessageRole::Assistant, "Hello\nthere", 1);
+        reply.metadata.model = Some("claude-sonnet-4.5".to_string());
+        reply.metadata.tool_calls = vec!["Bash".to_string()];
+        reply.metadata.attachments = vec![Attachment {
+            media_type: "image/png".to_string(),
+            data: b"png".to_vec(),
+        }];
+
+        ChatSession {
+            session_id: "abc".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: now,
+            updated_at: now + Duration::seconds(1),
+            messages: vec![message(MessageRole::User, "Hi", 0), reply],
+        }
+    }
+
+    #[test]
+    fn test_write_records() {
+        let session = session();
+        let records: Vec<_> = records(&session).collect();
+        assert_eq!(records[1].key(), "abc#1");
+
+        let mut buffer = Vec::new();
+        write_records(&mut buffer, &records).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["schema"], SCHEMA);
+        assert_eq!(lines[0]["role"], "user");
+        assert_eq!(lines[1]["content"], "Hello\nthere");
+        assert_eq!(lines[1]["session_id"], "abc");
+        assert_eq!(lines[1]["metadata"]["model"], "claude-sonnet-4.5");
+        assert_eq!(lines[1]["metadata"]["attachments"][0]["data"], "cG5n");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original = session();
+        let mut buffer = Vec::new();
+        write_records(&mut buffer, &records(&original).collect::<Vec<_>>()).unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+        assert!(is_jsonl(&content));
+
+        // Order of lines does not matter
+        let reversed: Vec<_> = content.lines().rev().collect();
+        let sessions = read_sessions(&reversed.join("\n")).unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        let restored = &sessions[0];
+        assert_eq!(restored.session_id, original.session_id);
+        assert_eq!(restored.provider, original.provider);
+        assert_eq!(restored.project_path, original.project_path);
+        assert_eq!(restored.started_at, original.started_at);
+        assert_eq!(restored.updated_at, original.updated_at);
+        assert_eq!(restored.messages.len(), 2);
+        assert_eq!(restored.messages[0].content, "Hi");
+        assert_eq!(restored.messages[1].metadata.tool_calls, ["Bash"]);
+        assert_eq!(restored.messages[1].metadata.attachments[0].data, b"png");
+    }
+
+    #[test]
+    fn test_read_sessions_reports_line() {
+        let err = read_sessions("\n{not json}\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert!(!is_jsonl(r#"{"messages": []}"#));
+    }
+}
@@ -1,62 +1,116 @@
+use super::labels::Labels;
+use crate::config::HeaderConfig;
+use crate::error::Result;
 use crate::providers::base::{ChatMessage, MessageRole};
+use crate::utils::time::Timezone;
 use chrono::{DateTime, Utc};
 
-/// Format a single message
-pub(crate) fn format_message(message: &ChatMessage) -> String {
-    let mut md = String::new();
+/// Format a single message with the active `message.md.tera` template
+pub(crate) fn format_message(message: &ChatMessage, labels: &Labels) -> Result<String> {
+    super::template::get().render_message(message, labels)
+}
 
-    // Header with role and timestamp
-    let role_emoji = match message.role {
-        MessageRole::User => "👤",
-        MessageRole::Assistant => "🤖",
-        MessageRole::System => "⚙️",
-    };
+/// Header line with role and timestamp, e.g. `## 👤 User (2024-01-01 12:00:00 UTC)`
+pub(crate) fn format_header(
+    message: &ChatMessage,
+    labels: &Labels,
+    headers: &HeaderConfig,
+) -> String {
+    let (role_emoji, role_name) = role_label(message, labels);
 
-    let role_name = match message.role {
-        MessageRole::User => "User",
-        MessageRole::Assistant => "Assistant",
-        MessageRole::System => "System",
+    let hashes = "#".repeat(headers.level.clamp(1, 6) as usize);
+    let role = if headers.emoji {
+        format!("{} {}", role_emoji, role_name)
+    } else {
+        role_name
     };
 
-    md.push_str(&format!(
-        "## {} {} ({})\n\n",
-        role_emoji,
-        role_name,
+    format!(
+        "{} {} ({})",
+        hashes,
+        role,
         format_datetime(&message.timestamp)
-    ));
+    )
+}
 
-    // Content
-    md.push_str(&message.content);
-    md.push('\n');
+/// Summary line of a message's folded thoughts, e.g. `💭 Thoughts`
+pub(crate) fn format_thoughts_label(labels: &Labels, headers: &HeaderConfig) -> String {
+    if headers.emoji {
+        format!("💭 {}", labels.thoughts)
+    } else {
+        labels.thoughts.clone()
+    }
+}
+
+/// Annotation added with `waylog note`: a quote headed by the label and
+/// time, e.g. `> **📝 Note** (2024-01-01 12:00:00 UTC)`
+pub(crate) fn format_note(
+    text: &str,
+    at: &DateTime<Utc>,
+    labels: &Labels,
+    headers: &HeaderConfig,
+) -> String {
+    let label = if headers.emoji {
+        format!("📝 {}", labels.note)
+    } else {
+        labels.note.clone()
+    };
 
-    // Tool calls (Claude Code)
-    if !message.metadata.tool_calls.is_empty() {
-        md.push_str("\n**Tools Used:**\n");
-        for tool in &message.metadata.tool_calls {
-            md.push_str(&format!("- `{}`\n", tool));
+    let mut block = format!("> **{}** ({})\n>", label, format_datetime(at));
+    for line in text.trim().lines() {
+        block.push_str("\n>");
+        if !line.is_empty() {
+            block.push(' ');
+            block.push_str(line);
         }
     }
+    block
+}
 
-    // Thoughts (Gemini)
-    if !message.metadata.thoughts.is_empty() {
-        md.push_str("\n<details>\n<summary>💭 Thoughts</summary>\n\n");
-        for thought in &message.metadata.thoughts {
-            md.push_str(&format!("- {}\n", thought));
-        }
-        md.push_str("\n</details>\n");
+/// Section listing the commits made during a session, at the level of the
+/// message headers, e.g. `## Commits made during this session`
+pub(crate) fn format_commits(
+    commits: &[(String, String)],
+    labels: &Labels,
+    headers: &HeaderConfig,
+) -> String {
+    let hashes = "#".repeat(headers.level.clamp(1, 6) as usize);
+    let mut section = format!("{} {}\n", hashes, labels.commits);
+    for (hash, subject) in commits {
+        section.push_str(&format!("\n- `{}` {}", hash, subject));
     }
+    section
+}
 
-    md
+/// Emoji and display name of a message's role, with `{model}` filled in
+pub(crate) fn role_label(message: &ChatMessage, labels: &Labels) -> (&'static str, String) {
+    let role_emoji = match message.role {
+        MessageRole::User => "👤",
+        MessageRole::Assistant => "🤖",
+        MessageRole::System => "⚙️",
+    };
+
+    let role_name = match message.role {
+        MessageRole::User => &labels.user,
+        MessageRole::Assistant => &labels.assistant,
+        MessageRole::System => &labels.system,
+    };
+    let role_name = role_name.replace(
+        "{model}",
+        message.metadata.model.as_deref().unwrap_or("unknown"),
+    );
+
+    (role_emoji, role_name)
 }
 
 /// Extract a title from the first user message
-pub(crate) fn extract_title(messages: &[ChatMessage]) -> String {
+pub(crate) fn extract_title(messages: &[ChatMessage], labels: &Labels) -> String {
     messages
         .iter()
         .find(|m| matches!(m.role, MessageRole::User))
         .map(|m| {
             // Take first line or first 60 characters (char-boundary safe)
-            let first_line = m.content.lines().next().unwrap_or("Untitled Session");
+            let first_line = m.content.lines().next().unwrap_or(&labels.untitled);
             let char_count = first_line.chars().count();
             if char_count > 60 {
                 let truncated: String = first_line.chars().take(60).collect();
@@ -65,12 +119,12 @@ pub(crate) fn extract_title(messages: &[ChatMessage]) -> String {
                 first_line.to_string()
             }
         })
-        .unwrap_or_else(|| "Untitled Session".to_string())
+        .unwrap_or_else(|| labels.untitled.clone())
 }
 
-/// Format datetime in a human-readable way
+/// Format datetime in a human-readable way, in the configured `export.timezone`
 pub(crate) fn format_datetime(dt: &DateTime<Utc>) -> String {
-    dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    Timezone::from_config().format_datetime(dt)
 }
 
 #[cfg(test)]
@@ -91,7 +145,7 @@ mod tests {
     #[test]
     fn test_extract_title_short_english() {
         let messages = vec![create_test_message("Hello world", MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "Hello world");
     }
 
@@ -100,7 +154,7 @@ mod tests {
         let long_text =
             "This is a very long message that exceeds sixty characters and should be truncated";
         let messages = vec![create_test_message(long_text, MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert!(title.ends_with("..."));
         assert!(title.len() <= 63); // 60 chars + "..."
     }
@@ -108,7 +162,7 @@ mod tests {
     #[test]
     fn test_extract_title_short_chinese() {
         let messages = vec![create_test_message("你好世界", MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "你好世界");
     }
 
@@ -118,7 +172,7 @@ mod tests {
             "把 pg_stateful.yaml 改写为 docker compose 可以运行的yaml，输出到 docker-compose.yaml";
         let messages = vec![create_test_message(long_chinese, MessageRole::User)];
         // This should not panic
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert!(title.ends_with("..."));
     }
 
@@ -126,7 +180,7 @@ mod tests {
     fn test_extract_title_mixed_long() {
         let mixed = "这是一个包含English和中文的very long message that should be truncated properly without panic";
         let messages = vec![create_test_message(mixed, MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert!(title.ends_with("..."));
     }
 
@@ -134,14 +188,14 @@ mod tests {
     fn test_extract_title_multiline() {
         let multiline = "First line\nSecond line\nThird line";
         let messages = vec![create_test_message(multiline, MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "First line");
     }
 
     #[test]
     fn test_extract_title_empty_messages() {
         let messages: Vec<ChatMessage> = vec![];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "Untitled Session");
     }
 
@@ -151,7 +205,7 @@ mod tests {
             create_test_message("Assistant response", MessageRole::Assistant),
             create_test_message("System message", MessageRole::System),
         ];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "Untitled Session");
     }
 
@@ -159,7 +213,7 @@ mod tests {
     fn test_extract_title_exactly_60_chars() {
         let exactly_60 = "a".repeat(60);
         let messages = vec![create_test_message(&exactly_60, MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, exactly_60);
         assert!(!title.ends_with("..."));
     }
@@ -168,9 +222,9 @@ mod tests {
     fn test_extract_title_with_emoji() {
         let with_emoji = "Hello 👋 this is a message with emoji 🎉 that might be long enough to truncate properly";
         let messages = vec![create_test_message(with_emoji, MessageRole::User)];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         // Should not panic on emoji boundaries
-        assert!(title.len() > 0);
+        assert!(!title.is_empty());
     }
 
     #[test]
@@ -180,7 +234,32 @@ mod tests {
             create_test_message("First user message", MessageRole::User),
             create_test_message("Second user message", MessageRole::User),
         ];
-        let title = extract_title(&messages);
+        let title = extract_title(&messages, &Labels::default());
         assert_eq!(title, "First user message");
     }
+
+    #[test]
+    fn test_format_header_style() {
+        let mut message = create_test_message("Hi", MessageRole::Assistant);
+        message.metadata.model = Some("claude-sonnet-4.5".to_string());
+        let labels = Labels {
+            assistant: "Claude ({model})".to_string(),
+            ..Labels::default()
+        };
+
+        let header = format_header(&message, &labels, &HeaderConfig::default());
+        assert!(header.starts_with("## 🤖 Claude (claude-sonnet-4.5) ("));
+
+        let plain = HeaderConfig {
+            emoji: false,
+            level: 3,
+        };
+        let header = format_header(&message, &labels, &plain);
+        assert!(header.starts_with("### Claude (claude-sonnet-4.5) ("));
+        assert_eq!(format_thoughts_label(&labels, &plain), "Thoughts");
+        assert_eq!(
+            format_thoughts_label(&labels, &HeaderConfig::default()),
+            "💭 Thoughts"
+        );
+    }
 }
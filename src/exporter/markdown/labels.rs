@@ -0,0 +1,169 @@
+use crate::config::LabelOverrides;
+use serde::Serialize;
+
+/// Human-readable strings written into exported markdown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Labels {
+    pub user: String,
+    pub assistant: String,
+    pub system: String,
+    pub tools_used: String,
+    pub thoughts: String,
+    pub untitled: String,
+    /// Navigation between the files of a split session
+    pub part: String,
+    /// Annotations added with `waylog note`
+    pub note: String,
+    /// Heading of the commits made while `waylog run` was active
+    pub commits: String,
+}
+
+impl Labels {
+    /// Built-in labels for a language code (e.g. "en", "zh", "de-DE").
+    /// Unknown languages fall back to English.
+    pub fn for_language(language: &str) -> Self {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let (user, assistant, system, tools_used, thoughts, untitled, part, note, commits) =
+            match primary.as_str() {
+                "zh" => (
+                    "用户",
+                    "助手",
+                    "系统",
+                    "使用的工具",
+                    "思考过程",
+                    "未命名会话",
+                    "部分",
+                    "备注",
+                    "本次会话中的提交",
+                ),
+                "ja" => (
+                    "ユーザー",
+                    "アシスタント",
+                    "システム",
+                    "使用したツール",
+                    "思考",
+                    "無題のセッション",
+                    "パート",
+                    "メモ",
+                    "このセッション中のコミット",
+                ),
+                "de" => (
+                    "Benutzer",
+                    "Assistent",
+                    "System",
+                    "Verwendete Tools",
+                    "Gedanken",
+                    "Unbenannte Sitzung",
+                    "Teil",
+                    "Notiz",
+                    "Commits während dieser Sitzung",
+                ),
+                "fr" => (
+                    "Utilisateur",
+                    "Assistant",
+                    "Système",
+                    "Outils utilisés",
+                    "Réflexions",
+                    "Session sans titre",
+                    "Partie",
+                    "Note",
+                    "Commits effectués pendant cette session",
+                ),
+                "es" => (
+                    "Usuario",
+                    "Asistente",
+                    "Sistema",
+                    "Herramientas usadas",
+                    "Razonamiento",
+                    "Sesión sin título",
+                    "Parte",
+                    "Nota",
+                    "Commits realizados durante esta sesión",
+                ),
+                _ => (
+                    "User",
+                    "Assistant",
+                    "System",
+                    "Tools Used",
+                    "Thoughts",
+                    "Untitled Session",
+                    "Part",
+                    "Note",
+                    "Commits made during this session",
+                ),
+            };
+
+        Self {
+            user: user.to_string(),
+            assistant: assistant.to_string(),
+            system: system.to_string(),
+            tools_used: tools_used.to_string(),
+            thoughts: thoughts.to_string(),
+            untitled: untitled.to_string(),
+            part: part.to_string(),
+            note: note.to_string(),
+            commits: commits.to_string(),
+        }
+    }
+
+    /// Apply individual label overrides from config
+    pub fn with_overrides(mut self, overrides: &LabelOverrides) -> Self {
+        let apply = |target: &mut String, value: &Option<String>| {
+            if let Some(v) = value {
+                *target = v.clone();
+            }
+        };
+        apply(&mut self.user, &overrides.user);
+        apply(&mut self.assistant, &overrides.assistant);
+        apply(&mut self.system, &overrides.system);
+        apply(&mut self.tools_used, &overrides.tools_used);
+        apply(&mut self.thoughts, &overrides.thoughts);
+        apply(&mut self.untitled, &overrides.untitled);
+        apply(&mut self.part, &overrides.part);
+        apply(&mut self.note, &overrides.note);
+        apply(&mut self.commits, &overrides.commits);
+        self
+    }
+
+    /// Labels from the active config
+    pub fn from_config() -> Self {
+        let export = &crate::config::get().export;
+        Self::for_language(&export.language).with_overrides(&export.labels)
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self::for_language("en")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_language() {
+        assert_eq!(Labels::for_language("en").user, "User");
+        assert_eq!(Labels::for_language("zh-CN").assistant, "助手");
+        assert_eq!(Labels::for_language("de_DE").tools_used, "Verwendete Tools");
+        assert_eq!(Labels::for_language("xx"), Labels::default());
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let overrides = LabelOverrides {
+            user: Some("Me".to_string()),
+            ..Default::default()
+        };
+        let labels = Labels::for_language("fr").with_overrides(&overrides);
+        assert_eq!(labels.user, "Me");
+        assert_eq!(labels.assistant, "Assistant");
+        assert_eq!(labels.system, "Système");
+    }
+}
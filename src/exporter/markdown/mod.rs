@@ -1,84 +1,364 @@
-mod formatter;
-
-use crate::error::Result;
-use crate::providers::base::{ChatMessage, ChatSession};
-use std::path::Path;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-
-/// Generate markdown content from a chat session
-pub fn generate_markdown(session: &ChatSession) -> String {
-    let mut md = String::new();
-
-    // Frontmatter
-    md.push_str("---\n");
-    md.push_str(&format!("provider: {}\n", session.provider));
-    md.push_str(&format!("session_id: {}\n", session.session_id));
-    md.push_str(&format!("project: {}\n", session.project_path.display()));
-    md.push_str(&format!(
-        "started_at: {}\n",
-        session.started_at.to_rfc3339()
-    ));
-    md.push_str(&format!(
-        "updated_at: {}\n",
-        session.updated_at.to_rfc3339()
-    ));
-    md.push_str(&format!("message_count: {}\n", session.messages.len()));
-
-    // Calculate total tokens if available
-    let total_tokens: u32 = session
-        .messages
+pub(crate) mod formatter;
+pub mod labels;
+pub mod parts;
+pub mod template;
+
+use crate::config::CollisionPolicy;
+use crate::error::{Result, WaylogError};
+use crate::exporter::attachments;
+use crate::exporter::encryption;
+use crate::exporter::frontmatter;
+use crate::exporter::redact::Redactor;
+use crate::providers::base::{ChatMessage, ChatSession, FileAccessKind};
+use crate::utils::time::Timezone;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// The name given to a session in its extra frontmatter (`title`, written
+/// JSON-quoted by `run --session-name`)
+pub fn given_title(extra_frontmatter: &[(&str, String)]) -> Option<String> {
+    extra_frontmatter
         .iter()
-        .filter_map(|m| m.metadata.tokens.as_ref())
-        .map(|t| t.input + t.output)
-        .sum();
+        .find(|(key, _)| *key == "title")
+        .map(|(_, value)| serde_json::from_str(value).unwrap_or_else(|_| value.clone()))
+}
+
+/// Generate one part of a split session; `session` holds only the part's messages
+fn generate_part(
+    session: &ChatSession,
+    extra_frontmatter: &[(&str, String)],
+    part: Option<&template::Part>,
+) -> Result<String> {
+    let labels = labels::Labels::from_config();
+    let messages = Redactor::from_config().redact_messages(&session.messages);
+    template::get().render_session(session, &messages, extra_frontmatter, &labels, part)
+}
+
+/// Render sessions as one document for `export --merge markdown`, in the
+/// given order after a table of contents. Messages use the active
+/// `message.md.tera`; sessions are expected to be redacted already.
+pub fn render_combined(sessions: &[ChatSession], title: &str) -> Result<String> {
+    let labels = labels::Labels::from_config();
+    let mut doc = format!("# {}\n\n## Contents\n\n", title);
+    for (i, session) in sessions.iter().enumerate() {
+        doc.push_str(&format!(
+            "{}. [{}](#session-{}) · {} · {}\n",
+            i + 1,
+            formatter::extract_title(&session.messages, &labels),
+            i + 1,
+            session.provider,
+            formatter::format_datetime(&session.started_at)
+        ));
+    }
 
-    if total_tokens > 0 {
-        md.push_str(&format!("total_tokens: {}\n", total_tokens));
+    for (i, session) in sessions.iter().enumerate() {
+        doc.push_str(&format!(
+            "\n---\n\n<a id=\"session-{}\"></a>\n\n# {}. {}\n\n_{} · {} · {} messages_\n\n",
+            i + 1,
+            i + 1,
+            formatter::extract_title(&session.messages, &labels),
+            session.provider,
+            formatter::format_datetime(&session.started_at),
+            session.messages.len()
+        ));
+        for message in &session.messages {
+            doc.push_str(&formatter::format_message(message, &labels)?);
+            doc.push_str("\n\n");
+        }
     }
+    Ok(doc)
+}
+
+/// Files edited in `messages`, relative to `project_path` where possible, sorted and deduplicated
+pub(crate) fn edited_files(messages: &[ChatMessage], project_path: &Path) -> Vec<String> {
+    let mut files: Vec<String> = messages
+        .iter()
+        .flat_map(|m| &m.metadata.file_accesses)
+        .filter(|a| a.kind == FileAccessKind::Edit)
+        .map(|a| {
+            let path = Path::new(&a.path);
+            path.strip_prefix(project_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
 
-    md.push_str("---\n\n");
+/// Build the markdown filename for a session from `export.filename_template`
+pub fn session_filename(session: &ChatSession) -> String {
+    named_session_filename(session, None)
+}
+
+/// Build the markdown filename for a session, with the name the user gave
+/// it (if any) in place of the first message in `{slug}` and `{title}`
+pub fn named_session_filename(session: &ChatSession, name: Option<&str>) -> String {
+    let export = &crate::config::get().export;
+    let stem = render_filename_template(&export.filename_template, session, name);
+    crate::utils::string::truncate_filename(&stem, "md", export.max_filename_length)
+}
 
-    // Title
-    let title = formatter::extract_title(&session.messages);
-    md.push_str(&format!("# {}\n\n", title));
+/// Pick the markdown path for a new session in `dir`, applying `export.on_collision`
+/// when another file already has the generated name
+pub fn session_path(dir: &Path, session: &ChatSession) -> PathBuf {
+    named_session_path(dir, session, None)
+}
 
-    // Messages
-    for message in &session.messages {
-        md.push_str(&formatter::format_message(message));
-        md.push_str("\n\n");
+/// `session_path` for a session the user gave `name` (`run --session-name`)
+pub fn named_session_path(dir: &Path, session: &ChatSession, name: Option<&str>) -> PathBuf {
+    let export = &crate::config::get().export;
+    let candidate = dir.join(named_session_filename(session, name));
+    if !encryption::exists(&candidate) || export.on_collision == CollisionPolicy::Overwrite {
+        return candidate;
     }
+    // `-2` would depend on which files this machine happens to have
+    let on_collision = match export.on_collision {
+        CollisionPolicy::Suffix if crate::config::get().sync.shared => CollisionPolicy::SessionId,
+        policy => policy,
+    };
+
+    let stem = render_filename_template(&export.filename_template, session, name);
+    let with_suffix = |suffix: &str| {
+        // Keep the suffix intact when the stem has to be shortened
+        let budget = export.max_filename_length.saturating_sub(suffix.len());
+        let name = crate::utils::string::truncate_filename(&stem, "md", budget);
+        let name = name.strip_suffix(".md").unwrap_or(&name);
+        dir.join(format!("{}{}.md", name, suffix))
+    };
+
+    if on_collision == CollisionPolicy::SessionId {
+        let short: String = session.session_id.chars().take(8).collect();
+        let path = with_suffix(&format!("-{}", short));
+        if !encryption::exists(&path) {
+            return path;
+        }
+    }
+
+    (2..)
+        .map(|n| with_suffix(&format!("-{}", n)))
+        .find(|p| !encryption::exists(p))
+        .unwrap_or(candidate)
+}
 
-    md
+/// Substitute the filename placeholders for a session, taking `{slug}` and
+/// `{title}` from `name` if it was given one
+fn render_filename_template(template: &str, session: &ChatSession, name: Option<&str>) -> String {
+    use crate::utils::string::{sanitize_filename, slugify};
+
+    let slug = match name {
+        Some(name) => slugify(name),
+        None => session
+            .messages
+            .iter()
+            .find(|m| m.role == crate::providers::base::MessageRole::User)
+            .map(|m| slugify(&m.content))
+            .unwrap_or_else(|| session.session_id.clone()),
+    };
+    let title = match name {
+        Some(name) => name.to_string(),
+        None => formatter::extract_title(&session.messages, &labels::Labels::from_config()),
+    };
+
+    let timezone = if crate::config::get().sync.shared {
+        Timezone::Utc
+    } else {
+        Timezone::from_config()
+    };
+    let started_at = &session.started_at;
+    let stem = template
+        .replace("{date}", &timezone.format(started_at, "%Y-%m-%d"))
+        .replace("{time}", &timezone.format(started_at, "%H-%M-%S"))
+        .replace("{offset}", &timezone.offset_suffix(started_at))
+        .replace("{provider}", &session.provider)
+        .replace("{slug}", &slug)
+        .replace("{session_id}", &session.session_id)
+        .replace("{title}", &title);
+    let stem = sanitize_filename(&stem);
+
+    if stem.is_empty() {
+        session.session_id.clone()
+    } else {
+        stem
+    }
+}
+
+/// Append a note (`waylog note`) to an existing markdown file, redacted like
+/// the messages around it
+pub async fn append_note(file_path: &Path, text: &str, at: &DateTime<Utc>) -> Result<()> {
+    let text = Redactor::from_config().redact(text).into_owned();
+    append_block(
+        file_path,
+        &formatter::format_note(
+            &text,
+            at,
+            &labels::Labels::from_config(),
+            &crate::config::get().export.headers,
+        ),
+    )
+    .await
 }
 
-/// Append new messages to an existing markdown file
+/// Append the commits (short hash and subject) made while `waylog run` was
+/// active to an existing markdown file, as a section of their own
+pub async fn append_commits(file_path: &Path, commits: &[(String, String)]) -> Result<()> {
+    let redactor = Redactor::from_config();
+    let commits: Vec<_> = commits
+        .iter()
+        .map(|(hash, subject)| (hash.clone(), redactor.redact(subject).into_owned()))
+        .collect();
+    append_block(
+        file_path,
+        &formatter::format_commits(
+            &commits,
+            &labels::Labels::from_config(),
+            &crate::config::get().export.headers,
+        ),
+    )
+    .await
+}
+
+/// Append `block` and a blank line to an existing markdown file
+async fn append_block(file_path: &Path, block: &str) -> Result<()> {
+    let mut content = encryption::read(file_path).await?;
+    if !is_complete(&content) {
+        return Err(WaylogError::TruncatedFile(file_path.display().to_string()));
+    }
+
+    content.push_str(block);
+    content.push_str("\n\n");
+    encryption::write(file_path, content).await
+}
+
+/// Append new messages to an existing markdown file. The file is rewritten
+/// as a whole, so a crash leaves either the old or the new content.
 pub async fn append_messages(file_path: &Path, messages: &[ChatMessage]) -> Result<()> {
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(file_path)
-        .await?;
+    store_attachments(file_path, messages).await?;
+
+    let mut content = match encryption::read(file_path).await {
+        Ok(content) => content,
+        Err(WaylogError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    if !is_complete(&content) {
+        return Err(WaylogError::TruncatedFile(file_path.display().to_string()));
+    }
 
-    for message in messages {
-        let content = formatter::format_message(message);
-        file.write_all(content.as_bytes()).await?;
-        file.write_all(b"\n\n").await?;
+    let labels = labels::Labels::from_config();
+    for message in Redactor::from_config().redact_messages(messages).iter() {
+        content.push_str(&formatter::format_message(message, &labels)?);
+        content.push_str("\n\n");
     }
 
-    file.flush().await?;
-    Ok(())
+    let fm = frontmatter::parse_frontmatter_str(&content);
+    if let Some(last) = messages.last() {
+        content = frontmatter::set_field(
+            &content,
+            "updated_at",
+            &Timezone::from_config().rfc3339(&last.timestamp),
+        );
+    }
+    if let Some(count) = fm.message_count {
+        content = frontmatter::set_field(
+            &content,
+            "message_count",
+            &(count + messages.len()).to_string(),
+        );
+    }
+    let tokens: u64 = messages
+        .iter()
+        .filter_map(|m| m.metadata.tokens.as_ref())
+        .map(|t| u64::from(t.input + t.output))
+        .sum();
+    if tokens > 0 {
+        content = frontmatter::set_field_after(
+            &content,
+            "total_tokens",
+            &(fm.total_tokens.unwrap_or(0) + tokens).to_string(),
+            Some("message_count"),
+        );
+    }
+
+    // Keep the edited-file list in the frontmatter current
+    let project = fm.project.clone().unwrap_or_default();
+    let new_files = edited_files(messages, &project);
+    if new_files.iter().any(|f| !fm.files_edited.contains(f)) {
+        let mut files = fm.files_edited;
+        files.extend(new_files);
+        files.sort();
+        files.dedup();
+        content =
+            frontmatter::set_field(&content, "files_edited", &frontmatter::format_list(&files));
+    }
+
+    encryption::write(file_path, content).await?;
+    super::mirror(file_path).await
+}
+
+/// Whether a file's content ends where a write of waylog's ends: after a
+/// newline, and not inside the frontmatter
+fn is_complete(content: &str) -> bool {
+    if content.is_empty() {
+        return true;
+    }
+    if !content.ends_with('\n') {
+        return false;
+    }
+    match content.strip_prefix("---\n") {
+        Some(rest) => rest.contains("\n---"),
+        None => true,
+    }
 }
 
 /// Create a new markdown file with the full session
 pub async fn create_markdown_file(file_path: &Path, session: &ChatSession) -> Result<()> {
-    let content = generate_markdown(session);
-    fs::write(file_path, content).await?;
-    Ok(())
+    create_markdown_file_with(file_path, session, &[]).await
+}
+
+/// Create a new markdown file with additional frontmatter fields
+pub async fn create_markdown_file_with(
+    file_path: &Path,
+    session: &ChatSession,
+    extra_frontmatter: &[(&str, String)],
+) -> Result<()> {
+    create_part_file(file_path, session, extra_frontmatter, None).await
+}
+
+/// Create a session file, or one part of a split session
+async fn create_part_file(
+    file_path: &Path,
+    session: &ChatSession,
+    extra_frontmatter: &[(&str, String)],
+    part: Option<&template::Part>,
+) -> Result<()> {
+    store_attachments(file_path, &session.messages).await?;
+
+    let content = generate_part(session, extra_frontmatter, part)?;
+    encryption::write(file_path, content).await?;
+    super::mirror(file_path).await
+}
+
+/// Persist message attachments before the markdown that links to them
+async fn store_attachments(file_path: &Path, messages: &[ChatMessage]) -> Result<()> {
+    let pending: Vec<_> = messages
+        .iter()
+        .flat_map(|m| m.metadata.attachments.iter())
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    match attachments::store_dir_for(file_path) {
+        Some(dir) => attachments::store(&dir, &pending).await,
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::labels::Labels;
     use super::*;
     use crate::providers::base::{MessageRole, TokenUsage};
     use chrono::Utc;
@@ -115,7 +395,7 @@ mod tests {
         )];
 
         assert_eq!(
-            formatter::extract_title(&messages),
+            formatter::extract_title(&messages, &Labels::default()),
             "How do I implement a CLI tool?"
         );
     }
@@ -127,7 +407,7 @@ mod tests {
             "This is a very long message that should be truncated because it exceeds the maximum length",
         )];
 
-        let title = formatter::extract_title(&messages);
+        let title = formatter::extract_title(&messages, &Labels::default());
         assert!(title.len() <= 63); // 60 + "..."
         assert!(title.ends_with("..."));
     }
@@ -139,13 +419,19 @@ mod tests {
             create_test_message(MessageRole::Assistant, "Assistant response"),
         ];
 
-        assert_eq!(formatter::extract_title(&messages), "Untitled Session");
+        assert_eq!(
+            formatter::extract_title(&messages, &Labels::default()),
+            "Untitled Session"
+        );
     }
 
     #[test]
     fn test_extract_title_empty_messages() {
         let messages = vec![];
-        assert_eq!(formatter::extract_title(&messages), "Untitled Session");
+        assert_eq!(
+            formatter::extract_title(&messages, &Labels::default()),
+            "Untitled Session"
+        );
     }
 
     #[test]
@@ -155,13 +441,19 @@ mod tests {
             "First line\nSecond line\nThird line",
         )];
 
-        assert_eq!(formatter::extract_title(&messages), "First line");
+        assert_eq!(
+            formatter::extract_title(&messages, &Labels::default()),
+            "First line"
+        );
     }
 
     #[test]
     fn test_extract_title_empty_content() {
         let messages = vec![create_test_message(MessageRole::User, "")];
-        assert_eq!(formatter::extract_title(&messages), "Untitled Session");
+        assert_eq!(
+            formatter::extract_title(&messages, &Labels::default()),
+            "Untitled Session"
+        );
     }
 
     // format_datetime tests
@@ -179,7 +471,7 @@ mod tests {
     #[test]
     fn test_format_message_user() {
         let message = create_test_message(MessageRole::User, "Hello, world!");
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("👤"));
         assert!(formatted.contains("User"));
         assert!(formatted.contains("Hello, world!"));
@@ -188,7 +480,7 @@ mod tests {
     #[test]
     fn test_format_message_assistant() {
         let message = create_test_message(MessageRole::Assistant, "Hello! How can I help?");
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("🤖"));
         assert!(formatted.contains("Assistant"));
         assert!(formatted.contains("Hello! How can I help?"));
@@ -197,7 +489,7 @@ mod tests {
     #[test]
     fn test_format_message_system() {
         let message = create_test_message(MessageRole::System, "System prompt");
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("⚙️"));
         assert!(formatted.contains("System"));
         assert!(formatted.contains("System prompt"));
@@ -207,7 +499,7 @@ mod tests {
     fn test_format_message_with_tool_calls() {
         let mut message = create_test_message(MessageRole::Assistant, "I'll use some tools");
         message.metadata.tool_calls = vec!["read_file".to_string(), "write_file".to_string()];
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("**Tools Used:**"));
         assert!(formatted.contains("`read_file`"));
         assert!(formatted.contains("`write_file`"));
@@ -217,23 +509,45 @@ mod tests {
     fn test_format_message_with_thoughts() {
         let mut message = create_test_message(MessageRole::Assistant, "Response");
         message.metadata.thoughts = vec!["Thought 1".to_string(), "Thought 2".to_string()];
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("<details>"));
         assert!(formatted.contains("<summary>💭 Thoughts</summary>"));
         assert!(formatted.contains("Thought 1"));
         assert!(formatted.contains("Thought 2"));
     }
 
+    #[test]
+    fn test_format_message_with_attachment() {
+        let mut message = create_test_message(MessageRole::User, "See screenshot");
+        message.metadata.attachments = vec![crate::providers::base::Attachment {
+            media_type: "image/png".to_string(),
+            data: b"hello".to_vec(),
+        }];
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
+        assert!(formatted.contains("![image/png](../attachments/2c/"));
+        assert!(formatted.contains(".png)"));
+    }
+
+    #[test]
+    fn test_format_message_localized_labels() {
+        let mut message = create_test_message(MessageRole::Assistant, "你好");
+        message.metadata.tool_calls = vec!["read_file".to_string()];
+        let formatted = formatter::format_message(&message, &Labels::for_language("zh")).unwrap();
+        assert!(formatted.contains("助手"));
+        assert!(formatted.contains("**使用的工具:**"));
+        assert!(!formatted.contains("Assistant"));
+    }
+
     #[test]
     fn test_format_message_multiline_content() {
         let message = create_test_message(MessageRole::User, "Line 1\nLine 2\nLine 3");
-        let formatted = formatter::format_message(&message);
+        let formatted = formatter::format_message(&message, &Labels::default()).unwrap();
         assert!(formatted.contains("Line 1"));
         assert!(formatted.contains("Line 2"));
         assert!(formatted.contains("Line 3"));
     }
 
-    // generate_markdown tests
+    // generate_part tests
     #[test]
     fn test_generate_markdown_basic() {
         let messages = vec![
@@ -241,7 +555,7 @@ mod tests {
             create_test_message(MessageRole::Assistant, "Hi there!"),
         ];
         let session = create_test_session(messages);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         assert!(md.contains("provider: claude"));
         assert!(md.contains("session_id: test-session"));
@@ -260,7 +574,7 @@ mod tests {
             cached: 5,
         });
         let session = create_test_session(vec![message]);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         assert!(md.contains("total_tokens: 30")); // 10 + 20
     }
@@ -269,15 +583,24 @@ mod tests {
     fn test_generate_markdown_without_tokens() {
         let messages = vec![create_test_message(MessageRole::User, "Test")];
         let session = create_test_session(messages);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         assert!(!md.contains("total_tokens"));
     }
 
+    #[test]
+    fn test_generate_markdown_with_extra_frontmatter() {
+        let session = create_test_session(vec![create_test_message(MessageRole::User, "Test")]);
+        let md = generate_part(&session, &[("invocation", "claude".to_string())], None).unwrap();
+
+        let frontmatter_end = md[3..].find("---").unwrap() + 3;
+        assert!(md[..frontmatter_end].contains("invocation: claude\n"));
+    }
+
     #[test]
     fn test_generate_markdown_empty_messages() {
         let session = create_test_session(vec![]);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         assert!(md.contains("message_count: 0"));
         assert!(md.contains("# Untitled Session"));
@@ -292,7 +615,7 @@ mod tests {
             create_test_message(MessageRole::Assistant, "Answer 2"),
         ];
         let session = create_test_session(messages);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         assert!(md.contains("message_count: 4"));
         assert!(md.contains("Question 1"));
@@ -305,7 +628,7 @@ mod tests {
     fn test_generate_markdown_frontmatter_format() {
         let messages = vec![create_test_message(MessageRole::User, "Test")];
         let session = create_test_session(messages);
-        let md = generate_markdown(&session);
+        let md = generate_part(&session, &[], None).unwrap();
 
         // Check frontmatter format
         assert!(md.starts_with("---\n"));
@@ -314,6 +637,70 @@ mod tests {
         assert!(md.contains("updated_at:"));
     }
 
+    // filename template tests
+    #[test]
+    fn test_render_filename_template_default() {
+        use chrono::DateTime;
+        let mut session = create_test_session(vec![create_test_message(
+            MessageRole::User,
+            "Fix the build",
+        )]);
+        session.started_at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            render_filename_template(crate::config::DEFAULT_FILENAME_TEMPLATE, &session, None),
+            "2024-01-01_12-00-00Z-claude-fix-the-build"
+        );
+        assert_eq!(
+            render_filename_template("{title} ({session_id})", &session, None),
+            "Fix the build (test-session)"
+        );
+        assert_eq!(
+            render_filename_template(
+                crate::config::DEFAULT_FILENAME_TEMPLATE,
+                &session,
+                Some("Release checklist")
+            ),
+            "2024-01-01_12-00-00Z-claude-release-checklist"
+        );
+    }
+
+    #[test]
+    fn test_session_path_collision_suffix() {
+        let temp_dir = TempDir::new().unwrap();
+        let session = create_test_session(vec![create_test_message(MessageRole::User, "Hi")]);
+
+        let first = session_path(temp_dir.path(), &session);
+        std::fs::write(&first, "taken").unwrap();
+        let second = session_path(temp_dir.path(), &session);
+
+        assert_ne!(first, second);
+        assert!(second.to_string_lossy().ends_with("-hi-2.md"));
+    }
+
+    #[test]
+    fn test_render_combined_lists_each_session() {
+        let claude = create_test_session(vec![create_test_message(
+            MessageRole::User,
+            "Why does cargo build fail?",
+        )]);
+        let mut codex = create_test_session(vec![create_test_message(
+            MessageRole::User,
+            "Add a README section",
+        )]);
+        codex.provider = "codex".to_string();
+
+        let doc = render_combined(&[claude, codex], "Sessions").unwrap();
+        assert!(doc.starts_with("# Sessions\n\n## Contents\n"), "{}", doc);
+        assert!(doc.contains("](#session-2) · codex"), "{}", doc);
+        let first = doc.find("# 1. ").unwrap();
+        let second = doc.find("# 2. ").unwrap();
+        assert!(first < second && doc[first..second].contains("cargo build"));
+        assert!(doc.contains("<a id=\"session-2\"></a>"));
+    }
+
     // Async function tests
     #[tokio::test]
     async fn test_create_markdown_file() {
@@ -358,6 +745,60 @@ mod tests {
         assert!(content.contains("Second message"));
     }
 
+    #[tokio::test]
+    async fn test_append_messages_updates_files_edited() {
+        use crate::providers::base::FileAccess;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.md");
+        let session = create_test_session(vec![create_test_message(MessageRole::User, "Hi")]);
+        create_markdown_file(&file_path, &session).await.unwrap();
+
+        let mut edit = create_test_message(MessageRole::Assistant, "Done");
+        edit.metadata.file_accesses = vec![FileAccess {
+            path: session
+                .project_path
+                .join("src/main.rs")
+                .to_string_lossy()
+                .to_string(),
+            kind: FileAccessKind::Edit,
+        }];
+        append_messages(&file_path, &[edit]).await.unwrap();
+
+        let fm = frontmatter::parse_frontmatter(&file_path).await.unwrap();
+        assert_eq!(fm.files_edited, ["src/main.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_append_messages_refuses_truncated_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.md");
+        let session = create_test_session(vec![create_test_message(MessageRole::User, "Hi")]);
+        create_markdown_file(&file_path, &session).await.unwrap();
+
+        let content = tokio::fs::read_to_string(&file_path).await.unwrap();
+        let truncated = &content[..content.len() - 3];
+        tokio::fs::write(&file_path, truncated).await.unwrap();
+
+        let messages = [create_test_message(MessageRole::Assistant, "Hello")];
+        assert!(matches!(
+            append_messages(&file_path, &messages).await,
+            Err(WaylogError::TruncatedFile(_))
+        ));
+        assert_eq!(
+            tokio::fs::read_to_string(&file_path).await.unwrap(),
+            truncated
+        );
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete(""));
+        assert!(is_complete("---\nmessage_count: 1\n---\n\n# Title\n"));
+        assert!(!is_complete("---\nmessage_count: 1\n---\n\n# Tit"));
+        assert!(!is_complete("---\nmessage_count: 1\n"));
+    }
+
     #[tokio::test]
     async fn test_append_messages_to_new_file() {
         let temp_dir = TempDir::new().unwrap();
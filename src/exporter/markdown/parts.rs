@@ -0,0 +1,331 @@
+//! Very long sessions are split over several files. Once a session's file
+//! reaches `[export] max_messages_per_file` or `max_bytes_per_file`, further
+//! messages go to `<name>-part2.md`, `<name>-part3.md`, ..., each linking back
+//! to the previous part and linked from it. The first file keeps the plain
+//! name, so it stays the session's markdown path everywhere else.
+use super::labels::Labels;
+use super::template::Part;
+use super::{append_messages, create_part_file, formatter};
+use crate::error::Result;
+use crate::exporter::redact::Redactor;
+use crate::exporter::{encryption, frontmatter};
+use crate::providers::base::{ChatMessage, ChatSession};
+use crate::utils::path;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Path of part `number` of the session whose first file is `first`
+pub fn part_path(first: &Path, number: usize) -> PathBuf {
+    if number <= 1 {
+        return first.to_path_buf();
+    }
+    let stem = first.file_stem().unwrap_or_default().to_string_lossy();
+    first.with_file_name(format!("{}-part{}.md", stem, number))
+}
+
+/// The files of a session that exist on disk, first part first
+pub fn existing_parts(first: &Path) -> Vec<PathBuf> {
+    (1..)
+        .map(|number| part_path(first, number))
+        .take_while(|path| encryption::exists(path))
+        .collect()
+}
+
+/// SHA-256 over the files of a session (all parts of a split one); empty if unreadable
+pub fn checksum(first: &Path) -> String {
+    let mut hasher = Sha256::new();
+    for path in existing_parts(first) {
+        match std::fs::read(path::extended_length(&encryption::stored_path(&path))) {
+            Ok(content) => hasher.update(&content),
+            Err(_) => return String::new(),
+        }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// When a session file is full
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    messages: usize,
+    bytes: u64,
+}
+
+impl Limits {
+    fn from_config() -> Self {
+        let export = &crate::config::get().export;
+        Self {
+            messages: export.max_messages_per_file,
+            bytes: export.max_bytes_per_file,
+        }
+    }
+
+    /// Whether a file would be over a limit with this many messages and bytes
+    fn exceeded(&self, messages: usize, bytes: u64) -> bool {
+        (self.messages > 0 && messages > self.messages) || (self.bytes > 0 && bytes > self.bytes)
+    }
+}
+
+/// Write `new_messages` of `session` after the `synced` messages already in
+/// the file(s) starting at `first`, opening new parts as files fill up. With
+/// `synced == 0` the session is written from scratch.
+pub async fn write_session(
+    first: &Path,
+    session: &ChatSession,
+    synced: usize,
+    new_messages: &[ChatMessage],
+    extra_frontmatter: &[(&str, String)],
+) -> Result<()> {
+    write_with(
+        first,
+        session,
+        synced,
+        new_messages,
+        extra_frontmatter,
+        Limits::from_config(),
+    )
+    .await
+}
+
+async fn write_with(
+    first: &Path,
+    session: &ChatSession,
+    synced: usize,
+    new_messages: &[ChatMessage],
+    extra_frontmatter: &[(&str, String)],
+    limits: Limits,
+) -> Result<()> {
+    let labels = Labels::from_config();
+
+    // Where writing starts: the last part, or a fresh first file
+    let (mut number, mut count, mut bytes) = if synced == 0 {
+        remove_parts_after(first, 1)?;
+        (1, 0, 0)
+    } else {
+        let parts = existing_parts(first);
+        let last = parts.last().cloned().unwrap_or_else(|| first.to_path_buf());
+        let count = frontmatter::parse_frontmatter(&last)
+            .await
+            .ok()
+            .and_then(|fm| fm.message_count)
+            .unwrap_or(0);
+        let bytes = std::fs::metadata(encryption::stored_path(&last))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        (parts.len().max(1), count, bytes)
+    };
+    let start = number;
+
+    let redacted = Redactor::from_config().redact_messages(new_messages);
+    let mut chunks: Vec<(usize, Vec<ChatMessage>)> = vec![(number, Vec::new())];
+    for (message, shown) in new_messages.iter().zip(redacted.iter()) {
+        let size = if limits.bytes > 0 {
+            formatter::format_message(shown, &labels)?.len() as u64 + 2
+        } else {
+            0
+        };
+        if count > 0 && limits.exceeded(count + 1, bytes + size) {
+            number += 1;
+            chunks.push((number, Vec::new()));
+            count = 0;
+            bytes = 0;
+        }
+        if let Some((_, chunk)) = chunks.last_mut() {
+            chunk.push(message.clone());
+        }
+        count += 1;
+        bytes += size;
+    }
+
+    let mut title = None;
+    for (number, messages) in chunks {
+        if messages.is_empty() {
+            continue;
+        }
+        let path = part_path(first, number);
+        if synced > 0 && number == start {
+            append_messages(&path, &messages).await?;
+        } else if number == 1 {
+            create_part_file(
+                &path,
+                &with_messages(session, messages),
+                extra_frontmatter,
+                None,
+            )
+            .await?;
+        } else {
+            let title = title.get_or_insert_with(|| {
+                heading(first).unwrap_or_else(|| {
+                    let all = Redactor::from_config().redact_messages(&session.messages);
+                    formatter::extract_title(&all, &labels)
+                })
+            });
+            let previous = part_path(first, number - 1);
+            let part = Part {
+                number,
+                previous: file_name(&previous),
+                title: title.clone(),
+            };
+            let mut frontmatter = extra_frontmatter.to_vec();
+            frontmatter.push(("part", number.to_string()));
+            create_part_file(
+                &path,
+                &with_messages(session, messages),
+                &frontmatter,
+                Some(&part),
+            )
+            .await?;
+            link_next(&previous, &path, number, &labels).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The `# ` heading of the first part, so later parts repeat its title even
+/// when `session` holds only the newest messages
+fn heading(first: &Path) -> Option<String> {
+    let content = encryption::read_sync(first).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(str::to_string)
+}
+
+/// Delete the parts after `number`, left over from an earlier, longer render
+fn remove_parts_after(first: &Path, number: usize) -> Result<()> {
+    for path in existing_parts(first).iter().skip(number) {
+        std::fs::remove_file(encryption::stored_path(path))?;
+    }
+    Ok(())
+}
+
+/// Append the link to part `number` at `next` to the end of `previous`
+async fn link_next(previous: &Path, next: &Path, number: usize, labels: &Labels) -> Result<()> {
+    let mut content = encryption::read(previous).await?;
+    content.push_str(&format!(
+        "[{} {} →]({})\n",
+        labels.part,
+        number,
+        file_name(next)
+    ));
+    encryption::write(previous, content).await?;
+    crate::exporter::mirror(previous).await
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `session` with only `messages`, for rendering one part
+fn with_messages(session: &ChatSession, messages: Vec<ChatMessage>) -> ChatSession {
+    ChatSession {
+        session_id: session.session_id.clone(),
+        provider: session.provider.clone(),
+        project_path: session.project_path.clone(),
+        started_at: session.started_at,
+        updated_at: session.updated_at,
+        messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{MessageMetadata, MessageRole};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn session(contents: &[&str]) -> ChatSession {
+        let messages = contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| ChatMessage {
+                id: format!("m{}", i),
+                timestamp: Utc::now(),
+                role: MessageRole::User,
+                content: content.to_string(),
+                metadata: MessageMetadata::default(),
+            })
+            .collect();
+        ChatSession {
+            session_id: "s".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            messages,
+        }
+    }
+
+    #[test]
+    fn test_part_path() {
+        let first = Path::new("/h/2025-01-15-claude-fix.md");
+        assert_eq!(part_path(first, 1), first);
+        assert_eq!(
+            part_path(first, 3),
+            Path::new("/h/2025-01-15-claude-fix-part3.md")
+        );
+    }
+
+    #[test]
+    fn test_limits_exceeded() {
+        let unlimited = Limits {
+            messages: 0,
+            bytes: 0,
+        };
+        assert!(!unlimited.exceeded(100_000, u64::MAX));
+
+        let limits = Limits {
+            messages: 2,
+            bytes: 1000,
+        };
+        assert!(!limits.exceeded(2, 1000));
+        assert!(limits.exceeded(3, 10));
+        assert!(limits.exceeded(1, 1001));
+    }
+
+    #[tokio::test]
+    async fn test_long_sessions_are_split_into_linked_parts() {
+        let dir = TempDir::new().unwrap();
+        let first = dir.path().join("2025-01-15-claude-fix.md");
+        let long = session(&["Why does cargo build fail?", "It lacks a feature", "Thanks"]);
+        let limits = Limits {
+            messages: 2,
+            bytes: 0,
+        };
+        write_with(&first, &long, 0, &long.messages, &[], limits)
+            .await
+            .unwrap();
+
+        let second = part_path(&first, 2);
+        assert_eq!(existing_parts(&first), [first.clone(), second.clone()]);
+        let first_content = std::fs::read_to_string(&first).unwrap();
+        assert!(
+            first_content.contains("message_count: 2"),
+            "{}",
+            first_content
+        );
+        assert!(first_content.ends_with("[Part 2 →](2025-01-15-claude-fix-part2.md)\n"));
+
+        let second_content = std::fs::read_to_string(&second).unwrap();
+        assert!(second_content.contains("message_count: 1"));
+        assert!(second_content.contains("part: 2"));
+        // Later parts repeat the title of the first
+        assert!(second_content.contains("# Why does cargo build fail?"));
+        assert!(second_content.contains("[← Part 1](2025-01-15-claude-fix.md)"));
+        assert!(second_content.contains("Thanks"));
+
+        // Written again from scratch, a shorter session drops the extra part
+        let short = session(&["Hello"]);
+        write_with(&first, &short, 0, &short.messages, &[], limits)
+            .await
+            .unwrap();
+        assert_eq!(existing_parts(&first), [first]);
+    }
+}
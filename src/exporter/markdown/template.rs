@@ -0,0 +1,338 @@
+//! Tera templates that lay out the markdown history files. The built-in
+//! layout lives in `templates/`; a project overrides it by putting
+//! `session.md.tera` and/or `message.md.tera` in `.waylog/templates/`.
+//!
+//! `session.md.tera` renders a new file from the whole session (or one part
+//! of a split session), while `message.md.tera` renders each message,
+//! including the ones appended to an existing file as a session grows.
+use super::formatter::{self, format_datetime, format_header, format_thoughts_label, role_label};
+use super::labels::Labels;
+use crate::config::HeaderConfig;
+use crate::error::{Result, WaylogError};
+use crate::exporter::attachments;
+use crate::init::WAYLOG_DIR;
+use crate::providers::base::{ChatMessage, ChatSession, FileAccess, TokenUsage};
+use crate::utils::time::Timezone;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tera::{Context, Tera};
+
+/// Directory inside `.waylog` with the project's templates
+pub const TEMPLATES_DIR: &str = "templates";
+pub const SESSION_TEMPLATE: &str = "session.md.tera";
+pub const MESSAGE_TEMPLATE: &str = "message.md.tera";
+
+const BUILTIN: [(&str, &str); 2] = [
+    (SESSION_TEMPLATE, include_str!("templates/session.md.tera")),
+    (MESSAGE_TEMPLATE, include_str!("templates/message.md.tera")),
+];
+
+/// Compiled markdown templates
+pub struct Templates {
+    tera: Tera,
+}
+
+impl Templates {
+    /// The built-in layout
+    pub fn builtin() -> Self {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(BUILTIN)
+            .expect("built-in markdown templates are valid");
+        Self { tera }
+    }
+
+    /// The built-in layout with the project's overrides from `.waylog/templates`
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let dir = templates_dir(project_dir);
+        let mut templates = Vec::new();
+        for (name, builtin) in BUILTIN {
+            let path = dir.join(name);
+            if path.exists() {
+                tracing::debug!("Using markdown template {}", path.display());
+                templates.push((name, std::fs::read_to_string(&path)?));
+            } else {
+                templates.push((name, builtin.to_string()));
+            }
+        }
+
+        let mut tera = Tera::default();
+        tera.add_raw_templates(templates)?;
+        Ok(Self { tera })
+    }
+
+    /// Render a session file with `messages`, the whole session unless `part` is given
+    pub fn render_session(
+        &self,
+        session: &ChatSession,
+        messages: &[ChatMessage],
+        extra_frontmatter: &[(&str, String)],
+        labels: &Labels,
+        part: Option<&Part>,
+    ) -> Result<String> {
+        let timezone = Timezone::from_config();
+        let headers = &crate::config::get().export.headers;
+
+        let total_tokens: u32 = messages
+            .iter()
+            .filter_map(|m| m.metadata.tokens.as_ref())
+            .map(|t| t.input + t.output)
+            .sum();
+        let given_title = super::given_title(extra_frontmatter);
+        let extra_frontmatter: Vec<_> = extra_frontmatter
+            .iter()
+            .map(|(key, value)| FieldContext { key, value })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert(
+            "session",
+            &SessionContext {
+                provider: &session.provider,
+                session_id: &session.session_id,
+                project: session.project_path.display().to_string(),
+                started_at: timezone.rfc3339(&session.started_at),
+                updated_at: timezone.rfc3339(&session.updated_at),
+                messages: messages
+                    .iter()
+                    .map(|m| MessageContext::new(m, labels, headers, &timezone))
+                    .collect(),
+            },
+        );
+        let title = match part {
+            Some(part) => part.title.clone(),
+            None => given_title.unwrap_or_else(|| formatter::extract_title(messages, labels)),
+        };
+        context.insert("title", &title);
+        context.insert("total_tokens", &total_tokens);
+        context.insert(
+            "files_edited",
+            &super::edited_files(&session.messages, &session.project_path),
+        );
+        context.insert("extra_frontmatter", &extra_frontmatter);
+        context.insert("labels", labels);
+        context.insert("part", &part);
+
+        Ok(self.tera.render(SESSION_TEMPLATE, &context)?)
+    }
+
+    /// Render a single message
+    pub fn render_message(&self, message: &ChatMessage, labels: &Labels) -> Result<String> {
+        let timezone = Timezone::from_config();
+        let headers = &crate::config::get().export.headers;
+
+        let mut context = Context::new();
+        context.insert(
+            "message",
+            &MessageContext::new(message, labels, headers, &timezone),
+        );
+        context.insert("labels", labels);
+
+        Ok(self.tera.render(MESSAGE_TEMPLATE, &context)?)
+    }
+}
+
+pub fn templates_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(TEMPLATES_DIR)
+}
+
+static TEMPLATES: OnceLock<Templates> = OnceLock::new();
+
+/// Install the project's templates for the rest of the process
+pub fn init(templates: Templates) {
+    if TEMPLATES.set(templates).is_err() {
+        tracing::debug!("Markdown templates already initialized, ignoring");
+    }
+}
+
+/// Get the active templates (built-in if `init` was never called, e.g. in tests)
+pub fn get() -> &'static Templates {
+    TEMPLATES.get_or_init(Templates::builtin)
+}
+
+impl From<tera::Error> for WaylogError {
+    fn from(error: tera::Error) -> Self {
+        // Tera puts the useful part (line, unknown variable) in the source chain
+        let mut message = error.to_string();
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        WaylogError::Template(message)
+    }
+}
+
+/// Where a file sits in a session split into several files
+#[derive(Debug, Clone, Serialize)]
+pub struct Part {
+    /// 2 for `-part2.md`, ...
+    pub number: usize,
+    /// File name of the previous part
+    pub previous: String,
+    /// Title of the whole session, from its first message
+    pub title: String,
+}
+
+#[derive(Serialize)]
+struct SessionContext<'a> {
+    provider: &'a str,
+    session_id: &'a str,
+    project: String,
+    started_at: String,
+    updated_at: String,
+    messages: Vec<MessageContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct FieldContext<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+
+#[derive(Serialize)]
+struct MessageContext<'a> {
+    id: &'a str,
+    role: crate::providers::base::MessageRole,
+    role_emoji: &'static str,
+    role_name: String,
+    /// RFC 3339 in the configured timezone
+    timestamp: String,
+    /// Human-readable, as in the built-in headers
+    time: String,
+    /// The built-in header line, following `[export.headers]`
+    header: String,
+    content: &'a str,
+    model: Option<&'a str>,
+    tokens: Option<&'a TokenUsage>,
+    tool_calls: &'a [String],
+    thoughts: &'a [String],
+    /// The summary line the thoughts are folded under, following `[export.headers]`
+    thoughts_label: String,
+    file_accesses: &'a [FileAccess],
+    attachments: Vec<AttachmentContext>,
+}
+
+#[derive(Serialize)]
+struct AttachmentContext {
+    media_type: String,
+    /// Relative link to the stored blob
+    link: String,
+}
+
+impl<'a> MessageContext<'a> {
+    fn new(
+        message: &'a ChatMessage,
+        labels: &Labels,
+        headers: &HeaderConfig,
+        timezone: &Timezone,
+    ) -> Self {
+        let (role_emoji, role_name) = role_label(message, labels);
+
+        Self {
+            id: &message.id,
+            role: message.role,
+            role_emoji,
+            role_name,
+            timestamp: timezone.rfc3339(&message.timestamp),
+            time: format_datetime(&message.timestamp),
+            header: format_header(message, labels, headers),
+            content: &message.content,
+            model: message.metadata.model.as_deref(),
+            tokens: message.metadata.tokens.as_ref(),
+            tool_calls: &message.metadata.tool_calls,
+            thoughts: &message.metadata.thoughts,
+            thoughts_label: format_thoughts_label(labels, headers),
+            file_accesses: &message.metadata.file_accesses,
+            attachments: message
+                .metadata
+                .attachments
+                .iter()
+                .map(|attachment| AttachmentContext {
+                    media_type: attachment.media_type.clone(),
+                    link: attachments::markdown_link(attachment),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{MessageMetadata, MessageRole};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn session() -> ChatSession {
+        let now = Utc::now();
+        ChatSession {
+            session_id: "abc".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: now,
+            updated_at: now,
+            messages: vec![ChatMessage {
+                id: "m0".to_string(),
+                timestamp: now,
+                role: MessageRole::User,
+                content: "Fix the build".to_string(),
+                metadata: MessageMetadata::default(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_project_templates_override_builtin() {
+        let dir = TempDir::new().unwrap();
+        let templates = templates_dir(dir.path());
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(
+            templates.join(SESSION_TEMPLATE),
+            "{{ title }} ({{ session.provider }})\n{% for message in session.messages %}{% include \"message.md.tera\" %}{% endfor %}",
+        )
+        .unwrap();
+        std::fs::write(
+            templates.join(MESSAGE_TEMPLATE),
+            "> {{ message.role }}: {{ message.content }}\n",
+        )
+        .unwrap();
+
+        let session = session();
+        let labels = Labels::default();
+        let loaded = Templates::load(dir.path()).unwrap();
+        assert_eq!(
+            loaded
+                .render_session(&session, &session.messages, &[], &labels, None)
+                .unwrap(),
+            "Fix the build (claude)\n> user: Fix the build\n"
+        );
+        assert_eq!(
+            loaded
+                .render_message(&session.messages[0], &labels)
+                .unwrap(),
+            "> user: Fix the build\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_template_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let templates = templates_dir(dir.path());
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(templates.join(MESSAGE_TEMPLATE), "{{ message.content").unwrap();
+
+        assert!(matches!(
+            Templates::load(dir.path()),
+            Err(WaylogError::Template(_))
+        ));
+
+        // Unknown variables only show up when rendering
+        std::fs::write(templates.join(MESSAGE_TEMPLATE), "{{ nope }}").unwrap();
+        let error = Templates::load(dir.path())
+            .unwrap()
+            .render_message(&session().messages[0], &Labels::default())
+            .unwrap_err();
+        assert!(error.to_string().contains("nope"), "{}", error);
+    }
+}
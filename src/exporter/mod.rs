@@ -1,6 +1,45 @@
+pub mod anonymize;
+pub mod archive;
+pub mod attachments;
+pub mod command;
+pub mod csv;
+pub mod derived;
+pub mod encryption;
 pub mod frontmatter;
+pub mod html;
+pub mod jsonl;
 pub mod markdown;
+pub mod public;
+pub mod redact;
+pub mod sample;
+pub mod schema;
+pub mod site;
+pub mod sqlite;
 
-pub use markdown::{append_messages, create_markdown_file};
+pub use markdown::{create_markdown_file, create_markdown_file_with, session_path};
 
-pub use frontmatter::parse_frontmatter;
+pub use frontmatter::{format_merged_sessions, parse_frontmatter};
+
+use crate::error::Result;
+use std::path::Path;
+
+/// Bring the copies of a history file up to date: the shareable tree and the
+/// global archive. An unreachable archive only warns, so it never stops a sync.
+pub async fn mirror(markdown_path: &Path) -> Result<()> {
+    public::mirror(markdown_path).await?;
+    if let Err(e) = archive::mirror(markdown_path).await {
+        tracing::warn!(
+            "Could not copy {} to the archive: {}",
+            markdown_path.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+/// Whether syncing a session needs all of its messages rather than only the
+/// new ones: the SQLite archive and the external exporter are written from
+/// the whole session
+pub fn needs_full_session(project_dir: &Path) -> bool {
+    crate::config::get().export.command.is_some() || sqlite::database_path(project_dir).exists()
+}
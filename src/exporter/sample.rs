@@ -0,0 +1,86 @@
+//! Deterministic sampling for dataset exports
+pub use crate::cli::{SampleUnit, Stratify};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Keep `fraction` of `items`, chosen by hashing each item's id with `seed`.
+///
+/// Every group (as returned by `group`) contributes `round(fraction * len)`
+/// items, so small groups are not crowded out by large ones. The selection
+/// only depends on the seed and the ids, not on the order of `items`, and the
+/// kept items stay in their original order.
+pub fn sample<T>(
+    items: Vec<T>,
+    fraction: f64,
+    seed: u64,
+    id: impl Fn(&T) -> String,
+    group: impl Fn(&T) -> String,
+) -> Vec<T> {
+    let mut groups: BTreeMap<String, Vec<(usize, [u8; 32])>> = BTreeMap::new();
+    for (index, item) in items.iter().enumerate() {
+        groups
+            .entry(group(item))
+            .or_default()
+            .push((index, sample_key(seed, &id(item))));
+    }
+
+    let mut keep = vec![false; items.len()];
+    for members in groups.values_mut() {
+        let count = (members.len() as f64 * fraction).round() as usize;
+        members.sort_by_key(|(_, key)| *key);
+        for (index, _) in members.iter().take(count) {
+            keep[*index] = true;
+        }
+    }
+
+    items
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then_some(item))
+        .collect()
+}
+
+fn sample_key(seed: u64, id: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    hasher.update(id.as_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(n: usize, prefix: &str) -> Vec<String> {
+        (0..n).map(|i| format!("{}-{}", prefix, i)).collect()
+    }
+
+    #[test]
+    fn test_sample_is_deterministic() {
+        let items = ids(50, "s");
+        let first = sample(items.clone(), 0.2, 42, |s| s.clone(), |_| String::new());
+        let again = sample(items.clone(), 0.2, 42, |s| s.clone(), |_| String::new());
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, again);
+
+        let mut reversed = items.clone();
+        reversed.reverse();
+        let mut from_reversed = sample(reversed, 0.2, 42, |s| s.clone(), |_| String::new());
+        from_reversed.reverse();
+        assert_eq!(first, from_reversed, "selection does not depend on order");
+
+        let other_seed = sample(items, 0.2, 7, |s| s.clone(), |_| String::new());
+        assert_ne!(first, other_seed);
+    }
+
+    #[test]
+    fn test_sample_stratified() {
+        let mut items = ids(90, "claude");
+        items.extend(ids(10, "codex"));
+        let group = |s: &String| s.split('-').next().unwrap().to_string();
+
+        let kept = sample(items, 0.2, 1, |s| s.clone(), group);
+        assert_eq!(kept.iter().filter(|s| s.starts_with("claude")).count(), 18);
+        assert_eq!(kept.iter().filter(|s| s.starts_with("codex")).count(), 2);
+    }
+}
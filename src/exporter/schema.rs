@@ -0,0 +1,309 @@
+//! JSON Schemas of what waylog writes for other programs to read: the JSONL
+//! export, the session JSON piped to `[export] command`, the events printed
+//! with `--output json`, and the outcome `pull --output json` prints. Each schema's id carries the same version as
+//! the data (e.g. `waylog.message.v1`), which only changes on incompatible
+//! changes; `waylog api dump-schema` prints them.
+use serde_json::{json, Value};
+
+/// Schema tag of the session JSON given to external exporters
+pub const SESSION_SCHEMA: &str = "waylog.session.v1";
+
+/// Schema tag of the `--output json` events
+pub const EVENT_SCHEMA: &str = "waylog.event.v1";
+
+/// Schema tag of the document printed by `pull --output json`
+pub const PULL_SCHEMA: &str = "waylog.pull.v1";
+
+/// All schemas, by name
+pub fn all() -> Vec<(&'static str, Value)> {
+    vec![
+        (super::jsonl::SCHEMA, message()),
+        (SESSION_SCHEMA, session()),
+        (EVENT_SCHEMA, event()),
+        (PULL_SCHEMA, pull()),
+    ]
+}
+
+/// One line of `waylog export --format jsonl`
+pub fn message() -> Value {
+    let mut properties = message_properties();
+    properties.extend(
+        json!({
+            "schema": { "const": super::jsonl::SCHEMA },
+            "provider": { "type": "string", "description": "claude, gemini, codex, ..." },
+            "session_id": { "type": "string" },
+            "project": { "type": "string", "description": "Project directory the session ran in" },
+            "index": { "type": "integer", "minimum": 0, "description": "Position of the message within its session" }
+        })
+        .as_object()
+        .cloned()
+        .unwrap_or_default(),
+    );
+
+    document(
+        super::jsonl::SCHEMA,
+        "One message of a JSONL export, with its session context",
+        json!({
+            "type": "object",
+            "required": ["schema", "provider", "session_id", "project", "index", "id", "timestamp", "role", "content", "metadata"],
+            "properties": properties,
+            "$defs": defs_map(),
+        }),
+    )
+}
+
+/// The session JSON that `[export] command` receives on stdin
+pub fn session() -> Value {
+    let mut defs = defs_map();
+    defs.insert(
+        "message".to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "timestamp", "role", "content", "metadata"],
+            "properties": message_properties(),
+        }),
+    );
+
+    document(
+        SESSION_SCHEMA,
+        "A chat session with its messages, as given to external exporters",
+        json!({
+            "type": "object",
+            "required": ["session_id", "provider", "project_path", "started_at", "updated_at", "messages"],
+            "properties": {
+                "session_id": { "type": "string" },
+                "provider": { "type": "string" },
+                "project_path": { "type": "string" },
+                "started_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" },
+                "messages": { "type": "array", "items": { "$ref": "#/$defs/message" } }
+            },
+            "$defs": defs,
+        }),
+    )
+}
+
+/// One line printed by a command run with `--output json`
+pub fn event() -> Value {
+    document(
+        EVENT_SCHEMA,
+        "A progress or result event printed with --output json",
+        json!({
+            "type": "object",
+            "required": ["level", "message", "timestamp"],
+            "properties": {
+                "level": {
+                    "type": "string",
+                    "description": "Kind of event, e.g. info, success, error, synced, up_to_date, summary"
+                },
+                "message": { "type": "string" },
+                "timestamp": { "type": "string", "format": "date-time" }
+            }
+        }),
+    )
+}
+
+/// The outcome of a pull, printed by `pull --output json` (one document
+/// per project with `--all-projects`)
+pub fn pull() -> Value {
+    let count = json!({ "type": "integer", "minimum": 0 });
+    document(
+        PULL_SCHEMA,
+        "The outcome of waylog pull, by provider and session",
+        json!({
+            "type": "object",
+            "required": ["schema", "project", "started_at", "finished_at", "duration_ms", "dry_run", "totals", "providers"],
+            "properties": {
+                "schema": { "const": PULL_SCHEMA },
+                "project": { "type": "string", "description": "Project directory pulled into" },
+                "started_at": { "type": "string", "format": "date-time" },
+                "finished_at": { "type": "string", "format": "date-time" },
+                "duration_ms": count,
+                "dry_run": { "type": "boolean" },
+                "totals": {
+                    "type": "object",
+                    "required": ["synced", "up_to_date", "skipped", "failed", "new_messages", "warnings"],
+                    "properties": {
+                        "synced": count,
+                        "up_to_date": count,
+                        "skipped": count,
+                        "failed": count,
+                        "new_messages": count,
+                        "warnings": { "type": "integer", "minimum": 0, "description": "Malformed source lines skipped" }
+                    }
+                },
+                "providers": { "type": "array", "items": { "$ref": "#/$defs/provider" } }
+            },
+            "$defs": {
+                "provider": {
+                    "type": "object",
+                    "required": ["name", "sessions"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "error": { "type": "string", "description": "Why the provider's sessions could not be listed" },
+                        "sessions": { "type": "array", "items": { "$ref": "#/$defs/session" } }
+                    }
+                },
+                "session": {
+                    "type": "object",
+                    "required": ["provider", "file", "status", "new_messages", "warnings", "duration_ms"],
+                    "properties": {
+                        "provider": { "type": "string" },
+                        "file": { "type": "string", "description": "The provider's session file" },
+                        "status": { "enum": ["synced", "up_to_date", "would_sync", "skipped", "failed"] },
+                        "markdown_path": { "type": "string", "description": "History file written, or that would be on a dry run" },
+                        "new_messages": count,
+                        "warnings": count,
+                        "reason": { "type": "string", "description": "Why the session was skipped, or what failed" },
+                        "duration_ms": count
+                    }
+                }
+            }
+        }),
+    )
+}
+
+/// Wrap a schema body with its id, title and version
+fn document(id: &str, title: &str, body: Value) -> Value {
+    let mut schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("urn:{}", id),
+        "title": title,
+        "x-waylog-version": env!("CARGO_PKG_VERSION"),
+    });
+    if let (Some(schema), Some(body)) = (schema.as_object_mut(), body.as_object()) {
+        schema.extend(body.clone());
+    }
+    schema
+}
+
+/// Fields of a `ChatMessage`
+fn message_properties() -> serde_json::Map<String, Value> {
+    json!({
+        "id": { "type": "string" },
+        "timestamp": { "type": "string", "format": "date-time" },
+        "role": { "enum": ["user", "assistant", "system"] },
+        "content": { "type": "string" },
+        "metadata": { "$ref": "#/$defs/metadata" }
+    })
+    .as_object()
+    .cloned()
+    .unwrap_or_default()
+}
+
+/// Definitions shared by the message and session schemas
+fn defs_map() -> serde_json::Map<String, Value> {
+    json!({
+        "metadata": {
+            "type": "object",
+            "required": ["tool_calls", "thoughts"],
+            "properties": {
+                "model": { "type": ["string", "null"] },
+                "tokens": {
+                    "oneOf": [
+                        { "type": "null" },
+                        {
+                            "type": "object",
+                            "required": ["input", "output", "cached"],
+                            "properties": {
+                                "input": { "type": "integer", "minimum": 0 },
+                                "output": { "type": "integer", "minimum": 0 },
+                                "cached": { "type": "integer", "minimum": 0 }
+                            }
+                        }
+                    ]
+                },
+                "tool_calls": { "type": "array", "items": { "type": "string" } },
+                "thoughts": { "type": "array", "items": { "type": "string" } },
+                "attachments": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["media_type", "data"],
+                        "properties": {
+                            "media_type": { "type": "string" },
+                            "data": { "type": "string", "contentEncoding": "base64" }
+                        }
+                    }
+                },
+                "file_accesses": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["path", "kind"],
+                        "properties": {
+                            "path": { "type": "string" },
+                            "kind": { "enum": ["Read", "Edit"] }
+                        }
+                    }
+                }
+            }
+        }
+    })
+    .as_object()
+    .cloned()
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{ChatMessage, ChatSession, MessageMetadata, MessageRole};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    /// Every field serde writes is described by the schema
+    fn assert_covers(schema: &Value, value: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        for key in value.as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "{} is not in the schema", key);
+        }
+        for key in schema["required"].as_array().unwrap() {
+            assert!(
+                value.get(key.as_str().unwrap()).is_some(),
+                "{} is missing",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_schemas_match_serialized_data() {
+        let session = ChatSession {
+            session_id: "s".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/p"),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            messages: vec![ChatMessage {
+                id: "m".to_string(),
+                timestamp: Utc::now(),
+                role: MessageRole::User,
+                content: "hi".to_string(),
+                metadata: MessageMetadata::default(),
+            }],
+        };
+
+        let record = crate::exporter::jsonl::records(&session).next().unwrap();
+        let record = serde_json::to_value(record).unwrap();
+        let schema = message();
+        assert_covers(&schema, &record);
+        assert_covers(&schema["$defs"]["metadata"], &record["metadata"]);
+
+        let session = serde_json::to_value(&session).unwrap();
+        let schema = super::session();
+        assert_covers(&schema, &session);
+        assert_covers(&schema["$defs"]["message"], &session["messages"][0]);
+
+        let ids: Vec<_> = all().iter().map(|(_, s)| s["$id"].clone()).collect();
+        assert_eq!(
+            ids,
+            [
+                "urn:waylog.message.v1",
+                "urn:waylog.session.v1",
+                "urn:waylog.event.v1",
+                "urn:waylog.pull.v1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! A static site built from the history files for `waylog site`: a page per
+//! session, and listings of the sessions grouped by month, for all of them,
+//! for each provider and for each tag. Links are relative, so the site works
+//! from any directory of a web server (GitHub Pages included) or from disk.
+use super::html::{document, escape, markdown_to_html};
+use crate::session::catalog::SessionSummary;
+use crate::utils::string::slugify;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A session to publish: what its frontmatter says and the markdown of its
+/// parts, without their frontmatter
+#[derive(Debug, Clone)]
+pub struct SiteSession {
+    pub summary: SessionSummary,
+    pub markdown: String,
+}
+
+/// The pages of the site, as paths relative to its root and their HTML
+pub fn build(title: &str, sessions: &[SiteSession]) -> Vec<(PathBuf, String)> {
+    let mut sessions: Vec<&SiteSession> = sessions.iter().collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(started(&s.summary)));
+
+    let mut providers: BTreeMap<&str, Vec<&SiteSession>> = BTreeMap::new();
+    let mut tags: BTreeMap<&str, Vec<&SiteSession>> = BTreeMap::new();
+    for session in &sessions {
+        providers
+            .entry(&session.summary.provider)
+            .or_default()
+            .push(session);
+        for tag in &session.summary.tags {
+            tags.entry(tag).or_default().push(session);
+        }
+    }
+    let nav = Nav {
+        providers: providers.iter().map(|(p, s)| (*p, s.len())).collect(),
+        tags: tags.iter().map(|(t, s)| (*t, s.len())).collect(),
+    };
+
+    let mut pages = vec![(
+        PathBuf::from("index.html"),
+        listing(title, title, &sessions, &nav, ""),
+    )];
+    for (provider, listed) in &providers {
+        pages.push((
+            PathBuf::from("providers").join(format!("{}.html", slugify(provider))),
+            listing(provider, title, listed, &nav, "../"),
+        ));
+    }
+    for (tag, listed) in &tags {
+        pages.push((
+            PathBuf::from("tags").join(format!("{}.html", slugify(tag))),
+            listing(&format!("#{}", tag), title, listed, &nav, "../"),
+        ));
+    }
+    for session in &sessions {
+        pages.push((
+            PathBuf::from("sessions").join(page_name(&session.summary)),
+            session_page(session, title),
+        ));
+    }
+    pages
+}
+
+/// The markdown of a history file after its frontmatter
+pub fn strip_frontmatter(content: &str) -> &str {
+    content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.find("\n---\n").map(|end| &rest[end + 5..]))
+        .unwrap_or(content)
+}
+
+/// Providers and tags with their session counts, linked from every listing
+struct Nav<'a> {
+    providers: Vec<(&'a str, usize)>,
+    tags: Vec<(&'a str, usize)>,
+}
+
+impl Nav<'_> {
+    fn render(&self, root: &str) -> String {
+        let links = |dir: &str, items: &[(&str, usize)], prefix: &str| {
+            items
+                .iter()
+                .map(|(name, count)| {
+                    format!(
+                        "<a href=\"{}{}/{}.html\">{}{}</a> ({})",
+                        root,
+                        dir,
+                        slugify(name),
+                        prefix,
+                        escape(name),
+                        count
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" &middot; ")
+        };
+        let mut nav = format!(
+            "<nav>\n<p><a href=\"{}index.html\">All sessions</a> &middot; {}</p>\n",
+            root,
+            links("providers", &self.providers, "")
+        );
+        if !self.tags.is_empty() {
+            nav.push_str(&format!("<p>{}</p>\n", links("tags", &self.tags, "#")));
+        }
+        nav.push_str("</nav>\n");
+        nav
+    }
+}
+
+/// A page listing `sessions` (newest first) under a heading per month
+fn listing(
+    heading: &str,
+    site_title: &str,
+    sessions: &[&SiteSession],
+    nav: &Nav,
+    root: &str,
+) -> String {
+    let mut body = format!("{}<h1>{}</h1>\n", nav.render(root), escape(heading));
+    let mut month = None;
+    for session in sessions {
+        let summary = &session.summary;
+        let this_month = started(summary).map(|at| at.format("%B %Y").to_string());
+        if month.as_ref() != Some(&this_month) {
+            if month.is_some() {
+                body.push_str("</tbody>\n</table>\n");
+            }
+            body.push_str(&format!(
+                "<h2>{}</h2>\n<table>\n<thead><tr><th>Started</th><th>Provider</th><th>Session</th><th>Messages</th></tr></thead>\n<tbody>\n",
+                escape(this_month.as_deref().unwrap_or("Undated"))
+            ));
+            month = Some(this_month);
+        }
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td><a href=\"{}sessions/{}\">{}</a></td><td>{}</td></tr>\n",
+            started(summary)
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            escape(&summary.provider),
+            root,
+            escape(&page_name(summary)),
+            escape(title(summary)),
+            summary.message_count
+        ));
+    }
+    if month.is_some() {
+        body.push_str("</tbody>\n</table>\n");
+    } else {
+        body.push_str("<p>No sessions yet.</p>\n");
+    }
+
+    let page_title = if heading == site_title {
+        site_title.to_string()
+    } else {
+        format!("{} · {}", heading, site_title)
+    };
+    document(&page_title, &body)
+}
+
+fn session_page(session: &SiteSession, site_title: &str) -> String {
+    let summary = &session.summary;
+    let mut details = vec![format!(
+        "<a href=\"../providers/{}.html\">{}</a>",
+        slugify(&summary.provider),
+        escape(&summary.provider)
+    )];
+    if let Some(at) = started(summary) {
+        details.push(at.format("%Y-%m-%d %H:%M UTC").to_string());
+    }
+    details.push(format!("{} messages", summary.message_count));
+    details.extend(summary.tags.iter().map(|tag| {
+        format!(
+            "<a href=\"../tags/{}.html\">#{}</a>",
+            slugify(tag),
+            escape(tag)
+        )
+    }));
+
+    let body = format!(
+        "<p><a href=\"../index.html\">&larr; All sessions</a></p>\n\
+         <header class=\"session\">\n<p>{}</p>\n</header>\n{}",
+        details.join(" &middot; "),
+        markdown_to_html(&session.markdown)
+    );
+    document(&format!("{} · {}", title(summary), site_title), &body)
+}
+
+/// The session page's file name: the history file's, ending in `.html`
+fn page_name(summary: &SessionSummary) -> String {
+    let stem = summary
+        .markdown_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| summary.session_id.clone());
+    format!("{}.html", stem)
+}
+
+fn started(summary: &SessionSummary) -> Option<chrono::DateTime<chrono::Utc>> {
+    summary.started_at.or(summary.updated_at)
+}
+
+fn title(summary: &SessionSummary) -> &str {
+    if summary.title.is_empty() {
+        &summary.session_id
+    } else {
+        &summary.title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn session(id: &str, provider: &str, month: u32, tags: &[&str]) -> SiteSession {
+        SiteSession {
+            summary: SessionSummary {
+                session_id: id.to_string(),
+                provider: provider.to_string(),
+                title: format!("Session {}", id),
+                started_at: Some(Utc.with_ymd_and_hms(2025, month, 1, 9, 0, 0).unwrap()),
+                updated_at: None,
+                message_count: 2,
+                total_tokens: None,
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                markdown_path: PathBuf::from(format!(".waylog/history/{}.md", id)),
+            },
+            markdown: "# Hi\n\n## User\n\n<script>x</script>\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_indexes_by_month_provider_and_tag() {
+        let pages = build(
+            "Team log",
+            &[
+                session("a", "claude", 1, &["Cache Fix"]),
+                session("b", "codex", 2, &[]),
+            ],
+        );
+        let names: Vec<_> = pages.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            names,
+            [
+                "index.html",
+                "providers/claude.html",
+                "providers/codex.html",
+                "tags/cache-fix.html",
+                "sessions/b.html",
+                "sessions/a.html",
+            ]
+            .map(PathBuf::from)
+        );
+
+        let index = &pages[0].1;
+        let february = index.find("<h2>February 2025</h2>").unwrap();
+        let january = index.find("<h2>January 2025</h2>").unwrap();
+        assert!(february < january);
+        assert!(index.contains("<a href=\"tags/cache-fix.html\">#Cache Fix</a> (1)"));
+
+        let claude = &pages[1].1;
+        assert!(claude.contains("href=\"../sessions/a.html\""));
+        assert!(!claude.contains("sessions/b.html"));
+
+        let page = &pages[5].1;
+        assert!(page.contains("<a href=\"../tags/cache-fix.html\">#Cache Fix</a>"));
+        assert!(page.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_strip_frontmatter() {
+        assert_eq!(strip_frontmatter("---\na: 1\n---\n\n# t\n"), "\n# t\n");
+        assert_eq!(strip_frontmatter("# t\n"), "# t\n");
+    }
+}
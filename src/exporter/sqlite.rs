@@ -0,0 +1,501 @@
+//! SQLite archive of synced sessions (`.waylog/history.db`) for ad-hoc SQL
+//! analysis. Written by `waylog export --format sqlite` and, once it exists,
+//! kept up to date by every sync. A first export of a long history is
+//! written in paced batches, possibly in the background, and records its
+//! progress in `.waylog/backfill.json` for `waylog export --status`.
+use super::markdown::formatter::extract_title;
+use super::markdown::labels::Labels;
+use crate::config::SqliteConfig;
+use crate::error::{Result, WaylogError};
+use crate::init::WAYLOG_DIR;
+use crate::providers::base::{ChatSession, FileAccessKind, MessageRole};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Archive file inside `.waylog`
+pub const DATABASE_FILE: &str = "history.db";
+
+/// Progress of the last backfill, inside `.waylog`
+pub const BACKFILL_FILE: &str = "backfill.json";
+
+/// Bumped (with a migration) when the tables change
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    provider      TEXT NOT NULL,
+    session_id    TEXT NOT NULL,
+    project       TEXT NOT NULL,
+    title         TEXT NOT NULL,
+    started_at    TEXT NOT NULL,
+    updated_at    TEXT NOT NULL,
+    message_count INTEGER NOT NULL,
+    PRIMARY KEY (provider, session_id)
+);
+CREATE TABLE IF NOT EXISTS messages (
+    provider      TEXT NOT NULL,
+    session_id    TEXT NOT NULL,
+    idx           INTEGER NOT NULL,
+    id            TEXT NOT NULL,
+    role          TEXT NOT NULL,
+    timestamp     TEXT NOT NULL,
+    content       TEXT NOT NULL,
+    model         TEXT,
+    input_tokens  INTEGER,
+    output_tokens INTEGER,
+    cached_tokens INTEGER,
+    tool_calls    TEXT NOT NULL,
+    thoughts      TEXT NOT NULL,
+    attachments   INTEGER NOT NULL,
+    PRIMARY KEY (provider, session_id, idx)
+);
+CREATE TABLE IF NOT EXISTS file_accesses (
+    provider      TEXT NOT NULL,
+    session_id    TEXT NOT NULL,
+    message_idx   INTEGER NOT NULL,
+    path          TEXT NOT NULL,
+    kind          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS file_accesses_path ON file_accesses (path);
+";
+
+/// Default location of the archive for a project
+pub fn database_path(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(DATABASE_FILE)
+}
+
+/// An open archive
+pub struct Archive {
+    conn: Connection,
+}
+
+impl Archive {
+    /// Open (or create) the archive at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self { conn })
+    }
+
+    /// Store a session, inserting only messages the archive does not have yet.
+    /// Returns the number of messages written.
+    pub fn write_session(&mut self, session: &ChatSession) -> Result<usize> {
+        self.write_session_batch(session, usize::MAX)
+    }
+
+    /// How many messages of `session` [`write_session`](Self::write_session)
+    /// would write
+    pub fn pending(&self, session: &ChatSession) -> Result<usize> {
+        let stored = stored_messages(&self.conn, session)?;
+        Ok(if stored > session.messages.len() {
+            session.messages.len()
+        } else {
+            session.messages.len() - stored
+        })
+    }
+
+    /// [`write_session`](Self::write_session), writing at most `limit` of the
+    /// new messages in this transaction. Returns the number written; the
+    /// session is stored completely once that is 0.
+    pub fn write_session_batch(&mut self, session: &ChatSession, limit: usize) -> Result<usize> {
+        let title = extract_title(&session.messages, &Labels::from_config());
+        let tx = self.conn.transaction()?;
+
+        let stored = stored_messages(&tx, session)?;
+        // A session that shrank (e.g. after re-parsing) is rewritten from scratch
+        let from = if stored > session.messages.len() {
+            for table in ["messages", "file_accesses"] {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE provider = ?1 AND session_id = ?2",
+                        table
+                    ),
+                    params![session.provider, session.session_id],
+                )?;
+            }
+            0
+        } else {
+            stored
+        };
+
+        tx.execute(
+            "INSERT INTO sessions (provider, session_id, project, title, started_at, updated_at, message_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (provider, session_id) DO UPDATE SET
+                 project = excluded.project, title = excluded.title,
+                 started_at = excluded.started_at, updated_at = excluded.updated_at,
+                 message_count = excluded.message_count",
+            params![
+                session.provider,
+                session.session_id,
+                session.project_path.to_string_lossy(),
+                title,
+                session.started_at.to_rfc3339(),
+                session.updated_at.to_rfc3339(),
+                session.messages.len(),
+            ],
+        )?;
+
+        let to = from.saturating_add(limit).min(session.messages.len());
+        for (idx, message) in session.messages.iter().enumerate().take(to).skip(from) {
+            let tokens = message.metadata.tokens.as_ref();
+            tx.execute(
+                "INSERT INTO messages (provider, session_id, idx, id, role, timestamp, content, model,
+                     input_tokens, output_tokens, cached_tokens, tool_calls, thoughts, attachments)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    session.provider,
+                    session.session_id,
+                    idx,
+                    message.id,
+                    role_name(message.role),
+                    message.timestamp.to_rfc3339(),
+                    message.content,
+                    message.metadata.model,
+                    tokens.map(|t| t.input),
+                    tokens.map(|t| t.output),
+                    tokens.map(|t| t.cached),
+                    serde_json::to_string(&message.metadata.tool_calls)?,
+                    serde_json::to_string(&message.metadata.thoughts)?,
+                    message.metadata.attachments.len(),
+                ],
+            )?;
+            for access in &message.metadata.file_accesses {
+                tx.execute(
+                    "INSERT INTO file_accesses (provider, session_id, message_idx, path, kind)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        session.provider,
+                        session.session_id,
+                        idx,
+                        access.path,
+                        match access.kind {
+                            FileAccessKind::Read => "read",
+                            FileAccessKind::Edit => "edit",
+                        },
+                    ],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(to - from)
+    }
+
+    /// Store `sessions` a batch of `[export.sqlite] batch_size` messages at a
+    /// time, pausing `pause_ms` between batches, and report each batch's
+    /// message count to `progress`. `status` is saved after every batch.
+    /// Returns the number of messages written.
+    pub async fn backfill(
+        &mut self,
+        sessions: &[ChatSession],
+        config: &SqliteConfig,
+        status: &mut Backfill,
+        mut progress: impl FnMut(usize),
+    ) -> Result<usize> {
+        let pause = Duration::from_millis(config.pause_ms);
+        let mut written = 0;
+        for session in sessions {
+            loop {
+                let batch = self.write_session_batch(session, config.batch_size.max(1))?;
+                if batch == 0 {
+                    break;
+                }
+                written += batch;
+                progress(batch);
+                status.written = written;
+                status.save().await?;
+                if !pause.is_zero() {
+                    tokio::time::sleep(pause).await;
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// How far a backfill of an archive has got
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backfill {
+    pub database: PathBuf,
+    /// The waylog process writing it
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Messages the archive was missing when the backfill started
+    pub total: usize,
+    pub written: usize,
+    pub finished: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the progress is saved; None saves nothing
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+impl Backfill {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(BACKFILL_FILE)
+    }
+
+    /// A backfill of `total` messages into `database`, by this process, saved
+    /// in the project's `.waylog` if it has one
+    pub fn start(project_dir: &Path, database: &Path, total: usize) -> Self {
+        let now = Utc::now();
+        Self {
+            database: database.to_path_buf(),
+            pid: std::process::id(),
+            started_at: now,
+            updated_at: now,
+            total,
+            written: 0,
+            finished: false,
+            error: None,
+            file: project_dir
+                .join(WAYLOG_DIR)
+                .is_dir()
+                .then(|| Self::path(project_dir)),
+        }
+    }
+
+    /// The project's last backfill, if it recorded one
+    pub fn load(project_dir: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path(project_dir)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub async fn save(&mut self) -> Result<()> {
+        self.updated_at = Utc::now();
+        match &self.file {
+            Some(file) => crate::utils::atomic::write(file, serde_json::to_string(self)?).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Record how the backfill ended
+    pub async fn finish(&mut self, result: &Result<usize>) -> Result<()> {
+        self.finished = true;
+        self.error = result.as_ref().err().map(|e| e.to_string());
+        self.save().await
+    }
+
+    /// Time left at the pace so far, once there is a pace to go by
+    pub fn eta(&self) -> Option<Duration> {
+        if self.finished || self.written == 0 {
+            return None;
+        }
+        let elapsed = (self.updated_at - self.started_at).to_std().ok()?;
+        let left = self.total.saturating_sub(self.written) as f64;
+        Some(elapsed.mul_f64(left / self.written as f64))
+    }
+}
+
+/// How many messages of `session` the archive holds
+fn stored_messages(conn: &Connection, session: &ChatSession) -> Result<usize> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM messages WHERE provider = ?1 AND session_id = ?2",
+        params![session.provider, session.session_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Update the project's archive with a synced session, if the project has one
+pub fn update(project_dir: &Path, session: &ChatSession) -> Result<()> {
+    let path = database_path(project_dir);
+    if !path.exists() {
+        return Ok(());
+    }
+    refuse_plaintext(&path, super::encryption::enabled())?;
+    let mut session = session.clone();
+    session.messages = super::redact::Redactor::from_config()
+        .redact_messages(&session.messages)
+        .into_owned();
+    Archive::open(&path)?.write_session(&session)?;
+    Ok(())
+}
+
+/// The archive holds the sessions in plaintext, so it must not be kept in
+/// the project once its history is encrypted
+fn refuse_plaintext(path: &Path, encrypted: bool) -> Result<()> {
+    if encrypted {
+        return Err(WaylogError::Config(format!(
+            "{} holds the sessions in plaintext and can't be kept with [encryption]; \
+             delete it, or move it out of the project",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+fn role_name(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{ChatMessage, FileAccess, MessageMetadata};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn session(contents: &[&str]) -> ChatSession {
+        let now = Utc::now();
+        let messages = contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| ChatMessage {
+                id: format!("m{}", i),
+                timestamp: now,
+                role: if i % 2 == 0 {
+                    MessageRole::User
+                } else {
+                    MessageRole::Assistant
+                },
+                content: content.to_string(),
+                metadata: MessageMetadata {
+                    file_accesses: vec![FileAccess {
+                        path: "src/main.rs".to_string(),
+                        kind: FileAccessKind::Edit,
+                    }],
+                    ..MessageMetadata::default()
+                },
+            })
+            .collect();
+        ChatSession {
+            session_id: "abc".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: now,
+            updated_at: now,
+            messages,
+        }
+    }
+
+    fn count(archive: &Archive, table: &str) -> usize {
+        archive
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_write_session_is_incremental() {
+        let dir = TempDir::new().unwrap();
+        let mut archive = Archive::open(&dir.path().join("history.db")).unwrap();
+
+        assert_eq!(
+            archive.write_session(&session(&["Hi", "Hello"])).unwrap(),
+            2
+        );
+        assert_eq!(
+            archive
+                .write_session(&session(&["Hi", "Hello", "Thanks"]))
+                .unwrap(),
+            1
+        );
+        assert_eq!(count(&archive, "messages"), 3);
+        assert_eq!(count(&archive, "file_accesses"), 3);
+        assert_eq!(count(&archive, "sessions"), 1);
+
+        let (title, messages): (String, usize) = archive
+            .conn
+            .query_row(
+                "SELECT title, message_count FROM sessions WHERE session_id = 'abc'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(title, "Hi");
+        assert_eq!(messages, 3);
+
+        // A shorter session replaces the stored messages
+        assert_eq!(archive.write_session(&session(&["Hi"])).unwrap(), 1);
+        assert_eq!(count(&archive, "messages"), 1);
+        assert_eq!(count(&archive, "file_accesses"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_writes_in_batches() {
+        let dir = TempDir::new().unwrap();
+        let mut archive = Archive::open(&dir.path().join("history.db")).unwrap();
+        let long = session(&["1", "2", "3", "4", "5"]);
+        let mut other = session(&["a", "b"]);
+        other.session_id = "def".to_string();
+        archive.write_session(&session(&["1", "2"])).unwrap();
+        assert_eq!(archive.pending(&long).unwrap(), 3);
+
+        let config = SqliteConfig {
+            batch_size: 2,
+            pause_ms: 0,
+        };
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+        let mut status = Backfill::start(dir.path(), &dir.path().join("history.db"), 5);
+        let mut batches = Vec::new();
+        let written = archive
+            .backfill(&[long.clone(), other], &config, &mut status, |n| {
+                batches.push(n)
+            })
+            .await
+            .unwrap();
+        assert_eq!(written, 5);
+        assert_eq!(batches, vec![2, 1, 2]);
+        assert_eq!(count(&archive, "messages"), 7);
+        assert_eq!(archive.pending(&long).unwrap(), 0);
+
+        let saved = Backfill::load(dir.path()).unwrap();
+        assert_eq!((saved.written, saved.total), (5, 5));
+        assert!(!saved.finished);
+        status.finish(&Ok(written)).await.unwrap();
+        assert!(Backfill::load(dir.path()).unwrap().finished);
+    }
+
+    #[test]
+    fn test_backfill_eta_follows_the_pace() {
+        let dir = TempDir::new().unwrap();
+        let mut status = Backfill::start(dir.path(), Path::new("history.db"), 400);
+        assert_eq!(status.eta(), None);
+
+        status.written = 100;
+        status.updated_at = status.started_at + chrono::Duration::seconds(30);
+        assert_eq!(status.eta(), Some(Duration::from_secs(90)));
+
+        status.finished = true;
+        assert_eq!(status.eta(), None);
+    }
+
+    #[test]
+    fn test_update_keeps_an_existing_archive_current() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join(WAYLOG_DIR)).unwrap();
+
+        // Syncs don't create the archive
+        update(dir.path(), &session(&["Hi"])).unwrap();
+        assert!(!database_path(dir.path()).exists());
+
+        Archive::open(&database_path(dir.path())).unwrap();
+        update(dir.path(), &session(&["Hi", "Hello"])).unwrap();
+        let archive = Archive::open(&database_path(dir.path())).unwrap();
+        assert_eq!(count(&archive, "messages"), 2);
+        assert_eq!(count(&archive, "file_accesses"), 2);
+    }
+
+    #[test]
+    fn test_plaintext_archive_refused_with_encryption() {
+        let path = Path::new(".waylog/history.db");
+        assert!(refuse_plaintext(path, false).is_ok());
+        assert!(matches!(
+            refuse_plaintext(path, true),
+            Err(WaylogError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+//! Committing the history to the project's git repository (`[git]
+//! auto_commit`) when `waylog run` ends and when `pull` writes to it. Only
+//! `.waylog/history` goes into the commit: other changes, staged or not, are
+//! left as they are, and with `on_dirty = "skip"` nothing is committed while
+//! there are any.
+//!
+//! The commits made while `waylog run` is active are found by comparing
+//! `HEAD` before the agent starts with `HEAD` after it exits.
+use crate::config::DirtyPolicy;
+use crate::error::{Result, WaylogError};
+use crate::exporter::frontmatter;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use tokio::process::Command;
+
+/// What became of an auto-commit
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommitOutcome {
+    Committed {
+        hash: String,
+        subject: String,
+    },
+    /// The project is not in a git repository (or git is not installed)
+    NotARepository,
+    /// `.waylog/history` is ignored by git
+    Ignored,
+    /// The work tree has other changes and `on_dirty = "skip"`
+    Dirty,
+    NothingToCommit,
+}
+
+/// Commit the history of `project` (`[git] auto_commit`), describing the
+/// commit by the `sessions` (history files) that were written
+pub async fn commit_history(project: &Path, sessions: &[PathBuf]) -> Result<CommitOutcome> {
+    let config = &crate::config::get().git;
+    let history = crate::utils::path::get_waylog_dir(project);
+    if sessions.is_empty() || !history.is_dir() {
+        return Ok(CommitOutcome::NothingToCommit);
+    }
+
+    match git(project, &["rev-parse", "--show-toplevel"]).await {
+        Ok(output) if output.status.success() => {}
+        _ => return Ok(CommitOutcome::NotARepository),
+    }
+    let history_arg = history.to_string_lossy();
+    if git(project, &["check-ignore", "-q", &history_arg])
+        .await?
+        .status
+        .success()
+    {
+        return Ok(CommitOutcome::Ignored);
+    }
+
+    if config.on_dirty == DirtyPolicy::Skip {
+        // Changes of waylog's own files don't count
+        let waylog_dir = format!(":(exclude){}", crate::init::WAYLOG_DIR);
+        let status = checked(
+            git(
+                project,
+                &[
+                    "status",
+                    "--porcelain",
+                    "--untracked-files=no",
+                    "--",
+                    ":/",
+                    &waylog_dir,
+                ],
+            )
+            .await?,
+        )?;
+        if !status.stdout.is_empty() {
+            return Ok(CommitOutcome::Dirty);
+        }
+    }
+
+    checked(git(project, &["add", "--all", "--", &history_arg]).await?)?;
+    if git(
+        project,
+        &["diff", "--cached", "--quiet", "--", &history_arg],
+    )
+    .await?
+    .status
+    .success()
+    {
+        return Ok(CommitOutcome::NothingToCommit);
+    }
+
+    let subject = commit_message(sessions).await;
+    checked(
+        git(
+            project,
+            &["commit", "--quiet", "-m", &subject, "--", &history_arg],
+        )
+        .await?,
+    )?;
+    let hash = checked(git(project, &["rev-parse", "--short", "HEAD"]).await?)?;
+    Ok(CommitOutcome::Committed {
+        hash: String::from_utf8_lossy(&hash.stdout).trim().to_string(),
+        subject,
+    })
+}
+
+/// Where `HEAD` of a repository is
+#[derive(Debug, Clone, PartialEq)]
+pub enum Head {
+    /// No commit yet
+    Unborn,
+    At(String),
+}
+
+/// A commit, by its short hash and subject
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+}
+
+/// `HEAD` of the repository `project` is in, or `None` if it is not in one
+/// (or git is not installed)
+pub async fn head(project: &Path) -> Option<Head> {
+    match git(project, &["rev-parse", "--show-toplevel"]).await {
+        Ok(output) if output.status.success() => {}
+        _ => return None,
+    }
+    match git(project, &["rev-parse", "--verify", "--quiet", "HEAD"]).await {
+        Ok(output) if output.status.success() => Some(Head::At(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        Ok(_) => Some(Head::Unborn),
+        Err(_) => None,
+    }
+}
+
+/// The commits reachable from the current `HEAD` but not from `since`,
+/// oldest first
+pub async fn commits_since(project: &Path, since: &Head) -> Result<Vec<Commit>> {
+    if !git(project, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await?
+        .status
+        .success()
+    {
+        return Ok(Vec::new());
+    }
+    let range = match since {
+        Head::Unborn => "HEAD".to_string(),
+        Head::At(hash) => format!("{}..HEAD", hash),
+    };
+    let log = checked(
+        git(
+            project,
+            &["log", "--reverse", "--format=%h%x1f%s", &range, "--"],
+        )
+        .await?,
+    )?;
+    Ok(String::from_utf8_lossy(&log.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\x1f'))
+        .map(|(hash, subject)| Commit {
+            hash: hash.to_string(),
+            subject: subject.to_string(),
+        })
+        .collect())
+}
+
+/// `[git] message` for one session, `message_many` for several
+async fn commit_message(sessions: &[PathBuf]) -> String {
+    let config = &crate::config::get().git;
+    let mut messages = 0;
+    let mut described = Vec::new();
+    for path in sessions {
+        let Ok(content) = crate::exporter::encryption::read(path).await else {
+            continue;
+        };
+        let fm = frontmatter::parse_frontmatter_str(&content);
+        messages += fm.message_count.unwrap_or(0);
+        let title = fm.title.or_else(|| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(str::to_string)
+        });
+        described.push((fm.provider.unwrap_or_default(), title.unwrap_or_default()));
+    }
+
+    match described.as_slice() {
+        [(provider, title)] => config
+            .message
+            .replace("{provider}", provider)
+            .replace("{title}", title)
+            .replace("{messages}", &messages.to_string()),
+        _ => config
+            .message_many
+            .replace("{sessions}", &sessions.len().to_string())
+            .replace("{messages}", &messages.to_string()),
+    }
+}
+
+/// Run git with `args` in `dir`
+async fn git(dir: &Path, args: &[&str]) -> Result<Output> {
+    Ok(Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await?)
+}
+
+/// `output` of a git command that succeeded, or its error
+fn checked(output: Output) -> Result<Output> {
+    if output.status.success() {
+        return Ok(output);
+    }
+    Err(WaylogError::Git(
+        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ))
+}
@@ -0,0 +1,229 @@
+//! Integrations notified of synced messages (`[[hooks.message]]`). Each hook
+//! is a command that gets the event as JSON on stdin, or a URL the event is
+//! POSTed to. Messages are redacted as in the history files. A failing hook
+//! is logged and never fails the sync.
+//!
+//! `[hooks] pre_run` and `post_run` are commands run around `waylog run`,
+//! in the project directory, with the details in `WAYLOG_*` variables.
+use crate::config::Hook;
+use crate::error::{Result, WaylogError};
+use crate::exporter::redact::Redactor;
+use crate::providers::base::{ChatMessage, ChatSession};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// New messages of a session, sent after they are written to its history file
+#[derive(Debug, Serialize)]
+pub struct MessageEvent<'a> {
+    /// Always `"message"`
+    pub event: &'static str,
+    pub provider: &'a str,
+    pub session_id: &'a str,
+    pub project: &'a Path,
+    pub markdown_path: &'a Path,
+    pub messages: &'a [ChatMessage],
+}
+
+/// Fire the `[[hooks.message]]` hooks for `messages` just written to `markdown_path`
+pub async fn messages_synced(
+    project: &Path,
+    markdown_path: &Path,
+    session: &ChatSession,
+    messages: &[ChatMessage],
+) {
+    let hooks = &crate::config::get().hooks.message;
+    if hooks.is_empty() || messages.is_empty() {
+        return;
+    }
+
+    let messages = Redactor::from_config().redact_messages(messages);
+    let event = MessageEvent {
+        event: "message",
+        provider: &session.provider,
+        session_id: &session.session_id,
+        project,
+        markdown_path,
+        messages: &messages,
+    };
+    let payload = match serde_json::to_vec(&event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Could not encode hook event: {}", e);
+            return;
+        }
+    };
+
+    for hook in hooks {
+        if let Err(e) = fire(hook, &payload).await {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+/// Run `commands` one after another in `project`, with `env` and
+/// `WAYLOG_PROJECT` set, stopping at the first that fails. `stage` names
+/// them in errors (e.g. `pre_run`).
+pub async fn run_commands(
+    stage: &str,
+    commands: &[String],
+    project: &Path,
+    env: &[(&str, String)],
+) -> Result<()> {
+    for command in commands {
+        let words = crate::utils::string::split_command(command);
+        let Some((program, args)) = words.split_first() else {
+            continue;
+        };
+        let status = Command::new(program)
+            .args(args)
+            .current_dir(project)
+            .env("WAYLOG_PROJECT", project)
+            .envs(env.iter().map(|(key, value)| (key, value)))
+            .status()
+            .await
+            .map_err(|e| WaylogError::HookFailed(format!("{} `{}`: {}", stage, command, e)))?;
+        if !status.success() {
+            return Err(WaylogError::HookFailed(format!(
+                "{} `{}` exited with {}",
+                stage, command, status
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Run or POST to one hook, giving up after its timeout
+async fn fire(hook: &Hook, payload: &[u8]) -> Result<()> {
+    let timeout = Duration::from_secs(hook.timeout);
+    let (name, fired) = match (&hook.command, &hook.url) {
+        (Some(command), _) => (
+            command,
+            tokio::time::timeout(timeout, run(command, payload)).await,
+        ),
+        (None, Some(url)) => (url, tokio::time::timeout(timeout, post(url, payload)).await),
+        (None, None) => return Ok(()),
+    };
+    fired.map_err(|_| {
+        WaylogError::HookFailed(format!("{} took longer than {}s", name, hook.timeout))
+    })?
+}
+
+/// Run `command` with `payload` on stdin
+async fn run(command: &str, payload: &[u8]) -> Result<()> {
+    let words = crate::utils::string::split_command(command);
+    let Some((program, args)) = words.split_first() else {
+        return Err(WaylogError::Config("hook command is empty".to_string()));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| WaylogError::HookFailed(format!("{}: {}", program, e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook may not care about the payload; its exit status decides
+        if let Err(e) = stdin.write_all(payload).await {
+            tracing::debug!("{} did not read the whole event: {}", program, e);
+        }
+    }
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(WaylogError::HookFailed(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// POST `payload` to `url` as JSON
+pub(crate) async fn post(url: &str, payload: &[u8]) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload.to_vec())
+        .send()
+        .await
+        .map_err(|e| WaylogError::HookFailed(format!("{}: {}", url, e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(WaylogError::HookFailed(format!(
+            "{} answered {}",
+            url, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    fn hook(command: Option<&str>, url: Option<String>) -> Hook {
+        Hook {
+            command: command.map(str::to_string),
+            url,
+            ..Hook::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_hook_reports_failure() {
+        assert!(fire(&hook(Some("cat"), None), b"{}").await.is_ok());
+
+        let error = fire(&hook(Some("sh -c 'echo nope >&2; exit 2'"), None), b"{}")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WaylogError::HookFailed(_)));
+        assert!(error.to_string().contains("nope"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_the_event() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("{\"event\":\"message\"}") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        fire(&hook(None, Some(url)), b"{\"event\":\"message\"}")
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /events"), "{}", request);
+        assert!(
+            request.contains("content-type: application/json"),
+            "{}",
+            request
+        );
+    }
+}
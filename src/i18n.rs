@@ -0,0 +1,138 @@
+//! Localized terminal messages. The messages live in Fluent catalogs under
+//! `locales/`, one per language, built into the binary. The language comes
+//! from `[ui] language`, else `LC_ALL`, `LC_MESSAGES` or `LANG`; a message
+//! missing from a catalog is taken from the English one.
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// The languages there are catalogs for; the first is the fallback
+const CATALOGS: [(&str, &str); 2] = [
+    ("en-US", include_str!("../locales/en-US/waylog.ftl")),
+    ("zh-CN", include_str!("../locales/zh-CN/waylog.ftl")),
+];
+
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Index in `CATALOGS` of the language messages are printed in
+static LANGUAGE: AtomicUsize = AtomicUsize::new(0);
+
+/// The message `id` in the current language, with `name = value` arguments
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), fluent_bundle::FluentValue::from($value));)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+pub(crate) use t;
+
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    BUNDLES.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(language, source)| {
+                let language: LanguageIdentifier =
+                    language.parse().expect("catalog languages are valid");
+                let mut bundle = FluentBundle::new_concurrent(vec![language]);
+                // Terminals would print the bidi isolation marks around arguments
+                bundle.set_use_isolating(false);
+                let resource = FluentResource::try_new(source.to_string())
+                    .unwrap_or_else(|(resource, _)| resource);
+                bundle.add_resource_overriding(resource);
+                bundle
+            })
+            .collect()
+    })
+}
+
+/// Print messages in the catalog closest to `language` (`zh-CN`, `zh_CN.UTF-8`,
+/// `en`, ...); English if there is none
+pub fn set_language(language: &str) {
+    LANGUAGE.store(catalog_for(language).unwrap_or(0), Ordering::Relaxed);
+}
+
+/// The language the locale environment variables ask for, if any
+pub fn env_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// The catalog for a language tag or POSIX locale, matched by language
+/// (`zh-TW` gets the `zh-CN` one)
+fn catalog_for(language: &str) -> Option<usize> {
+    let tag = language.split(['.', '@']).next()?.replace('_', "-");
+    let wanted: LanguageIdentifier = tag.parse().ok()?;
+    CATALOGS.iter().position(|(catalog, _)| {
+        catalog
+            .parse::<LanguageIdentifier>()
+            .is_ok_and(|catalog| catalog.language == wanted.language)
+    })
+}
+
+/// The message `id` in the current language, or in English; the id itself
+/// if neither catalog has it. Use `t!` rather than calling this directly.
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundles = bundles();
+    let current = LANGUAGE.load(Ordering::Relaxed);
+    for bundle in [&bundles[current], &bundles[0]] {
+        let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+            continue;
+        };
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        if errors.is_empty() {
+            return text.into_owned();
+        }
+        tracing::debug!("Formatting message {}: {:?}", id, errors);
+    }
+    id.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ids of the messages in a catalog, which must parse
+    fn ids(source: &str) -> Vec<String> {
+        if let Err((_, errors)) = FluentResource::try_new(source.to_string()) {
+            panic!("catalog does not parse: {:?}", errors);
+        }
+        source
+            .lines()
+            .filter_map(|line| line.split_once(" ="))
+            .map(|(id, _)| id)
+            .filter(|id| id.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_messages() {
+        let english = ids(CATALOGS[0].1);
+        for (language, source) in &CATALOGS[1..] {
+            let mut missing: Vec<_> = english.clone();
+            missing.retain(|id| !ids(source).contains(id));
+            assert!(missing.is_empty(), "{} lacks {:?}", language, missing);
+            let mut extra = ids(source);
+            extra.retain(|id| !english.contains(id));
+            assert!(extra.is_empty(), "{} has unknown {:?}", language, extra);
+        }
+    }
+
+    #[test]
+    fn test_catalog_for_locales() {
+        assert_eq!(catalog_for("zh_CN.UTF-8"), Some(1));
+        assert_eq!(catalog_for("zh-TW"), Some(1));
+        assert_eq!(catalog_for("en_GB.UTF-8"), Some(0));
+        assert_eq!(catalog_for("C"), None);
+        assert_eq!(catalog_for("fr_FR"), None);
+    }
+}
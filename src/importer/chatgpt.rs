@@ -0,0 +1,140 @@
+use crate::error::Result;
+use crate::providers::base::{ChatMessage, ChatSession, MessageMetadata, MessageRole};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Parse a ChatGPT `conversations.json` export
+pub(super) fn parse(value: serde_json::Value, project_path: &Path) -> Result<Vec<ChatSession>> {
+    let conversations: Vec<Conversation> = super::one_or_many(value)?;
+
+    Ok(conversations
+        .into_iter()
+        .map(|conv| conv.into_session(project_path))
+        .filter(|s| !s.messages.is_empty())
+        .collect())
+}
+
+impl Conversation {
+    fn into_session(mut self, project_path: &Path) -> ChatSession {
+        // Conversations are trees (edits create branches); follow the branch the
+        // user last saw by walking parents up from the current node.
+        let mut branch = Vec::new();
+        let mut cursor = self.current_node.clone();
+        while let Some(id) = cursor {
+            match self.mapping.remove(&id) {
+                Some(node) => {
+                    cursor = node.parent;
+                    if let Some(msg) = node.message {
+                        branch.push(msg);
+                    }
+                }
+                None => break,
+            }
+        }
+        branch.reverse();
+
+        let messages: Vec<ChatMessage> = branch.into_iter().filter_map(convert_message).collect();
+
+        let started_at = self
+            .create_time
+            .and_then(super::from_epoch_secs)
+            .or_else(|| messages.first().map(|m| m.timestamp))
+            .unwrap_or_else(Utc::now);
+        let updated_at = self
+            .update_time
+            .and_then(super::from_epoch_secs)
+            .unwrap_or(started_at);
+
+        ChatSession {
+            session_id: self
+                .conversation_id
+                .or(self.id)
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            provider: super::ImportFormat::ChatGpt.provider_name().to_string(),
+            project_path: project_path.to_path_buf(),
+            started_at,
+            updated_at,
+            messages,
+        }
+    }
+}
+
+fn convert_message(msg: Message) -> Option<ChatMessage> {
+    let role = match msg.author.role.as_str() {
+        "user" => MessageRole::User,
+        "assistant" => MessageRole::Assistant,
+        "system" => MessageRole::System,
+        _ => return None,
+    };
+
+    let content = msg
+        .content
+        .parts
+        .iter()
+        .filter_map(|p| p.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    Some(ChatMessage {
+        id: msg.id,
+        timestamp: msg
+            .create_time
+            .and_then(super::from_epoch_secs)
+            .unwrap_or_else(Utc::now),
+        role,
+        content,
+        metadata: MessageMetadata {
+            model: msg.metadata.and_then(|m| m.model_slug),
+            ..Default::default()
+        },
+    })
+}
+
+// ChatGPT export structures
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    id: Option<String>,
+    conversation_id: Option<String>,
+    create_time: Option<f64>,
+    update_time: Option<f64>,
+    current_node: Option<String>,
+    #[serde(default)]
+    mapping: HashMap<String, Node>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    parent: Option<String>,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    id: String,
+    author: Author,
+    create_time: Option<f64>,
+    content: Content,
+    metadata: Option<Metadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    model_slug: Option<String>,
+}
@@ -0,0 +1,101 @@
+use crate::error::Result;
+use crate::providers::base::{ChatMessage, ChatSession, MessageMetadata, MessageRole};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Parse a Claude.ai `conversations.json` export
+pub(super) fn parse(value: serde_json::Value, project_path: &Path) -> Result<Vec<ChatSession>> {
+    let conversations: Vec<Conversation> = super::one_or_many(value)?;
+
+    Ok(conversations
+        .into_iter()
+        .map(|conv| {
+            let messages: Vec<ChatMessage> = conv
+                .chat_messages
+                .into_iter()
+                .filter_map(convert_message)
+                .collect();
+
+            let started_at = parse_time(conv.created_at.as_deref())
+                .or_else(|| messages.first().map(|m| m.timestamp))
+                .unwrap_or_else(Utc::now);
+
+            ChatSession {
+                session_id: conv.uuid,
+                provider: super::ImportFormat::ClaudeAi.provider_name().to_string(),
+                project_path: project_path.to_path_buf(),
+                started_at,
+                updated_at: parse_time(conv.updated_at.as_deref()).unwrap_or(started_at),
+                messages,
+            }
+        })
+        .filter(|s| !s.messages.is_empty())
+        .collect())
+}
+
+fn convert_message(msg: Message) -> Option<ChatMessage> {
+    let role = match msg.sender.as_str() {
+        "human" => MessageRole::User,
+        "assistant" => MessageRole::Assistant,
+        _ => return None,
+    };
+
+    // Newer exports keep the text in structured `content` blocks and leave `text` empty
+    let content = if msg.text.trim().is_empty() {
+        msg.content
+            .iter()
+            .filter(|c| c.content_type == "text")
+            .filter_map(|c| c.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        msg.text
+    };
+
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    Some(ChatMessage {
+        id: msg.uuid,
+        timestamp: parse_time(msg.created_at.as_deref()).unwrap_or_else(Utc::now),
+        role,
+        content,
+        metadata: MessageMetadata::default(),
+    })
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    value
+        .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+// Claude.ai export structures
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    uuid: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    #[serde(default)]
+    chat_messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    uuid: String,
+    sender: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    content: Vec<ContentItem>,
+    created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentItem {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: Option<String>,
+}
@@ -0,0 +1,221 @@
+//! Converters for chat exports produced outside of the supported CLI agents
+//! (ChatGPT web, Claude.ai, generic OpenAI-style message lists).
+mod chatgpt;
+mod claude_ai;
+mod openai;
+
+pub use crate::cli::ImportFormat;
+use crate::error::{Result, WaylogError};
+use crate::exporter::jsonl;
+use crate::providers::base::ChatSession;
+use std::path::Path;
+
+impl ImportFormat {
+    /// Provider name recorded in the frontmatter of imported sessions
+    pub fn provider_name(&self) -> &'static str {
+        match self {
+            ImportFormat::ChatGpt => "chatgpt",
+            ImportFormat::ClaudeAi => "claude-ai",
+            ImportFormat::OpenAi => "openai",
+            ImportFormat::WaylogJsonl => "waylog",
+        }
+    }
+
+    /// Guess the export format from the document structure
+    pub fn detect(value: &serde_json::Value) -> Self {
+        let first = match value {
+            serde_json::Value::Array(items) => items.first(),
+            other => Some(other),
+        };
+
+        match first {
+            Some(item) if item.get("mapping").is_some() => ImportFormat::ChatGpt,
+            Some(item) if item.get("chat_messages").is_some() => ImportFormat::ClaudeAi,
+            _ => ImportFormat::OpenAi,
+        }
+    }
+}
+
+/// Read an export file and convert every conversation it contains into a chat session.
+/// When `format` is `None` the format is detected from the file contents.
+pub async fn import_file(
+    path: &Path,
+    format: Option<ImportFormat>,
+    project_path: &Path,
+) -> Result<(ImportFormat, Vec<ChatSession>)> {
+    let content = tokio::fs::read_to_string(path).await?;
+
+    // Sessions exported by waylog keep their own provider and ids
+    let is_waylog = match format {
+        Some(format) => format == ImportFormat::WaylogJsonl,
+        None => jsonl::is_jsonl(&content),
+    };
+    if is_waylog {
+        return Ok((ImportFormat::WaylogJsonl, jsonl::read_sessions(&content)?));
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&content)?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(&value));
+
+    let fallback_id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("import")
+        .to_string();
+
+    let sessions = match format {
+        ImportFormat::ChatGpt => chatgpt::parse(value, project_path)?,
+        ImportFormat::ClaudeAi => claude_ai::parse(value, project_path)?,
+        ImportFormat::OpenAi => openai::parse(value, project_path, &fallback_id)?,
+        ImportFormat::WaylogJsonl => unreachable!("handled above"),
+    };
+
+    Ok((format, sessions))
+}
+
+/// Deserialize either a single object or a list of objects
+fn one_or_many<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Result<Vec<T>> {
+    match value {
+        serde_json::Value::Array(_) => serde_json::from_value(value).map_err(WaylogError::Json),
+        other => Ok(vec![serde_json::from_value(other)?]),
+    }
+}
+
+/// Convert seconds since the Unix epoch (as used by ChatGPT exports) to a UTC timestamp
+fn from_epoch_secs(secs: f64) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::from_timestamp_millis((secs * 1000.0) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::MessageRole;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn project() -> PathBuf {
+        PathBuf::from("/test/project")
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            ImportFormat::detect(&json!([{ "mapping": {} }])),
+            ImportFormat::ChatGpt
+        );
+        assert_eq!(
+            ImportFormat::detect(&json!([{ "chat_messages": [] }])),
+            ImportFormat::ClaudeAi
+        );
+        assert_eq!(
+            ImportFormat::detect(&json!({ "messages": [] })),
+            ImportFormat::OpenAi
+        );
+    }
+
+    #[test]
+    fn test_parse_chatgpt_follows_current_branch() {
+        let export = json!([{
+            "id": "conv-1",
+            "title": "Rust lifetimes",
+            "create_time": 1704067200.0,
+            "update_time": 1704067260.0,
+            "current_node": "n3",
+            "mapping": {
+                "root": { "id": "root", "message": null, "parent": null, "children": ["n1"] },
+                "n1": {
+                    "id": "n1", "parent": "root", "children": ["n2", "n2b"],
+                    "message": {
+                        "id": "n1", "author": { "role": "user" }, "create_time": 1704067200.0,
+                        "content": { "content_type": "text", "parts": ["What is a lifetime?"] }
+                    }
+                },
+                "n2b": {
+                    "id": "n2b", "parent": "n1", "children": [],
+                    "message": {
+                        "id": "n2b", "author": { "role": "assistant" }, "create_time": 1704067210.0,
+                        "content": { "content_type": "text", "parts": ["Discarded branch"] }
+                    }
+                },
+                "n2": {
+                    "id": "n2", "parent": "n1", "children": ["n3"],
+                    "message": {
+                        "id": "n2", "author": { "role": "assistant" }, "create_time": 1704067220.0,
+                        "content": { "content_type": "text", "parts": ["A scope for references."] },
+                        "metadata": { "model_slug": "gpt-4o" }
+                    }
+                },
+                "n3": {
+                    "id": "n3", "parent": "n2", "children": [],
+                    "message": {
+                        "id": "n3", "author": { "role": "user" }, "create_time": 1704067260.0,
+                        "content": { "content_type": "text", "parts": ["Thanks"] }
+                    }
+                }
+            }
+        }]);
+
+        let sessions = chatgpt::parse(export, &project()).unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        let session = &sessions[0];
+        assert_eq!(session.session_id, "conv-1");
+        assert_eq!(session.provider, "chatgpt");
+        let contents: Vec<_> = session
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["What is a lifetime?", "A scope for references.", "Thanks"]
+        );
+        assert_eq!(
+            session.messages[1].metadata.model.as_deref(),
+            Some("gpt-4o")
+        );
+    }
+
+    #[test]
+    fn test_parse_claude_ai_export() {
+        let export = json!([{
+            "uuid": "c-1",
+            "name": "Refactor",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:05:00Z",
+            "chat_messages": [
+                { "uuid": "m1", "sender": "human", "text": "Hello", "created_at": "2024-01-01T00:00:00Z" },
+                {
+                    "uuid": "m2", "sender": "assistant", "text": "",
+                    "content": [{ "type": "text", "text": "Hi!" }],
+                    "created_at": "2024-01-01T00:01:00Z"
+                }
+            ]
+        }]);
+
+        let sessions = claude_ai::parse(export, &project()).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "c-1");
+        assert_eq!(sessions[0].messages.len(), 2);
+        assert_eq!(sessions[0].messages[0].role, MessageRole::User);
+        assert_eq!(sessions[0].messages[1].content, "Hi!");
+    }
+
+    #[test]
+    fn test_parse_openai_messages() {
+        let export = json!({
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": [{ "type": "text", "text": "Hi" }] },
+                { "role": "assistant", "content": "Hello" },
+                { "role": "tool", "content": "ignored" }
+            ]
+        });
+
+        let sessions = openai::parse(export, &project(), "chat").unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "chat");
+        assert_eq!(sessions[0].messages.len(), 3);
+        assert_eq!(sessions[0].messages[1].content, "Hi");
+    }
+}
@@ -0,0 +1,133 @@
+use crate::error::Result;
+use crate::providers::base::{ChatMessage, ChatSession, MessageMetadata, MessageRole};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Parse OpenAI chat-format JSON: a `{"messages": [...]}` document, a list of such
+/// documents, or a bare list of messages
+pub(super) fn parse(
+    value: serde_json::Value,
+    project_path: &Path,
+    fallback_id: &str,
+) -> Result<Vec<ChatSession>> {
+    let is_bare_message_list = value
+        .as_array()
+        .and_then(|items| items.first())
+        .is_some_and(|first| first.get("role").is_some());
+
+    let conversations: Vec<Conversation> = if is_bare_message_list {
+        vec![Conversation {
+            id: None,
+            model: None,
+            created: None,
+            messages: serde_json::from_value(value)?,
+        }]
+    } else {
+        super::one_or_many(value)?
+    };
+
+    let multiple = conversations.len() > 1;
+
+    Ok(conversations
+        .into_iter()
+        .enumerate()
+        .map(|(index, conv)| {
+            let started_at = conv
+                .created
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .unwrap_or_else(Utc::now);
+
+            let messages: Vec<ChatMessage> = conv
+                .messages
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, msg)| convert_message(msg, i, started_at, conv.model.clone()))
+                .collect();
+
+            let session_id = conv.id.unwrap_or_else(|| {
+                if multiple {
+                    format!("{}-{}", fallback_id, index + 1)
+                } else {
+                    fallback_id.to_string()
+                }
+            });
+
+            ChatSession {
+                session_id,
+                provider: super::ImportFormat::OpenAi.provider_name().to_string(),
+                project_path: project_path.to_path_buf(),
+                started_at,
+                updated_at: started_at,
+                messages,
+            }
+        })
+        .filter(|s| !s.messages.is_empty())
+        .collect())
+}
+
+fn convert_message(
+    msg: Message,
+    index: usize,
+    timestamp: DateTime<Utc>,
+    model: Option<String>,
+) -> Option<ChatMessage> {
+    let role = match msg.role.as_str() {
+        "user" => MessageRole::User,
+        "assistant" => MessageRole::Assistant,
+        "system" | "developer" => MessageRole::System,
+        _ => return None,
+    };
+
+    let content = match msg.content {
+        Some(Content::Text(text)) => text,
+        Some(Content::Parts(parts)) => parts
+            .into_iter()
+            .filter_map(|p| p.text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => String::new(),
+    };
+
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    Some(ChatMessage {
+        id: format!("msg-{}", index),
+        timestamp,
+        role,
+        content,
+        metadata: MessageMetadata {
+            model,
+            ..Default::default()
+        },
+    })
+}
+
+// OpenAI chat format structures
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    id: Option<String>,
+    model: Option<String>,
+    created: Option<i64>,
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    role: String,
+    content: Option<Content>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentPart {
+    text: Option<String>,
+}
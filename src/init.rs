@@ -1,6 +1,7 @@
 use crate::cli::Commands;
-use crate::error::Result;
+use crate::error::{Result, WaylogError};
 use crate::output::Output;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter};
 
@@ -8,6 +9,9 @@ use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter};
 /// The name of the waylog project directory (e.g., `.waylog`)
 pub const WAYLOG_DIR: &str = ".waylog";
 
+/// The name of the project config file inside `.waylog`
+pub const WAYLOG_CONFIG_FILE: &str = "config.toml";
+
 /// The name of the waylog log file
 pub const WAYLOG_LOG_FILE: &str = "waylog.log";
 
@@ -16,8 +20,23 @@ pub mod subdirs {
     /// History directory for markdown files
     pub const HISTORY: &str = "history";
 
+    /// Redacted, shareable mirror of the history directory
+    pub const HISTORY_PUBLIC: &str = "history-public";
+
+    /// Content-addressed attachment blobs referenced from history files
+    pub const ATTACHMENTS: &str = "attachments";
+
     /// Logs directory for log files
     pub const LOGS: &str = "logs";
+
+    /// Soft-deleted files, kept for `[trash] retention_days`
+    pub const TRASH: &str = "trash";
+
+    /// Named checkpoints recorded by `waylog snapshot create`
+    pub const SNAPSHOTS: &str = "snapshots";
+
+    /// Terminal recordings of `waylog run --record`
+    pub const RECORDINGS: &str = "recordings";
 }
 
 /// Resolve the project root directory based on the command being executed.
@@ -26,7 +45,15 @@ pub fn resolve_project_root(command: &Commands, output: &mut Output) -> Result<(
     let found_root = crate::utils::path::find_project_root();
 
     match command {
-        Commands::Pull { .. } => match found_root {
+        // A dry run writes nothing, so there is nothing to initialize
+        Commands::Pull { dry_run: true, .. } if found_root.is_none() => {
+            Ok((std::env::current_dir()?, false))
+        }
+        // Each project is pulled from its own directory
+        Commands::Pull {
+            all_projects: true, ..
+        } => Ok((std::env::current_dir()?, false)),
+        Commands::Pull { .. } | Commands::Import { .. } => match found_root {
             Some(root) => {
                 output.found_tracking(&root)?;
                 Ok((root, false))
@@ -35,12 +62,16 @@ pub fn resolve_project_root(command: &Commands, output: &mut Output) -> Result<(
                 // Interactive prompt for initialization
                 let current_dir = std::env::current_dir()?;
                 let waylog_path = current_dir.join(WAYLOG_DIR);
+                let is_home = crate::utils::path::is_home_dir(&current_dir);
 
                 output.not_initialized()?;
+                if is_home {
+                    output.home_directory_warning(&current_dir)?;
+                }
                 output.init_prompt(&waylog_path)?;
 
                 if dialoguer::Confirm::new()
-                    .default(true)
+                    .default(!is_home)
                     .show_default(true)
                     .interact()
                     .unwrap_or(false)
@@ -52,32 +83,113 @@ pub fn resolve_project_root(command: &Commands, output: &mut Output) -> Result<(
                 }
             }
         },
-        Commands::Run { .. } => match found_root {
+        Commands::Open { .. }
+        | Commands::Gist { .. }
+        | Commands::Export { .. }
+        | Commands::Reexport { .. }
+        | Commands::Site { .. }
+        | Commands::Merge { .. }
+        | Commands::Files { .. }
+        | Commands::Related { .. }
+        | Commands::Redact { .. }
+        | Commands::Trash { .. }
+        | Commands::Snapshot { .. }
+        | Commands::Diff { .. }
+        | Commands::Gc { .. }
+        | Commands::Batch { .. }
+        | Commands::Relink { .. }
+        | Commands::Repair { .. }
+        | Commands::Mcp
+        | Commands::Serve { .. }
+        | Commands::Note { .. } => match found_root {
+            Some(root) => Ok((root, false)),
+            None => Err(WaylogError::ProjectNotFound),
+        },
+        // Files named on the command line (an archive, say) need no project
+        Commands::ScanSecrets { paths, .. } => match found_root {
+            Some(root) => Ok((root, false)),
+            None if !paths.is_empty() => Ok((std::env::current_dir()?, false)),
+            None => Err(WaylogError::ProjectNotFound),
+        },
+        Commands::Completions { .. } | Commands::Api { .. } => {
+            Ok((std::env::current_dir()?, false))
+        }
+        Commands::Init { here, .. } => {
+            let current = std::env::current_dir()?;
+            let is_new = !current.join(WAYLOG_DIR).exists();
+            if is_new {
+                confirm_home_project(&current, *here, output)?;
+            }
+            Ok((current, is_new))
+        }
+        // Source files are found by project path, which need not be tracked yet
+        Commands::Validate { .. }
+        | Commands::Doctor
+        | Commands::Usage { .. }
+        | Commands::Tail { .. } => match found_root {
+            Some(root) => Ok((root, false)),
+            None => Ok((std::env::current_dir()?, false)),
+        },
+        Commands::Run { here, .. } => match found_root {
             Some(root) => Ok((root, false)),
             None => {
                 // For 'run', if no project found, initialize in current dir
                 let current = std::env::current_dir()?;
+                confirm_home_project(&current, *here, output)?;
                 Ok((current, true))
             }
         },
     }
 }
 
+/// Make sure the user means it before the home directory becomes a project:
+/// its `.waylog` would be found from every directory below it, and syncing it
+/// scans the provider histories of all repositories. `--here` skips the check.
+fn confirm_home_project(dir: &Path, here: bool, output: &mut Output) -> Result<()> {
+    if here || !crate::utils::path::is_home_dir(dir) {
+        return Ok(());
+    }
+
+    output.home_directory_warning(dir)?;
+    if !std::io::stdin().is_terminal() {
+        return Err(WaylogError::HomeDirectoryProject(dir.display().to_string()));
+    }
+
+    output.init_prompt(&dir.join(WAYLOG_DIR))?;
+    if dialoguer::Confirm::new()
+        .default(false)
+        .show_default(true)
+        .interact()
+        .unwrap_or(false)
+    {
+        Ok(())
+    } else {
+        output.aborted()?;
+        std::process::exit(0);
+    }
+}
+
 /// Setup logging system.
 /// - Default: No file logging, no console output (tracing is disabled for console)
 /// - With --verbose: Creates log file with detailed format, enables console tracing with simple format
 /// - With --quiet: Completely silent (no tracing output at all)
-pub fn setup_logging(project_root: &Path, verbose: bool, quiet: bool) -> Result<()> {
-    // Determine log level based on verbose flag
+/// - `ansi` colors the console output
+pub fn setup_logging(project_root: &Path, verbose: u8, quiet: bool, ansi: bool) -> Result<()> {
+    // Determine log level based on the number of -v flags
     // Use RUST_LOG environment variable if set, otherwise use default based on verbose
-    let default_log_level = if verbose { "debug" } else { "warn" };
+    let default_log_level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_log_level));
 
     let base_subscriber = tracing_subscriber::registry().with(env_filter);
 
     // Build subscriber with conditional layers
-    if verbose {
+    if verbose > 0 {
         let log_dir = project_root.join(WAYLOG_DIR).join(subdirs::LOGS);
 
         // Create log directory if it doesn't exist
@@ -110,6 +222,7 @@ pub fn setup_logging(project_root: &Path, verbose: bool, quiet: bool) -> Result<
             let subscriber = subscriber_with_file.with(
                 fmt::layer()
                     .with_writer(std::io::stderr) // Use stderr for logs
+                    .with_ansi(ansi)
                     .with_target(false) // Don't show module in console
                     .with_file(false)
                     .with_line_number(false)
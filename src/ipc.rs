@@ -0,0 +1,235 @@
+//! The control channel of a running `waylog run`, which other waylog
+//! commands in the same project talk to (`waylog note`). It is a Unix socket
+//! in `$XDG_RUNTIME_DIR` (or the temp directory) on Unix and a named pipe on
+//! Windows, named after the project, with one JSON request and one JSON reply
+//! per connection, each on a line of its own.
+use crate::error::{Result, WaylogError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// How long a connection may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something asked of the running `waylog run`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    /// Add an annotation to the session being recorded
+    Note { text: String },
+}
+
+/// The answer of the running `waylog run`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reply", rename_all = "snake_case")]
+pub enum Reply {
+    /// The note was added to this history file
+    Noted {
+        markdown_path: PathBuf,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// Short name of the channel of `project`
+fn channel_name(project: &Path) -> String {
+    let digest = Sha256::digest(project.to_string_lossy().as_bytes());
+    let hash: String = digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("waylog-{}", hash)
+}
+
+/// Where the channel of `project` is: a socket path outside the project,
+/// since socket paths are limited to about 100 bytes
+#[cfg(unix)]
+fn endpoint(project: &Path) -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("{}.sock", channel_name(project)))
+}
+
+#[cfg(windows)]
+fn endpoint(project: &Path) -> PathBuf {
+    PathBuf::from(format!(r"\\.\pipe\{}", channel_name(project)))
+}
+
+/// Send `request` to the `waylog run` active in `project` and wait for its reply
+pub async fn send(project: &Path, request: &Request) -> Result<Reply> {
+    let endpoint = endpoint(project);
+
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(&endpoint).await;
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(&endpoint);
+
+    let mut stream = stream.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+            WaylogError::NoActiveRun
+        }
+        _ => e.into(),
+    })?;
+
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).await?;
+    Ok(serde_json::from_str(&reply)?)
+}
+
+/// Answer requests for `project` with `handle` until the returned future is
+/// dropped, one connection at a time so requests never run concurrently
+pub async fn serve<F, Fut>(project: &Path, handle: F) -> Result<()>
+where
+    F: Fn(Request) -> Fut,
+    Fut: Future<Output = Reply>,
+{
+    let endpoint = endpoint(project);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A socket left behind by a run that crashed is replaced
+        if endpoint.exists() {
+            if tokio::net::UnixStream::connect(&endpoint).await.is_ok() {
+                return Err(WaylogError::Internal(format!(
+                    "{} is already in use",
+                    endpoint.display()
+                )));
+            }
+            std::fs::remove_file(&endpoint)?;
+        }
+        let listener = tokio::net::UnixListener::bind(&endpoint)?;
+        let _socket = RemoveOnDrop(endpoint.clone());
+        // Only the user running the agent may write to its history
+        std::fs::set_permissions(&endpoint, std::fs::Permissions::from_mode(0o600))?;
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            answer(stream, &handle).await;
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&endpoint)?;
+        loop {
+            server.connect().await?;
+            let client = std::mem::replace(&mut server, ServerOptions::new().create(&endpoint)?);
+            answer(client, &handle).await;
+        }
+    }
+}
+
+/// Read one request from `stream`, and write back what `handle` replies
+async fn answer<S, F, Fut>(stream: S, handle: &F)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(Request) -> Fut,
+    Fut: Future<Output = Reply>,
+{
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    let reply = match tokio::time::timeout(REQUEST_TIMEOUT, stream.read_line(&mut line)).await {
+        Ok(Ok(_)) => match serde_json::from_str(&line) {
+            Ok(request) => handle(request).await,
+            Err(e) => Reply::Failed {
+                error: format!("bad request: {}", e),
+            },
+        },
+        Ok(Err(e)) => {
+            tracing::debug!("Reading a request: {}", e);
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let Ok(mut reply) = serde_json::to_vec(&reply) else {
+        return;
+    };
+    reply.push(b'\n');
+    if let Err(e) = stream.get_mut().write_all(&reply).await {
+        tracing::debug!("Answering a request: {}", e);
+    }
+}
+
+/// Removes the socket file when the server stops
+#[cfg(unix)]
+struct RemoveOnDrop(PathBuf);
+
+#[cfg(unix)]
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_request_and_reply() {
+        let project = TempDir::new().unwrap();
+        assert!(matches!(
+            send(project.path(), &Request::Note { text: "hi".into() }).await,
+            Err(WaylogError::NoActiveRun)
+        ));
+
+        let server = tokio::spawn({
+            let project = project.path().to_path_buf();
+            async move {
+                serve(&project, |request| async move {
+                    match request {
+                        Request::Note { text } => Reply::Noted {
+                            markdown_path: PathBuf::from(text),
+                        },
+                    }
+                })
+                .await
+            }
+        });
+
+        let mut reply = Err(WaylogError::NoActiveRun);
+        for _ in 0..50 {
+            reply = send(
+                project.path(),
+                &Request::Note {
+                    text: "a.md".into(),
+                },
+            )
+            .await;
+            if !matches!(reply, Err(WaylogError::NoActiveRun)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            reply.unwrap(),
+            Reply::Noted {
+                markdown_path: PathBuf::from("a.md")
+            }
+        );
+
+        server.abort();
+        let _ = server.await;
+        #[cfg(unix)]
+        assert!(!endpoint(project.path()).exists());
+    }
+}
@@ -1,8 +1,15 @@
 mod cli;
 mod commands;
+mod config;
 mod error;
 mod exporter;
+mod git;
+mod hooks;
+mod i18n;
+mod importer;
 mod init;
+mod ipc;
+mod notify;
 mod output;
 mod providers;
 mod session;
@@ -11,11 +18,19 @@ mod utils;
 mod watcher;
 
 use clap::Parser;
-use cli::{Cli, Commands, OutputFormat};
-use commands::{handle_pull, handle_run};
+use cli::{ApiAction, Cli, ColorWhen, Commands, CompletionsAction, OutputFormat};
+use commands::{
+    handle_batch, handle_completions, handle_diff, handle_doctor, handle_dump_schema,
+    handle_export, handle_files, handle_first_run, handle_gc, handle_gist, handle_import,
+    handle_init, handle_mcp, handle_merge, handle_note, handle_open, handle_pull, handle_redact,
+    handle_reexport, handle_related, handle_relink, handle_repair, handle_run, handle_scan_secrets,
+    handle_serve, handle_site, handle_snapshot, handle_tail, handle_trash, handle_usage,
+    handle_validate,
+};
 use error::WaylogError;
-use output::Output;
-use std::io::Write;
+use output::{ColorMode, Output};
+use providers::base::TimeRange;
+use std::io::IsTerminal;
 
 #[tokio::main]
 async fn main() {
@@ -25,10 +40,51 @@ async fn main() {
     let cli = Cli::parse();
 
     // Create output handler
-    let mut output = Output::new(cli.quiet, matches!(cli.output, OutputFormat::Json));
+    let color = match cli.color {
+        ColorWhen::Auto => ColorMode::Auto,
+        ColorWhen::Always => ColorMode::Always,
+        ColorWhen::Never => ColorMode::Never,
+    };
+    // Prompts and progress bars color through `console`
+    console::set_colors_enabled(color.enabled(std::io::stdout().is_terminal()));
+    console::set_colors_enabled_stderr(color.enabled(std::io::stderr().is_terminal()));
+    let json = cli.json || matches!(cli.output, OutputFormat::Json);
+    let mut output = Output::new(cli.quiet, json, color);
+    // Messages follow the locale, refined by `[ui] language` once the config
+    // is loaded; JSON stays in English for the scripts reading it
+    if !json {
+        i18n::set_language(&i18n::env_language().unwrap_or_default());
+    }
+    // `pull --output json` prints one document with the whole outcome (one
+    // line per project with `--all-projects`)
+    if let Commands::Pull { .. } = cli.command {
+        output.hold_json_events();
+    }
 
     // Execute main logic and handle errors with appropriate exit codes
     let result = async {
+        // Completion scripts don't need a project, logging or config
+        if let Commands::Completions { shell, action } = &cli.command {
+            return match action {
+                Some(CompletionsAction::Install { shell, yes }) => {
+                    commands::completions::handle_install(*shell, *yes, &mut output)
+                }
+                None => handle_completions(
+                    shell.expect("clap requires a shell without a subcommand"),
+                    &mut std::io::stdout(),
+                ),
+            };
+        }
+        if let Commands::Api {
+            action: ApiAction::DumpSchema { schema, out },
+        } = &cli.command
+        {
+            return handle_dump_schema(*schema, out.as_deref(), &mut output);
+        }
+        if let Commands::Pull { schema: true, .. } = &cli.command {
+            return handle_dump_schema(Some(cli::SchemaName::Pull), None, &mut output);
+        }
+
         // 0. Validate provider for pull command BEFORE resolving project root
         // This ensures we catch invalid providers even if project is not initialized
         if let Commands::Pull {
@@ -39,11 +95,7 @@ async fn main() {
             match providers::get_provider(provider_name) {
                 Ok(_) => {} // Provider is valid, continue
                 Err(WaylogError::ProviderNotFound(ref name)) => {
-                    output.error(format!("'{}' is not a recognized provider.", name))?;
-                    writeln!(output.stderr(), "\nAvailable providers:")?;
-                    for provider in providers::list_providers() {
-                        writeln!(output.stderr(), "- {}", provider)?;
-                    }
+                    output.unknown_provider(name)?;
                     return Err(WaylogError::ProviderNotFound(name.clone()));
                 }
                 Err(e) => return Err(e),
@@ -54,9 +106,72 @@ async fn main() {
         let (project_root, is_new_project) = init::resolve_project_root(&cli.command, &mut output)?;
 
         // 2. Setup logging (only creates log file if verbose)
-        init::setup_logging(&project_root, cli.verbose, cli.quiet)?;
+        init::setup_logging(
+            &project_root,
+            cli.verbose,
+            cli.quiet,
+            color.enabled(std::io::stderr().is_terminal()),
+        )?;
+
+        // `init`, or the first `run`/`pull` here, writes the starter config
+        // that is loaded below
+        let pull_after_init = match &cli.command {
+            Commands::Init {
+                yes,
+                agent,
+                layout,
+                gitignore,
+                ..
+            } => {
+                let setup = commands::init::SetupOptions {
+                    agent: agent.clone(),
+                    layout: *layout,
+                    gitignore: gitignore.map(|gitignore| match gitignore {
+                        cli::GitignorePolicy::Commit => config::GitignorePolicy::Commit,
+                        cli::GitignorePolicy::Ignore => config::GitignorePolicy::Ignore,
+                        cli::GitignorePolicy::Local => config::GitignorePolicy::Local,
+                    }),
+                };
+                handle_init(*yes, setup, &project_root, &mut output).await?
+            }
+            Commands::Run { agent, .. } if is_new_project => {
+                handle_first_run(agent.as_deref(), &project_root, &mut output).await?;
+                false
+            }
+            Commands::Pull { .. } if is_new_project => {
+                handle_first_run(None, &project_root, &mut output).await?;
+                false
+            }
+            _ => false,
+        };
 
-        // 3. Log new project initialization if needed
+        // 3. Load project config
+        config::init(config::Config::load(&project_root)?);
+        if let Some(language) = config::get().ui.language.as_deref().filter(|_| !json) {
+            i18n::set_language(language);
+        }
+        exporter::markdown::template::init(exporter::markdown::template::Templates::load(
+            &project_root,
+        )?);
+
+        if let Some(fixtures) = utils::path::provider_fixtures_dir() {
+            tracing::info!(
+                "Provider sandbox mode: reading sessions from {}",
+                fixtures.display()
+            );
+        }
+
+        // Only one waylog writes to a project's history at a time
+        let _lock = if session::lock::needed_by(&cli.command) || pull_after_init {
+            session::lock::acquire(&project_root, cli.wait, |holder| {
+                Ok(output.waiting_for_lock(holder.map(|h| h.to_string()).as_deref())?)
+            })
+            .await?
+        } else {
+            None
+        };
+
+        // 4. Log new project initialization if needed
         if is_new_project {
             tracing::info!(
                 "Initializing new waylog project in: {}",
@@ -64,13 +179,249 @@ async fn main() {
             );
         }
 
-        // 4. Dispatch command
+        // 5. Dispatch command
         match cli.command {
-            Commands::Run { agent, args } => {
-                handle_run(agent, args, project_root, &mut output).await?;
+            Commands::Run {
+                env,
+                unset_env,
+                clean_env,
+                echo_sync,
+                timeout,
+                all_providers,
+                debounce,
+                min_sync_interval,
+                rescan_interval,
+                no_pty,
+                record,
+                ask_name,
+                session_name,
+                agent,
+                args,
+                ..
+            } => {
+                let env = commands::run::EnvOptions {
+                    clean: clean_env,
+                    set: env,
+                    unset: unset_env,
+                };
+                let defaults = watcher::WatchTuning::from_config(&config::get().watch);
+                let watch = watcher::WatchTuning {
+                    debounce: debounce.unwrap_or(defaults.debounce),
+                    min_sync_interval: min_sync_interval.unwrap_or(defaults.min_sync_interval),
+                    rescan_interval: rescan_interval.unwrap_or(defaults.rescan_interval),
+                };
+                let options = commands::run::RunOptions {
+                    env,
+                    echo_sync,
+                    timeout,
+                    all_providers: all_providers || config::get().watch.all_providers,
+                    watch,
+                    pty: !no_pty && config::get().run.pty,
+                    record: match record {
+                        Some(cli::RecordFormat::Cast) => config::Recording::Cast,
+                        Some(cli::RecordFormat::Log) => config::Recording::Log,
+                        None => config::get().run.record,
+                    },
+                    ask_name: ask_name || config::get().run.ask_name,
+                    session_name,
+                };
+                handle_run(agent, args, options, project_root, &mut output).await?;
+            }
+            Commands::Batch {
+                agent,
+                prompts,
+                label,
+                args,
+            } => {
+                let options = commands::batch::BatchOptions {
+                    agent,
+                    prompts,
+                    label,
+                    args,
+                };
+                handle_batch(options, project_root, &mut output).await?;
+            }
+            Commands::Init { .. } => {
+                if pull_after_init {
+                    let options = commands::pull::PullOptions::default();
+                    handle_pull(options, cli.verbose, project_root, &mut output).await?;
+                }
+            }
+            Commands::Pull {
+                provider,
+                force,
+                anonymize,
+                session,
+                rebuild,
+                report,
+                timings,
+                dry_run,
+                since,
+                until,
+                all_projects,
+                ..
+            } => {
+                let options = commands::pull::PullOptions {
+                    provider,
+                    force: force || rebuild.is_some(),
+                    anonymize,
+                    rebuild: rebuild.is_some(),
+                    session: rebuild.or(session),
+                    dry_run,
+                    range: TimeRange { since, until },
+                    wait: cli.wait,
+                    report,
+                    timings,
+                };
+                if all_projects {
+                    commands::pull::handle_pull_all_projects(options, cli.verbose, &mut output)
+                        .await?;
+                } else {
+                    handle_pull(options, cli.verbose, project_root, &mut output).await?;
+                }
+            }
+            Commands::Import {
+                file,
+                format,
+                force,
+            } => {
+                handle_import(file, format, force, project_root, &mut output).await?;
+            }
+            Commands::Export {
+                format,
+                out,
+                provider,
+                sample,
+                seed,
+                sample_by,
+                stratify,
+                anonymize,
+                since,
+                until,
+                query,
+                merge,
+                title,
+                background,
+                status,
+            } => {
+                if status {
+                    commands::export::handle_status(&project_root, &mut output)?;
+                } else {
+                    let options = commands::export::ExportOptions {
+                        format,
+                        out,
+                        provider,
+                        sampling: sample.map(|fraction| commands::export::Sampling {
+                            fraction,
+                            seed,
+                            unit: sample_by,
+                            stratify,
+                        }),
+                        anonymize,
+                        range: TimeRange { since, until },
+                        query,
+                        merge,
+                        title,
+                        background,
+                    };
+                    handle_export(options, project_root, &mut output).await?;
+                }
+            }
+            Commands::Completions { .. } | Commands::Api { .. } => {
+                unreachable!("handled before project resolution")
+            }
+            Commands::Site { out, title } => {
+                handle_site(out, title, project_root, &mut output).await?;
+            }
+            Commands::Merge { sessions } => {
+                handle_merge(sessions, project_root, &mut output).await?;
+            }
+            Commands::Files { session } => {
+                handle_files(session, project_root, &mut output).await?;
+            }
+            Commands::Related { session, index } => {
+                handle_related(session, index, project_root, &mut output).await?;
+            }
+            Commands::Redact { dry_run } => {
+                handle_redact(dry_run, project_root, &mut output).await?;
+            }
+            Commands::ScanSecrets { paths, fix } => {
+                handle_scan_secrets(paths, fix, project_root, &mut output).await?;
+            }
+            Commands::Serve { bind, port } => {
+                handle_serve(bind, port, project_root, &mut output).await?;
+            }
+            Commands::Mcp => {
+                handle_mcp(project_root).await?;
+            }
+            Commands::Repair { dry_run } => {
+                handle_repair(dry_run, project_root, &mut output).await?;
+            }
+            Commands::Validate { provider } => {
+                handle_validate(provider, project_root, &mut output).await?;
+            }
+            Commands::Reexport {
+                format,
+                out,
+                changed_since,
+            } => {
+                let options = commands::reexport::ReexportOptions {
+                    format,
+                    out,
+                    changed_since,
+                };
+                handle_reexport(options, project_root, &mut output).await?;
+            }
+            Commands::Doctor => {
+                handle_doctor(project_root, &mut output).await?;
+            }
+            Commands::Usage { global } => {
+                handle_usage(global, project_root, &mut output).await?;
+            }
+            Commands::Snapshot { action } => {
+                handle_snapshot(action, project_root, &mut output).await?;
+            }
+            Commands::Diff { since_tag } => {
+                handle_diff(since_tag, project_root, &mut output).await?;
+            }
+            Commands::Trash { action } => {
+                handle_trash(action, project_root, &mut output).await?;
+            }
+            Commands::Gc { dry_run } => {
+                handle_gc(dry_run, project_root, &mut output).await?;
+            }
+            Commands::Relink { old_paths } => {
+                handle_relink(old_paths, project_root, &mut output).await?;
+            }
+            Commands::Open {
+                id,
+                latest,
+                gui,
+                reveal,
+            } => {
+                handle_open(id, latest, gui, reveal, project_root, &mut output).await?;
+            }
+            Commands::Gist {
+                id,
+                latest,
+                anonymize,
+                redact,
+                public,
+                paste,
+            } => {
+                let options = commands::gist::GistOptions {
+                    anonymize,
+                    redact,
+                    public,
+                    paste,
+                };
+                handle_gist(id, latest, options, project_root, &mut output).await?;
+            }
+            Commands::Tail { provider, lines } => {
+                handle_tail(provider, lines, project_root, &mut output).await?;
             }
-            Commands::Pull { provider, force } => {
-                handle_pull(provider, force, cli.verbose, project_root, &mut output).await?;
+            Commands::Note { text } => {
+                handle_note(text.join(" "), project_root, &mut output).await?;
             }
         }
 
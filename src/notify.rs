@@ -0,0 +1,190 @@
+//! A short summary of new sessions posted to a Slack or Discord webhook
+//! (`[notify]`), for teams keeping an eye on how agents are used. Titles
+//! come from the history files, which are already redacted.
+use crate::error::{Result, WaylogError};
+use crate::session::catalog::{self, SessionSummary};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Sessions listed in one post; the rest are only counted
+const MAX_LISTED: usize = 10;
+
+/// What wrote the sessions posted about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// `waylog run` ended
+    Run,
+    /// `pull` wrote new messages
+    Pull,
+}
+
+/// Post about the history files `sessions` to `[notify] webhook`, if one is
+/// set and posting after `trigger` is on. Returns whether anything was posted.
+pub async fn sessions_written(
+    project: &Path,
+    trigger: Trigger,
+    sessions: &[PathBuf],
+) -> Result<bool> {
+    let config = &crate::config::get().notify;
+    let Some(webhook) = &config.webhook else {
+        return Ok(false);
+    };
+    let enabled = match trigger {
+        Trigger::Run => config.on_run,
+        Trigger::Pull => config.on_pull,
+    };
+    if !enabled {
+        return Ok(false);
+    }
+
+    let mut summaries = Vec::new();
+    for path in sessions {
+        let Ok(content) = crate::exporter::encryption::read(path).await else {
+            continue;
+        };
+        summaries.push(catalog::summarize(project, path, &content));
+    }
+    if summaries.is_empty() {
+        return Ok(false);
+    }
+
+    let project_name = project
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| project.display().to_string());
+    let text = message(
+        &project_name,
+        trigger,
+        &summaries,
+        config.link.as_deref(),
+        config.price_per_million_tokens,
+    );
+    let payload = serde_json::to_vec(&payload(webhook, &text))?;
+    tokio::time::timeout(
+        Duration::from_secs(config.timeout),
+        crate::hooks::post(webhook, &payload),
+    )
+    .await
+    .map_err(|_| {
+        WaylogError::HookFailed(format!("the webhook took longer than {}s", config.timeout))
+    })??;
+    Ok(true)
+}
+
+/// The body Slack (`text`) or Discord (`content`) expects for `text`
+fn payload(webhook: &str, text: &str) -> Value {
+    let host = reqwest::Url::parse(webhook)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    if host.ends_with("discord.com") || host.ends_with("discordapp.com") {
+        json!({ "content": text })
+    } else {
+        json!({ "text": text })
+    }
+}
+
+/// A heading, then one line per session: title, provider, messages, tokens
+/// (with their cost at `price` per million) and where to read it
+fn message(
+    project: &str,
+    trigger: Trigger,
+    sessions: &[SessionSummary],
+    link: Option<&str>,
+    price: Option<f64>,
+) -> String {
+    let mut text = match (trigger, sessions.len()) {
+        (Trigger::Run, _) => format!("Agent session ended in `{}`", project),
+        (Trigger::Pull, 1) => format!("1 session updated in `{}`", project),
+        (Trigger::Pull, n) => format!("{} sessions updated in `{}`", n, project),
+    };
+
+    for session in sessions.iter().take(MAX_LISTED) {
+        let mut details = vec![
+            session.provider.clone(),
+            format!("{} messages", session.message_count),
+        ];
+        if let Some(tokens) = session.total_tokens {
+            details.push(match price {
+                Some(price) => format!(
+                    "{} tokens, ${:.2}",
+                    tokens,
+                    tokens as f64 * price / 1_000_000.0
+                ),
+                None => format!("{} tokens", tokens),
+            });
+        }
+        let path = session.markdown_path.to_string_lossy().replace('\\', "/");
+        let location = match link {
+            Some(link) => format!("{}/{}", link.trim_end_matches('/'), path),
+            None => format!("`{}`", path),
+        };
+        let title = if session.title.is_empty() {
+            "Untitled session"
+        } else {
+            &session.title
+        };
+        text.push_str(&format!(
+            "\n• {} ({}) {}",
+            title,
+            details.join(", "),
+            location
+        ));
+    }
+    if sessions.len() > MAX_LISTED {
+        text.push_str(&format!("\n…and {} more", sessions.len() - MAX_LISTED));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(title: &str, tokens: Option<u64>) -> SessionSummary {
+        SessionSummary {
+            session_id: "s1".to_string(),
+            provider: "claude".to_string(),
+            title: title.to_string(),
+            started_at: None,
+            updated_at: None,
+            message_count: 12,
+            total_tokens: tokens,
+            tags: Vec::new(),
+            markdown_path: PathBuf::from(".waylog/history/fix-cache.md"),
+        }
+    }
+
+    #[test]
+    fn test_message_lists_each_session() {
+        let text = message(
+            "app",
+            Trigger::Run,
+            &[session("Fix the cache", Some(250_000))],
+            Some("https://github.com/acme/app/blob/main/"),
+            Some(3.0),
+        );
+        assert_eq!(
+            text,
+            "Agent session ended in `app`\n• Fix the cache (claude, 12 messages, 250000 tokens, $0.75) https://github.com/acme/app/blob/main/.waylog/history/fix-cache.md"
+        );
+
+        let sessions: Vec<_> = (0..12).map(|_| session("", None)).collect();
+        let text = message("app", Trigger::Pull, &sessions, None, None);
+        assert!(text.starts_with("12 sessions updated in `app`\n• Untitled session (claude, 12 messages) `.waylog/history/fix-cache.md`"));
+        assert!(text.ends_with("\n…and 2 more"), "{}", text);
+    }
+
+    #[test]
+    fn test_payload_fits_the_service() {
+        assert_eq!(
+            payload("https://discord.com/api/webhooks/1/x", "hi"),
+            json!({ "content": "hi" })
+        );
+        assert_eq!(
+            payload("https://hooks.slack.com/services/T/B/x", "hi"),
+            json!({ "text": "hi" })
+        );
+    }
+}
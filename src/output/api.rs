@@ -0,0 +1,24 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+
+impl Output {
+    /// A schema file written by `api dump-schema --out`
+    pub fn schema_written(&mut self, path: &Path) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal("schema_written", &path.display().to_string());
+        }
+        writeln!(
+            self.stdout(),
+            "{} {}",
+            SUCCESS,
+            t!("wrote", path = path.display().to_string())
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use super::symbols::{FAILURE, SUCCESS};
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+/// Longest prompt shown when a batch run starts
+const PROMPT_PREVIEW_CHARS: usize = 60;
+
+impl Output {
+    /// Print the prompt a batch run is starting with
+    pub fn batch_prompt(&mut self, label: &str, prompt: &str) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let mut preview: String = prompt.chars().take(PROMPT_PREVIEW_CHARS).collect();
+        if prompt.chars().count() > PROMPT_PREVIEW_CHARS {
+            preview.push_str("...");
+        }
+        if self.json() {
+            return self.print_json_internal("batch_prompt", &format!("{}: {}", label, preview));
+        }
+        self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+        write!(self.stdout(), "{}", label)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), " {}", preview)
+    }
+
+    /// Print how a batch run ended and how many sessions it recorded
+    pub fn batch_result(
+        &mut self,
+        label: &str,
+        code: Option<i32>,
+        synced: usize,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let ok = code == Some(0);
+        let status = match code {
+            Some(0) => t!("batch-done"),
+            Some(code) => t!("batch-exited", code = code),
+            None => t!("batch-killed"),
+        };
+        let message = t!(
+            "batch-result",
+            label = label,
+            status = status,
+            count = synced
+        );
+        if self.json() {
+            return self.print_json_internal("batch_result", &message);
+        }
+        let (symbol, color) = if ok {
+            (SUCCESS, Color::Green)
+        } else {
+            (FAILURE, Color::Red)
+        };
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(color)))?;
+        write!(self.stdout(), "  {} ", symbol)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print the batch summary
+    pub fn batch_summary(&mut self, succeeded: usize, failed: usize) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!("batch-summary", succeeded = succeeded, failed = failed);
+        if self.json() {
+            return self.print_json_internal("batch_summary", &message);
+        }
+        writeln!(self.stdout(), "\n{}", message)
+    }
+}
@@ -0,0 +1,56 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use clap_complete::Shell;
+use std::io::{self, Write};
+use std::path::Path;
+
+impl Output {
+    /// Ask before installing the completion script (interactive, always shown)
+    pub fn completions_prompt(&mut self, shell: Shell, path: &Path) -> io::Result<()> {
+        let (exists, shell, path) = (path.exists(), shell.to_string(), path.display().to_string());
+        let question = if exists {
+            t!("completions-replace", shell = shell, path = path)
+        } else {
+            t!("completions-install", shell = shell, path = path)
+        };
+        writeln!(self.stdout(), "{}", question)?;
+        Ok(())
+    }
+
+    /// Print the installed script and any startup file lines the shell needs
+    pub fn completions_installed(
+        &mut self,
+        path: &Path,
+        setup: Option<&(&'static str, String)>,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal("completions_installed", &path.display().to_string());
+        }
+
+        writeln!(
+            self.stdout(),
+            "{} {}",
+            SUCCESS,
+            t!("completions-installed", path = path.display().to_string())
+        )?;
+        match setup {
+            Some((rc_file, lines)) => {
+                writeln!(
+                    self.stdout(),
+                    "\n{}\n",
+                    t!("completions-add-to", file = *rc_file)
+                )?;
+                for line in lines.lines() {
+                    writeln!(self.stdout(), "    {}", line)?;
+                }
+                writeln!(self.stdout(), "\n{}", t!("completions-then-new-shell"))?;
+            }
+            None => writeln!(self.stdout(), "{}", t!("completions-new-shell"))?,
+        }
+        Ok(())
+    }
+}
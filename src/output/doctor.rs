@@ -0,0 +1,69 @@
+use super::symbols::{SKIPPED, SUCCESS, WARNING};
+use super::Output;
+use crate::i18n::t;
+use crate::providers::telemetry::Counts;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print an installed provider and how many sessions it has for the project
+    pub fn doctor_provider(&mut self, provider: &str, sessions: usize) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!("doctor-installed", provider = provider, count = sessions);
+        if self.json() {
+            return self.print_json_internal("doctor_provider", &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(self.stdout(), "{} ", SUCCESS)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print a provider that is not installed (dim)
+    pub fn doctor_not_installed(&mut self, provider: &str) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!("doctor-not-installed", provider = provider);
+        if self.json() {
+            return self.print_json_internal("doctor_not_installed", &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_intense(true))?;
+        writeln!(self.stdout(), "{} {}", SKIPPED, message)?;
+        self.stdout().reset()
+    }
+
+    /// Warn about provider events the parsers skipped since the last upgrade.
+    /// Shown even with --quiet, since it means history may be incomplete.
+    pub fn doctor_unrecognized_events(
+        &mut self,
+        provider: &str,
+        unknown: &Counts,
+    ) -> io::Result<()> {
+        let total: usize = unknown.values().sum();
+        let message = t!("doctor-unrecognized", provider = provider, count = total);
+        let types = unknown
+            .iter()
+            .map(|(event_type, count)| format!("{} ({}x)", event_type, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if self.json() {
+            return self
+                .print_json_internal("doctor_unrecognized", &format!("{} [{}]", message, types));
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        writeln!(self.stdout(), "  {} {}", WARNING, message)?;
+        self.stdout().reset()?;
+        writeln!(
+            self.stdout(),
+            "    {}",
+            t!("doctor-event-types", types = types)
+        )
+    }
+}
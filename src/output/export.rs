@@ -0,0 +1,97 @@
+use super::Output;
+use crate::exporter::sqlite::Backfill;
+use crate::i18n::t;
+use crate::utils::string::format_duration;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// A backfill without progress for this long may have been stopped
+const STALLED_AFTER: Duration = Duration::from_secs(60);
+
+impl Output {
+    /// Print the result of `waylog export` (to stderr, since stdout may carry the data)
+    pub fn export_summary(
+        &mut self,
+        sessions: usize,
+        messages: usize,
+        out: Option<&Path>,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+
+        let summary = match out {
+            Some(path) => t!(
+                "export-summary-to",
+                messages = messages,
+                sessions = sessions,
+                path = path.display().to_string()
+            ),
+            None => t!("export-summary", messages = messages, sessions = sessions),
+        };
+        match out {
+            Some(_) if self.json() => self.print_json_internal("export", &summary),
+            // JSON status lines would be mixed into the exported data on stdout
+            None if self.json() => Ok(()),
+            _ => writeln!(self.stderr(), "{}", summary),
+        }
+    }
+
+    /// Say that `export --background` went on in another process
+    pub fn export_backgrounded(&mut self, database: &Path) -> io::Result<()> {
+        let message = t!("export-background", path = database.display().to_string());
+        self.info(message)
+    }
+
+    /// Print how far the last SQLite backfill got (`export --status`)
+    pub fn backfill_status(&mut self, status: Option<&Backfill>) -> io::Result<()> {
+        let Some(status) = status else {
+            return self.info(t!("export-status-none"));
+        };
+        if self.json() {
+            let json = serde_json::to_string(status).map_err(io::Error::other)?;
+            return writeln!(self.stdout(), "{}", json);
+        }
+
+        let path = status.database.display().to_string();
+        let took = (status.updated_at - status.started_at)
+            .to_std()
+            .unwrap_or_default();
+        let whole_seconds = |d: Duration| format_duration(Duration::from_secs(d.as_secs()));
+        let mut message = match (&status.error, status.finished) {
+            (Some(error), _) => t!(
+                "export-status-failed",
+                path = path,
+                written = status.written,
+                total = status.total,
+                error = error.as_str()
+            ),
+            (None, true) => t!(
+                "export-status-done",
+                path = path,
+                written = status.written,
+                took = whole_seconds(took)
+            ),
+            (None, false) => t!(
+                "export-status-running",
+                path = path,
+                written = status.written,
+                total = status.total,
+                percent = (status.written * 100)
+                    .checked_div(status.total)
+                    .unwrap_or(100)
+            ),
+        };
+        if let Some(left) = status.eta() {
+            message.push_str(&t!("export-status-eta", left = whole_seconds(left)));
+        }
+        let idle = (chrono::Utc::now() - status.updated_at)
+            .to_std()
+            .unwrap_or_default();
+        if !status.finished && idle >= STALLED_AFTER {
+            message.push_str(&t!("export-status-stalled", idle = whole_seconds(idle)));
+        }
+        writeln!(self.stdout(), "{}", message)
+    }
+}
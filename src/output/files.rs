@@ -0,0 +1,54 @@
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print the header for `waylog files`
+    pub fn files_header(&mut self, markdown: &std::path::Path, count: usize) -> io::Result<()> {
+        if !self.quiet() {
+            let name = markdown.file_name().unwrap_or_default().to_string_lossy();
+            if self.json() {
+                self.print_json_internal("files", &format!("{}: {} files", name, count))?;
+            } else if count == 0 {
+                writeln!(self.stdout(), "{}", t!("files-none", name = name))?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{}\n",
+                    t!("files-header", count = count, name = name)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print one file with its read/edit counts (edited files in yellow)
+    pub fn file_access(&mut self, path: &str, reads: usize, edits: usize) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    "file",
+                    &format!("{}: {} reads, {} edits", path, reads, edits),
+                )?;
+            } else {
+                if edits > 0 {
+                    self.stdout()
+                        .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+                }
+                writeln!(
+                    self.stdout(),
+                    "  {}",
+                    t!(
+                        "files-access",
+                        reads = format!("{:>3}", reads),
+                        edits = format!("{:>3}", edits),
+                        path = path
+                    )
+                )?;
+                self.stdout().reset()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use termcolor::{ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a removed (or, in a dry run, removable) attachment blob (dim)
+    pub fn gc_removed(&mut self, blob: &std::path::Path, dry_run: bool) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    if dry_run {
+                        "gc_would_remove"
+                    } else {
+                        "gc_removed"
+                    },
+                    &blob.display().to_string(),
+                )?;
+            } else {
+                self.stdout()
+                    .set_color(ColorSpec::new().set_intense(true))?;
+                let blob = blob.display().to_string();
+                let message = if dry_run {
+                    t!("gc-would-remove", path = blob)
+                } else {
+                    t!("gc-removed", path = blob)
+                };
+                writeln!(self.stdout(), "  {}", message)?;
+                self.stdout().reset()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print garbage collection summary
+    pub fn gc_summary(
+        &mut self,
+        removed: usize,
+        freed_bytes: u64,
+        kept: usize,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                let verb = if dry_run {
+                    "would be removed"
+                } else {
+                    "removed"
+                };
+                self.print_json_internal(
+                    "gc_summary",
+                    &format!(
+                        "{} blobs {} ({} bytes), {} kept",
+                        removed, verb, freed_bytes, kept
+                    ),
+                )?;
+            } else {
+                let message = if dry_run {
+                    t!(
+                        "gc-summary-dry-run",
+                        removed = removed,
+                        bytes = freed_bytes,
+                        kept = kept
+                    )
+                } else {
+                    t!(
+                        "gc-summary",
+                        removed = removed,
+                        bytes = freed_bytes,
+                        kept = kept
+                    )
+                };
+                writeln!(self.stdout(), "{} {}", SUCCESS, message)?;
+            }
+        }
+        Ok(())
+    }
+}
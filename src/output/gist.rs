@@ -0,0 +1,14 @@
+use super::Output;
+use std::io::{self, Write};
+
+impl Output {
+    /// Print where a shared session can be read. The URL is what the
+    /// command is run for, so `--quiet` prints it too.
+    pub fn shared(&mut self, url: &str) -> io::Result<()> {
+        if self.json() {
+            self.print_json_internal("shared", url)
+        } else {
+            writeln!(self.stdout(), "{}", url)
+        }
+    }
+}
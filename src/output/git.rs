@@ -0,0 +1,25 @@
+use super::Output;
+use crate::git::CommitOutcome;
+use crate::i18n::t;
+use std::io;
+
+impl Output {
+    /// Tell the user what became of the auto-commit of the history
+    pub fn history_commit(&mut self, outcome: &CommitOutcome) -> io::Result<()> {
+        match outcome {
+            CommitOutcome::Committed { hash, subject } => self.success(t!(
+                "git-committed",
+                hash = hash.as_str(),
+                subject = subject.as_str()
+            )),
+            CommitOutcome::Ignored => self.warn(t!("git-ignored")),
+            CommitOutcome::Dirty => self.info(t!("git-dirty")),
+            CommitOutcome::NotARepository | CommitOutcome::NothingToCommit => Ok(()),
+        }
+    }
+
+    /// Warn that the history could not be committed
+    pub fn history_commit_failed(&mut self, error: &impl std::fmt::Display) -> io::Result<()> {
+        self.warn(t!("git-commit-failed", error = error.to_string()))
+    }
+}
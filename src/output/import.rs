@@ -0,0 +1,99 @@
+use super::symbols::{SKIPPED, SPARKLES, SUCCESS};
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print import start message
+    pub fn import_start(
+        &mut self,
+        file: &std::path::Path,
+        format: &str,
+        count: usize,
+    ) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    "import_start",
+                    &format!("{} ({}): {} conversations", file.display(), format, count),
+                )?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{}",
+                    t!(
+                        "import-start",
+                        count = count,
+                        file = file.display().to_string(),
+                        format = format
+                    )
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print imported session (green)
+    pub fn imported(&mut self, filename: &str, messages: usize) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    "imported",
+                    &format!("{}: {} messages", filename, messages),
+                )?;
+            } else {
+                self.stdout()
+                    .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+                writeln!(
+                    self.stdout(),
+                    "  {} {}",
+                    SUCCESS,
+                    t!("import-imported", file = filename, count = messages)
+                )?;
+                self.stdout().reset()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print already-imported session (dim)
+    pub fn import_skipped(&mut self, session_id: &str) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal("import_skipped", session_id)?;
+            } else {
+                self.stdout()
+                    .set_color(ColorSpec::new().set_intense(true))?;
+                writeln!(
+                    self.stdout(),
+                    "  {} {}",
+                    SKIPPED,
+                    t!("import-skipped", id = session_id)
+                )?;
+                self.stdout().reset()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print import summary
+    pub fn import_summary(&mut self, imported: usize, skipped: usize) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    "import_summary",
+                    &format!("{} imported, {} skipped", imported, skipped),
+                )?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "\n{}{}",
+                    SPARKLES,
+                    t!("import-complete", imported = imported, skipped = skipped)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
+use super::symbols::{SKIPPED, SPARKLES, SUCCESS, SYNCED, WARNING};
 use super::Output;
+use crate::i18n::t;
 use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
 
 impl Output {
     /// Print found tracking message
@@ -10,8 +14,8 @@ impl Output {
             } else {
                 writeln!(
                     self.stdout(),
-                    "Found existing tracking at: {}",
-                    path.display()
+                    "{}",
+                    t!("init-found-tracking", path = path.display().to_string())
                 )?;
             }
         }
@@ -20,23 +24,142 @@ impl Output {
 
     /// Print not initialized message (interactive, always shown)
     pub fn not_initialized(&mut self) -> io::Result<()> {
-        writeln!(self.stdout(), "Not initialized.")?;
+        writeln!(self.stdout(), "{}", t!("init-not-initialized"))?;
         Ok(())
     }
 
     /// Print initialization prompt (interactive, always shown)
     pub fn init_prompt(&mut self, path: &std::path::Path) -> io::Result<()> {
+        writeln!(self.stdout(), "{}", t!("init-prompt"))?;
         writeln!(
             self.stdout(),
-            "Start tracking AI chat history in this directory?"
+            "{}",
+            t!("init-prompt-path", path = path.display().to_string())
         )?;
-        writeln!(self.stdout(), "Path: {}", path.display())?;
         Ok(())
     }
 
+    /// Warn that the directory about to be tracked is the home directory
+    /// (interactive, always shown)
+    pub fn home_directory_warning(&mut self, path: &Path) -> io::Result<()> {
+        self.stderr()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        writeln!(
+            self.stderr(),
+            "{} {}",
+            WARNING,
+            t!("init-home-directory", path = path.display().to_string())
+        )?;
+        self.stderr().reset()
+    }
+
     /// Print aborted message (interactive, always shown)
     pub fn aborted(&mut self) -> io::Result<()> {
-        writeln!(self.stdout(), "Aborted.")?;
+        writeln!(self.stdout(), "{}", t!("aborted"))?;
         Ok(())
     }
 }
+
+impl Output {
+    /// Print what `waylog init` found for a provider
+    pub fn init_probe(&mut self, probe: &crate::commands::init::ProviderProbe) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = if probe.installed {
+            t!(
+                "doctor-installed",
+                provider = probe.name,
+                count = probe.sessions
+            )
+        } else {
+            t!("doctor-not-installed", provider = probe.name)
+        };
+        if self.json() {
+            return self.print_json_internal("init_probe", &message);
+        }
+
+        let (symbol, color) = match (probe.installed, probe.sessions) {
+            (true, 0) => (SUCCESS, Some(Color::Green)),
+            (true, _) => (SYNCED, Some(Color::Cyan)),
+            (false, _) => (SKIPPED, None),
+        };
+        let mut spec = ColorSpec::new();
+        spec.set_fg(color).set_intense(color.is_none());
+        self.stdout().set_color(&spec)?;
+        write!(self.stdout(), "  {} ", symbol)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print the starter config that was written
+    pub fn init_config_written(&mut self, path: &Path, enabled: &[&str]) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let providers = if enabled.is_empty() {
+            t!("init-all-providers")
+        } else {
+            enabled.join(", ")
+        };
+        let message = t!(
+            "init-config-written",
+            path = path.display().to_string(),
+            providers = providers
+        );
+        if self.json() {
+            return self.print_json_internal("init_config", &message);
+        }
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print that an existing config was left alone
+    pub fn init_config_kept(&mut self, path: &Path) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!("init-config-kept", path = path.display().to_string());
+        if self.json() {
+            return self.print_json_internal("init_config", &message);
+        }
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Greet a first run in a directory without config
+    pub fn welcome(&mut self) -> io::Result<()> {
+        if self.quiet() || self.json() {
+            return Ok(());
+        }
+        writeln!(self.stdout(), "{} {}", SPARKLES, t!("init-welcome"))
+    }
+
+    /// Ask one guided setup question (interactive, always shown)
+    pub fn setup_question(&mut self, question: &str) -> io::Result<()> {
+        writeln!(self.stdout(), "{}", question)
+    }
+
+    /// Print that the project's .gitignore was changed to ignore `entries`
+    pub fn gitignore_updated(&mut self, path: &Path, entries: &[String]) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!(
+            "init-gitignore-updated",
+            path = path.display().to_string(),
+            entries = entries.join(", ")
+        );
+        if self.json() {
+            return self.print_json_internal("init_gitignore", &message);
+        }
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Ask whether to run the first pull (interactive, always shown)
+    pub fn init_pull_prompt(&mut self, sessions: usize) -> io::Result<()> {
+        writeln!(
+            self.stdout(),
+            "{}",
+            t!("init-pull-prompt", count = sessions)
+        )
+    }
+}
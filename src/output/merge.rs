@@ -0,0 +1,41 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+
+impl Output {
+    /// Print merge result
+    pub fn merged(
+        &mut self,
+        path: &std::path::Path,
+        sessions: usize,
+        messages: usize,
+    ) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    "merged",
+                    &format!(
+                        "{}: {} sessions, {} messages",
+                        path.display(),
+                        sessions,
+                        messages
+                    ),
+                )?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{} {}",
+                    SUCCESS,
+                    t!(
+                        "merged",
+                        sessions = sessions,
+                        messages = messages,
+                        path = path.display().to_string()
+                    )
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,37 +1,130 @@
+use crate::i18n::t;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::io::{self, IsTerminal, Write};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+pub mod api;
+pub mod batch;
+pub mod completions;
+pub mod doctor;
+pub mod export;
+pub mod files;
+pub mod gc;
+pub mod gist;
+pub mod git;
+pub mod import;
 pub mod init;
+pub mod merge;
+pub mod note;
+pub mod open;
 pub mod pull;
+pub mod redact;
+pub mod reexport;
+pub mod related;
+pub mod relink;
+pub mod repair;
 pub mod run;
+pub mod scan_secrets;
+pub mod serve;
+pub mod site;
+pub mod snapshot;
+mod stream;
+pub mod symbols;
+pub mod tail;
+pub mod trash;
+pub mod usage;
+pub mod validate;
+
+pub(crate) use stream::set_raw_terminal;
+use stream::Stream;
+use symbols::{FAILURE, SUCCESS, WARNING};
+
+/// When output is colored (`--color`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Color a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether a stream that is (or isn't) a terminal gets colors
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        }
+    }
+
+    fn choice(self, terminal: bool) -> ColorChoice {
+        match self {
+            ColorMode::Always => ColorChoice::Always,
+            // termcolor's Auto also leaves out colors where TERM=dumb
+            _ if self.enabled(terminal) => ColorChoice::Auto,
+            _ => ColorChoice::Never,
+        }
+    }
+}
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template(
+            "{spinner:.green} {prefix:.bold} [{bar:30.cyan/blue}] {pos}/{len} (ETA {eta}) {wide_msg}",
+        )
+        .unwrap()
+        .progress_chars("=> ")
+}
 
 /// Output handler for user-facing messages
 /// Uses Write trait for flexibility and testability
 pub struct Output {
-    stdout: StandardStream,
-    stderr: StandardStream,
+    stdout: Stream,
+    stderr: Stream,
     quiet: bool,
     json: bool,
+    color: ColorMode,
+    /// JSON events are held back for a command that prints one document
+    /// instead; warnings and errors still go to stderr
+    hold_events: bool,
 }
 
 impl Output {
     /// Create a new Output instance
-    pub fn new(quiet: bool, json: bool) -> Self {
-        let color_choice = if std::io::stdout().is_terminal() {
-            ColorChoice::Auto
-        } else {
-            ColorChoice::Never
-        };
+    pub fn new(quiet: bool, json: bool, color: ColorMode) -> Self {
+        symbols::enable_utf8_console();
 
         Self {
-            stdout: StandardStream::stdout(color_choice),
-            stderr: StandardStream::stderr(color_choice),
+            stdout: Self::stream(
+                StandardStream::stdout,
+                std::io::stdout().is_terminal(),
+                color,
+            ),
+            stderr: Self::stream(
+                StandardStream::stderr,
+                std::io::stderr().is_terminal(),
+                color,
+            ),
             quiet,
             json,
+            color,
+            hold_events: false,
         }
     }
 
+    fn stream(open: fn(ColorChoice) -> StandardStream, terminal: bool, color: ColorMode) -> Stream {
+        Stream::new(open(color.choice(terminal)), terminal)
+    }
+
+    /// Another handle with the same settings, for a task that prints on its own
+    pub fn new_handle(&self) -> Self {
+        Self::new(self.quiet, self.json, self.color)
+    }
+
     // ========== Basic Output Methods ==========
 
     /// Print an info message
@@ -56,7 +149,7 @@ impl Output {
             } else {
                 self.stdout
                     .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-                writeln!(self.stdout, "✓ {}", msg.as_ref())?;
+                writeln!(self.stdout, "{} {}", SUCCESS, msg.as_ref())?;
                 self.stdout.reset()?;
             }
         }
@@ -70,14 +163,13 @@ impl Output {
         } else {
             self.stderr
                 .set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
-            writeln!(self.stderr, "✗ {}", msg.as_ref())?;
+            writeln!(self.stderr, "{} {}", FAILURE, msg.as_ref())?;
             self.stderr.reset()?;
         }
         Ok(())
     }
 
     /// Print a warning message (yellow)
-    #[allow(dead_code)]
     pub fn warn(&mut self, msg: impl AsRef<str>) -> io::Result<()> {
         if !self.quiet {
             if self.json {
@@ -85,32 +177,71 @@ impl Output {
             } else {
                 self.stderr
                     .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
-                writeln!(self.stderr, "⚠ {}", msg.as_ref())?;
+                writeln!(self.stderr, "{} {}", WARNING, msg.as_ref())?;
                 self.stderr.reset()?;
             }
         }
         Ok(())
     }
 
+    // ========== Shared Messages ==========
+
+    /// Warn that the `[notify]` webhook could not be posted to
+    pub fn webhook_failed(&mut self, error: &impl std::fmt::Display) -> io::Result<()> {
+        self.warn(t!("webhook-failed", error = error.to_string()))
+    }
+
+    /// Tell the user this waylog waits for the one holding the project lock
+    pub fn waiting_for_lock(&mut self, holder: Option<&str>) -> io::Result<()> {
+        let holder = holder.map_or_else(|| t!("lock-another-process"), String::from);
+        self.warn(t!("lock-waiting", holder = holder))
+    }
+
     // ========== Progress Bar ==========
 
-    /// Create a progress bar (returns None if quiet or json mode)
-    #[allow(dead_code)]
-    pub fn create_progress(&self, total: u64, message: &str) -> Option<ProgressBar> {
-        if self.quiet || self.json {
+    /// Create a progress bar of `total` steps on stderr, labelled `prefix`,
+    /// with an ETA (returns None if quiet or json mode, or stderr is not a terminal)
+    pub fn create_progress(&self, total: u64, prefix: &str) -> Option<ProgressBar> {
+        if !self.shows_progress() {
             return None;
         }
 
         let pb = ProgressBar::new(total);
+        pb.set_style(bar_style());
+        pb.set_prefix(prefix.to_string());
+        Some(pb)
+    }
+
+    /// Turn a spinner from [`create_spinner`](Self::create_spinner) into a bar
+    /// of `total` steps, once the length of the work is known
+    pub fn spinner_to_bar(spinner: &ProgressBar, total: u64) {
+        spinner.set_style(bar_style());
+        spinner.set_length(total);
+        spinner.set_position(0);
+    }
+
+    /// A spinner on stderr labelled `prefix`, for work of unknown length
+    /// (None when [`create_progress`](Self::create_progress) would be)
+    pub fn create_spinner(&self, prefix: &str) -> Option<ProgressBar> {
+        if !self.shows_progress() {
+            return None;
+        }
+
+        let pb = ProgressBar::new_spinner();
         pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] {msg}")
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {prefix:.bold} {wide_msg}")
                 .unwrap(),
         );
-        pb.set_message(message.to_string());
+        pb.set_prefix(prefix.to_string());
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
         Some(pb)
     }
 
+    fn shows_progress(&self) -> bool {
+        !self.quiet && !self.json && self.stderr.is_terminal()
+    }
+
     // ========== JSON Output ==========
 
     fn print_json(&mut self, level: &str, message: &str) -> io::Result<()> {
@@ -119,17 +250,27 @@ impl Output {
             "message": message,
             "timestamp": chrono::Utc::now().to_rfc3339(),
         });
-        writeln!(self.stdout, "{}", json)?;
+        match level {
+            _ if !self.hold_events => writeln!(self.stdout, "{}", json)?,
+            "warn" | "error" => writeln!(self.stderr, "{}", json)?,
+            _ => {}
+        }
         Ok(())
     }
 
+    /// With `--output json`, print only the document the command prints at
+    /// the end on stdout, holding back the events
+    pub fn hold_json_events(&mut self) {
+        self.hold_events = self.json;
+    }
+
     // ========== Internal helpers for submodules ==========
 
-    pub(crate) fn stdout(&mut self) -> &mut StandardStream {
+    pub(crate) fn stdout(&mut self) -> &mut Stream {
         &mut self.stdout
     }
 
-    pub(crate) fn stderr(&mut self) -> &mut StandardStream {
+    pub(crate) fn stderr(&mut self) -> &mut Stream {
         &mut self.stderr
     }
 
@@ -141,7 +282,47 @@ impl Output {
         self.json
     }
 
+    pub(crate) fn color(&self) -> ColorMode {
+        self.color
+    }
+
     pub(crate) fn print_json_internal(&mut self, level: &str, message: &str) -> io::Result<()> {
         self.print_json(level, message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(quiet: bool, json: bool, terminal: bool) -> Output {
+        Output {
+            stdout: Output::stream(StandardStream::stdout, terminal, ColorMode::Never),
+            stderr: Output::stream(StandardStream::stderr, terminal, ColorMode::Never),
+            quiet,
+            json,
+            color: ColorMode::Never,
+            hold_events: false,
+        }
+    }
+
+    #[test]
+    fn test_progress_only_on_a_terminal() {
+        assert!(output(false, false, true)
+            .create_progress(3, "claude")
+            .is_some());
+        assert!(output(false, false, true)
+            .create_spinner("claude")
+            .is_some());
+
+        for (quiet, json, terminal) in [
+            (false, false, false),
+            (true, false, true),
+            (false, true, true),
+        ] {
+            let output = output(quiet, json, terminal);
+            assert!(output.create_progress(3, "claude").is_none());
+            assert!(output.create_spinner("claude").is_none());
+        }
+    }
+}
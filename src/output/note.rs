@@ -0,0 +1,22 @@
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+
+impl Output {
+    /// Tell the user which history file the note went to
+    pub fn note_added(&mut self, markdown_path: &Path) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            self.print_json_internal("note_added", &markdown_path.display().to_string())
+        } else {
+            writeln!(
+                self.stdout(),
+                "{}",
+                t!("note-added", path = markdown_path.display().to_string())
+            )
+        }
+    }
+}
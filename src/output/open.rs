@@ -0,0 +1,37 @@
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+
+impl Output {
+    /// Print the session file being opened
+    pub fn opening(&mut self, path: &std::path::Path) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal("opening", &path.display().to_string())?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{}",
+                    t!("opening", path = path.display().to_string())
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the session file being revealed
+    pub fn revealing(&mut self, path: &std::path::Path) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal("revealing", &path.display().to_string())?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{}",
+                    t!("revealing", path = path.display().to_string())
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,32 @@
+use super::symbols::{FAILURE, SKIPPED, SPARKLES, SUCCESS, SYNCED};
 use super::Output;
-use console::Emoji;
+use crate::commands::pull::{ProviderCounts, ProviderTimings};
+use crate::i18n::t;
+use crate::session::report::PullDocument;
+use crate::synchronizer::Phase;
+use crate::utils::string::format_duration;
+use comfy_table::{presets::UTF8_FULL_CONDENSED, CellAlignment, Table};
 use std::io::{self, Write};
 use termcolor::{Color, ColorSpec, WriteColor};
 
+/// Sessions listed under each provider by `pull --timings`
+const SLOWEST_SESSIONS: usize = 10;
+
 impl Output {
+    /// The label of `provider`'s progress bar while it scans for sessions
+    /// (`phase` None) or syncs one
+    pub fn pull_progress_label(provider: &str, phase: Option<Phase>) -> String {
+        match phase {
+            None => t!("pull-progress-scanning", provider = provider),
+            Some(Phase::Parse) => t!("pull-progress-parsing", provider = provider),
+            Some(Phase::Export) => t!("pull-progress-exporting", provider = provider),
+        }
+    }
+
     /// Print unknown provider error
     pub fn unknown_provider(&mut self, name: &str) -> io::Result<()> {
-        self.error(format!("'{}' is not a recognized provider.", name))?;
-        writeln!(self.stderr(), "\nAvailable providers:")?;
+        self.error(t!("unknown-provider", name = name))?;
+        writeln!(self.stderr(), "\n{}", t!("available-providers"))?;
         for provider in crate::providers::list_providers() {
             writeln!(self.stderr(), "- {}", provider)?;
         }
@@ -27,8 +46,8 @@ impl Output {
             } else {
                 writeln!(
                     self.stdout(),
-                    "Pulling chat history for project: {}",
-                    project_path.display()
+                    "{}",
+                    t!("pull-start", path = project_path.display().to_string())
                 )?;
             }
         }
@@ -44,7 +63,11 @@ impl Output {
                     &format!("{}: {} sessions", provider, count),
                 )?;
             } else {
-                writeln!(self.stdout(), "\n[{}] Found {} sessions", provider, count)?;
+                writeln!(
+                    self.stdout(),
+                    "\n{}",
+                    t!("pull-provider-header", provider = provider, count = count)
+                )?;
             }
         }
         Ok(())
@@ -63,9 +86,9 @@ impl Output {
                     .set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
                 writeln!(
                     self.stdout(),
-                    "  ↑ Synced: {} ({} new messages)",
-                    filename,
-                    new_messages
+                    "  {} {}",
+                    SYNCED,
+                    t!("pull-synced", file = filename, count = new_messages)
                 )?;
                 self.stdout().reset()?;
             }
@@ -73,6 +96,81 @@ impl Output {
         Ok(())
     }
 
+    /// Warn about lines of a session file that could not be read and were skipped
+    pub fn malformed_lines(&mut self, filename: &str, count: usize) -> io::Result<()> {
+        self.warn(t!("pull-malformed-lines", file = filename, count = count))
+    }
+
+    /// Print the file a dry run would write (always shown, it is the point of a dry run)
+    pub fn would_sync(
+        &mut self,
+        filename: &str,
+        markdown_path: &std::path::Path,
+        new_messages: usize,
+        create: bool,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let target = markdown_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        if self.json() {
+            let action = if create { "create" } else { "append to" };
+            self.print_json_internal(
+                "would_sync",
+                &format!(
+                    "{}: {} {} ({} messages)",
+                    filename, action, target, new_messages
+                ),
+            )?;
+        } else {
+            let message = if create {
+                t!(
+                    "pull-would-create",
+                    target = target.as_ref(),
+                    count = new_messages,
+                    file = filename
+                )
+            } else {
+                t!(
+                    "pull-would-append",
+                    target = target.as_ref(),
+                    count = new_messages,
+                    file = filename
+                )
+            };
+            writeln!(self.stdout(), "  {} {}", SYNCED, message)?;
+        }
+        Ok(())
+    }
+
+    /// Print the totals of a dry run
+    pub fn dry_run_summary(
+        &mut self,
+        create: usize,
+        append: usize,
+        messages: usize,
+        uptodate: usize,
+    ) -> io::Result<()> {
+        if !self.quiet() {
+            let text = t!(
+                "pull-dry-run-summary",
+                create = create,
+                append = append,
+                messages = messages,
+                uptodate = uptodate
+            );
+            if self.json() {
+                self.print_json_internal("dry_run_summary", &text)?;
+            } else {
+                writeln!(self.stdout(), "\n{}", text)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Print up-to-date status (green)
     pub fn up_to_date(&mut self, filename: &str, verbose: bool) -> io::Result<()> {
         if !self.quiet() && verbose {
@@ -81,7 +179,12 @@ impl Output {
             } else {
                 self.stdout()
                     .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-                writeln!(self.stdout(), "  ✓ Up to date: {}", filename)?;
+                writeln!(
+                    self.stdout(),
+                    "  {} {}",
+                    SUCCESS,
+                    t!("pull-up-to-date", file = filename)
+                )?;
                 self.stdout().reset()?;
             }
         }
@@ -95,24 +198,30 @@ impl Output {
         } else {
             self.stderr()
                 .set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
-            writeln!(self.stderr(), "  ✗ Failed to sync {}: {}", filename, error)?;
+            writeln!(
+                self.stderr(),
+                "  {} {}",
+                FAILURE,
+                t!("pull-failed", file = filename, error = error)
+            )?;
             self.stderr().reset()?;
         }
         Ok(())
     }
 
     /// Print skipped status (dim)
-    pub fn skipped(&mut self, filename: &str, verbose: bool) -> io::Result<()> {
+    pub fn skipped(&mut self, filename: &str, reason: &str, verbose: bool) -> io::Result<()> {
         if !self.quiet() && verbose {
             if self.json() {
-                self.print_json_internal("skipped", filename)?;
+                self.print_json_internal("skipped", &format!("{}: {}", filename, reason))?;
             } else {
                 self.stdout()
                     .set_color(ColorSpec::new().set_intense(true))?;
                 writeln!(
                     self.stdout(),
-                    "  ⊘ Skipped: {} (empty or invalid session)",
-                    filename
+                    "  {} {}",
+                    SKIPPED,
+                    t!("pull-skipped", file = filename, reason = reason)
                 )?;
                 self.stdout().reset()?;
             }
@@ -120,43 +229,183 @@ impl Output {
         Ok(())
     }
 
-    /// Print summary with emoji
-    pub fn summary(&mut self, synced: usize, uptodate: usize) -> io::Result<()> {
-        if !self.quiet() {
-            if self.json() {
-                self.print_json_internal(
-                    "summary",
-                    &format!("{} synced, {} up to date", synced, uptodate),
-                )?;
+    /// Print what each provider's sessions came to as a table, with totals
+    pub fn pull_table(
+        &mut self,
+        rows: &[ProviderCounts],
+        totals: &ProviderCounts,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal(
+                "summary",
+                &format!(
+                    "{} synced, {} up to date, {} failed",
+                    totals.synced, totals.up_to_date, totals.failed
+                ),
+            );
+        }
+        if !dry_run {
+            self.stdout()
+                .set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
+            writeln!(self.stdout(), "\n{}{}", SPARKLES, t!("pull-complete"))?;
+            self.stdout().reset()?;
+        }
+        if rows.is_empty() {
+            return writeln!(self.stdout(), "{}", t!("pull-no-sessions"));
+        }
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL_CONDENSED).set_header([
+            t!("pull-column-provider"),
+            t!("pull-column-found"),
+            if dry_run {
+                t!("pull-column-would-sync")
+            } else {
+                t!("pull-column-synced")
+            },
+            t!("pull-column-up-to-date"),
+            t!("pull-column-skipped"),
+            t!("pull-column-failed"),
+            t!("pull-column-new-messages"),
+        ]);
+        for row in rows.iter().chain([totals]) {
+            let provider = if std::ptr::eq(row, totals) {
+                t!("pull-total")
             } else {
+                row.provider.clone()
+            };
+            table.add_row([
+                provider,
+                row.found.to_string(),
+                row.synced.to_string(),
+                row.up_to_date.to_string(),
+                row.skipped.to_string(),
+                row.failed.to_string(),
+                row.new_messages.to_string(),
+            ]);
+        }
+        for column in table.column_iter_mut().skip(1) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+        writeln!(self.stdout(), "{}", table)
+    }
+
+    /// Print where the time of each provider's pull went, and its slowest sessions
+    pub fn pull_timings(&mut self, providers: &[ProviderTimings]) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        for timings in providers {
+            let message = t!(
+                "pull-timings",
+                provider = timings.provider.as_str(),
+                scan = format_duration(timings.scan),
+                parse = format_duration(timings.parse),
+                export = format_duration(timings.export),
+                save = format_duration(timings.save)
+            );
+            if self.json() {
+                self.print_json_internal("timings", &message)?;
+                continue;
+            }
+            self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+            writeln!(self.stdout(), "\n{}", message)?;
+            self.stdout().reset()?;
+
+            let mut sessions: Vec<_> = timings.sessions.iter().collect();
+            sessions.sort_by_key(|(_, session)| std::cmp::Reverse(session.total));
+            for (path, session) in sessions.into_iter().take(SLOWEST_SESSIONS) {
                 writeln!(
                     self.stdout(),
-                    "\n{} Pull complete! {} sessions updated, {} up to date.",
-                    Emoji("✨", ""),
-                    synced,
-                    uptodate
+                    "  {:>8}  {}",
+                    format_duration(session.total),
+                    t!(
+                        "pull-timings-session",
+                        file = path.file_name().unwrap_or_default().to_string_lossy(),
+                        parse = format_duration(session.parse),
+                        export = format_duration(session.export)
+                    )
                 )?;
             }
         }
         Ok(())
     }
 
-    /// Print compact summary (non-verbose mode)
-    pub fn summary_compact(&mut self, synced: usize, uptodate: usize) -> io::Result<()> {
-        if !self.quiet() {
-            if synced > 0 {
-                self.stdout()
-                    .set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
-                writeln!(self.stdout(), "  ↑ {} sessions synced", synced)?;
-                self.stdout().reset()?;
-            }
-            if uptodate > 0 {
-                self.stdout()
-                    .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-                writeln!(self.stdout(), "  ✓ {} sessions up to date", uptodate)?;
-                self.stdout().reset()?;
-            }
+    /// Print the header of a project pulled by `pull --all-projects`
+    pub fn project_header(&mut self, project: &std::path::Path, sessions: usize) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal(
+                "project_header",
+                &format!("{}: {} sessions", project.display(), sessions),
+            );
+        }
+        self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(self.stdout(), "\n=== {} ===", project.display())?;
+        self.stdout().reset()?;
+        Ok(())
+    }
+
+    /// A project with sessions but without `.waylog`, left alone by `pull --all-projects`
+    pub fn project_not_initialized(
+        &mut self,
+        project: &std::path::Path,
+        sessions: usize,
+        verbose: bool,
+    ) -> io::Result<()> {
+        self.skipped(
+            &project.display().to_string(),
+            &t!("pull-project-not-initialized", count = sessions),
+            verbose,
+        )
+    }
+
+    /// Print the summary of `pull --all-projects`
+    pub fn all_projects_summary(
+        &mut self,
+        pulled: usize,
+        failed: usize,
+        uninitialized: usize,
+        unmapped: usize,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let mut parts = vec![t!("all-projects-pulled", count = pulled)];
+        if failed > 0 {
+            parts.push(t!("all-projects-failed", count = failed));
+        }
+        if uninitialized > 0 {
+            parts.push(t!("all-projects-uninitialized", count = uninitialized));
         }
+        if unmapped > 0 {
+            parts.push(t!("all-projects-unmapped", count = unmapped));
+        }
+        let message = parts.join(&t!("list-separator"));
+        if self.json() {
+            return self.print_json_internal("all_projects_summary", &message);
+        }
+        writeln!(
+            self.stdout(),
+            "\n{}{}",
+            SPARKLES,
+            t!("all-projects-summary", summary = message)
+        )?;
         Ok(())
     }
+
+    /// Print the outcome of a pull as one JSON document (`--output json` only)
+    pub fn pull_document(&mut self, document: &PullDocument) -> io::Result<()> {
+        if self.quiet() || !self.json() {
+            return Ok(());
+        }
+        let json = serde_json::to_string(document).map_err(io::Error::other)?;
+        writeln!(self.stdout(), "{}", json)
+    }
 }
@@ -0,0 +1,64 @@
+use super::symbols::{SUCCESS, WARNING};
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a history file that had (or, in a dry run, has) secrets (yellow)
+    pub fn redacted(&mut self, filename: &str, dry_run: bool) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal(
+                    if dry_run {
+                        "contains_secrets"
+                    } else {
+                        "redacted"
+                    },
+                    filename,
+                )?;
+            } else {
+                self.stdout()
+                    .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+                let message = if dry_run {
+                    t!("redact-contains-secrets", file = filename)
+                } else {
+                    t!("redact-redacted", file = filename)
+                };
+                writeln!(self.stdout(), "  {} {}", WARNING, message)?;
+                self.stdout().reset()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print redaction summary
+    pub fn redact_summary(
+        &mut self,
+        redacted: usize,
+        total: usize,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                let verb = if dry_run {
+                    "contain secrets"
+                } else {
+                    "redacted"
+                };
+                self.print_json_internal(
+                    "redact_summary",
+                    &format!("{} of {} files {}", redacted, total, verb),
+                )?;
+            } else {
+                let message = if dry_run {
+                    t!("redact-summary-dry-run", redacted = redacted, total = total)
+                } else {
+                    t!("redact-summary", redacted = redacted, total = total)
+                };
+                writeln!(self.stdout(), "{} {}", SUCCESS, message)?;
+            }
+        }
+        Ok(())
+    }
+}
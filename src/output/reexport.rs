@@ -0,0 +1,48 @@
+use super::symbols::{SUCCESS, SYNCED};
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a regenerated page
+    pub fn reexported(&mut self, page: &Path) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal("reexported", &page.display().to_string());
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        write!(self.stdout(), "  {} ", SYNCED)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", page.display())
+    }
+
+    /// Print the result of `waylog reexport`
+    pub fn reexport_summary(
+        &mut self,
+        regenerated: usize,
+        unchanged: usize,
+        out: &Path,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let summary = t!(
+            "reexport-summary",
+            regenerated = regenerated,
+            path = out.display().to_string(),
+            unchanged = unchanged
+        );
+        if self.json() {
+            return self.print_json_internal("reexport_summary", &summary);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        writeln!(self.stdout(), "{} {}", SUCCESS, summary)?;
+        self.stdout().reset()
+    }
+}
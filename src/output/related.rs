@@ -0,0 +1,60 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+
+impl Output {
+    /// Print the header for `waylog related`
+    pub fn related_header(&mut self, markdown: &Path, count: usize) -> io::Result<()> {
+        if !self.quiet() {
+            let name = markdown.file_name().unwrap_or_default().to_string_lossy();
+            if self.json() {
+                self.print_json_internal("related", &format!("{}: {} sessions", name, count))?;
+            } else if count == 0 {
+                writeln!(self.stdout(), "{}", t!("related-none", name = name))?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{}\n",
+                    t!("related-header", count = count, name = name)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a related session with the files it shares
+    pub fn related_session(&mut self, markdown: &Path, shared: &[String]) -> io::Result<()> {
+        if !self.quiet() {
+            let name = markdown.file_name().unwrap_or_default().to_string_lossy();
+            if self.json() {
+                self.print_json_internal(
+                    "related_session",
+                    &format!("{}: {}", name, shared.join(", ")),
+                )?;
+            } else {
+                writeln!(self.stdout(), "  {}", name)?;
+                writeln!(self.stdout(), "    {}", shared.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print where the file index was written
+    pub fn index_written(&mut self, path: &Path) -> io::Result<()> {
+        if !self.quiet() {
+            if self.json() {
+                self.print_json_internal("index_written", &path.display().to_string())?;
+            } else {
+                writeln!(
+                    self.stdout(),
+                    "{} {}",
+                    SUCCESS,
+                    t!("wrote", path = path.display().to_string())
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print that an old project path was registered, with the sessions found under it
+    pub fn relinked(&mut self, old_path: &Path, sessions: usize) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!(
+            "relinked",
+            path = old_path.display().to_string(),
+            count = sessions
+        );
+        if self.json() {
+            return self.print_json_internal("relinked", &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(self.stdout(), "{} ", SUCCESS)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+}
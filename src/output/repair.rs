@@ -0,0 +1,85 @@
+use super::symbols::{SUCCESS, WARNING};
+use super::Output;
+use crate::commands::repair::Problem;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a history file that was (or, in a dry run, would be) repaired (yellow)
+    pub fn repaired(
+        &mut self,
+        path: &Path,
+        problem: Problem,
+        merged: &[PathBuf],
+        added: usize,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let name = |path: &Path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        };
+        let what = match problem {
+            Problem::ConflictMarkers => t!("repair-conflict-markers"),
+            Problem::Duplicates => t!(
+                "repair-merged",
+                files = merged
+                    .iter()
+                    .map(|p| name(p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let message = if dry_run {
+            t!(
+                "repair-would-add",
+                file = name(path),
+                what = what,
+                count = added
+            )
+        } else {
+            t!(
+                "repair-added",
+                file = name(path),
+                what = what,
+                count = added
+            )
+        };
+
+        if self.json() {
+            return self
+                .print_json_internal(if dry_run { "needs_repair" } else { "repaired" }, &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        writeln!(self.stdout(), "  {} {}", WARNING, message)?;
+        self.stdout().reset()
+    }
+
+    /// Print repair summary
+    pub fn repair_summary(
+        &mut self,
+        repaired: usize,
+        total: usize,
+        dry_run: bool,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = if dry_run {
+            t!("repair-summary-dry-run", repaired = repaired, total = total)
+        } else {
+            t!("repair-summary", repaired = repaired, total = total)
+        };
+        if self.json() {
+            return self.print_json_internal("repair_summary", &message);
+        }
+        writeln!(self.stdout(), "{} {}", SUCCESS, message)
+    }
+}
@@ -1,37 +1,151 @@
 use super::Output;
+use crate::i18n::t;
+use crate::providers::base::{ChatMessage, MessageRole};
 use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+/// Longest message preview printed by `--echo-sync`
+const ECHO_PREVIEW_CHARS: usize = 100;
 
 impl Output {
     /// Print missing agent error
     pub fn missing_agent(&mut self) -> io::Result<()> {
-        self.error("Missing required argument <AGENT>")?;
-        writeln!(self.stderr(), "\nUsage: waylog run <AGENT> [ARGS]...\n")?;
-        writeln!(self.stderr(), "Available agents:")?;
-        for provider in crate::providers::list_providers() {
-            writeln!(self.stderr(), "- {}", provider)?;
-        }
-        writeln!(self.stderr(), "\nExample:\n  waylog run claude")?;
+        self.error(t!("run-missing-agent"))?;
+        writeln!(self.stderr(), "\n{}\n", t!("run-usage"))?;
+        writeln!(self.stderr(), "{}", t!("run-available-agents"))?;
+        self.list_agents()?;
+        writeln!(
+            self.stderr(),
+            "\n{}\n  waylog run claude",
+            t!("run-example")
+        )?;
         Ok(())
     }
 
     /// Print unknown agent error
     pub fn unknown_agent(&mut self, name: &str) -> io::Result<()> {
-        self.error(format!("'{}' is not a recognized agent.", name))?;
-        writeln!(self.stderr(), "\nAvailable agents:")?;
+        self.error(t!("run-unknown-agent", name = name))?;
+        writeln!(self.stderr(), "\n{}", t!("run-available-agents"))?;
+        self.list_agents()?;
+        writeln!(self.stderr(), "\n{}", t!("run-did-you-mean-pull"))?;
+        Ok(())
+    }
+
+    /// Tell the user the agent is being stopped because `--timeout` ran out
+    pub fn run_timed_out(&mut self, agent: &str) -> io::Result<()> {
+        self.warn(t!("run-timed-out", agent = agent))
+    }
+
+    /// Warn that live syncing stopped and is retried after `retry_in`
+    pub fn live_sync_degraded(
+        &mut self,
+        error: &impl std::fmt::Display,
+        retry_in: std::time::Duration,
+    ) -> io::Result<()> {
+        self.warn(t!(
+            "run-live-sync-degraded",
+            error = error.to_string(),
+            seconds = retry_in.as_secs()
+        ))
+    }
+
+    /// Tell the user where the agent's terminal is recorded
+    pub fn recording_to(&mut self, path: &std::path::Path) -> io::Result<()> {
+        self.info(t!("run-recording-to", path = path.display().to_string()))
+    }
+
+    /// Tell the user the session's history file was named
+    pub fn session_named(&mut self, path: &std::path::Path) -> io::Result<()> {
+        self.success(t!("run-session-named", path = path.display().to_string()))
+    }
+
+    /// Warn that the session could not be named after `--ask-name`
+    pub fn naming_failed(&mut self, error: &impl std::fmt::Display) -> io::Result<()> {
+        self.warn(t!("run-naming-failed", error = error.to_string()))
+    }
+
+    /// Warn that the commits made during the session could not be listed
+    pub fn commits_not_listed(&mut self, error: &impl std::fmt::Display) -> io::Result<()> {
+        self.warn(t!("run-commits-failed", error = error.to_string()))
+    }
+
+    /// Warn that the terminal can't be recorded without a pseudo-terminal
+    pub fn recording_needs_pty(&mut self) -> io::Result<()> {
+        self.warn(t!("run-recording-needs-pty"))
+    }
+
+    /// Tell the user the final sync continues in the background
+    pub fn final_sync_detached(&mut self, args: &[String]) -> io::Result<()> {
+        let command = crate::utils::string::shell_join(
+            std::iter::once("waylog").chain(args.iter().map(String::as_str)),
+        );
+        self.info(t!("run-final-sync-detached", command = command))
+    }
+
+    /// Print agent not installed error
+    pub fn agent_not_installed(&mut self, command: &str) -> io::Result<()> {
+        self.error(t!("run-agent-not-installed", command = command))?;
+        writeln!(self.stderr(), "{}", t!("run-install-first"))?;
+        Ok(())
+    }
+
+    /// List built-in agents and aliases from config
+    fn list_agents(&mut self) -> io::Result<()> {
         for provider in crate::providers::list_providers() {
             writeln!(self.stderr(), "- {}", provider)?;
         }
-        writeln!(self.stderr(), "\nDid you mean to run 'waylog pull'?")?;
+        for (alias, agent) in &crate::config::get().agents {
+            writeln!(self.stderr(), "- {} ({})", alias, agent.provider)?;
+        }
         Ok(())
     }
 
-    /// Print agent not installed error
-    pub fn agent_not_installed(&mut self, command: &str) -> io::Result<()> {
-        self.error(format!("{} is not installed or not in PATH", command))?;
-        writeln!(
-            self.stderr(),
-            "Please install it first before using waylog."
-        )?;
+    /// Print a one-line preview of each captured message to stderr (`waylog run --echo-sync`)
+    pub fn echo_messages(&mut self, messages: &[ChatMessage]) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+
+        for message in messages {
+            let (role, color) = match message.role {
+                MessageRole::User => ("user", Color::Cyan),
+                MessageRole::Assistant => ("assistant", Color::Green),
+                MessageRole::System => ("system", Color::Yellow),
+            };
+            let preview = echo_preview(&message.content);
+
+            if self.json() {
+                self.print_json_internal("captured", &format!("{}: {}", role, preview))?;
+                continue;
+            }
+
+            self.stderr()
+                .set_color(ColorSpec::new().set_intense(true))?;
+            write!(
+                self.stderr(),
+                "[waylog {}] ",
+                message.timestamp.format("%H:%M:%S")
+            )?;
+            self.stderr()
+                .set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+            write!(self.stderr(), "{}", role)?;
+            self.stderr().reset()?;
+            writeln!(self.stderr(), " {}", preview)?;
+        }
         Ok(())
     }
 }
+
+/// First line of `content`, shortened to `ECHO_PREVIEW_CHARS`
+fn echo_preview(content: &str) -> String {
+    let first_line = content.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let first_line = first_line.trim();
+    if first_line.chars().count() > ECHO_PREVIEW_CHARS {
+        let truncated: String = first_line.chars().take(ECHO_PREVIEW_CHARS).collect();
+        format!("{}...", truncated)
+    } else if content.trim().lines().count() > 1 {
+        format!("{} ...", first_line)
+    } else {
+        first_line.to_string()
+    }
+}
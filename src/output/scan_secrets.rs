@@ -0,0 +1,74 @@
+use super::symbols::{SUCCESS, WARNING};
+use super::Output;
+use crate::exporter::redact::Finding;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a secret `waylog scan-secrets` found, as `file:line` so editors
+    /// can jump to it. Shown even with --quiet, like errors.
+    pub fn secret_found(&mut self, file: &Path, finding: &Finding) -> io::Result<()> {
+        let location = format!("{}:{}", file.display(), finding.line);
+        if self.json() {
+            return self
+                .print_json_internal("secret_found", &format!("{}: {}", location, finding.rule));
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        writeln!(
+            self.stdout(),
+            "  {} {}",
+            WARNING,
+            t!(
+                "scan-found",
+                location = location,
+                rule = finding.rule.as_str(),
+                preview = finding.preview.as_str()
+            )
+        )?;
+        self.stdout().reset()
+    }
+
+    /// Print how many secrets were found (or redacted) in how many files
+    pub fn scan_summary(
+        &mut self,
+        found: usize,
+        affected: usize,
+        total: usize,
+        fixed: bool,
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = match (found, fixed) {
+            (0, _) => t!("scan-clean", total = total),
+            (_, true) => t!(
+                "scan-summary-fixed",
+                found = found,
+                affected = affected,
+                total = total
+            ),
+            (_, false) => t!(
+                "scan-summary",
+                found = found,
+                affected = affected,
+                total = total
+            ),
+        };
+        if self.json() {
+            return self.print_json_internal("scan_summary", &message);
+        }
+        if found == 0 {
+            self.stdout()
+                .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+            writeln!(self.stdout(), "{} {}", SUCCESS, message)?;
+            return self.stdout().reset();
+        }
+        writeln!(self.stdout())?;
+        self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(self.stdout(), "{}", message)?;
+        self.stdout().reset()
+    }
+}
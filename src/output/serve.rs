@@ -0,0 +1,21 @@
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+
+impl Output {
+    /// Tell the user where `waylog serve` listens
+    pub fn serving(&mut self, addr: &SocketAddr) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let url = format!("http://{}", addr);
+        if self.json() {
+            self.print_json_internal("serving", &url)?;
+        } else {
+            writeln!(self.stdout(), "{}", t!("serving", url = url))?;
+        }
+        // Scripts wait for this line before they connect
+        self.stdout().flush()
+    }
+}
@@ -0,0 +1,28 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print the result of `waylog site`
+    pub fn site_built(&mut self, out: &Path, sessions: usize, pages: usize) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let summary = t!(
+            "site-built",
+            pages = pages,
+            sessions = sessions,
+            path = out.display().to_string()
+        );
+        if self.json() {
+            return self.print_json_internal("site_built", &summary);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        writeln!(self.stdout(), "{} {}", SUCCESS, summary)?;
+        self.stdout().reset()
+    }
+}
@@ -0,0 +1,138 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::exporter::markdown::formatter::format_datetime;
+use crate::i18n::t;
+use crate::session::snapshot::{ChangeKind, SessionChange, Snapshot};
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a newly recorded snapshot
+    pub fn snapshot_created(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = t!(
+            "snapshot-created",
+            name = snapshot.name.as_str(),
+            sessions = snapshot.sessions.len(),
+            messages = snapshot.message_count()
+        );
+        if self.json() {
+            return self.print_json_internal("snapshot_created", &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(self.stdout(), "{} ", SUCCESS)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print a recorded snapshot with when it was taken
+    pub fn snapshot_entry(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let created = format_datetime(&snapshot.created_at);
+        let summary = t!(
+            "snapshot-entry",
+            name = snapshot.name.as_str(),
+            sessions = snapshot.sessions.len(),
+            messages = snapshot.message_count()
+        );
+        if self.json() {
+            return self.print_json_internal("snapshot_entry", &format!("{} {}", created, summary));
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_intense(true))?;
+        write!(self.stdout(), "  {}  ", created)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", summary)
+    }
+
+    /// Tell the user there are no snapshots yet
+    pub fn no_snapshots(&mut self) -> io::Result<()> {
+        self.info(t!("snapshot-none"))
+    }
+
+    /// Print how a session changed between two snapshots
+    pub fn diff_change(&mut self, change: &SessionChange) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let (marker, color, detail) = match change.kind {
+            ChangeKind::New => ("+", Color::Green, t!("diff-new", messages = change.after)),
+            ChangeKind::Appended => (
+                "~",
+                Color::Yellow,
+                t!(
+                    "diff-appended",
+                    added = change.added(),
+                    before = change.before,
+                    after = change.after
+                ),
+            ),
+            ChangeKind::Rewritten => (
+                "!",
+                Color::Magenta,
+                t!("diff-rewritten", messages = change.after),
+            ),
+            ChangeKind::Removed => ("-", Color::Red, t!("diff-removed")),
+        };
+        if self.json() {
+            return self.print_json_internal(
+                "diff_change",
+                &format!("{} {} ({})", marker, change.file, detail),
+            );
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(color)))?;
+        write!(self.stdout(), "{} ", marker)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{} ({})", change.file, detail)
+    }
+
+    /// Print the totals of a diff from `from` to `to` (or to the current history)
+    pub fn diff_summary(
+        &mut self,
+        from: &Snapshot,
+        to: Option<&Snapshot>,
+        changes: &[SessionChange],
+    ) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+        let added: usize = changes.iter().map(SessionChange::added).sum();
+        let range = match to {
+            Some(to) => t!(
+                "diff-range",
+                from = from.name.as_str(),
+                to = to.name.as_str()
+            ),
+            None => t!(
+                "diff-since",
+                from = from.name.as_str(),
+                created = format_datetime(&from.created_at)
+            ),
+        };
+        let message = t!(
+            "diff-summary",
+            range = range,
+            new = count(ChangeKind::New),
+            appended = count(ChangeKind::Appended),
+            added = added,
+            rewritten = count(ChangeKind::Rewritten),
+            removed = count(ChangeKind::Removed)
+        );
+        if self.json() {
+            return self.print_json_internal("diff_summary", &message);
+        }
+        if !changes.is_empty() {
+            writeln!(self.stdout())?;
+        }
+        self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(self.stdout(), "{}", message)?;
+        self.stdout().reset()
+    }
+}
@@ -0,0 +1,90 @@
+//! The streams `Output` prints on. While the agent runs in a pseudo-terminal
+//! our terminal is in raw mode, where a newline moves down without going back
+//! to the left edge, so lines printed meanwhile end in `\r\n` instead.
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use termcolor::{ColorSpec, StandardStream, WriteColor};
+
+/// Whether our terminal is in raw mode
+static RAW_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+/// Note that our terminal was put in raw mode, or given back
+pub(crate) fn set_raw_terminal(raw: bool) {
+    RAW_TERMINAL.store(raw, Ordering::Relaxed);
+}
+
+/// stdout or stderr, ending lines in `\r\n` while it is a terminal in raw mode
+pub struct Stream<W = StandardStream> {
+    inner: W,
+    terminal: bool,
+}
+
+impl<W> Stream<W> {
+    /// `inner`, which is a terminal or not
+    pub fn new(inner: W, terminal: bool) -> Self {
+        Self { inner, terminal }
+    }
+
+    /// Whether this stream is a terminal
+    pub fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+}
+
+impl<W: Write> Write for Stream<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !(self.terminal && RAW_TERMINAL.load(Ordering::Relaxed)) {
+            return self.inner.write(buf);
+        }
+        let mut lines = buf.split(|b| *b == b'\n');
+        if let Some(first) = lines.next() {
+            self.inner.write_all(first)?;
+        }
+        for line in lines {
+            self.inner.write_all(b"\r\n")?;
+            self.inner.write_all(line)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: WriteColor> WriteColor for Stream<W> {
+    fn supports_color(&self) -> bool {
+        self.inner.supports_color()
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.inner.set_color(spec)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.inner.reset()
+    }
+
+    fn is_synchronous(&self) -> bool {
+        self.inner.is_synchronous()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_end_in_crlf_in_raw_mode() {
+        let mut terminal = Stream::new(Vec::new(), true);
+        let mut file = Stream::new(Vec::new(), false);
+        set_raw_terminal(true);
+        write!(terminal, "one\ntwo\n").unwrap();
+        write!(file, "one\ntwo\n").unwrap();
+        set_raw_terminal(false);
+        writeln!(terminal, "three").unwrap();
+
+        assert_eq!(terminal.inner, b"one\r\ntwo\r\nthree\n");
+        assert_eq!(file.inner, b"one\ntwo\n");
+    }
+}
@@ -0,0 +1,79 @@
+//! Status markers for terminal output.
+//!
+//! Each marker falls back to plain ASCII on consoles that can't render Unicode
+//! (e.g. legacy Windows consoles whose code page could not be switched to
+//! UTF-8, or non-UTF-8 locales).
+use std::fmt;
+use std::sync::OnceLock;
+
+/// A status marker and the ASCII it falls back to
+#[derive(Debug, Clone, Copy)]
+pub struct Marker(&'static str, &'static str);
+
+pub const SUCCESS: Marker = Marker("✓", "+");
+pub const FAILURE: Marker = Marker("✗", "x");
+pub const WARNING: Marker = Marker("⚠", "!");
+pub const SYNCED: Marker = Marker("↑", "^");
+pub const SKIPPED: Marker = Marker("⊘", "-");
+pub const SPARKLES: Marker = Marker("✨ ", "");
+
+impl Marker {
+    fn pick(self, unicode: bool) -> &'static str {
+        if unicode {
+            self.0
+        } else {
+            self.1
+        }
+    }
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.pick(unicode_console()))
+    }
+}
+
+/// Whether the console renders the Unicode markers, decided once per run
+static UNICODE: OnceLock<bool> = OnceLock::new();
+
+fn unicode_console() -> bool {
+    *UNICODE.get_or_init(|| console::Term::stdout().features().wants_emoji())
+}
+
+/// Switch the Windows console to the UTF-8 code page so session titles and
+/// filenames containing non-ASCII characters are not printed as mojibake.
+/// The markers are Unicode only if the switch succeeded.
+#[cfg(windows)]
+pub(crate) fn enable_utf8_console() {
+    use std::io::IsTerminal;
+    use windows_sys::Win32::System::Console::SetConsoleOutputCP;
+    const CP_UTF8: u32 = 65001;
+
+    UNICODE.get_or_init(|| {
+        // SAFETY: SetConsoleOutputCP has no memory-safety preconditions; it simply
+        // fails (returns 0) when there is no console attached.
+        let switched = unsafe { SetConsoleOutputCP(CP_UTF8) } != 0;
+        if !switched {
+            tracing::debug!("Failed to switch console to UTF-8, falling back to ASCII markers");
+        }
+        switched && std::io::stdout().is_terminal()
+    });
+}
+
+#[cfg(not(windows))]
+pub(crate) fn enable_utf8_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers_fall_back_to_ascii() {
+        for marker in [SUCCESS, FAILURE, WARNING, SYNCED, SKIPPED, SPARKLES] {
+            assert!(marker.pick(false).is_ascii(), "{:?}", marker);
+            assert!(!marker.pick(true).is_ascii(), "{:?}", marker);
+        }
+        assert_eq!(SUCCESS.pick(false), "+");
+        assert_eq!(SUCCESS.pick(true), "✓");
+    }
+}
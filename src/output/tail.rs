@@ -0,0 +1,54 @@
+use super::Output;
+use crate::i18n::t;
+use crate::providers::base::{ChatMessage, MessageRole};
+use crate::utils::time::Timezone;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Tell the user which session file `waylog tail` follows now
+    pub fn tail_following(&mut self, provider: &str, file: &Path) -> io::Result<()> {
+        self.info(t!(
+            "tail-following",
+            provider = provider,
+            path = file.display().to_string()
+        ))
+    }
+
+    /// Tell the user there is no session to follow yet
+    pub fn tail_waiting(&mut self, project: &Path) -> io::Result<()> {
+        self.info(t!("tail-waiting", path = project.display().to_string()))
+    }
+
+    /// Print messages in full as `waylog tail` reads them, or one JSON
+    /// object per line with `--json`
+    pub fn tail_messages(&mut self, messages: &[ChatMessage]) -> io::Result<()> {
+        let timezone = Timezone::from_config();
+        for message in messages {
+            if self.json() {
+                writeln!(self.stdout(), "{}", serde_json::to_string(message)?)?;
+                continue;
+            }
+
+            let (role, color) = match message.role {
+                MessageRole::User => ("user", Color::Cyan),
+                MessageRole::Assistant => ("assistant", Color::Green),
+                MessageRole::System => ("system", Color::Yellow),
+            };
+            self.stdout()
+                .set_color(ColorSpec::new().set_intense(true))?;
+            write!(
+                self.stdout(),
+                "[{}] ",
+                timezone.format(&message.timestamp, "%H:%M:%S")
+            )?;
+            self.stdout()
+                .set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+            writeln!(self.stdout(), "{}", role)?;
+            self.stdout().reset()?;
+            writeln!(self.stdout(), "{}\n", message.content.trim_end())?;
+        }
+        self.stdout().flush()
+    }
+}
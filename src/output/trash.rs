@@ -0,0 +1,63 @@
+use super::symbols::SUCCESS;
+use super::Output;
+use crate::i18n::t;
+use chrono::{DateTime, Utc};
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print a file in the trash with when it was deleted
+    pub fn trash_entry(&mut self, id: &str, deleted_at: DateTime<Utc>) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let deleted = crate::exporter::markdown::formatter::format_datetime(&deleted_at);
+        if self.json() {
+            return self.print_json_internal(
+                "trash_entry",
+                &t!("trash-entry", id = id, deleted = deleted),
+            );
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_intense(true))?;
+        write!(self.stdout(), "  {}  ", deleted)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", id)
+    }
+
+    /// Print how many files are in the trash and how long they are kept
+    pub fn trash_summary(&mut self, count: usize, retention_days: u64) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let summary = if retention_days == 0 {
+            t!("trash-summary-kept", count = count)
+        } else {
+            t!("trash-summary", count = count, days = retention_days)
+        };
+        if self.json() {
+            return self.print_json_internal("trash_summary", &summary);
+        }
+        writeln!(self.stdout(), "{}", summary)
+    }
+
+    /// Print a restored file
+    pub fn trash_restored(&mut self, path: &Path) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if self.json() {
+            return self.print_json_internal("trash_restored", &path.display().to_string());
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(self.stdout(), "{} ", SUCCESS)?;
+        self.stdout().reset()?;
+        writeln!(
+            self.stdout(),
+            "{}",
+            t!("trash-restored", path = path.display().to_string())
+        )
+    }
+}
@@ -0,0 +1,73 @@
+use super::symbols::{SKIPPED, SUCCESS};
+use super::Output;
+use crate::commands::usage::ProviderUsage;
+use crate::i18n::t;
+use crate::utils::string::format_bytes;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print the sessions, disk usage and date range of one provider
+    pub fn usage_provider(&mut self, provider: &str, usage: &ProviderUsage) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        if usage.sessions == 0 {
+            let message = t!("usage-no-sessions", provider = provider);
+            if self.json() {
+                return self.print_json_internal("usage_provider", &message);
+            }
+            self.stdout()
+                .set_color(ColorSpec::new().set_intense(true))?;
+            writeln!(self.stdout(), "{} {}", SKIPPED, message)?;
+            return self.stdout().reset();
+        }
+
+        let message = format!("{}: {}", provider, summary(usage));
+        if self.json() {
+            return self.print_json_internal("usage_provider", &message);
+        }
+        self.stdout()
+            .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(self.stdout(), "{} ", SUCCESS)?;
+        self.stdout().reset()?;
+        writeln!(self.stdout(), "{}", message)
+    }
+
+    /// Print the totals over all providers
+    pub fn usage_total(&mut self, usage: &ProviderUsage, global: bool) -> io::Result<()> {
+        if self.quiet() {
+            return Ok(());
+        }
+        let message = if global {
+            t!("usage-total-global", summary = summary(usage))
+        } else {
+            t!("usage-total", summary = summary(usage))
+        };
+        if self.json() {
+            return self.print_json_internal("usage_total", &message);
+        }
+        writeln!(self.stdout())?;
+        self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(self.stdout(), "{}", message)?;
+        self.stdout().reset()
+    }
+}
+
+/// `3 sessions, 1.2 MiB, 2025-01-03 to 2025-06-01`
+fn summary(usage: &ProviderUsage) -> String {
+    let sessions = t!(
+        "usage-sessions",
+        sessions = usage.sessions,
+        size = format_bytes(usage.bytes)
+    );
+    match (usage.first, usage.last) {
+        (Some(first), Some(last)) => t!(
+            "usage-range",
+            sessions = sessions,
+            first = first.format("%Y-%m-%d").to_string(),
+            last = last.format("%Y-%m-%d").to_string()
+        ),
+        _ => sessions,
+    }
+}
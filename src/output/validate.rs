@@ -0,0 +1,106 @@
+use super::symbols::{FAILURE, SUCCESS, WARNING};
+use super::Output;
+use crate::i18n::t;
+use crate::providers::validate::ValidationReport;
+use std::io::{self, Write};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+impl Output {
+    /// Print the findings of `waylog validate` for one provider
+    pub fn validation_report(
+        &mut self,
+        provider: &str,
+        report: &ValidationReport,
+    ) -> io::Result<()> {
+        let unknowns = [
+            (t!("validate-event-type"), &report.unknown_event_types),
+            (t!("validate-field"), &report.unknown_fields),
+            (t!("validate-content-type"), &report.unknown_content_types),
+        ];
+
+        if self.json() {
+            for (kind, values) in &unknowns {
+                for (value, count) in *values {
+                    self.print_json_internal(
+                        "validate_unknown",
+                        &format!("{}: unknown {} `{}` ({}x)", provider, kind, value, count),
+                    )?;
+                }
+            }
+            for error in &report.errors {
+                self.print_json_internal(
+                    "validate_error",
+                    &format!("{}: {}", provider, describe_error(error)),
+                )?;
+            }
+            return self.print_json_internal(
+                "validate_summary",
+                &format!(
+                    "{}: {}",
+                    provider,
+                    t!(
+                        "validate-checked",
+                        files = report.files,
+                        events = report.events
+                    )
+                ),
+            );
+        }
+
+        if !self.quiet() {
+            self.stdout().set_color(ColorSpec::new().set_bold(true))?;
+            write!(self.stdout(), "{}", provider)?;
+            self.stdout().reset()?;
+            writeln!(
+                self.stdout(),
+                ": {}",
+                t!(
+                    "validate-checked",
+                    files = report.files,
+                    events = report.events
+                )
+            )?;
+        }
+
+        // Findings are shown even with --quiet, like errors
+        for (kind, values) in unknowns {
+            for (value, count) in values {
+                self.stdout()
+                    .set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+                writeln!(
+                    self.stdout(),
+                    "  {} {}",
+                    WARNING,
+                    t!(
+                        "validate-unknown",
+                        kind = kind.as_str(),
+                        value = value.as_str(),
+                        count = *count
+                    )
+                )?;
+                self.stdout().reset()?;
+            }
+        }
+        for error in &report.errors {
+            self.stdout()
+                .set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
+            writeln!(self.stdout(), "  {} {}", FAILURE, describe_error(error))?;
+            self.stdout().reset()?;
+        }
+
+        if report.is_clean() && !self.quiet() {
+            self.stdout()
+                .set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+            writeln!(self.stdout(), "  {} {}", SUCCESS, t!("validate-clean"))?;
+            self.stdout().reset()?;
+        }
+        Ok(())
+    }
+}
+
+fn describe_error(error: &crate::providers::validate::ValidationError) -> String {
+    match error.line {
+        Some(line) => format!("{}:{}: {}", error.file.display(), line, error.message),
+        None => format!("{}: {}", error.file.display(), error.message),
+    }
+}
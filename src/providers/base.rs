@@ -7,6 +7,7 @@ use std::path::{Path, PathBuf};
 /// Represents a chat message from any AI provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
+    /// The provider's id for the message, empty if the provider has none
     pub id: String,
     pub timestamp: DateTime<Utc>,
     pub role: MessageRole,
@@ -14,7 +15,8 @@ pub struct ChatMessage {
     pub metadata: MessageMetadata,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     User,
     Assistant,
@@ -34,6 +36,54 @@ pub struct MessageMetadata {
 
     /// Thoughts (for Gemini)
     pub thoughts: Vec<String>,
+
+    /// Binary attachments such as pasted screenshots
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+
+    /// Project files the agent read or edited through tool calls
+    #[serde(default)]
+    pub file_accesses: Vec<FileAccess>,
+}
+
+/// A file touched by a tool call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAccess {
+    /// Path as given to the tool (usually absolute)
+    pub path: String,
+    pub kind: FileAccessKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FileAccessKind {
+    Read,
+    Edit,
+}
+
+/// Binary content attached to a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// MIME type (e.g. "image/png")
+    pub media_type: String,
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// Attachment bytes are written as base64 strings in JSON
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +104,62 @@ pub struct ChatSession {
     pub messages: Vec<ChatMessage>,
 }
 
+/// `--since`/`--until`: the sessions last modified within a time range
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// Whether a session file can hold a session in the range, judged by its
+    /// mtime without parsing it. A file not written since `since` cannot;
+    /// `until` is left to `contains`, as a file can be touched after its
+    /// last message.
+    pub fn may_contain(&self, file_path: &Path) -> bool {
+        let Some(since) = self.since else {
+            return true;
+        };
+        match std::fs::metadata(file_path).and_then(|m| m.modified()) {
+            Ok(modified) => DateTime::<Utc>::from(modified) >= since,
+            Err(_) => true,
+        }
+    }
+
+    /// Whether a parsed session was last updated within the range
+    pub fn contains(&self, session: &ChatSession) -> bool {
+        self.since.is_none_or(|since| session.updated_at >= since)
+            && self.until.is_none_or(|until| session.updated_at <= until)
+    }
+}
+
+/// Where parsing of a session file stopped, so the next sync can read only
+/// what was appended after it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParseCursor {
+    /// Byte offset just past the last line that was parsed
+    pub offset: u64,
+    /// Messages parsed before `offset`
+    pub message_count: usize,
+    pub session_id: String,
+    pub project_path: PathBuf,
+    /// Timestamp of the first message, once there is one
+    pub started_at: Option<DateTime<Utc>>,
+    /// Unrecognized event types before `offset`
+    #[serde(default)]
+    pub unknown: std::collections::BTreeMap<String, usize>,
+    /// Provider-specific state needed to resume (e.g. Codex's duplicate check)
+    #[serde(default)]
+    pub resume: Option<String>,
+    /// Complete lines before `offset` that were not valid and were skipped
+    #[serde(default)]
+    pub malformed_lines: usize,
+}
+
 /// Provider trait - each AI CLI tool implements this
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -72,12 +178,120 @@ pub trait Provider: Send + Sync {
     /// Parse a session file and return a chat session
     async fn parse_session(&self, file_path: &Path) -> Result<ChatSession>;
 
+    /// Parse a session file from `cursor` on (from the start if `None`). The
+    /// session holds only the messages after the cursor, at most `limit` of
+    /// them, and the returned cursor is where the next call resumes. `None`
+    /// if the provider cannot parse incrementally; callers then use
+    /// `parse_session`.
+    async fn parse_session_from(
+        &self,
+        _file_path: &Path,
+        _cursor: Option<&ParseCursor>,
+        _limit: Option<usize>,
+    ) -> Result<Option<(ChatSession, ParseCursor)>> {
+        Ok(None)
+    }
+
+    /// The directory `waylog run` watches, recursively, for the project's
+    /// session files being written
+    fn watch_dir(&self, project_path: &Path) -> Result<PathBuf> {
+        self.session_dir(project_path)
+    }
+
     /// Get all session files for a specific project
     async fn get_all_sessions(&self, project_path: &Path) -> Result<Vec<PathBuf>>;
 
+    /// Get all session files for a project, including those recorded under
+    /// the paths it had before it was moved (`[project] previous_paths`),
+    /// newest first. The lists are cached in the project's state file.
+    async fn get_project_sessions(&self, project_path: &Path) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![project_path.to_path_buf()];
+        paths.extend(crate::config::get().project.previous_paths.iter().cloned());
+        super::resolution::project_sessions(self, project_path, &paths).await
+    }
+
+    /// The directories `get_all_sessions(project_path)` lists; a session file
+    /// appearing in or leaving them changes one of their modification times
+    fn listing_dirs(&self, project_path: &Path) -> Result<Vec<PathBuf>> {
+        Ok(vec![self.session_dir(project_path)?])
+    }
+
+    /// Get the session files for a project that may hold sessions modified
+    /// within `range`. By default a cheap mtime check on `get_project_sessions`;
+    /// callers still check the parsed session with `TimeRange::contains`.
+    async fn get_sessions_in_range(
+        &self,
+        project_path: &Path,
+        range: &TimeRange,
+    ) -> Result<Vec<PathBuf>> {
+        let mut sessions = self.get_project_sessions(project_path).await?;
+        sessions.retain(|path| range.may_contain(path));
+        Ok(sessions)
+    }
+
+    /// The project directory a session file belongs to, if the file says
+    async fn session_project(&self, file_path: &Path) -> Result<Option<PathBuf>> {
+        let project = self.parse_session(file_path).await?.project_path;
+        Ok(Some(project).filter(|p| p.is_absolute()))
+    }
+
+    /// Get the session files of every project on this machine
+    async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>>;
+
     /// Check if the CLI tool is installed
     fn is_installed(&self) -> bool;
 
     /// Get the command to run the CLI tool
     fn command(&self) -> &str;
+
+    /// Arguments that make the CLI tool answer `prompt` and exit instead of
+    /// starting an interactive session (`waylog batch`)
+    fn print_args(&self, prompt: &str) -> Vec<String> {
+        vec!["-p".to_string(), prompt.to_string()]
+    }
+
+    /// Check a session file against the source format this provider understands
+    /// (`waylog validate`). By default only checks that the file parses.
+    async fn validate_session(
+        &self,
+        file_path: &Path,
+        report: &mut super::validate::ValidationReport,
+    ) -> Result<()> {
+        report.files += 1;
+        if let Err(e) = self.parse_session(file_path).await {
+            report.error(file_path, None, e.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_time_range() {
+        let at = |day| Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap();
+        let session = ChatSession {
+            session_id: "s".to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: at(14),
+            updated_at: at(15),
+            messages: Vec::new(),
+        };
+        let range = |since, until| TimeRange { since, until };
+        assert!(range(None, None).contains(&session));
+        assert!(range(Some(at(15)), Some(at(15))).contains(&session));
+        assert!(!range(Some(at(16)), None).contains(&session));
+        assert!(!range(None, Some(at(1))).contains(&session));
+
+        // A file last written before `since` is ruled out without parsing it
+        let file = tempfile::NamedTempFile::new().unwrap();
+        file.as_file().set_modified(at(1).into()).unwrap();
+        assert!(!range(Some(at(2)), None).may_contain(file.path()));
+        assert!(range(Some(at(1)), None).may_contain(file.path()));
+        assert!(range(None, Some(at(1))).may_contain(file.path()));
+    }
 }
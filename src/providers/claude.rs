@@ -1,5 +1,8 @@
 use crate::error::{Result, WaylogError};
 use crate::providers::base::*;
+use crate::providers::jsonl;
+use crate::providers::telemetry;
+use crate::providers::validate::{self, KnownFormat, ValidationReport};
 use crate::utils::path;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -60,74 +63,154 @@ impl Provider for ClaudeProvider {
         }
 
         // Sort by modification time, newest first
-        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.1));
 
         Ok(candidates.into_iter().map(|(p, _)| p).collect())
     }
 
+    async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+        let data_dir = self.data_dir()?;
+        if !data_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in walkdir::WalkDir::new(&data_dir)
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if entry.file_type().is_file()
+                && path.extension().and_then(|s| s.to_str()) == Some("jsonl")
+                && self.is_main_session(path).await.unwrap_or(false)
+            {
+                sessions.push(path.to_path_buf());
+            }
+        }
+        Ok(sessions)
+    }
+
     async fn parse_session(&self, file_path: &Path) -> Result<ChatSession> {
-        let file = fs::File::open(file_path).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+        Ok(self.parse_from(file_path, None, None).await?.0)
+    }
 
+    async fn parse_session_from(
+        &self,
+        file_path: &Path,
+        cursor: Option<&ParseCursor>,
+        limit: Option<usize>,
+    ) -> Result<Option<(ChatSession, ParseCursor)>> {
+        self.parse_from(file_path, cursor, limit).await.map(Some)
+    }
+
+    fn is_installed(&self) -> bool {
+        // Fixture sessions can be synced without the agent being installed
+        path::provider_fixtures_dir().is_some() || which::which("claude").is_ok()
+    }
+
+    fn command(&self) -> &str {
+        "claude"
+    }
+
+    async fn validate_session(
+        &self,
+        file_path: &Path,
+        report: &mut ValidationReport,
+    ) -> Result<()> {
+        validate::validate_jsonl::<ClaudeEvent>(
+            file_path,
+            &CLAUDE_FORMAT,
+            |event| event.get("message")?.get("content")?.as_array(),
+            report,
+        )
+        .await
+    }
+}
+
+impl ClaudeProvider {
+    async fn parse_from(
+        &self,
+        file_path: &Path,
+        cursor: Option<&ParseCursor>,
+        limit: Option<usize>,
+    ) -> Result<(ChatSession, ParseCursor)> {
+        let mut cursor = cursor.cloned().unwrap_or_default();
         let mut messages = Vec::new();
-        let mut session_id = String::new();
-        let mut started_at = Utc::now();
-        let mut project_path = PathBuf::new();
 
+        let mut lines = jsonl::LineReader::open(file_path, cursor.offset).await?;
         while let Some(line) = lines.next_line().await? {
-            if line.trim().is_empty() {
+            if limit.is_some_and(|limit| messages.len() >= limit) {
+                break;
+            }
+            if line.text.trim().is_empty() {
+                cursor.offset = line.end;
                 continue;
             }
 
-            let event: ClaudeEvent = serde_json::from_str(&line).map_err(WaylogError::Json)?;
+            let event: ClaudeEvent = match serde_json::from_str(&line.text) {
+                Ok(event) => event,
+                // The agent is still writing this line; the next sync reads it
+                Err(_) if !line.complete => break,
+                // One corrupt line must not cost the rest of the session
+                Err(e) => {
+                    tracing::warn!(
+                        "{}: skipping malformed line ending at byte {}: {}",
+                        file_path.display(),
+                        line.end,
+                        e
+                    );
+                    cursor.malformed_lines += 1;
+                    cursor.offset = line.end;
+                    continue;
+                }
+            };
+            CLAUDE_FORMAT.tally_event(&event.event_type, &mut cursor.unknown);
 
             // Extract session metadata from first event
-            if session_id.is_empty() {
-                session_id = event.session_id.clone().unwrap_or_else(|| {
+            if cursor.session_id.is_empty() {
+                cursor.session_id = event.session_id.clone().unwrap_or_else(|| {
                     file_path
                         .file_stem()
                         .and_then(|s| s.to_str())
                         .unwrap_or("unknown")
                         .to_string()
                 });
+            }
 
+            // The first line may be a summary without cwd
+            if cursor.project_path.as_os_str().is_empty() {
                 if let Some(cwd) = &event.cwd {
-                    project_path = PathBuf::from(cwd);
+                    cursor.project_path = PathBuf::from(cwd);
                 }
             }
 
             // Parse user and assistant messages
             if event.event_type == "user" || event.event_type == "assistant" {
                 if let Some(msg) = self.parse_message(event)? {
-                    if messages.is_empty() {
-                        started_at = msg.timestamp;
-                    }
+                    cursor.started_at.get_or_insert(msg.timestamp);
                     messages.push(msg);
                 }
             }
+            cursor.offset = line.end;
         }
 
-        Ok(ChatSession {
-            session_id,
+        telemetry::record(self.name(), file_path, cursor.unknown.clone());
+        cursor.message_count += messages.len();
+
+        let started_at = cursor.started_at.unwrap_or_else(Utc::now);
+        let session = ChatSession {
+            session_id: cursor.session_id.clone(),
             provider: self.name().to_string(),
-            project_path,
+            project_path: cursor.project_path.clone(),
             started_at,
             updated_at: messages.last().map(|m| m.timestamp).unwrap_or(started_at),
             messages,
-        })
-    }
-
-    fn is_installed(&self) -> bool {
-        which::which("claude").is_ok()
-    }
-
-    fn command(&self) -> &str {
-        "claude"
+        };
+        Ok((session, cursor))
     }
-}
 
-impl ClaudeProvider {
     fn parse_message(&self, event: ClaudeEvent) -> Result<Option<ChatMessage>> {
         let role = match event.event_type.as_str() {
             "user" => MessageRole::User,
@@ -154,7 +237,18 @@ impl ClaudeProvider {
             None => return Ok(None),
         };
 
-        if content.is_empty() {
+        // Inline images (e.g. pasted screenshots) arrive as base64 `image` blocks
+        let attachments = match event.message.as_ref().map(|msg| &msg.content) {
+            Some(ClaudeContent::Array(items)) => items
+                .iter()
+                .filter(|item| item.content_type == "image")
+                .filter_map(|item| item.source.as_ref())
+                .filter_map(ClaudeImageSource::decode)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if content.is_empty() && attachments.is_empty() {
             return Ok(None);
         }
 
@@ -172,7 +266,7 @@ impl ClaudeProvider {
                 .map_err(|e| WaylogError::Internal(e.to_string()))?;
             let clean_content = re.replace_all(&content, "").to_string();
 
-            if clean_content.trim().is_empty() {
+            if clean_content.trim().is_empty() && attachments.is_empty() {
                 // If nothing remains after removing tags, it was purely internal state -> Skip
                 return Ok(None);
             }
@@ -205,7 +299,7 @@ impl ClaudeProvider {
             .unwrap_or_else(Utc::now);
 
         // Extract metadata
-        let (model, tokens, tool_calls) = if let Some(msg) = &event.message {
+        let (model, tokens, tool_calls, file_accesses) = if let Some(msg) = &event.message {
             let model = msg.model.clone();
             let tokens = msg.usage.as_ref().map(|u| TokenUsage {
                 input: u.input_tokens,
@@ -224,15 +318,24 @@ impl ClaudeProvider {
                 Vec::new()
             };
 
-            (model, tokens, tool_calls)
+            // Extract files read or edited by the file tools
+            let file_accesses = if let ClaudeContent::Array(items) = &msg.content {
+                items
+                    .iter()
+                    .filter(|item| item.content_type == "tool_use")
+                    .filter_map(Self::file_access)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            (model, tokens, tool_calls, file_accesses)
         } else {
-            (None, None, Vec::new())
+            (None, None, Vec::new(), Vec::new())
         };
 
         Ok(Some(ChatMessage {
-            id: event
-                .uuid
-                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            id: event.uuid.unwrap_or_default(),
             timestamp,
             role,
             content,
@@ -241,10 +344,28 @@ impl ClaudeProvider {
                 tokens,
                 tool_calls,
                 thoughts: Vec::new(),
+                attachments,
+                file_accesses,
             },
         }))
     }
 
+    /// File touched by a `tool_use` block, if it is one of the file tools
+    fn file_access(item: &ClaudeContentItem) -> Option<FileAccess> {
+        let (kind, key) = match item.name.as_deref()? {
+            "Read" => (FileAccessKind::Read, "file_path"),
+            "NotebookRead" => (FileAccessKind::Read, "notebook_path"),
+            "Edit" | "MultiEdit" | "Write" => (FileAccessKind::Edit, "file_path"),
+            "NotebookEdit" => (FileAccessKind::Edit, "notebook_path"),
+            _ => return None,
+        };
+        let path = item.input.as_ref()?.get(key)?.as_str()?;
+        Some(FileAccess {
+            path: path.to_string(),
+            kind,
+        })
+    }
+
     /// Format Claude Code XML tags into markdown-friendly text
     fn format_claude_xml(content: &str) -> String {
         // Handle Command Name: <command-name>cmd</command-name>
@@ -310,6 +431,59 @@ impl ClaudeProvider {
     }
 }
 
+/// Parts of the Claude Code session format that are parsed or deliberately skipped
+const CLAUDE_FORMAT: KnownFormat = KnownFormat {
+    event_types: &[
+        "user",
+        "assistant",
+        "summary",
+        "system",
+        "file-history-snapshot",
+        "queue-operation",
+    ],
+    fields: &[
+        "type",
+        "sessionId",
+        "cwd",
+        "timestamp",
+        "uuid",
+        "parentUuid",
+        "logicalParentUuid",
+        "isSidechain",
+        "isMeta",
+        "isCompactSummary",
+        "isApiErrorMessage",
+        "isSnapshotUpdate",
+        "message",
+        "userType",
+        "version",
+        "gitBranch",
+        "requestId",
+        "toolUseResult",
+        "thinkingMetadata",
+        "todos",
+        "summary",
+        "leafUuid",
+        "level",
+        "subtype",
+        "content",
+        "compactMetadata",
+        "messageId",
+        "snapshot",
+        "operation",
+        "agentId",
+        "slug",
+    ],
+    content_types: &[
+        "text",
+        "image",
+        "tool_use",
+        "tool_result",
+        "thinking",
+        "redacted_thinking",
+    ],
+};
+
 // Claude Code JSONL event structures
 #[derive(Debug, Deserialize)]
 struct ClaudeEvent {
@@ -350,7 +524,37 @@ struct ClaudeContentItem {
     #[serde(rename = "type")]
     content_type: String,
     text: Option<String>,
-    name: Option<String>, // For tool_use
+    name: Option<String>,              // For tool_use
+    source: Option<ClaudeImageSource>, // For image
+    input: Option<serde_json::Value>,  // For tool_use
+}
+
+#[derive(Debug, Deserialize)]
+struct ClaudeImageSource {
+    #[serde(rename = "type")]
+    source_type: String,
+    media_type: Option<String>,
+    data: Option<String>,
+}
+
+impl ClaudeImageSource {
+    fn decode(&self) -> Option<Attachment> {
+        use base64::Engine;
+
+        if self.source_type != "base64" {
+            return None;
+        }
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(self.data.as_deref()?)
+            .ok()?;
+        Some(Attachment {
+            media_type: self
+                .media_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            data,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -363,7 +567,6 @@ struct ClaudeUsage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::providers::base::{MessageRole, Provider};
 
     // Helper to create a user message event with content
     fn create_user_event(content: &str) -> ClaudeEvent {
@@ -409,4 +612,90 @@ mod tests {
             "Tag should be stripped from mixed content"
         );
     }
+
+    #[test]
+    fn test_image_only_message_keeps_attachment() {
+        let provider = ClaudeProvider::new();
+        let line = r#"{"type":"user","message":{"role":"user","content":[{"type":"image","source":{"type":"base64","media_type":"image/png","data":"aGVsbG8="}}]}}"#;
+        let event: ClaudeEvent = serde_json::from_str(line).unwrap();
+        let msg = provider.parse_message(event).unwrap().unwrap();
+
+        assert_eq!(msg.metadata.attachments.len(), 1);
+        assert_eq!(msg.metadata.attachments[0].media_type, "image/png");
+        assert_eq!(msg.metadata.attachments[0].data, b"hello");
+    }
+
+    #[test]
+    fn test_file_accesses_from_tool_use() {
+        let provider = ClaudeProvider::new();
+        let line = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Fixing"},{"type":"tool_use","name":"Read","input":{"file_path":"/p/src/main.rs"}},{"type":"tool_use","name":"Edit","input":{"file_path":"/p/src/lib.rs","old_string":"a","new_string":"b"}},{"type":"tool_use","name":"Bash","input":{"command":"ls"}}]}}"#;
+        let event: ClaudeEvent = serde_json::from_str(line).unwrap();
+        let msg = provider.parse_message(event).unwrap().unwrap();
+
+        assert_eq!(
+            msg.metadata.file_accesses,
+            vec![
+                FileAccess {
+                    path: "/p/src/main.rs".to_string(),
+                    kind: FileAccessKind::Read
+                },
+                FileAccess {
+                    path: "/p/src/lib.rs".to_string(),
+                    kind: FileAccessKind::Edit
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_in_batches_resumes_after_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("session.jsonl");
+        let lines: Vec<_> = ["one", "two", "three"]
+            .iter()
+            .map(|text| {
+                format!(
+                    r#"{{"type":"user","sessionId":"s","message":{{"role":"user","content":"{}"}}}}"#,
+                    text
+                )
+            })
+            .collect();
+        std::fs::write(&file, lines.join("\n") + "\n").unwrap();
+
+        let provider = ClaudeProvider::new();
+        let (first, cursor) = provider.parse_from(&file, None, Some(2)).await.unwrap();
+        assert_eq!(first.messages.len(), 2);
+        let (rest, cursor) = provider
+            .parse_from(&file, Some(&cursor), Some(2))
+            .await
+            .unwrap();
+        let contents: Vec<_> = rest.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["three"]);
+        assert_eq!(cursor.message_count, 3);
+        assert_eq!(cursor.offset, std::fs::metadata(&file).unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_line_is_skipped_and_counted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("session.jsonl");
+        let lines = [
+            r#"{"type":"user","sessionId":"s","message":{"role":"user","content":"first"}}"#,
+            r#"{"type":"assistant","message":{"role":"assis"#,
+            r#"{"type":"user","sessionId":"s","message":{"role":"user","content":"second"}}"#,
+        ];
+        std::fs::write(&file, lines.join("\n") + "\n").unwrap();
+
+        let (session, cursor) = ClaudeProvider::new()
+            .parse_from(&file, None, None)
+            .await
+            .unwrap();
+        let contents: Vec<_> = session
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["first", "second"]);
+        assert_eq!(cursor.malformed_lines, 1);
+    }
 }
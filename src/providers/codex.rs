@@ -1,5 +1,8 @@
 use crate::error::Result;
 use crate::providers::base::*;
+use crate::providers::jsonl;
+use crate::providers::telemetry;
+use crate::providers::validate::{self, KnownFormat, ValidationReport};
 use crate::utils::path;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -23,7 +26,7 @@ impl Provider for CodexProvider {
     }
 
     fn data_dir(&self) -> Result<PathBuf> {
-        Ok(path::home_dir()?.join(".codex").join("sessions"))
+        Ok(path::provider_home_dir()?.join(".codex").join("sessions"))
     }
 
     fn session_dir(&self, _project_path: &Path) -> Result<PathBuf> {
@@ -81,7 +84,7 @@ impl Provider for CodexProvider {
         }
 
         // Sort by modification time, newest first
-        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.1));
 
         Ok(candidates.into_iter().next().map(|(p, _)| p))
     }
@@ -121,87 +124,171 @@ impl Provider for CodexProvider {
         }
 
         // Sort by modification time, newest first
-        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.1));
 
         Ok(candidates.into_iter().map(|(p, _)| p).collect())
     }
 
+    fn watch_dir(&self, _project_path: &Path) -> Result<PathBuf> {
+        // A session started just before midnight keeps writing to yesterday's directory
+        self.data_dir()
+    }
+
+    fn listing_dirs(&self, _project_path: &Path) -> Result<Vec<PathBuf>> {
+        // Sessions of every project share the dated directories
+        Ok(walkdir::WalkDir::new(self.data_dir()?)
+            .into_iter()
+            .filter_entry(|e| e.file_type().is_dir())
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .collect())
+    }
+
+    async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+        let base_session_dir = self.data_dir()?;
+        if !base_session_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(walkdir::WalkDir::new(&base_session_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_type().is_file()
+                    && e.path().extension().and_then(|s| s.to_str()) == Some("jsonl")
+            })
+            .map(|e| e.into_path())
+            .collect())
+    }
+
     async fn parse_session(&self, file_path: &Path) -> Result<ChatSession> {
-        let file = fs::File::open(file_path).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+        Ok(self.parse_from(file_path, None, None).await?.0)
+    }
+
+    async fn parse_session_from(
+        &self,
+        file_path: &Path,
+        cursor: Option<&ParseCursor>,
+        limit: Option<usize>,
+    ) -> Result<Option<(ChatSession, ParseCursor)>> {
+        self.parse_from(file_path, cursor, limit).await.map(Some)
+    }
+
+    fn is_installed(&self) -> bool {
+        // Fixture sessions can be synced without the agent being installed
+        path::provider_fixtures_dir().is_some() || which::which("codex").is_ok()
+    }
+
+    fn command(&self) -> &str {
+        "codex"
+    }
+
+    fn print_args(&self, prompt: &str) -> Vec<String> {
+        vec!["exec".to_string(), prompt.to_string()]
+    }
+
+    async fn validate_session(
+        &self,
+        file_path: &Path,
+        report: &mut ValidationReport,
+    ) -> Result<()> {
+        validate::validate_jsonl::<CodexEvent>(
+            file_path,
+            &CODEX_FORMAT,
+            |event| event.get("payload")?.get("content")?.as_array(),
+            report,
+        )
+        .await
+    }
+}
 
+impl CodexProvider {
+    async fn parse_from(
+        &self,
+        file_path: &Path,
+        cursor: Option<&ParseCursor>,
+        limit: Option<usize>,
+    ) -> Result<(ChatSession, ParseCursor)> {
+        let mut cursor = cursor.cloned().unwrap_or_default();
         let mut messages = Vec::new();
-        let mut session_id = String::new();
-        let mut started_at = Utc::now();
-        let mut session_project_path = PathBuf::new();
 
+        let mut lines = jsonl::LineReader::open(file_path, cursor.offset).await?;
         while let Some(line) = lines.next_line().await? {
-            if line.trim().is_empty() {
+            if limit.is_some_and(|limit| messages.len() >= limit) {
+                break;
+            }
+            if line.text.trim().is_empty() {
+                cursor.offset = line.end;
                 continue;
             }
 
-            if let Ok(event) = serde_json::from_str::<CodexEvent>(&line) {
-                // Pick session metadata
-                if session_id.is_empty() {
-                    session_id = file_path
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
+            let event = match serde_json::from_str::<CodexEvent>(&line.text) {
+                Ok(event) => event,
+                // The agent is still writing this line; the next sync reads it
+                Err(_) if !line.complete => break,
+                Err(e) => {
+                    tracing::warn!(
+                        "{}: skipping malformed line ending at byte {}: {}",
+                        file_path.display(),
+                        line.end,
+                        e
+                    );
+                    cursor.malformed_lines += 1;
+                    cursor.offset = line.end;
+                    continue;
                 }
+            };
+            CODEX_FORMAT.tally_event(&event.event_type, &mut cursor.unknown);
+
+            // Pick session metadata
+            if cursor.session_id.is_empty() {
+                cursor.session_id = file_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+            }
 
-                match event.event_type.as_str() {
-                    "session_meta" | "turn_context" => {
-                        if let Some(cwd) = event.payload.as_ref().and_then(|p| p.cwd.clone()) {
-                            session_project_path = PathBuf::from(cwd);
-                        }
+            match event.event_type.as_str() {
+                "session_meta" | "turn_context" => {
+                    if let Some(cwd) = event.payload.as_ref().and_then(|p| p.cwd.clone()) {
+                        cursor.project_path = PathBuf::from(cwd);
                     }
-                    "response_item" => {
-                        if let Some(payload) = event.payload {
-                            if let Some(msg) =
-                                self.parse_response_item(payload, &event.timestamp)?
-                            {
-                                if messages.is_empty() {
-                                    started_at = msg.timestamp;
-                                }
-
-                                // Simple deduplication
-                                let is_duplicate =
-                                    messages.last().is_some_and(|last: &ChatMessage| {
-                                        last.role == msg.role && last.content == msg.content
-                                    });
-                                if !is_duplicate {
-                                    messages.push(msg);
-                                }
+                }
+                "response_item" => {
+                    if let Some(payload) = event.payload {
+                        if let Some(msg) = self.parse_response_item(payload, &event.timestamp)? {
+                            cursor.started_at.get_or_insert(msg.timestamp);
+
+                            // Simple deduplication, also across incremental parses
+                            let key = dedup_key(&msg);
+                            if cursor.resume.as_ref() != Some(&key) {
+                                cursor.resume = Some(key);
+                                messages.push(msg);
                             }
                         }
                     }
-                    _ => {}
                 }
+                _ => {}
             }
+            cursor.offset = line.end;
         }
 
-        Ok(ChatSession {
-            session_id,
+        telemetry::record(self.name(), file_path, cursor.unknown.clone());
+        cursor.message_count += messages.len();
+
+        let started_at = cursor.started_at.unwrap_or_else(Utc::now);
+        let session = ChatSession {
+            session_id: cursor.session_id.clone(),
             provider: self.name().to_string(),
-            project_path: session_project_path,
+            project_path: cursor.project_path.clone(),
             started_at,
             updated_at: messages.last().map(|m| m.timestamp).unwrap_or(started_at),
             messages,
-        })
-    }
-
-    fn is_installed(&self) -> bool {
-        which::which("codex").is_ok()
-    }
-
-    fn command(&self) -> &str {
-        "codex"
+        };
+        Ok((session, cursor))
     }
-}
 
-impl CodexProvider {
     async fn probe_project_path(
         &self,
         file_path: &Path,
@@ -291,7 +378,8 @@ impl CodexProvider {
         }
 
         Ok(Some(ChatMessage {
-            id: uuid::Uuid::new_v4().to_string(),
+            // Codex logs no message ids
+            id: String::new(),
             timestamp,
             role,
             content,
@@ -300,11 +388,39 @@ impl CodexProvider {
                 tokens: None,
                 tool_calls: Vec::new(),
                 thoughts: Vec::new(),
+                attachments: Vec::new(),
+                file_accesses: Vec::new(),
             },
         }))
     }
 }
 
+/// Parts of the Codex rollout format that are parsed or deliberately skipped
+/// Identifies a message for Codex's duplicate check, small enough to keep in a `ParseCursor`
+fn dedup_key(message: &ChatMessage) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{:?}\0", message.role));
+    hasher.update(&message.content);
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+const CODEX_FORMAT: KnownFormat = KnownFormat {
+    event_types: &[
+        "session_meta",
+        "response_item",
+        "turn_context",
+        "event_msg",
+        "compacted",
+    ],
+    fields: &["timestamp", "type", "payload"],
+    content_types: &["input_text", "output_text", "input_image"],
+};
+
 // Codex JSONL event structures
 #[derive(Debug, Deserialize)]
 struct CodexEvent {
@@ -1,9 +1,12 @@
 use crate::error::{Result, WaylogError};
 use crate::providers::base::*;
+use crate::providers::telemetry;
+use crate::providers::validate::{KnownFormat, ValidationReport};
 use crate::utils::path;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
@@ -56,16 +59,51 @@ impl Provider for GeminiProvider {
         }
 
         // Sort by modification time, newest first
-        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.1));
 
         Ok(candidates.into_iter().map(|(p, _)| p).collect())
     }
 
+    /// Gemini only keeps a hash of the project path
+    async fn session_project(&self, _file_path: &Path) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
+    async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+        let data_dir = self.data_dir()?;
+        if !data_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        // tmp/<project hash>/chats/*.json
+        Ok(walkdir::WalkDir::new(&data_dir)
+            .min_depth(3)
+            .max_depth(3)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_type().is_file()
+                    && e.path().extension().and_then(|s| s.to_str()) == Some("json")
+                    && e.path()
+                        .parent()
+                        .and_then(|p| p.file_name())
+                        .is_some_and(|name| name == "chats")
+            })
+            .map(|e| e.into_path())
+            .collect())
+    }
+
     async fn parse_session(&self, file_path: &Path) -> Result<ChatSession> {
         let content = fs::read_to_string(file_path).await?;
         let session_data: GeminiSession =
             serde_json::from_str(&content).map_err(WaylogError::Json)?;
 
+        let mut unknown = BTreeMap::new();
+        for msg in &session_data.messages {
+            GEMINI_MESSAGE_FORMAT.tally_event(&msg.message_type, &mut unknown);
+        }
+        telemetry::record(self.name(), file_path, unknown);
+
         let messages = session_data
             .messages
             .into_iter()
@@ -105,6 +143,47 @@ impl Provider for GeminiProvider {
     fn command(&self) -> &str {
         "gemini"
     }
+
+    async fn validate_session(
+        &self,
+        file_path: &Path,
+        report: &mut ValidationReport,
+    ) -> Result<()> {
+        report.files += 1;
+        let content = fs::read_to_string(file_path).await?;
+        let session: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                report.error(file_path, None, e.to_string());
+                return Ok(());
+            }
+        };
+
+        for key in session.as_object().into_iter().flat_map(|o| o.keys()) {
+            if !GEMINI_SESSION_FIELDS.contains(&key.as_str()) {
+                *report.unknown_fields.entry(key.clone()).or_default() += 1;
+            }
+        }
+
+        let messages = match session.get("messages").and_then(|m| m.as_array()) {
+            Some(messages) => messages.clone(),
+            None => {
+                report.error(file_path, None, "missing `messages` list");
+                return Ok(());
+            }
+        };
+        // Each message is checked like an event of the JSONL providers
+        for message in messages {
+            report.check_event::<GeminiMessage>(
+                message,
+                &GEMINI_MESSAGE_FORMAT,
+                |_| None,
+                file_path,
+                None,
+            );
+        }
+        Ok(())
+    }
 }
 
 impl GeminiProvider {
@@ -148,11 +227,38 @@ impl GeminiProvider {
                 tokens,
                 tool_calls: Vec::new(),
                 thoughts,
+                attachments: Vec::new(),
+                file_accesses: Vec::new(),
             },
         }))
     }
 }
 
+/// Top-level fields of a Gemini CLI session file
+const GEMINI_SESSION_FIELDS: &[&str] = &[
+    "sessionId",
+    "projectHash",
+    "startTime",
+    "lastUpdated",
+    "messages",
+];
+
+/// Parts of the Gemini CLI message format that are parsed or deliberately skipped
+const GEMINI_MESSAGE_FORMAT: KnownFormat = KnownFormat {
+    event_types: &["user", "gemini", "info", "error", "warning"],
+    fields: &[
+        "id",
+        "timestamp",
+        "type",
+        "content",
+        "model",
+        "thoughts",
+        "tokens",
+        "toolCalls",
+    ],
+    content_types: &[],
+};
+
 // Gemini JSON session structures
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,87 @@
+//! Reading JSONL session files from a byte offset, for incremental parsing
+use crate::error::Result;
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+
+/// A line of a JSONL file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    /// Byte offset just past the line (and its newline)
+    pub end: u64,
+    /// Whether the line ends with a newline; the last line of a file that is
+    /// still being written may not be complete yet
+    pub complete: bool,
+}
+
+/// The lines of a JSONL file, read one at a time so that a file of hundreds
+/// of megabytes is never held in memory whole
+pub struct LineReader {
+    reader: BufReader<fs::File>,
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl LineReader {
+    /// Read `file_path` from byte `offset` on
+    pub async fn open(file_path: &Path, offset: u64) -> Result<Self> {
+        let mut file = fs::File::open(file_path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            offset,
+            buf: Vec::new(),
+        })
+    }
+
+    /// The next line, or `None` at the end of the file
+    pub async fn next_line(&mut self) -> Result<Option<Line>> {
+        self.buf.clear();
+        let read = self.reader.read_until(b'\n', &mut self.buf).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.offset += read as u64;
+        let text = String::from_utf8_lossy(&self.buf);
+        Ok(Some(Line {
+            text: text.trim_end_matches(['\n', '\r']).to_string(),
+            end: self.offset,
+            complete: self.buf.ends_with(b"\n"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn read_lines_from(file_path: &Path, offset: u64) -> Vec<Line> {
+        let mut reader = LineReader::open(file_path, offset).await.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().await.unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_from_offset() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("s.jsonl");
+        std::fs::write(&file, "{\"a\":1}\n\n{\"b\":2}\r\n{\"c\"").unwrap();
+
+        let lines = read_lines_from(&file, 0).await;
+        let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["{\"a\":1}", "", "{\"b\":2}", "{\"c\""]);
+        assert_eq!(lines[0].end, 8);
+        assert!(lines[2].complete);
+        assert!(!lines[3].complete);
+
+        let rest = read_lines_from(&file, lines[1].end).await;
+        assert_eq!(rest[0].text, "{\"b\":2}");
+        assert_eq!(rest[0].end, lines[2].end);
+    }
+}
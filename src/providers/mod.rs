@@ -2,8 +2,14 @@ pub mod base;
 pub mod claude;
 pub mod codex;
 pub mod gemini;
+pub mod jsonl;
+pub mod resolution;
+pub mod telemetry;
+pub mod validate;
 
 use crate::error::{Result, WaylogError};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Get a provider by name
@@ -17,7 +23,6 @@ pub fn get_provider(name: &str) -> Result<Arc<dyn base::Provider>> {
 }
 
 /// Get all available providers
-#[allow(dead_code)]
 pub fn all_providers() -> Vec<Arc<dyn base::Provider>> {
     vec![
         Arc::new(codex::CodexProvider::new()),
@@ -25,7 +30,93 @@ pub fn all_providers() -> Vec<Arc<dyn base::Provider>> {
         Arc::new(gemini::GeminiProvider::new()),
     ]
 }
+/// The providers synced when none is named: every known provider that
+/// `[providers]` does not leave out, in `enabled` order if it lists them
+pub fn enabled_providers() -> Vec<Arc<dyn base::Provider>> {
+    let config = &crate::config::get().providers;
+    let mut providers: Vec<_> = all_providers()
+        .into_iter()
+        .filter(|p| config.is_enabled(p.name()))
+        .collect();
+    providers.sort_by_key(|p| {
+        config
+            .enabled
+            .iter()
+            .position(|e| e.eq_ignore_ascii_case(p.name()))
+    });
+    providers
+}
+
 /// Get a list of supported provider names
 pub fn list_providers() -> Vec<&'static str> {
     vec!["claude", "gemini", "codex"]
 }
+
+/// The provider whose data directory holds a session file
+pub fn provider_for_file(file: &Path) -> Result<Arc<dyn base::Provider>> {
+    let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    for name in list_providers() {
+        let provider = get_provider(name)?;
+        let Ok(data_dir) = provider.data_dir() else {
+            continue;
+        };
+        let data_dir = data_dir.canonicalize().unwrap_or(data_dir);
+        if file.starts_with(&data_dir) {
+            return Ok(provider);
+        }
+    }
+    Err(WaylogError::PathError(format!(
+        "{} is not a session file of any known provider; pass --provider",
+        file.display()
+    )))
+}
+
+/// Locate and parse the source file of a session by its id
+pub async fn find_session(
+    provider: &Arc<dyn base::Provider>,
+    project_path: &Path,
+    session_id: &str,
+) -> Result<Option<base::ChatSession>> {
+    for path in provider.get_project_sessions(project_path).await? {
+        match provider.parse_session(&path).await {
+            Ok(session) if session.session_id == session_id => return Ok(Some(session)),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    Ok(None)
+}
+
+/// Projects the providers have sessions for, with their number of session
+/// files, and the number of session files whose project is unknown
+pub async fn discover_projects(
+    providers: &[Arc<dyn base::Provider>],
+) -> Result<(BTreeMap<PathBuf, usize>, usize)> {
+    let mut projects = BTreeMap::new();
+    let mut unresolved = Vec::new();
+    for provider in providers {
+        for path in provider.get_all_sessions_global().await? {
+            match provider.session_project(&path).await {
+                Ok(Some(project)) => *projects.entry(project).or_insert(0) += 1,
+                Ok(None) => unresolved.push((provider, path)),
+                Err(e) => tracing::debug!("Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    // Providers that only keep an encoding of the project path (Gemini) are
+    // matched against the projects the others know about
+    let mut unmapped = 0;
+    for (provider, path) in unresolved {
+        let project = projects.keys().find(|project| {
+            provider
+                .session_dir(project)
+                .is_ok_and(|dir| path.starts_with(dir))
+        });
+        match project.cloned() {
+            Some(project) => *projects.entry(project).or_insert(0) += 1,
+            None => unmapped += 1,
+        }
+    }
+    Ok((projects, unmapped))
+}
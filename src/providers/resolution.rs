@@ -0,0 +1,205 @@
+//! Which session files belong to a project. Finding out means opening every
+//! candidate file (Claude skips sidechains, Codex matches the recorded
+//! working directory), so the answer is kept in `.waylog/state.json` and
+//! reused for as long as the provider's session directories are unchanged.
+use super::base::Provider;
+use crate::error::Result;
+use crate::session::state::{modified_nanos, ResolvedSessions, StateFile};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Directories changed this recently may still be getting a file whose
+/// header is not written yet, so their listing is not cached
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+
+/// The session files under each of `paths`, from the cache in
+/// `project_dir`'s state file where still valid, newest first
+pub async fn project_sessions<P: Provider + ?Sized>(
+    provider: &P,
+    project_dir: &Path,
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let mut state = StateFile::load(project_dir);
+    let mut changed = false;
+    let mut sessions = Vec::new();
+
+    for path in paths {
+        let dirs = fingerprint(&provider.listing_dirs(path)?);
+        let cached = state
+            .resolved
+            .get(provider.name())
+            .and_then(|resolved| resolved.get(path))
+            .filter(|resolved| resolved.dirs == dirs);
+        if let Some(cached) = cached {
+            tracing::debug!(
+                "{}: session list of {} cached",
+                provider.name(),
+                path.display()
+            );
+            sessions.extend(cached.sessions.iter().cloned());
+            continue;
+        }
+
+        let found = provider.get_all_sessions(path).await?;
+        if settled(&dirs) {
+            state
+                .resolved
+                .entry(provider.name().to_string())
+                .or_default()
+                .insert(
+                    path.clone(),
+                    ResolvedSessions {
+                        resolved_at: Utc::now(),
+                        dirs,
+                        sessions: found.clone(),
+                    },
+                );
+            changed = true;
+        }
+        sessions.extend(found);
+    }
+
+    if changed {
+        // Only a cache: a failed write costs the next run a full scan
+        if let Err(e) = state.save(project_dir) {
+            tracing::warn!("Failed to cache session list: {}", e);
+        }
+    }
+
+    sessions.sort();
+    sessions.dedup();
+    sessions.sort_by_key(|path| {
+        std::cmp::Reverse(std::fs::metadata(path).and_then(|m| m.modified()).ok())
+    });
+    Ok(sessions)
+}
+
+/// Modification time of each directory, 0 for one that does not exist
+fn fingerprint(dirs: &[PathBuf]) -> BTreeMap<PathBuf, u64> {
+    dirs.iter()
+        .map(|dir| {
+            let modified = std::fs::metadata(dir)
+                .map(|m| modified_nanos(&m))
+                .unwrap_or(0);
+            (dir.clone(), modified)
+        })
+        .collect()
+}
+
+/// Whether a listing of these directories can be cached: they all exist
+/// (an empty listing is cheap anyway) and none was changed just now
+fn settled(dirs: &BTreeMap<PathBuf, u64>) -> bool {
+    let Some(cutoff) = SystemTime::now()
+        .checked_sub(SETTLE_TIME)
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    else {
+        return false;
+    };
+    let cutoff = cutoff.as_nanos() as u64;
+    dirs.values()
+        .all(|&modified| modified != 0 && modified < cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::WaylogError;
+    use crate::init::WAYLOG_DIR;
+    use crate::providers::base::ChatSession;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// Lists the files of one directory, counting how often it does
+    struct DirProvider {
+        dir: PathBuf,
+        scans: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for DirProvider {
+        fn name(&self) -> &str {
+            "dir"
+        }
+
+        fn data_dir(&self) -> Result<PathBuf> {
+            Ok(self.dir.clone())
+        }
+
+        fn session_dir(&self, _project_path: &Path) -> Result<PathBuf> {
+            Ok(self.dir.clone())
+        }
+
+        async fn find_latest_session(&self, _project_path: &Path) -> Result<Option<PathBuf>> {
+            Ok(None)
+        }
+
+        async fn parse_session(&self, _file_path: &Path) -> Result<ChatSession> {
+            Err(WaylogError::Internal(
+                "resolution never parses sessions".to_string(),
+            ))
+        }
+
+        async fn get_all_sessions(&self, _project_path: &Path) -> Result<Vec<PathBuf>> {
+            self.scans.fetch_add(1, Ordering::SeqCst);
+            Ok(std::fs::read_dir(&self.dir)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .collect())
+        }
+
+        async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+            self.get_all_sessions(&self.dir).await
+        }
+
+        fn is_installed(&self) -> bool {
+            true
+        }
+
+        fn command(&self) -> &str {
+            "dir"
+        }
+    }
+
+    fn backdate(dir: &Path) {
+        let past = SystemTime::now() - Duration::from_secs(60);
+        std::fs::File::open(dir)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_listing_is_reused_until_directory_changes() {
+        let project = TempDir::new().unwrap();
+        std::fs::create_dir(project.path().join(WAYLOG_DIR)).unwrap();
+        let sessions = TempDir::new().unwrap();
+        std::fs::write(sessions.path().join("a.jsonl"), "").unwrap();
+        backdate(sessions.path());
+
+        let provider = DirProvider {
+            dir: sessions.path().to_path_buf(),
+            scans: AtomicUsize::new(0),
+        };
+        let paths = [project.path().to_path_buf()];
+
+        let first = project_sessions(&provider, project.path(), &paths)
+            .await
+            .unwrap();
+        let again = project_sessions(&provider, project.path(), &paths)
+            .await
+            .unwrap();
+        assert_eq!(first, again);
+        assert_eq!(provider.scans.load(Ordering::SeqCst), 1);
+
+        // A new session file changes the directory
+        std::fs::write(sessions.path().join("b.jsonl"), "").unwrap();
+        let found = project_sessions(&provider, project.path(), &paths)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(provider.scans.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,157 @@
+//! Unrecognized provider events seen during normal syncs. Parsers record what
+//! they skipped, and the counts are kept in `.waylog/format-telemetry.json` so
+//! `waylog doctor` can point out upstream format changes that drop data.
+use crate::error::Result;
+use crate::init::WAYLOG_DIR;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Telemetry file inside `.waylog`
+pub const TELEMETRY_FILE: &str = "format-telemetry.json";
+
+/// Event type -> number of occurrences
+pub type Counts = BTreeMap<String, usize>;
+
+/// Latest counts recorded by the parsers, per session file, not yet saved
+static PENDING: Mutex<BTreeMap<PathBuf, (String, Counts)>> = Mutex::new(BTreeMap::new());
+
+/// Record the unrecognized event types of one parse of a session file.
+/// Each parse replaces the previous counts of the file, so re-syncing a
+/// growing session does not count its events twice.
+pub fn record(provider: &str, file: &Path, unknown: Counts) {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.insert(file.to_path_buf(), (provider.to_string(), unknown));
+    }
+}
+
+/// Unrecognized events of a project since waylog was last upgraded
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FormatTelemetry {
+    /// waylog version the counts were collected with
+    pub version: String,
+    /// provider -> session file -> counts
+    pub providers: BTreeMap<String, BTreeMap<PathBuf, Counts>>,
+}
+
+impl FormatTelemetry {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(TELEMETRY_FILE)
+    }
+
+    /// Load the project's telemetry; counts from another waylog version are dropped
+    pub fn load(project_dir: &Path) -> Self {
+        let current = Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Self::default()
+        };
+        std::fs::read_to_string(Self::path(project_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|telemetry| telemetry.version == current.version)
+            .unwrap_or(current)
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        std::fs::write(Self::path(project_dir), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Set the counts of one session file; returns whether anything changed
+    pub fn update(&mut self, provider: &str, file: &Path, unknown: Counts) -> bool {
+        let files = self.providers.entry(provider.to_string()).or_default();
+        let changed = if unknown.is_empty() {
+            files.remove(file).is_some()
+        } else {
+            files.insert(file.to_path_buf(), unknown.clone()) != Some(unknown)
+        };
+        self.providers.retain(|_, files| !files.is_empty());
+        changed
+    }
+
+    /// Unrecognized event types of a provider, summed over its session files
+    pub fn totals(&self, provider: &str) -> Counts {
+        let mut totals = Counts::new();
+        for counts in self
+            .providers
+            .get(provider)
+            .into_iter()
+            .flat_map(|f| f.values())
+        {
+            for (event_type, count) in counts {
+                *totals.entry(event_type.clone()).or_default() += count;
+            }
+        }
+        totals
+    }
+}
+
+/// Save what the parsers recorded since the last flush into the project's
+/// telemetry file. Does nothing when the counts are unchanged.
+pub fn flush(project_dir: &Path) -> Result<()> {
+    let pending = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return Ok(()),
+    };
+    if pending.is_empty() || !project_dir.join(WAYLOG_DIR).is_dir() {
+        return Ok(());
+    }
+
+    let mut telemetry = FormatTelemetry::load(project_dir);
+    let mut changed = false;
+    for (file, (provider, unknown)) in pending {
+        changed |= telemetry.update(&provider, &file, unknown);
+    }
+    if changed {
+        telemetry.save(project_dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(pairs: &[(&str, usize)]) -> Counts {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_update_replaces_counts_per_file() {
+        let mut telemetry = FormatTelemetry::default();
+        let a = Path::new("a.jsonl");
+        let b = Path::new("b.jsonl");
+
+        assert!(telemetry.update("claude", a, counts(&[("progress", 2)])));
+        assert!(telemetry.update("claude", b, counts(&[("progress", 1), ("hint", 1)])));
+        // Re-parsing the same file does not add up
+        assert!(!telemetry.update("claude", a, counts(&[("progress", 2)])));
+        assert_eq!(
+            telemetry.totals("claude"),
+            counts(&[("hint", 1), ("progress", 3)])
+        );
+
+        assert!(telemetry.update("claude", a, Counts::new()));
+        assert!(telemetry.update("claude", b, Counts::new()));
+        assert!(telemetry.providers.is_empty());
+        assert!(telemetry.totals("claude").is_empty());
+    }
+
+    #[test]
+    fn test_load_resets_after_upgrade() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join(WAYLOG_DIR)).unwrap();
+
+        let mut old = FormatTelemetry {
+            version: "0.0.1".to_string(),
+            ..FormatTelemetry::default()
+        };
+        old.update("codex", Path::new("x.jsonl"), counts(&[("new_event", 5)]));
+        old.save(dir.path()).unwrap();
+
+        let loaded = FormatTelemetry::load(dir.path());
+        assert_eq!(loaded.version, env!("CARGO_PKG_VERSION"));
+        assert!(loaded.providers.is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! Checks of provider source files against what the parsers understand, so
+//! upstream format changes show up before they silently drop data.
+use crate::error::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Event types, fields and content block types a provider's parser knows,
+/// whether it reads them or deliberately ignores them
+#[derive(Debug)]
+pub struct KnownFormat {
+    pub event_types: &'static [&'static str],
+    pub fields: &'static [&'static str],
+    pub content_types: &'static [&'static str],
+}
+
+impl KnownFormat {
+    /// Count `event_type` in `unknown` unless the parser knows it
+    pub fn tally_event(&self, event_type: &str, unknown: &mut BTreeMap<String, usize>) {
+        if !self.event_types.contains(&event_type) {
+            *unknown.entry(event_type.to_string()).or_default() += 1;
+        }
+    }
+}
+
+/// A source line that failed to parse or type-check
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub file: PathBuf,
+    /// 1-based line for JSONL files
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Findings of `waylog validate` for one provider
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub files: usize,
+    pub events: usize,
+    pub errors: Vec<ValidationError>,
+    /// Unrecognized values with the number of times they were seen
+    pub unknown_event_types: BTreeMap<String, usize>,
+    pub unknown_fields: BTreeMap<String, usize>,
+    pub unknown_content_types: BTreeMap<String, usize>,
+}
+
+impl ValidationReport {
+    /// Whether nothing unexpected was found
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+            && self.unknown_event_types.is_empty()
+            && self.unknown_fields.is_empty()
+            && self.unknown_content_types.is_empty()
+    }
+
+    pub fn error(&mut self, file: &Path, line: Option<usize>, message: impl Into<String>) {
+        self.errors.push(ValidationError {
+            file: file.to_path_buf(),
+            line,
+            message: message.into(),
+        });
+    }
+
+    /// Check one event against `format`, and type-check it as `E`
+    pub fn check_event<E: DeserializeOwned>(
+        &mut self,
+        event: Value,
+        format: &KnownFormat,
+        content: impl Fn(&Value) -> Option<&Vec<Value>>,
+        file: &Path,
+        line: Option<usize>,
+    ) {
+        self.events += 1;
+
+        if let Some(event_type) = event.get("type").and_then(Value::as_str) {
+            if !format.event_types.contains(&event_type) {
+                *self
+                    .unknown_event_types
+                    .entry(event_type.to_string())
+                    .or_default() += 1;
+            }
+        }
+
+        if let Some(object) = event.as_object() {
+            for key in object.keys() {
+                if !format.fields.contains(&key.as_str()) {
+                    *self.unknown_fields.entry(key.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        for item in content(&event).into_iter().flatten() {
+            if let Some(content_type) = item.get("type").and_then(Value::as_str) {
+                if !format.content_types.contains(&content_type) {
+                    *self
+                        .unknown_content_types
+                        .entry(content_type.to_string())
+                        .or_default() += 1;
+                }
+            }
+        }
+
+        if let Err(e) = serde_json::from_value::<E>(event) {
+            self.error(file, line, e.to_string());
+        }
+    }
+}
+
+/// Validate every line of a JSONL session file
+pub async fn validate_jsonl<E: DeserializeOwned>(
+    path: &Path,
+    format: &KnownFormat,
+    content: impl Fn(&Value) -> Option<&Vec<Value>>,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    report.files += 1;
+
+    let file = fs::File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut line_number = 0;
+
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(event) => report.check_event::<E>(event, format, &content, path, Some(line_number)),
+            Err(e) => report.error(path, Some(line_number), e.to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    const FORMAT: KnownFormat = KnownFormat {
+        event_types: &["user"],
+        fields: &["type", "count", "message"],
+        content_types: &["text"],
+    };
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Event {
+        count: Option<u32>,
+    }
+
+    fn content(event: &Value) -> Option<&Vec<Value>> {
+        event.get("message")?.as_array()
+    }
+
+    #[test]
+    fn test_check_event_reports_unknowns() {
+        let file = Path::new("session.jsonl");
+        let mut report = ValidationReport::default();
+
+        report.check_event::<Event>(
+            json!({"type": "user", "message": [{"type": "text"}]}),
+            &FORMAT,
+            content,
+            file,
+            Some(1),
+        );
+        assert!(report.is_clean());
+
+        report.check_event::<Event>(
+            json!({"type": "progress", "extra": 1, "message": [{"type": "audio"}]}),
+            &FORMAT,
+            content,
+            file,
+            Some(2),
+        );
+        assert_eq!(report.unknown_event_types["progress"], 1);
+        assert_eq!(report.unknown_fields["extra"], 1);
+        assert_eq!(report.unknown_content_types["audio"], 1);
+        assert!(report.errors.is_empty());
+
+        // Known field with the wrong type
+        report.check_event::<Event>(
+            json!({"type": "user", "count": "x"}),
+            &FORMAT,
+            content,
+            file,
+            Some(3),
+        );
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, Some(3));
+        assert_eq!(report.events, 3);
+    }
+}
@@ -0,0 +1,134 @@
+//! Audit trail of who read which transcripts: with `[audit] access_log`,
+//! commands that open or export sessions append one JSON line to
+//! `.waylog/access.log`. The log is only ever appended to.
+use crate::error::Result;
+use crate::exporter::anonymize::{host_name, user_name};
+use crate::init::WAYLOG_DIR;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Log file inside `.waylog`
+pub const ACCESS_LOG: &str = "access.log";
+
+/// One access to one or more sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessEntry {
+    pub at: DateTime<Utc>,
+    pub user: Option<String>,
+    pub host: Option<String>,
+    /// The waylog command that read the sessions, e.g. `open` or `export`
+    pub command: String,
+    pub sessions: Vec<String>,
+    /// Where the sessions went: an output file or directory, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Where the sessions were uploaded to, e.g. a gist, if anywhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Where accessed sessions went
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    /// An output file or directory
+    File(&'a Path),
+    /// The URL of an upload
+    Url(&'a str),
+}
+
+pub fn log_path(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(ACCESS_LOG)
+}
+
+/// Record an access if `[audit] access_log` is on
+pub fn record<S: AsRef<str>>(
+    project_dir: &Path,
+    command: &str,
+    sessions: &[S],
+    target: Option<Target<'_>>,
+) -> Result<()> {
+    if !crate::config::get().audit.access_log || sessions.is_empty() {
+        return Ok(());
+    }
+    append(
+        project_dir,
+        &AccessEntry {
+            at: Utc::now(),
+            user: user_name(),
+            host: host_name(),
+            command: command.to_string(),
+            sessions: sessions.iter().map(|s| s.as_ref().to_string()).collect(),
+            target: match target {
+                Some(Target::File(path)) => Some(path.display().to_string()),
+                _ => None,
+            },
+            url: match target {
+                Some(Target::Url(url)) => Some(url.to_string()),
+                _ => None,
+            },
+        },
+    )
+}
+
+fn append(project_dir: &Path, entry: &AccessEntry) -> Result<()> {
+    if !project_dir.join(WAYLOG_DIR).is_dir() {
+        return Ok(());
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path(project_dir))?;
+    // One write per line, so concurrent waylog processes don't interleave
+    file.write_all(format!("{}\n", serde_json::to_string(entry)?).as_bytes())?;
+    Ok(())
+}
+
+/// Every recorded access, oldest first; unreadable lines are skipped
+#[cfg(test)]
+fn read(project_dir: &Path) -> Result<Vec<AccessEntry>> {
+    match std::fs::read_to_string(log_path(project_dir)) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(command: &str) -> AccessEntry {
+        AccessEntry {
+            at: Utc::now(),
+            user: Some("alice".to_string()),
+            host: None,
+            command: command.to_string(),
+            sessions: vec!["s1".to_string(), "s2".to_string()],
+            target: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_entries_are_appended() {
+        let dir = TempDir::new().unwrap();
+        append(dir.path(), &entry("open")).unwrap();
+        assert!(read(dir.path()).unwrap().is_empty(), "no .waylog, no log");
+
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+        append(dir.path(), &entry("open")).unwrap();
+        append(dir.path(), &entry("export")).unwrap();
+        let commands: Vec<_> = read(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.command)
+            .collect();
+        assert_eq!(commands, ["open", "export"]);
+    }
+}
@@ -0,0 +1,179 @@
+//! Reading the history back for other programs (`waylog mcp`, `waylog
+//! serve`): the synced sessions with what their frontmatter says, the text
+//! of one session, and sessions containing some words.
+use crate::error::{Result, WaylogError};
+use crate::exporter::{encryption, frontmatter, markdown::parts};
+use crate::session::SessionTracker;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Characters of context on each side of a search match
+const SNIPPET_CONTEXT: usize = 80;
+
+/// A synced session, as described by its history file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub provider: String,
+    pub title: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub message_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The (first) history file, relative to the project
+    pub markdown_path: PathBuf,
+}
+
+/// A session containing every word searched for
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub session: SessionSummary,
+    /// Where the first word occurs, with some text around it
+    pub snippet: String,
+}
+
+/// Every synced session of `project`, most recently updated first
+pub async fn list(project: &Path) -> Result<Vec<SessionSummary>> {
+    let state = SessionTracker::load_project_state(project).await?;
+    let mut sessions = Vec::new();
+    for session in state.sessions.values() {
+        let Ok(content) = encryption::read(&session.markdown_path).await else {
+            continue;
+        };
+        sessions.push(summarize(project, &session.markdown_path, &content));
+    }
+    sessions.sort_by(|a, b| {
+        b.updated_at
+            .cmp(&a.updated_at)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    Ok(sessions)
+}
+
+/// The session `query` (an id, id prefix or part of the file name) and the
+/// markdown of all its parts
+pub async fn read(project: &Path, query: &str) -> Result<(SessionSummary, String)> {
+    let state = SessionTracker::load_project_state(project).await?;
+    let session = state
+        .find_session(query)
+        .ok_or_else(|| WaylogError::SessionNotFound(query.to_string()))?;
+
+    let mut summary = None;
+    let mut content = String::new();
+    for part in parts::existing_parts(&session.markdown_path) {
+        let text = encryption::read(&part).await?;
+        summary.get_or_insert_with(|| summarize(project, &session.markdown_path, &text));
+        content.push_str(&text);
+    }
+    let summary = summary.ok_or_else(|| WaylogError::SessionNotFound(query.to_string()))?;
+    Ok((summary, content))
+}
+
+/// Sessions containing every word of `query` (ignoring case), most
+/// recently updated first
+pub async fn search(project: &Path, query: &str) -> Result<Vec<SearchHit>> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut hits = Vec::new();
+    for session in list(project).await? {
+        let path = project.join(&session.markdown_path);
+        let mut content = String::new();
+        for part in parts::existing_parts(&path) {
+            content.push_str(&encryption::read(&part).await.unwrap_or_default());
+        }
+        let lowered = content.to_lowercase();
+        if !words.iter().all(|word| lowered.contains(word.as_str())) {
+            continue;
+        }
+        let snippet = snippet(&content, &words[0]);
+        hits.push(SearchHit { session, snippet });
+    }
+    Ok(hits)
+}
+
+/// The last `count` messages of `content` (a history file), headers included
+pub fn last_messages(content: &str, count: usize) -> String {
+    let level = crate::config::get().export.headers.level;
+    let (_, messages) = super::repair::split_messages(content, level);
+    let skip = messages.len().saturating_sub(count);
+    messages
+        .into_iter()
+        .skip(skip)
+        .map(|(_, text)| text)
+        .collect()
+}
+
+/// What the frontmatter of `content`, the history file at `markdown_path`, says
+pub fn summarize(project: &Path, markdown_path: &Path, content: &str) -> SessionSummary {
+    let fm = frontmatter::parse_frontmatter_str(content);
+    let title = fm.title.clone().or_else(|| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(str::to_string)
+    });
+    SessionSummary {
+        session_id: fm.session_id.unwrap_or_default(),
+        provider: fm.provider.unwrap_or_default(),
+        title: title.unwrap_or_default(),
+        started_at: fm.started_at,
+        updated_at: fm.updated_at,
+        message_count: fm.message_count.unwrap_or(0),
+        total_tokens: fm.total_tokens,
+        tags: fm.tags,
+        markdown_path: markdown_path
+            .strip_prefix(project)
+            .unwrap_or(markdown_path)
+            .to_path_buf(),
+    }
+}
+
+/// The text around the first occurrence of `word` (lowercase) in `content`,
+/// on one line
+fn snippet(content: &str, word: &str) -> String {
+    let lowered = content.to_lowercase();
+    // Lowercasing can change byte lengths; fall back to the start if so
+    let at = lowered
+        .find(word)
+        .filter(|_| lowered.len() == content.len())
+        .unwrap_or(0);
+    let mut start = at.saturating_sub(SNIPPET_CONTEXT);
+    while !content.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (at + word.len() + SNIPPET_CONTEXT).min(content.len());
+    while !content.is_char_boundary(end) {
+        end += 1;
+    }
+    content[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_and_last_messages() {
+        let content = "---\nmessage_count: 3\n---\n\n# t\n\n## User (1)\n\nhow do we\nfix the Cache?\n\n## Assistant (2)\n\nflush it\n\n## User (3)\n\nthanks\n\n";
+        assert_eq!(
+            snippet("a b\nWhere is the cache? c", "cache"),
+            "a b Where is the cache? c"
+        );
+        assert_eq!(
+            last_messages(content, 2),
+            "## Assistant (2)\n\nflush it\n\n## User (3)\n\nthanks\n\n"
+        );
+        assert_eq!(last_messages(content, 9).matches("## ").count(), 3);
+    }
+}
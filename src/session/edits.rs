@@ -0,0 +1,145 @@
+//! Checksums of the markdown files waylog last wrote, kept in
+//! `.waylog/exported-files.json`. A file whose content no longer matches was
+//! edited by hand since, and appending to it could corrupt the edit or its
+//! `message_count`; `[sync] on_conflict` decides what happens instead.
+use crate::error::Result;
+use crate::exporter::markdown::parts;
+use crate::init::WAYLOG_DIR;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Record file inside `.waylog`
+pub const EXPORTED_FILES: &str = "exported-files.json";
+
+/// Bumped whenever the way files are checksummed changes
+const FORMAT_VERSION: u32 = 1;
+
+/// Checksum of each session's markdown files (all parts), by first file name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportedFiles {
+    version: u32,
+    files: BTreeMap<String, String>,
+}
+
+fn key(markdown_path: &Path) -> String {
+    markdown_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+impl ExportedFiles {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(EXPORTED_FILES)
+    }
+
+    /// Load the project's record; a missing, unreadable or outdated one is empty
+    pub fn load(project_dir: &Path) -> Self {
+        std::fs::read_to_string(Self::path(project_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|record| record.version == FORMAT_VERSION)
+            .unwrap_or_else(|| Self {
+                version: FORMAT_VERSION,
+                files: BTreeMap::new(),
+            })
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        if !project_dir.join(WAYLOG_DIR).is_dir() {
+            return Ok(());
+        }
+        std::fs::write(Self::path(project_dir), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Whether a session's files changed since waylog last wrote them. Files
+    /// written before checksums were recorded are taken as unedited.
+    pub fn is_edited(&self, markdown_path: &Path) -> bool {
+        self.files
+            .get(&key(markdown_path))
+            .is_some_and(|recorded| *recorded != parts::checksum(markdown_path))
+    }
+
+    /// Remember the current content of a session's files as waylog's own
+    pub fn record(&mut self, markdown_path: &Path) {
+        let checksum = parts::checksum(markdown_path);
+        if checksum.is_empty() {
+            self.files.remove(&key(markdown_path));
+        } else {
+            self.files.insert(key(markdown_path), checksum);
+        }
+    }
+
+    /// Record a session's files after waylog itself rewrote them outside a
+    /// sync (`redact`, `scan-secrets`, `merge`)
+    pub fn refresh(project_dir: &Path, markdown_path: &Path) -> Result<()> {
+        let mut record = Self::load(project_dir);
+        record.record(markdown_path);
+        record.save(project_dir)
+    }
+}
+
+/// Name of the next revision of a session file: `<name>-rev2.md` for
+/// `<name>.md`, `<name>-rev3.md` for `<name>-rev2.md`, ... skipping names
+/// already taken
+pub fn next_revision(markdown_path: &Path) -> (PathBuf, usize) {
+    let stem = markdown_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let (base, current) = match stem.rsplit_once("-rev") {
+        Some((base, n)) if n.parse::<usize>().is_ok_and(|n| n > 1) => {
+            (base.to_string(), n.parse().unwrap_or(1))
+        }
+        _ => (stem, 1),
+    };
+    (current + 1..)
+        .map(|n| {
+            (
+                markdown_path.with_file_name(format!("{}-rev{}.md", base, n)),
+                n,
+            )
+        })
+        .find(|(path, _)| !crate::exporter::encryption::exists(path))
+        .unwrap_or_else(|| (markdown_path.to_path_buf(), current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_edits_are_detected() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.md");
+        std::fs::write(&file, "---\nmessage_count: 1\n---\n").unwrap();
+
+        let mut record = ExportedFiles::default();
+        assert!(!record.is_edited(&file), "unrecorded files are not edits");
+        record.record(&file);
+        assert!(!record.is_edited(&file));
+
+        std::fs::write(&file, "---\nmessage_count: 1\n---\nmy notes\n").unwrap();
+        assert!(record.is_edited(&file));
+    }
+
+    #[test]
+    fn test_next_revision() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("2025-01-15-claude-fix.md");
+        assert_eq!(
+            next_revision(&file),
+            (dir.path().join("2025-01-15-claude-fix-rev2.md"), 2)
+        );
+        std::fs::write(dir.path().join("2025-01-15-claude-fix-rev3.md"), "").unwrap();
+        assert_eq!(
+            next_revision(&dir.path().join("2025-01-15-claude-fix-rev2.md")),
+            (dir.path().join("2025-01-15-claude-fix-rev4.md"), 4)
+        );
+    }
+}
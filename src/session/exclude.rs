@@ -0,0 +1,136 @@
+use crate::config::ExcludeConfig;
+use crate::providers::base::ChatSession;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Decides which sessions are never exported (`[exclude]`)
+#[derive(Debug, Default)]
+pub struct SessionFilter {
+    min_messages: usize,
+    patterns: Vec<Regex>,
+    session_ids: Vec<String>,
+}
+
+impl SessionFilter {
+    pub fn new(config: &ExcludeConfig) -> Self {
+        // Patterns are validated when the config is loaded
+        let patterns = config
+            .patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    tracing::warn!("Ignoring exclude pattern '{}': {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            min_messages: config.min_messages,
+            patterns,
+            session_ids: config.session_ids.clone(),
+        }
+    }
+
+    /// Filter for the active project config
+    pub fn from_config() -> &'static Self {
+        static FILTER: OnceLock<SessionFilter> = OnceLock::new();
+        FILTER.get_or_init(|| Self::new(&crate::config::get().exclude))
+    }
+
+    /// Whether deciding needs the content of every message (exclude patterns)
+    pub fn inspects_messages(&self) -> bool {
+        !self.patterns.is_empty()
+    }
+
+    /// Why `session` is excluded, or `None` if it should be exported
+    pub fn reason(&self, session: &ChatSession) -> Option<String> {
+        self.reason_after(session, 0)
+    }
+
+    /// Like `reason`, for a session parsed incrementally: `session` holds only
+    /// the messages after the first `earlier`, which were already checked
+    pub fn reason_after(&self, session: &ChatSession, earlier: usize) -> Option<String> {
+        if self.session_ids.contains(&session.session_id) {
+            return Some("excluded session id".to_string());
+        }
+
+        let count = earlier + session.messages.len();
+        if count < self.min_messages {
+            return Some(format!(
+                "{} of {} required messages",
+                count, self.min_messages
+            ));
+        }
+
+        for re in &self.patterns {
+            if session.messages.iter().any(|m| re.is_match(&m.content)) {
+                return Some(format!("matches exclude pattern '{}'", re.as_str()));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{ChatMessage, MessageMetadata, MessageRole};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn session(id: &str, contents: &[&str]) -> ChatSession {
+        ChatSession {
+            session_id: id.to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/project"),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            messages: contents
+                .iter()
+                .map(|content| ChatMessage {
+                    id: "m".to_string(),
+                    role: MessageRole::User,
+                    content: content.to_string(),
+                    timestamp: Utc::now(),
+                    metadata: MessageMetadata::default(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_excludes_nothing() {
+        let filter = SessionFilter::new(&ExcludeConfig::default());
+        assert_eq!(filter.reason(&session("a", &["scratch"])), None);
+    }
+
+    #[test]
+    fn test_exclusion_reasons() {
+        let filter = SessionFilter::new(&ExcludeConfig {
+            min_messages: 2,
+            patterns: vec!["(?i)scratch".to_string()],
+            session_ids: vec!["secret".to_string()],
+        });
+
+        assert_eq!(filter.reason(&session("a", &["hi", "there"])), None);
+        assert_eq!(
+            filter.reason(&session("a", &["hi"])).as_deref(),
+            Some("1 of 2 required messages")
+        );
+        assert_eq!(
+            filter
+                .reason(&session("a", &["hi", "Scratch pad"]))
+                .as_deref(),
+            Some("matches exclude pattern '(?i)scratch'")
+        );
+        assert_eq!(
+            filter
+                .reason(&session("secret", &["hi", "there"]))
+                .as_deref(),
+            Some("excluded session id")
+        );
+    }
+}
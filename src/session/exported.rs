@@ -0,0 +1,191 @@
+//! Which messages of each session are already in the history, kept in
+//! `.waylog/exported-messages.json`. New messages are those whose key is not
+//! recorded yet, so a provider file that is reordered or rewritten, or a
+//! message that is logged twice, neither duplicates nor drops messages.
+use crate::error::Result;
+use crate::init::WAYLOG_DIR;
+use crate::providers::base::ChatMessage;
+use crate::utils::atomic;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Record file inside `.waylog`
+pub const EXPORTED_FILE: &str = "exported-messages.json";
+
+/// Bumped whenever the way messages are keyed changes
+const FORMAT_VERSION: u32 = 2;
+
+/// Keys of the exported messages per session, in the order they were written
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportedMessages {
+    version: u32,
+    sessions: BTreeMap<String, Vec<String>>,
+}
+
+/// Identifies a message by the provider's id for it or, for providers that
+/// log none, by what it says, who said it and when
+pub fn message_key(message: &ChatMessage) -> String {
+    if !message.id.is_empty() {
+        return format!("id:{}", message.id);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{:?}", message.role));
+    hasher.update([0]);
+    hasher.update(message.timestamp.to_rfc3339());
+    hasher.update([0]);
+    hasher.update(&message.content);
+    hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl ExportedMessages {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(EXPORTED_FILE)
+    }
+
+    /// Load the project's record; a missing, unreadable or outdated one is empty
+    pub fn load(project_dir: &Path) -> Self {
+        std::fs::read_to_string(Self::path(project_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|record| record.version == FORMAT_VERSION)
+            .unwrap_or_else(|| Self {
+                version: FORMAT_VERSION,
+                sessions: BTreeMap::new(),
+            })
+    }
+
+    pub async fn save(&self, project_dir: &Path) -> Result<()> {
+        if !project_dir.join(WAYLOG_DIR).is_dir() {
+            return Ok(());
+        }
+        atomic::write(&Self::path(project_dir), serde_json::to_string(self)?).await
+    }
+
+    /// Whether the record of a session covers exactly its `synced` messages
+    pub fn tracks(&self, session_id: &str, synced: usize) -> bool {
+        synced == 0
+            || self
+                .sessions
+                .get(session_id)
+                .is_some_and(|keys| keys.len() == synced)
+    }
+
+    /// The messages not yet in the history, given that `synced` messages were
+    /// written and `messages` starts after the first `earlier` of the session.
+    /// Sessions exported before keys were recorded fall back to the count,
+    /// and their record is seeded from the messages it skips.
+    pub fn new_messages(
+        &mut self,
+        session_id: &str,
+        messages: &[ChatMessage],
+        synced: usize,
+        earlier: usize,
+    ) -> Vec<ChatMessage> {
+        if !self.tracks(session_id, synced) {
+            let skip = synced.saturating_sub(earlier);
+            if earlier > 0 || messages.len() < skip {
+                return messages.iter().skip(skip).cloned().collect();
+            }
+            self.sessions.insert(
+                session_id.to_string(),
+                messages[..skip].iter().map(message_key).collect(),
+            );
+        }
+
+        let mut seen: HashSet<String> = match self.sessions.get(session_id) {
+            Some(keys) if synced > 0 => keys.iter().cloned().collect(),
+            _ => HashSet::new(),
+        };
+        messages
+            .iter()
+            .filter(|m| seen.insert(message_key(m)))
+            .cloned()
+            .collect()
+    }
+
+    /// Record `written` after the `synced` messages already in the history
+    pub fn record(&mut self, session_id: &str, synced: usize, written: &[ChatMessage]) {
+        let keys = self.sessions.entry(session_id.to_string()).or_default();
+        if synced == 0 {
+            keys.clear();
+        } else if keys.len() != synced {
+            // The record no longer matches the history; count-based from here
+            self.sessions.remove(session_id);
+            return;
+        }
+        keys.extend(written.iter().map(message_key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{MessageMetadata, MessageRole};
+    use chrono::{TimeZone, Utc};
+
+    fn message(second: u32, content: &str) -> ChatMessage {
+        ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, second).unwrap(),
+            role: MessageRole::User,
+            content: content.to_string(),
+            metadata: MessageMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_new_messages_by_key() {
+        let mut record = ExportedMessages::default();
+        let (a, b, c) = (message(0, "a"), message(1, "b"), message(2, "c"));
+
+        let new = record.new_messages("s", &[a.clone(), b.clone(), b.clone()], 0, 0);
+        assert_eq!(new.len(), 2, "duplicates are written once");
+        record.record("s", 0, &new);
+
+        // Reordered and grown: only the unseen message is new
+        let new = record.new_messages("s", &[b.clone(), c.clone(), a.clone()], 2, 0);
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].content, "c");
+        record.record("s", 2, &new);
+        assert!(record.tracks("s", 3));
+    }
+
+    #[test]
+    fn test_message_key_prefers_the_provider_id() {
+        let (a, mut edited) = (message(0, "a"), message(0, "a"));
+        edited.id = a.id.clone();
+        edited.content = "a, reworded".to_string();
+        assert_eq!(message_key(&a), message_key(&edited));
+        // Repeating a message is not the same message
+        assert_ne!(message_key(&a), message_key(&message(0, "a")));
+
+        // Without ids, messages are told apart by their content
+        let (mut b, mut c) = (message(1, "b"), message(1, "b"));
+        b.id.clear();
+        c.id.clear();
+        assert_eq!(message_key(&b), message_key(&c));
+        c.content = "c".to_string();
+        assert_ne!(message_key(&b), message_key(&c));
+    }
+
+    #[test]
+    fn test_untracked_sessions_are_seeded_from_the_count() {
+        let mut record = ExportedMessages::default();
+        let messages = [message(0, "a"), message(1, "b"), message(2, "c")];
+
+        let new = record.new_messages("s", &messages, 2, 0);
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].content, "c");
+        record.record("s", 2, &new);
+        assert!(record.tracks("s", 3));
+
+        // A record that no longer matches the history is dropped
+        record.record("s", 5, &[message(3, "d")]);
+        assert!(!record.tracks("s", 6));
+    }
+}
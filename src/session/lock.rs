@@ -0,0 +1,183 @@
+//! One writer per project: commands that write history hold an exclusive
+//! lock on `.waylog/waylog.lock` while they run, so a `pull` next to an
+//! active `run` (or two pulls) cannot append the same messages twice.
+//!
+//! The lock is taken with the operating system's file locking, which is
+//! released when the holding process exits for whatever reason. A lock file
+//! left behind by a crashed waylog is therefore stale by construction: it is
+//! simply locked again and its holder details overwritten.
+use crate::cli::{Commands, SnapshotAction, TrashAction};
+use crate::error::{Result, WaylogError};
+use crate::exporter::anonymize::host_name;
+use crate::init::WAYLOG_DIR;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Lock file inside `.waylog`
+pub const LOCK_FILE: &str = "waylog.lock";
+
+/// Who holds the lock, written into the lock file for the error message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub host: Option<String>,
+    /// The waylog command line, e.g. `waylog run claude`
+    pub command: String,
+    pub started: DateTime<Local>,
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` (pid {}", self.command, self.pid)?;
+        if let Some(host) = &self.host {
+            write!(f, " on {}", host)?;
+        }
+        write!(f, ", since {})", self.started.format("%Y-%m-%d %H:%M:%S"))
+    }
+}
+
+/// The held lock; released when dropped
+#[derive(Debug)]
+pub struct ProjectLock {
+    _file: File,
+}
+
+pub fn lock_path(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(LOCK_FILE)
+}
+
+/// Whether a command writes to the project's history and must hold the lock
+pub fn needed_by(command: &Commands) -> bool {
+    match command {
+        Commands::Run { .. }
+        | Commands::Batch { .. }
+        | Commands::Import { .. }
+        | Commands::Merge { .. }
+        | Commands::Gc { .. } => true,
+        // Each project of `--all-projects` is pulled, and locked, by its own process
+        Commands::Pull {
+            dry_run,
+            all_projects,
+            ..
+        } => !dry_run && !all_projects,
+        Commands::Redact { dry_run } | Commands::Repair { dry_run } => !dry_run,
+        Commands::ScanSecrets { fix, .. } => *fix,
+        Commands::Trash { action } => matches!(action, TrashAction::Restore { .. }),
+        Commands::Snapshot { action } => matches!(action, SnapshotAction::Create { .. }),
+        _ => false,
+    }
+}
+
+/// Take the project lock. If another process holds it, fail with its
+/// details, or with `wait` block until it is released; `on_wait` is told
+/// who is being waited for first. Projects without `.waylog` need no lock.
+pub async fn acquire(
+    project_dir: &Path,
+    wait: bool,
+    on_wait: impl FnOnce(Option<&LockHolder>) -> Result<()>,
+) -> Result<Option<ProjectLock>> {
+    if !project_dir.join(WAYLOG_DIR).is_dir() {
+        return Ok(None);
+    }
+
+    let path = lock_path(project_dir);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = read_holder(&mut file);
+            if !wait {
+                return Err(WaylogError::ProjectLocked(match holder {
+                    Some(holder) => holder.to_string(),
+                    None => "another waylog process".to_string(),
+                }));
+            }
+            on_wait(holder.as_ref())?;
+            file = tokio::task::spawn_blocking(move || file.lock().map(|()| file))
+                .await
+                .map_err(|e| WaylogError::Internal(e.to_string()))??;
+        }
+        Err(TryLockError::Error(e)) if e.kind() == std::io::ErrorKind::Unsupported => {
+            // Some network filesystems cannot lock; carry on unprotected
+            tracing::warn!("Cannot lock {}: {}", path.display(), e);
+            return Ok(None);
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+
+    write_holder(&mut file)?;
+    Ok(Some(ProjectLock { _file: file }))
+}
+
+fn read_holder(file: &mut File) -> Option<LockHolder> {
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_holder(file: &mut File) -> Result<()> {
+    let holder = LockHolder {
+        pid: std::process::id(),
+        host: host_name(),
+        command: command_line(),
+        started: Local::now(),
+    };
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(serde_json::to_string(&holder)?.as_bytes())?;
+    Ok(())
+}
+
+/// This process's command line, with the program shortened to `waylog`
+fn command_line() -> String {
+    std::iter::once("waylog".to_string())
+        .chain(std::env::args().skip(1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_second_lock_fails_until_first_is_dropped() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+
+        let first = acquire(dir.path(), false, |_| Ok(())).await.unwrap();
+        assert!(first.is_some());
+        match acquire(dir.path(), false, |_| Ok(())).await {
+            Err(WaylogError::ProjectLocked(holder)) => {
+                assert!(holder.contains(&format!("pid {}", std::process::id())))
+            }
+            other => panic!("expected a locked project, got {:?}", other),
+        }
+
+        drop(first);
+        assert!(acquire(dir.path(), false, |_| Ok(()))
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_no_lock_without_waylog_dir() {
+        let dir = TempDir::new().unwrap();
+        assert!(acquire(dir.path(), false, |_| Ok(()))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!lock_path(dir.path()).exists());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::providers::base::{ChatMessage, ChatSession};
+use std::collections::HashSet;
+
+/// Combine several sessions into one, ordered chronologically.
+///
+/// The first session provides the identity (id, provider, project) of the result.
+/// Messages are de-duplicated by id, and by role/timestamp/content for providers
+/// that generate fresh ids on every parse.
+pub fn merge_sessions(sessions: &[ChatSession]) -> Option<ChatSession> {
+    let primary = sessions.first()?;
+
+    let mut messages: Vec<ChatMessage> = sessions
+        .iter()
+        .flat_map(|s| s.messages.iter().cloned())
+        .collect();
+    // Stable sort keeps the original order of messages sharing a timestamp
+    messages.sort_by_key(|m| m.timestamp);
+
+    let mut seen_ids = HashSet::new();
+    let mut seen_content = HashSet::new();
+    messages.retain(|m| {
+        let content_key = (m.role, m.timestamp, m.content.clone());
+        (m.id.is_empty() || seen_ids.insert(m.id.clone())) && seen_content.insert(content_key)
+    });
+
+    Some(ChatSession {
+        session_id: primary.session_id.clone(),
+        provider: primary.provider.clone(),
+        project_path: primary.project_path.clone(),
+        started_at: sessions.iter().map(|s| s.started_at).min()?,
+        updated_at: sessions.iter().map(|s| s.updated_at).max()?,
+        messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{MessageMetadata, MessageRole};
+    use chrono::{Duration, Utc};
+    use std::path::PathBuf;
+
+    fn session(id: &str, messages: &[(&str, i64, &str)]) -> ChatSession {
+        let base = Utc::now();
+        let messages: Vec<_> = messages
+            .iter()
+            .map(|(msg_id, offset, content)| ChatMessage {
+                id: msg_id.to_string(),
+                timestamp: base + Duration::seconds(*offset),
+                role: MessageRole::User,
+                content: content.to_string(),
+                metadata: MessageMetadata::default(),
+            })
+            .collect();
+
+        ChatSession {
+            session_id: id.to_string(),
+            provider: "claude".to_string(),
+            project_path: PathBuf::from("/test/project"),
+            started_at: messages.first().unwrap().timestamp,
+            updated_at: messages.last().unwrap().timestamp,
+            messages,
+        }
+    }
+
+    #[test]
+    fn test_merge_orders_and_deduplicates() {
+        let first = session("a", &[("m1", 0, "one"), ("m3", 20, "three")]);
+        let second = session("b", &[("m2", 10, "two"), ("m3", 20, "three")]);
+
+        let merged = merge_sessions(&[first.clone(), second.clone()]).unwrap();
+
+        assert_eq!(merged.session_id, "a");
+        let ids: Vec<_> = merged.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2", "m3"]);
+        assert_eq!(merged.started_at, first.started_at);
+        assert_eq!(merged.updated_at, first.updated_at.max(second.updated_at));
+    }
+
+    #[test]
+    fn test_merge_empty() {
+        assert!(merge_sessions(&[]).is_none());
+    }
+}
@@ -1,4 +1,16 @@
+pub mod access;
+pub mod catalog;
+pub mod edits;
+pub mod exclude;
+pub mod exported;
+pub mod lock;
+pub mod merge;
+pub mod offsets;
+pub mod repair;
+pub mod report;
+pub mod snapshot;
 pub mod state;
 pub mod tracker;
+pub mod trash;
 
 pub use tracker::SessionTracker;
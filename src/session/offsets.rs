@@ -0,0 +1,131 @@
+//! Where parsing of each provider session file stopped, kept in
+//! `.waylog/parse-index.json` so a sync of a growing session only reads the
+//! lines appended since the last one.
+use crate::error::Result;
+use crate::init::WAYLOG_DIR;
+use crate::providers::base::ParseCursor;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Index file inside `.waylog`
+pub const PARSE_INDEX_FILE: &str = "parse-index.json";
+
+/// Bumped whenever cursors saved by an older waylog can no longer be resumed from
+const FORMAT_VERSION: u32 = 2;
+
+/// Bytes before the cursor that must be unchanged for it to be resumed from
+const TAIL_LEN: u64 = 256;
+
+/// Parse cursors per session file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParseIndex {
+    version: u32,
+    files: BTreeMap<PathBuf, Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    cursor: ParseCursor,
+    /// SHA-256 of the bytes just before `cursor.offset`
+    tail: String,
+}
+
+/// Hash of the bytes before `offset`, or `None` if the file is shorter
+fn tail_hash(file: &Path, offset: u64) -> Option<String> {
+    let mut handle = std::fs::File::open(file).ok()?;
+    let start = offset.saturating_sub(TAIL_LEN);
+    handle.seek(SeekFrom::Start(start)).ok()?;
+    let mut tail = vec![0; (offset - start) as usize];
+    handle.read_exact(&mut tail).ok()?;
+    Some(
+        Sha256::digest(&tail)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+impl ParseIndex {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(PARSE_INDEX_FILE)
+    }
+
+    /// Load the project's index; a missing, unreadable or outdated one is empty
+    pub fn load(project_dir: &Path) -> Self {
+        std::fs::read_to_string(Self::path(project_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|index| index.version == FORMAT_VERSION)
+            .unwrap_or_else(|| Self {
+                version: FORMAT_VERSION,
+                files: BTreeMap::new(),
+            })
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        if !project_dir.join(WAYLOG_DIR).is_dir() {
+            return Ok(());
+        }
+        std::fs::write(Self::path(project_dir), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Cursor of a session file, if it can still be resumed from: a file
+    /// that shrank or changed before the cursor since was rewritten and has
+    /// to be parsed again
+    pub fn get(&self, file: &Path) -> Option<&ParseCursor> {
+        let entry = self.files.get(file)?;
+        (tail_hash(file, entry.cursor.offset)? == entry.tail).then_some(&entry.cursor)
+    }
+
+    pub fn set(&mut self, file: &Path, cursor: ParseCursor) {
+        match tail_hash(file, cursor.offset) {
+            Some(tail) => {
+                self.files
+                    .insert(file.to_path_buf(), Entry { cursor, tail });
+            }
+            None => self.remove(file),
+        }
+    }
+
+    pub fn remove(&mut self, file: &Path) {
+        self.files.remove(file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rewritten_files_are_not_resumed() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+        let file = dir.path().join("s.jsonl");
+        std::fs::write(&file, "0123456789").unwrap();
+
+        let mut index = ParseIndex::load(dir.path());
+        index.set(
+            &file,
+            ParseCursor {
+                offset: 10,
+                message_count: 2,
+                ..ParseCursor::default()
+            },
+        );
+        index.save(dir.path()).unwrap();
+
+        let index = ParseIndex::load(dir.path());
+        assert_eq!(index.get(&file).unwrap().message_count, 2);
+        std::fs::write(&file, "0123456789 appended").unwrap();
+        assert!(index.get(&file).is_some());
+        std::fs::write(&file, "01234").unwrap();
+        assert!(index.get(&file).is_none());
+        std::fs::write(&file, "9876543210 rewritten").unwrap();
+        assert!(index.get(&file).is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! History files that came apart while `.waylog/history` was synced between
+//! machines: conflict copies left by Dropbox or Syncthing, git conflict
+//! markers, and several files for one session. Their messages are merged
+//! back into one file, matched by header (role and time), which is what
+//! identifies a message in a history file.
+use crate::exporter::frontmatter;
+use std::collections::HashMap;
+
+/// Whether `name` is a copy a file-sync tool kept of a file changed on two
+/// machines at once, e.g. `a (laptop's conflicted copy 2025-01-15).md` or
+/// `a.sync-conflict-20250115-093000-ABCDEFG.md`
+pub fn is_conflict_copy(name: &str) -> bool {
+    name.contains(" conflicted copy") || name.contains(".sync-conflict-")
+}
+
+/// The two versions of content with git conflict markers, or `None` if it
+/// has none. The common ancestor of a `diff3` conflict is left out.
+pub fn conflict_sides(content: &str) -> Option<(String, String)> {
+    if !content.lines().any(|line| line.starts_with("<<<<<<<")) {
+        return None;
+    }
+
+    enum Side {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+    let (mut ours, mut theirs) = (String::new(), String::new());
+    let mut side = Side::Both;
+    for line in content.split_inclusive('\n') {
+        let marker = line.trim_end();
+        side = match side {
+            _ if marker.starts_with("<<<<<<<") => Side::Ours,
+            Side::Ours | Side::Base if marker.starts_with("|||||||") => Side::Base,
+            Side::Ours | Side::Base if marker == "=======" => Side::Theirs,
+            Side::Theirs if marker.starts_with(">>>>>>>") => Side::Both,
+            side => {
+                match side {
+                    Side::Both => {
+                        ours.push_str(line);
+                        theirs.push_str(line);
+                    }
+                    Side::Ours => ours.push_str(line),
+                    Side::Theirs => theirs.push_str(line),
+                    Side::Base => {}
+                }
+                side
+            }
+        };
+    }
+    Some((ours, theirs))
+}
+
+/// A history file put together from several versions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub content: String,
+    /// Messages found only in the other versions
+    pub added: usize,
+}
+
+/// Add the messages of `others` missing from `primary`, each after the
+/// message it follows in its own version. `level` is the heading level of
+/// message headers. Of two versions of one message, the longer is kept,
+/// since the shorter one was cut off. `message_count` is updated.
+pub fn merge_versions(primary: &str, others: &[&str], level: u8) -> Merged {
+    let (preamble, mut blocks) = split_messages(primary, level);
+    let mut added = 0;
+    for other in others {
+        let (_, other_blocks) = split_messages(other, level);
+        let mut after: Option<usize> = None;
+        for (key, text) in other_blocks {
+            let at = match blocks.iter().position(|(k, _)| *k == key) {
+                Some(i) => {
+                    if text.len() > blocks[i].1.len() {
+                        blocks[i].1 = text;
+                    }
+                    i
+                }
+                None => {
+                    let i = after.map_or(0, |i| i + 1);
+                    blocks.insert(i, (key, text));
+                    added += 1;
+                    i
+                }
+            };
+            after = Some(at);
+        }
+    }
+
+    let mut content = preamble;
+    for (_, text) in &blocks {
+        content.push_str(text);
+    }
+    if added > 0 {
+        if let Some(count) = frontmatter::parse_frontmatter_str(&content).message_count {
+            content =
+                frontmatter::set_field(&content, "message_count", &(count + added).to_string());
+        }
+    }
+    Merged { content, added }
+}
+
+/// What comes before the first message, and each message with the header
+/// it is known by (repeats of a header are numbered)
+pub(crate) fn split_messages(content: &str, level: u8) -> (String, Vec<(String, String)>) {
+    let prefix = format!("{} ", "#".repeat(level.clamp(1, 6) as usize));
+    let mut preamble = String::new();
+    let mut blocks: Vec<(String, String)> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut in_code = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        }
+        let is_header = !in_code && trimmed.starts_with(&prefix) && trimmed.ends_with(')');
+        if is_header {
+            let repeat = seen.entry(trimmed.to_string()).or_insert(0);
+            *repeat += 1;
+            blocks.push((format!("{}#{}", trimmed, repeat), line.to_string()));
+        } else if let Some((_, text)) = blocks.last_mut() {
+            text.push_str(line);
+        } else {
+            preamble.push_str(line);
+        }
+    }
+    (preamble, blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAD: &str = "---\nprovider: claude\nsession_id: s1\nmessage_count: 2\n---\n\n# hi\n\n";
+
+    #[test]
+    fn test_is_conflict_copy() {
+        assert!(is_conflict_copy(
+            "a (laptop's conflicted copy 2025-01-15).md"
+        ));
+        assert!(is_conflict_copy(
+            "a.sync-conflict-20250115-093000-ABCDEFG.md"
+        ));
+        assert!(!is_conflict_copy(
+            "2025-01-15_09-30-00Z-claude-fix-conflicts.md"
+        ));
+    }
+
+    #[test]
+    fn test_merge_versions_adds_missing_messages_in_order() {
+        let laptop = format!(
+            "{}## User (10:00)\n\nhi\n\n## Assistant (10:01)\n\nhello\n\n",
+            HEAD
+        );
+        let desktop = format!(
+            "{}## User (10:00)\n\nhi\n\n## User (10:02)\n\nthanks\n\n## Assistant (10:03)\n\n```\n## not (a header)\n```\n\n",
+            HEAD
+        );
+
+        let merged = merge_versions(&laptop, &[&desktop], 2);
+        assert_eq!(merged.added, 2);
+        assert_eq!(
+            merged.content,
+            format!(
+                "{}## User (10:00)\n\nhi\n\n## User (10:02)\n\nthanks\n\n## Assistant (10:03)\n\n```\n## not (a header)\n```\n\n## Assistant (10:01)\n\nhello\n\n",
+                HEAD.replace("message_count: 2", "message_count: 4")
+            )
+        );
+
+        // Merging again changes nothing
+        let again = merge_versions(&merged.content, &[&desktop], 2);
+        assert_eq!(again.added, 0);
+        assert_eq!(again.content, merged.content);
+    }
+
+    #[test]
+    fn test_merge_versions_keeps_the_longer_message() {
+        let cut = format!("{}## User (10:00)\n\nhi th", HEAD);
+        let whole = format!("{}## User (10:00)\n\nhi there\n\n", HEAD);
+        let merged = merge_versions(&cut, &[&whole], 2);
+        assert_eq!(merged.added, 0);
+        assert_eq!(merged.content, whole);
+    }
+
+    #[test]
+    fn test_conflict_sides() {
+        assert_eq!(conflict_sides("no conflict\n"), None);
+
+        let content =
+            "a\n<<<<<<< HEAD\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> origin/main\nb\n";
+        let (ours, theirs) = conflict_sides(content).unwrap();
+        assert_eq!(ours, "a\nours\nb\n");
+        assert_eq!(theirs, "a\ntheirs\nb\n");
+    }
+}
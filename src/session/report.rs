@@ -0,0 +1,279 @@
+//! Outcome of the last `waylog pull --report`, kept in
+//! `.waylog/last-sync.json` so CI jobs and scripts can check sync health
+//! without parsing the terminal output. Each pull replaces the file.
+//!
+//! `pull --output json` prints the same outcome grouped by provider, as one
+//! `waylog.pull.v1` document ([`PullDocument`]).
+use crate::error::Result;
+use crate::init::WAYLOG_DIR;
+use crate::synchronizer::SyncStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Report file inside `.waylog`
+pub const LAST_SYNC_FILE: &str = "last-sync.json";
+
+/// Bumped whenever fields are renamed or removed
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct SyncReport {
+    version: u32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub dry_run: bool,
+    pub totals: Totals,
+    pub sessions: Vec<SessionResult>,
+    /// Providers whose sessions could not be listed at all
+    pub provider_errors: Vec<ProviderError>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Totals {
+    pub synced: usize,
+    pub up_to_date: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub new_messages: usize,
+    /// Malformed source lines skipped
+    pub warnings: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResult {
+    pub provider: String,
+    pub file: PathBuf,
+    /// `synced`, `up_to_date`, `would_sync`, `skipped` or `failed`
+    pub status: &'static str,
+    /// History file written (or that would be, on a dry run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown_path: Option<PathBuf>,
+    pub new_messages: usize,
+    /// Malformed source lines skipped while reading the session
+    pub warnings: usize,
+    /// Why a session was skipped, or what failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderError {
+    pub provider: String,
+    pub error: String,
+}
+
+impl SyncReport {
+    pub fn new(dry_run: bool) -> Self {
+        let now = Utc::now();
+        Self {
+            version: FORMAT_VERSION,
+            started_at: now,
+            finished_at: now,
+            duration_ms: 0,
+            dry_run,
+            totals: Totals::default(),
+            sessions: Vec::new(),
+            provider_errors: Vec::new(),
+        }
+    }
+
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(LAST_SYNC_FILE)
+    }
+
+    pub fn add(&mut self, provider: &str, file: &Path, status: &SyncStatus, took: Duration) {
+        let mut warnings_here = 0;
+        let mut markdown_path = None;
+        let (name, new_messages, reason) = match status {
+            SyncStatus::Synced {
+                new_messages,
+                warnings,
+            } => {
+                self.totals.synced += 1;
+                self.totals.warnings += warnings;
+                warnings_here = *warnings;
+                ("synced", *new_messages, None)
+            }
+            SyncStatus::WouldSync {
+                new_messages,
+                markdown_path: path,
+                ..
+            } => {
+                markdown_path = Some(path.clone());
+                ("would_sync", *new_messages, None)
+            }
+            SyncStatus::UpToDate => {
+                self.totals.up_to_date += 1;
+                ("up_to_date", 0, None)
+            }
+            SyncStatus::Skipped(reason) => {
+                self.totals.skipped += 1;
+                ("skipped", 0, Some(reason.clone()))
+            }
+            SyncStatus::Failed(error) => {
+                self.totals.failed += 1;
+                ("failed", 0, Some(error.clone()))
+            }
+        };
+        self.totals.new_messages += new_messages;
+        self.sessions.push(SessionResult {
+            provider: provider.to_string(),
+            file: file.to_path_buf(),
+            status: name,
+            markdown_path,
+            new_messages,
+            warnings: warnings_here,
+            reason,
+            duration_ms: took.as_millis() as u64,
+        });
+    }
+
+    pub fn provider_failed(&mut self, provider: &str, error: String) {
+        self.provider_errors.push(ProviderError {
+            provider: provider.to_string(),
+            error,
+        });
+    }
+
+    /// Record the history file each of `provider`'s synced sessions went to
+    pub fn link_history(&mut self, provider: &str, history: impl Fn(&Path) -> Option<PathBuf>) {
+        for session in &mut self.sessions {
+            if session.provider == provider && session.markdown_path.is_none() {
+                session.markdown_path = history(&session.file);
+            }
+        }
+    }
+
+    /// Stamp the end time
+    pub fn finish(&mut self) {
+        self.finished_at = Utc::now();
+        self.duration_ms = (self.finished_at - self.started_at)
+            .num_milliseconds()
+            .max(0) as u64;
+    }
+
+    /// Write the report
+    pub async fn save(&self, project_dir: &Path) -> Result<()> {
+        if !project_dir.join(WAYLOG_DIR).is_dir() {
+            return Ok(());
+        }
+        crate::utils::atomic::write(
+            &Self::path(project_dir),
+            serde_json::to_string_pretty(self)?,
+        )
+        .await
+    }
+
+    /// The outcome as a `waylog.pull.v1` document, by provider in the order
+    /// they were pulled
+    pub fn document<'a>(&'a self, project: &'a Path) -> PullDocument<'a> {
+        let mut providers: Vec<ProviderResult> = Vec::new();
+        for session in &self.sessions {
+            provider_entry(&mut providers, &session.provider)
+                .sessions
+                .push(session);
+        }
+        for failure in &self.provider_errors {
+            provider_entry(&mut providers, &failure.provider).error = Some(&failure.error);
+        }
+
+        PullDocument {
+            schema: crate::exporter::schema::PULL_SCHEMA,
+            project,
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            duration_ms: self.duration_ms,
+            dry_run: self.dry_run,
+            totals: &self.totals,
+            providers,
+        }
+    }
+}
+
+/// What `pull --output json` prints: the whole outcome of a pull
+#[derive(Debug, Serialize)]
+pub struct PullDocument<'a> {
+    pub schema: &'static str,
+    pub project: &'a Path,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub dry_run: bool,
+    pub totals: &'a Totals,
+    pub providers: Vec<ProviderResult<'a>>,
+}
+
+/// The sessions of one provider, or why they could not be listed
+#[derive(Debug, Serialize)]
+pub struct ProviderResult<'a> {
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'a str>,
+    pub sessions: Vec<&'a SessionResult>,
+}
+
+/// The entry of `name` in `providers`, added at the end if it is not there yet
+fn provider_entry<'a, 'b>(
+    providers: &'b mut Vec<ProviderResult<'a>>,
+    name: &'a str,
+) -> &'b mut ProviderResult<'a> {
+    let i = match providers.iter().position(|p| p.name == name) {
+        Some(i) => i,
+        None => {
+            providers.push(ProviderResult {
+                name,
+                error: None,
+                sessions: Vec::new(),
+            });
+            providers.len() - 1
+        }
+    };
+    &mut providers[i]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_groups_sessions_by_provider() {
+        let mut report = SyncReport::new(false);
+        let took = Duration::from_millis(5);
+        report.add(
+            "claude",
+            Path::new("a.jsonl"),
+            &SyncStatus::Synced {
+                new_messages: 3,
+                warnings: 0,
+            },
+            took,
+        );
+        report.add("codex", Path::new("b.jsonl"), &SyncStatus::UpToDate, took);
+        report.add(
+            "claude",
+            Path::new("c.jsonl"),
+            &SyncStatus::Failed("bad".to_string()),
+            took,
+        );
+        report.provider_failed("gemini", "unreadable".to_string());
+        report.link_history("claude", |file| {
+            (file == Path::new("a.jsonl")).then(|| PathBuf::from("history/a.md"))
+        });
+
+        let document = serde_json::to_value(report.document(Path::new("/p"))).unwrap();
+        assert_eq!(document["schema"], "waylog.pull.v1");
+        assert_eq!(document["totals"]["new_messages"], 3);
+        let providers = document["providers"].as_array().unwrap();
+        let names: Vec<_> = providers.iter().map(|p| p["name"].clone()).collect();
+        assert_eq!(names, ["claude", "codex", "gemini"]);
+        assert_eq!(providers[0]["sessions"][0]["markdown_path"], "history/a.md");
+        assert_eq!(providers[0]["sessions"][1]["reason"], "bad");
+        assert!(providers[0]["sessions"][1].get("markdown_path").is_none());
+        assert_eq!(providers[2]["error"], "unreadable");
+        assert_eq!(providers[2]["sessions"], serde_json::json!([]));
+    }
+}
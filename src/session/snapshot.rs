@@ -0,0 +1,288 @@
+//! Named checkpoints of the history. `waylog snapshot create <name>` records
+//! which sessions exist, how many messages each had and a checksum of its
+//! files in `.waylog/snapshots/<name>.json`; `waylog snapshot diff` and
+//! `waylog diff --since-tag <name>` compare the history against it.
+use super::state::ProjectState;
+use crate::error::{Result, WaylogError};
+use crate::exporter::markdown::parts;
+use crate::init::{subdirs, WAYLOG_DIR};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The history at the time a snapshot was taken
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// session_id -> what was synced of it
+    pub sessions: BTreeMap<String, SnapshotSession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotSession {
+    pub provider: String,
+    /// Markdown file name inside `.waylog/history`
+    pub file: String,
+    pub message_count: usize,
+    /// SHA-256 of the session's markdown files (empty in snapshots from before checksums)
+    #[serde(default)]
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Not in the earlier snapshot
+    New,
+    /// Has more messages
+    Appended,
+    /// Same or fewer messages, but its files were edited or re-rendered
+    Rewritten,
+    /// No longer in the history
+    Removed,
+}
+
+/// How one session differs between two snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionChange {
+    pub session_id: String,
+    pub provider: String,
+    pub file: String,
+    pub kind: ChangeKind,
+    /// Messages in the earlier snapshot (0 for new sessions)
+    pub before: usize,
+    /// Messages in the later snapshot (0 for removed sessions)
+    pub after: usize,
+}
+
+impl SessionChange {
+    /// Messages added between the snapshots
+    pub fn added(&self) -> usize {
+        self.after.saturating_sub(self.before)
+    }
+}
+
+impl Snapshot {
+    /// Record the synced sessions of a project
+    pub fn capture(name: &str, state: &ProjectState) -> Self {
+        let sessions = state
+            .sessions
+            .values()
+            .map(|s| {
+                let file = s
+                    .markdown_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (
+                    s.session_id.clone(),
+                    SnapshotSession {
+                        provider: s.provider.clone(),
+                        file,
+                        message_count: s.synced_message_count,
+                        checksum: parts::checksum(&s.markdown_path),
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            created_at: Utc::now(),
+            sessions,
+        }
+    }
+
+    pub fn path(project_dir: &Path, name: &str) -> PathBuf {
+        snapshots_dir(project_dir).join(format!("{}.json", name))
+    }
+
+    /// Write the snapshot; an existing snapshot of the same name is never replaced
+    pub fn save(&self, project_dir: &Path) -> Result<PathBuf> {
+        let path = Self::path(project_dir, &self.name);
+        if path.exists() {
+            return Err(WaylogError::SnapshotExists(self.name.clone()));
+        }
+        std::fs::create_dir_all(snapshots_dir(project_dir))?;
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(project_dir: &Path, name: &str) -> Result<Self> {
+        let path = Self::path(project_dir, name);
+        if !path.exists() {
+            return Err(WaylogError::SnapshotNotFound(name.to_string()));
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// All snapshots of a project, oldest first
+    pub fn list(project_dir: &Path) -> Result<Vec<Self>> {
+        let dir = snapshots_dir(project_dir);
+        let mut snapshots = Vec::new();
+        if !dir.exists() {
+            return Ok(snapshots);
+        }
+
+        for entry in std::fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match std::fs::read_to_string(&path)
+                .map_err(WaylogError::from)
+                .and_then(|content| Ok(serde_json::from_str::<Self>(&content)?))
+            {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => tracing::warn!("Skipping snapshot {}: {}", path.display(), e),
+            }
+        }
+        snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.name.cmp(&b.name)));
+        Ok(snapshots)
+    }
+
+    /// Total messages over all sessions
+    pub fn message_count(&self) -> usize {
+        self.sessions.values().map(|s| s.message_count).sum()
+    }
+
+    /// How the sessions changed from this snapshot to `later`, oldest file first
+    pub fn changes_to(&self, later: &Snapshot) -> Vec<SessionChange> {
+        let change = |id: &str, session: &SnapshotSession, kind, before, after| SessionChange {
+            session_id: id.to_string(),
+            provider: session.provider.clone(),
+            file: session.file.clone(),
+            kind,
+            before,
+            after,
+        };
+
+        let mut changes: Vec<_> = later
+            .sessions
+            .iter()
+            .filter_map(|(id, now)| {
+                let Some(then) = self.sessions.get(id) else {
+                    return Some(change(id, now, ChangeKind::New, 0, now.message_count));
+                };
+                let kind = if now.message_count > then.message_count {
+                    ChangeKind::Appended
+                } else if !then.checksum.is_empty() && then.checksum != now.checksum {
+                    ChangeKind::Rewritten
+                } else {
+                    return None;
+                };
+                Some(change(id, now, kind, then.message_count, now.message_count))
+            })
+            .collect();
+        changes.extend(
+            self.sessions
+                .iter()
+                .filter(|(id, _)| !later.sessions.contains_key(*id))
+                .map(|(id, then)| change(id, then, ChangeKind::Removed, then.message_count, 0)),
+        );
+        changes.sort_by(|a, b| a.file.cmp(&b.file).then(a.session_id.cmp(&b.session_id)));
+        changes
+    }
+}
+
+pub fn snapshots_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(subdirs::SNAPSHOTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::state::SessionState;
+    use tempfile::TempDir;
+
+    fn state(sessions: &[(&str, &str, usize)]) -> ProjectState {
+        state_in(Path::new("/p/.waylog/history"), sessions)
+    }
+
+    fn state_in(history: &Path, sessions: &[(&str, &str, usize)]) -> ProjectState {
+        let mut state = ProjectState::default();
+        for (id, file, count) in sessions {
+            state.upsert_session(SessionState {
+                session_id: id.to_string(),
+                provider: "claude".to_string(),
+                file_path: PathBuf::new(),
+                markdown_path: history.join(file),
+                synced_message_count: *count,
+                last_sync_time: Utc::now(),
+            });
+        }
+        state
+    }
+
+    #[test]
+    fn test_changes_since_snapshot() {
+        let tag = Snapshot::capture("v1", &state(&[("a", "1-a.md", 4), ("b", "2-b.md", 2)]));
+        let now = Snapshot::capture(
+            "now",
+            &state(&[("a", "1-a.md", 4), ("b", "2-b.md", 5), ("c", "3-c.md", 1)]),
+        );
+
+        let changes = tag.changes_to(&now);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].file, "2-b.md");
+        assert_eq!(changes[0].kind, ChangeKind::Appended);
+        assert_eq!((changes[0].before, changes[0].added()), (2, 3));
+        assert_eq!(changes[1].session_id, "c");
+        assert_eq!(changes[1].kind, ChangeKind::New);
+        assert_eq!(changes[1].added(), 1);
+
+        let removed = now.changes_to(&tag);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].kind, ChangeKind::Removed);
+        assert_eq!(removed[0].session_id, "c");
+    }
+
+    #[test]
+    fn test_edited_files_are_rewritten() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("1-a.md"), "one").unwrap();
+        let sessions = [("a", "1-a.md", 1)];
+        let before = Snapshot::capture("v1", &state_in(dir.path(), &sessions));
+        assert_eq!(before.sessions["a"].checksum.len(), 64);
+        assert!(before
+            .changes_to(&Snapshot::capture("now", &state_in(dir.path(), &sessions)))
+            .is_empty());
+
+        std::fs::write(dir.path().join("1-a.md"), "edited").unwrap();
+        let after = Snapshot::capture("now", &state_in(dir.path(), &sessions));
+        let changes = before.changes_to(&after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::Rewritten);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let snapshot = Snapshot::capture("pre-refactor", &state(&[("a", "1-a.md", 4)]));
+        snapshot.save(dir.path()).unwrap();
+
+        assert_eq!(
+            Snapshot::load(dir.path(), "pre-refactor").unwrap(),
+            snapshot
+        );
+        assert!(matches!(
+            snapshot.save(dir.path()),
+            Err(WaylogError::SnapshotExists(_))
+        ));
+        assert!(matches!(
+            Snapshot::load(dir.path(), "missing"),
+            Err(WaylogError::SnapshotNotFound(_))
+        ));
+
+        Snapshot::capture("post-refactor", &state(&[]))
+            .save(dir.path())
+            .unwrap();
+        let names: Vec<_> = Snapshot::list(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, ["pre-refactor", "post-refactor"]);
+    }
+}
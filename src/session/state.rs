@@ -1,5 +1,22 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+//! Sync state of a project's sessions. The markdown frontmatter is the source
+//! of truth; `.waylog/state.json` adds what it does not record (source files,
+//! sync times) and caches what was read from each history file, so unchanged
+//! files need not be parsed again on every run.
+use crate::error::Result;
+use crate::exporter::frontmatter::Frontmatter;
+use crate::init::WAYLOG_DIR;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// State file inside `.waylog`
+pub const STATE_FILE: &str = "state.json";
+
+/// Bumped whenever the layout of `state.json` changes; older files are
+/// migrated by [`StateFile::migrate`]
+const FORMAT_VERSION: u32 = 1;
 
 /// Session sync state - tracks which messages have been synced
 #[derive(Debug, Clone)]
@@ -49,4 +66,372 @@ impl ProjectState {
             .map(|s| s.synced_message_count)
             .unwrap_or(0)
     }
+
+    /// Find a session by exact ID, unique ID prefix, or unique markdown filename fragment
+    pub fn find_session(&self, query: &str) -> Option<&SessionState> {
+        if let Some(state) = self.sessions.get(query) {
+            return Some(state);
+        }
+
+        let by_prefix: Vec<_> = self
+            .sessions
+            .values()
+            .filter(|s| s.session_id.starts_with(query))
+            .collect();
+        if !by_prefix.is_empty() {
+            return Self::unique(by_prefix);
+        }
+
+        Self::unique(
+            self.sessions
+                .values()
+                .filter(|s| {
+                    s.markdown_path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().contains(query))
+                })
+                .collect(),
+        )
+    }
+
+    fn unique(matches: Vec<&SessionState>) -> Option<&SessionState> {
+        match matches.as_slice() {
+            [only] => Some(*only),
+            _ => None,
+        }
+    }
+}
+
+/// Where a session came from, which its markdown does not say
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSource {
+    pub provider: String,
+    pub file_path: PathBuf,
+    pub last_sync_time: DateTime<Utc>,
+}
+
+/// The frontmatter fields state is restored from, as read from a history
+/// file of the given size and modification time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub size: u64,
+    /// Nanoseconds since the Unix epoch
+    pub modified: u64,
+    pub session_id: Option<String>,
+    pub provider: Option<String>,
+    pub message_count: Option<usize>,
+    pub part: Option<usize>,
+    pub revision: Option<usize>,
+    pub merged_sessions: Vec<(String, usize)>,
+}
+
+impl FileEntry {
+    pub fn new(metadata: &std::fs::Metadata, fm: Frontmatter) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: modified_nanos(metadata),
+            session_id: fm.session_id,
+            provider: fm.provider,
+            message_count: fm.message_count,
+            part: fm.part,
+            revision: fm.revision,
+            merged_sessions: fm.merged_sessions,
+        }
+    }
+
+    /// Whether this entry still describes a file with `metadata`
+    pub fn matches(&self, metadata: &std::fs::Metadata) -> bool {
+        self.size == metadata.len() && self.modified == modified_nanos(metadata)
+    }
+}
+
+/// The session files a provider keeps for one project path, as listed while
+/// its session directories had the recorded modification times. Adding or
+/// removing a file changes the time of the directory holding it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedSessions {
+    pub resolved_at: DateTime<Utc>,
+    /// Nanoseconds since the Unix epoch, by directory
+    pub dirs: BTreeMap<PathBuf, u64>,
+    pub sessions: Vec<PathBuf>,
+}
+
+pub(crate) fn modified_nanos(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// `.waylog/state.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StateFile {
+    version: u32,
+    /// Machine that saved the file (`utils::machine::id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    machine: Option<String>,
+    /// Source of each synced session, by session id
+    pub sessions: BTreeMap<String, SessionSource>,
+    /// What was read from each history file, by file name
+    pub files: BTreeMap<String, FileEntry>,
+    /// Session files found for each project path, by provider
+    #[serde(default)]
+    pub resolved: BTreeMap<String, BTreeMap<PathBuf, ResolvedSessions>>,
+    /// Written by a newer waylog, so it is read as empty and left alone
+    #[serde(skip)]
+    newer: bool,
+}
+
+impl StateFile {
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(WAYLOG_DIR).join(STATE_FILE)
+    }
+
+    /// Load the project's state file; a missing or unreadable one is empty,
+    /// and everything is then restored from the markdown files
+    pub fn load(project_dir: &Path) -> Self {
+        let mut state = std::fs::read_to_string(Self::path(project_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .and_then(Self::migrate)
+            .unwrap_or_else(|| Self {
+                version: FORMAT_VERSION,
+                ..Self::default()
+            });
+        state.claim(crate::utils::machine::id());
+        state
+    }
+
+    /// Make the state this `machine`'s. The sources and resolved paths of a
+    /// state saved on another machine (a `.waylog` synced between machines)
+    /// point at that machine's files, so they are dropped; the file cache is
+    /// kept, as entries that no longer match are ignored anyway.
+    fn claim(&mut self, machine: String) {
+        if self.newer {
+            return;
+        }
+        if self
+            .machine
+            .as_ref()
+            .is_some_and(|saved_by| *saved_by != machine)
+        {
+            tracing::info!(
+                "Sync state was saved on {}; re-resolving the session sources",
+                self.machine.as_deref().unwrap_or_default()
+            );
+            self.sessions.clear();
+            self.resolved.clear();
+        }
+        self.machine = Some(machine);
+    }
+
+    /// Bring a state file of any earlier version up to the current one
+    fn migrate(mut value: serde_json::Value) -> Option<Self> {
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version == 0 {
+            // Unversioned state files held the full state of each session;
+            // the sources are kept, the counts are in the markdown
+            let sessions: BTreeMap<String, SessionSource> = value
+                .get("sessions")?
+                .as_object()?
+                .iter()
+                .filter_map(|(id, session)| {
+                    let source = SessionSource {
+                        provider: session.get("provider")?.as_str()?.to_string(),
+                        file_path: PathBuf::from(session.get("file_path")?.as_str()?),
+                        last_sync_time: session
+                            .get("last_sync_time")
+                            .and_then(|t| serde_json::from_value(t.clone()).ok())
+                            .unwrap_or(DateTime::UNIX_EPOCH),
+                    };
+                    Some((id.clone(), source))
+                })
+                .collect();
+            value = serde_json::json!({
+                "version": 1,
+                "sessions": sessions,
+                "files": {},
+            });
+        }
+
+        match value.get("version").and_then(|v| v.as_u64()) {
+            Some(v) if v == FORMAT_VERSION as u64 => serde_json::from_value(value).ok(),
+            _ => Some(Self {
+                version: FORMAT_VERSION,
+                newer: true,
+                ..Self::default()
+            }),
+        }
+    }
+
+    /// Take over the sources of the sessions in `state`
+    pub fn update_sessions(&mut self, state: &ProjectState) {
+        for session in state.sessions.values() {
+            if session.file_path.as_os_str().is_empty() {
+                continue;
+            }
+            self.sessions.insert(
+                session.session_id.clone(),
+                SessionSource {
+                    provider: session.provider.clone(),
+                    file_path: session.file_path.clone(),
+                    last_sync_time: session.last_sync_time,
+                },
+            );
+        }
+    }
+
+    /// Write the state file, keeping what another process saved meanwhile
+    /// where it is more recent
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        if self.newer || !project_dir.join(WAYLOG_DIR).is_dir() {
+            return Ok(());
+        }
+
+        let mut merged = Self::load(project_dir);
+        if merged.newer {
+            return Ok(());
+        }
+        for (id, source) in &self.sessions {
+            let keep_theirs = merged
+                .sessions
+                .get(id)
+                .is_some_and(|theirs| theirs.last_sync_time > source.last_sync_time);
+            if !keep_theirs {
+                merged.sessions.insert(id.clone(), source.clone());
+            }
+        }
+        // Stale entries are harmless: they no longer match their file
+        merged.files.extend(self.files.clone());
+        for (provider, paths) in &self.resolved {
+            let theirs = merged.resolved.entry(provider.clone()).or_default();
+            for (path, resolved) in paths {
+                if theirs
+                    .get(path)
+                    .is_none_or(|t| t.resolved_at <= resolved.resolved_at)
+                {
+                    theirs.insert(path.clone(), resolved.clone());
+                }
+            }
+        }
+
+        std::fs::write(Self::path(project_dir), serde_json::to_string(&merged)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(sessions: &[(&str, &str)]) -> ProjectState {
+        let mut state = ProjectState::default();
+        for (id, file) in sessions {
+            state.upsert_session(SessionState {
+                session_id: id.to_string(),
+                provider: "claude".to_string(),
+                file_path: PathBuf::new(),
+                markdown_path: PathBuf::from(file),
+                synced_message_count: 1,
+                last_sync_time: chrono::Utc::now(),
+            });
+        }
+        state
+    }
+
+    #[test]
+    fn test_find_session_exact_and_prefix() {
+        let state = state_with(&[
+            ("abc-123", "2024-01-01_10-00-00Z-claude-hello.md"),
+            ("abd-456", "2024-01-02_10-00-00Z-claude-fix-bug.md"),
+        ]);
+
+        assert_eq!(state.find_session("abc-123").unwrap().session_id, "abc-123");
+        assert_eq!(state.find_session("abd").unwrap().session_id, "abd-456");
+        // Ambiguous prefix resolves to nothing
+        assert!(state.find_session("ab").is_none());
+    }
+
+    #[test]
+    fn test_state_file_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+
+        let mut state = state_with(&[("abc-123", "a.md")]);
+        state.sessions.get_mut("abc-123").unwrap().file_path = PathBuf::from("/src/a.jsonl");
+        let mut file = StateFile::load(dir.path());
+        file.update_sessions(&state);
+        file.save(dir.path()).unwrap();
+
+        let loaded = StateFile::load(dir.path());
+        assert_eq!(
+            loaded.sessions["abc-123"].file_path,
+            PathBuf::from("/src/a.jsonl")
+        );
+    }
+
+    #[test]
+    fn test_state_file_migrates_unversioned() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+        std::fs::write(
+            StateFile::path(dir.path()),
+            r#"{"sessions":{"abc-123":{"session_id":"abc-123","provider":"claude","file_path":"/src/a.jsonl","markdown_path":"/p/.waylog/history/a.md","synced_message_count":3,"last_sync_time":"2025-01-15T09:30:00Z"}}}"#,
+        )
+        .unwrap();
+
+        let loaded = StateFile::load(dir.path());
+        assert_eq!(loaded.version, FORMAT_VERSION);
+        let source = &loaded.sessions["abc-123"];
+        assert_eq!(source.provider, "claude");
+        assert_eq!(source.file_path, PathBuf::from("/src/a.jsonl"));
+        assert_eq!(
+            source.last_sync_time.to_rfc3339(),
+            "2025-01-15T09:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_state_file_from_newer_version_is_left_alone() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(WAYLOG_DIR)).unwrap();
+        let newer = r#"{"version":99,"sessions":{}}"#;
+        std::fs::write(StateFile::path(dir.path()), newer).unwrap();
+
+        let file = StateFile::load(dir.path());
+        assert!(file.sessions.is_empty());
+        file.save(dir.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(StateFile::path(dir.path())).unwrap(),
+            newer
+        );
+    }
+
+    #[test]
+    fn test_state_file_from_another_machine_keeps_no_sources() {
+        let mut file: StateFile = serde_json::from_str(
+            r#"{"version":1,"machine":"laptop","sessions":{"abc-123":{"provider":"claude","file_path":"/home/me/a.jsonl","last_sync_time":"2025-01-15T09:30:00Z"}},"files":{}}"#,
+        )
+        .unwrap();
+        file.claim("laptop".to_string());
+        assert_eq!(file.sessions.len(), 1);
+
+        file.claim("desktop".to_string());
+        assert!(file.sessions.is_empty());
+        assert_eq!(file.machine.as_deref(), Some("desktop"));
+    }
+
+    #[test]
+    fn test_find_session_by_filename() {
+        let state = state_with(&[
+            ("abc-123", "2024-01-01_10-00-00Z-claude-hello.md"),
+            ("abd-456", "2024-01-02_10-00-00Z-claude-fix-bug.md"),
+        ]);
+
+        assert_eq!(state.find_session("fix-bug").unwrap().session_id, "abd-456");
+        assert!(state.find_session("claude").is_none());
+        assert!(state.find_session("missing").is_none());
+    }
 }
@@ -1,42 +1,69 @@
 mod restore;
 
 use crate::error::Result;
-use crate::providers::base::{ChatSession, Provider};
-use crate::session::state::{ProjectState, SessionState};
+use crate::providers::base::{ChatMessage, ChatSession, Provider};
+use crate::session::edits::ExportedFiles;
+use crate::session::exported::ExportedMessages;
+use crate::session::state::{ProjectState, SessionState, StateFile};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
 
 /// Session tracker - manages active sessions and their sync state
 pub struct SessionTracker {
     project_dir: PathBuf,
     provider: Arc<dyn Provider>,
     state: Arc<Mutex<ProjectState>>,
+    /// Which messages of each session were written
+    exported: Arc<Mutex<ExportedMessages>>,
+    /// Checksums of the files last written, to notice hand edits
+    files: Arc<Mutex<ExportedFiles>>,
+    /// `.waylog/state.json` as restored, saved back with the current state
+    state_file: Arc<Mutex<StateFile>>,
+    /// Held while a history file is written, so that notes added to it
+    /// (`waylog note`) and synced messages don't write over each other
+    writing: Arc<Mutex<()>>,
 }
 
 impl SessionTracker {
     /// Create a new session tracker
     pub async fn new(project_dir: PathBuf, provider: Arc<dyn Provider>) -> Result<Self> {
-        // Start with empty state (stateless design)
-        let state = ProjectState {
-            sessions: std::collections::HashMap::new(),
-        };
+        // Restore state from existing markdown files
+        let (sessions, state_file) =
+            restore::restore_from_disk(&project_dir, provider.name()).await?;
 
-        let tracker = Self {
+        let exported = Arc::new(Mutex::new(ExportedMessages::load(&project_dir)));
+        let files = Arc::new(Mutex::new(ExportedFiles::load(&project_dir)));
+        Ok(Self {
             project_dir,
             provider,
-            state: Arc::new(Mutex::new(state)),
-        };
+            state: Arc::new(Mutex::new(ProjectState { sessions })),
+            exported,
+            files,
+            state_file: Arc::new(Mutex::new(state_file)),
+            writing: Arc::new(Mutex::new(())),
+        })
+    }
 
-        // Restore state from existing markdown files
-        let sessions_map =
-            restore::restore_from_disk(&tracker.project_dir, tracker.provider.name()).await?;
-        if !sessions_map.is_empty() {
-            let mut state = tracker.state.lock().await;
-            state.sessions = sessions_map;
+    /// A tracker for another provider's sessions that shares this one's
+    /// records, so that syncing both in one process does not save over
+    /// either's state
+    pub fn for_provider(&self, provider: Arc<dyn Provider>) -> Self {
+        Self {
+            project_dir: self.project_dir.clone(),
+            provider,
+            state: self.state.clone(),
+            exported: self.exported.clone(),
+            files: self.files.clone(),
+            state_file: self.state_file.clone(),
+            writing: self.writing.clone(),
         }
+    }
 
-        Ok(tracker)
+    /// Load the sync state of every exported session in a project, across all providers
+    pub async fn load_project_state(project_dir: &Path) -> Result<ProjectState> {
+        let (sessions, _) = restore::restore_from_disk(project_dir, "unknown").await?;
+        Ok(ProjectState { sessions })
     }
 
     /// Get the current sync state
@@ -46,8 +73,34 @@ impl SessionTracker {
 
     /// Save the current state to disk
     pub async fn save_state(&self) -> Result<()> {
-        // Persistence disabled: Markdown files are the source of truth
-        Ok(())
+        // Markdown files are the source of truth for the state itself;
+        // state.json only adds the session sources and a cache of the
+        // frontmatter, beside the keys of the exported messages and the file
+        // checksums
+        {
+            let state = self.state.lock().await;
+            let mut state_file = self.state_file.lock().await;
+            state_file.update_sessions(&state);
+            state_file.save(&self.project_dir)?;
+        }
+        self.exported.lock().await.save(&self.project_dir).await?;
+        self.files.lock().await.save(&self.project_dir)
+    }
+
+    /// Whether a session's markdown was edited by hand since it was last synced
+    pub async fn is_edited(&self, markdown_path: &Path) -> bool {
+        self.files.lock().await.is_edited(markdown_path)
+    }
+
+    /// Wait until no other task of this process writes a history file, and
+    /// keep them waiting until the guard is dropped
+    pub async fn lock_writes(&self) -> MutexGuard<'_, ()> {
+        self.writing.lock().await
+    }
+
+    /// Remember what a session's markdown looks like after a sync wrote it
+    pub async fn record_file(&self, markdown_path: &Path) {
+        self.files.lock().await.record(markdown_path);
     }
 
     /// Get the number of synced messages for a session
@@ -85,29 +138,57 @@ impl SessionTracker {
         };
 
         state.upsert_session(session_state);
-
-        // Persistence disabled
         Ok(())
     }
 
+    /// Whether the exported messages of a session are known one by one, so
+    /// it can be synced from a parse that starts after them
+    pub async fn tracks_messages(&self, session_id: &str, synced_count: usize) -> bool {
+        self.exported.lock().await.tracks(session_id, synced_count)
+    }
+
+    /// The messages of `session` that are not in the history yet, given
+    /// `synced_count` written ones and that `session` starts after the
+    /// first `earlier` messages
+    pub async fn diff_messages(
+        &self,
+        session: &ChatSession,
+        synced_count: usize,
+        earlier: usize,
+    ) -> Vec<ChatMessage> {
+        self.exported.lock().await.new_messages(
+            &session.session_id,
+            &session.messages,
+            synced_count,
+            earlier,
+        )
+    }
+
+    /// Record the messages written after the `synced_count` already exported
+    pub async fn record_messages(
+        &self,
+        session_id: &str,
+        synced_count: usize,
+        written: &[ChatMessage],
+    ) {
+        self.exported
+            .lock()
+            .await
+            .record(session_id, synced_count, written);
+    }
+
     /// Process a session file and return new messages
     pub async fn get_new_messages(
         &self,
         file_path: &Path,
-    ) -> Result<(ChatSession, Vec<crate::providers::base::ChatMessage>)> {
+    ) -> Result<(ChatSession, Vec<ChatMessage>)> {
         // Parse the session
         let session = self.provider.parse_session(file_path).await?;
 
         // Get the number of already synced messages
         let synced_count = self.get_synced_count(&session.session_id).await;
 
-        // Get new messages
-        let new_messages = session
-            .messages
-            .iter()
-            .skip(synced_count)
-            .cloned()
-            .collect();
+        let new_messages = self.diff_messages(&session, synced_count, 0).await;
 
         Ok((session, new_messages))
     }
@@ -172,6 +253,10 @@ mod tests {
             Ok(self.sessions.keys().cloned().collect())
         }
 
+        async fn get_all_sessions_global(&self) -> Result<Vec<PathBuf>> {
+            Ok(self.sessions.keys().cloned().collect())
+        }
+
         fn is_installed(&self) -> bool {
             true
         }
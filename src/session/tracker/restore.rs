@@ -1,47 +1,132 @@
 use crate::error::Result;
-use crate::session::state::SessionState;
-use std::collections::HashMap;
+use crate::session::state::{FileEntry, SessionState, StateFile};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use tokio::fs;
 
-/// Scan markdown files to restore session state
-/// Returns a map of session_id -> SessionState
+/// Scan markdown files to restore session state, reading the frontmatter of
+/// files that changed since `state.json` was saved and the cached fields of
+/// the others.
+/// Returns a map of session_id -> SessionState, and the state file updated
+/// to the files found
 pub(crate) async fn restore_from_disk(
     project_dir: &std::path::Path,
     provider_name: &str,
-) -> Result<HashMap<String, SessionState>> {
+) -> Result<(HashMap<String, SessionState>, StateFile)> {
+    let mut state_file = StateFile::load(project_dir);
     let history_dir = crate::utils::path::get_waylog_dir(project_dir);
     if !history_dir.exists() {
-        return Ok(HashMap::new());
+        return Ok((HashMap::new(), state_file));
     }
 
     // Read directory
     let mut entries = match fs::read_dir(&history_dir).await {
         Ok(e) => e,
-        Err(_) => return Ok(HashMap::new()),
+        Err(_) => return Ok((HashMap::new(), state_file)),
     };
 
-    let mut sessions_map = HashMap::new();
-
+    let mut files = BTreeMap::new();
     while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("md") {
+        // Encrypted files are listed under their `.md` name
+        let Some(path) = crate::exporter::encryption::history_path(&entry.path()) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let cached = state_file
+            .files
+            .remove(&name)
+            .filter(|cached| cached.matches(&metadata));
+        let file = match cached {
+            Some(cached) => cached,
             // Try to parse frontmatter
-            if let Ok(fm) = crate::exporter::parse_frontmatter(&path).await {
-                if let Some(sid) = fm.session_id {
-                    let session_state = SessionState {
-                        session_id: sid.clone(),
-                        provider: fm.provider.unwrap_or_else(|| provider_name.to_string()),
-                        file_path: PathBuf::new(), // Unknown source path
-                        markdown_path: path.clone(),
-                        synced_message_count: fm.message_count.unwrap_or(0),
-                        last_sync_time: chrono::Utc::now(), // Unknown
-                    };
-                    sessions_map.insert(sid, session_state);
-                }
+            None => match crate::exporter::parse_frontmatter(&path).await {
+                Ok(fm) => FileEntry::new(&metadata, fm),
+                Err(_) => continue,
+            },
+        };
+        files.insert(name, file);
+    }
+
+    let mut sessions_map = HashMap::new();
+    // Messages in the later files of split sessions, added to the first file's entry
+    let mut part_counts: HashMap<(String, usize), usize> = HashMap::new();
+    // A session rewritten after a hand edit continues in its latest revision
+    let mut revisions: HashMap<String, usize> = HashMap::new();
+    let session_state = |sid: &str, provider: Option<&String>, path: PathBuf, count: usize| {
+        let source = state_file.sessions.get(sid);
+        SessionState {
+            session_id: sid.to_string(),
+            provider: provider
+                .cloned()
+                .unwrap_or_else(|| provider_name.to_string()),
+            file_path: source.map(|s| s.file_path.clone()).unwrap_or_default(),
+            markdown_path: path,
+            synced_message_count: count,
+            // Unknown unless recorded
+            last_sync_time: source
+                .map(|s| s.last_sync_time)
+                .unwrap_or(chrono::DateTime::UNIX_EPOCH),
+        }
+    };
+
+    for (name, file) in &files {
+        let path = history_dir.join(name);
+
+        // Merged files track each source session separately
+        for (sid, count) in &file.merged_sessions {
+            sessions_map.insert(
+                sid.clone(),
+                session_state(sid, file.provider.as_ref(), path.clone(), *count),
+            );
+        }
+        if !file.merged_sessions.is_empty() {
+            continue;
+        }
+
+        if let Some(sid) = &file.session_id {
+            let revision = file.revision.unwrap_or(1);
+            if file.part.is_some_and(|part| part > 1) {
+                *part_counts.entry((sid.clone(), revision)).or_default() +=
+                    file.message_count.unwrap_or(0);
+                continue;
             }
+            if revisions.get(sid).is_some_and(|latest| *latest > revision) {
+                continue;
+            }
+            revisions.insert(sid.clone(), revision);
+
+            sessions_map.insert(
+                sid.clone(),
+                session_state(
+                    sid,
+                    file.provider.as_ref(),
+                    path,
+                    file.message_count.unwrap_or(0),
+                ),
+            );
+        }
+    }
+
+    for ((sid, revision), count) in part_counts {
+        if revisions
+            .get(&sid)
+            .is_some_and(|latest| *latest != revision)
+        {
+            continue;
+        }
+        if let Some(state) = sessions_map.get_mut(&sid) {
+            state.synced_message_count += count;
         }
     }
 
-    Ok(sessions_map)
+    // Entries of files that are gone are dropped
+    state_file.files = files;
+    Ok((sessions_map, state_file))
 }
@@ -0,0 +1,236 @@
+//! Soft delete: files waylog removes from `.waylog` are moved into
+//! `.waylog/trash/<deleted at>/` with their original layout, and purged once
+//! they are older than `[trash] retention_days`.
+use crate::error::{Result, WaylogError};
+use crate::init::{subdirs, WAYLOG_DIR};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Name of the per-deletion directories, e.g. `20250115T093000.123Z`
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// A file in the trash
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    /// Where the file is kept in the trash
+    pub path: PathBuf,
+    /// Where it is restored to
+    pub original: PathBuf,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    /// `<deleted at>/<path within .waylog>`, as shown by `waylog trash list`
+    pub fn id(&self, project_dir: &Path) -> String {
+        self.path
+            .strip_prefix(trash_dir(project_dir))
+            .unwrap_or(&self.path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+pub fn trash_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(WAYLOG_DIR).join(subdirs::TRASH)
+}
+
+/// Move a file inside `.waylog` to the trash, then purge expired entries
+pub fn move_to_trash(project_dir: &Path, file: &Path) -> Result<PathBuf> {
+    // A history file goes under the name it is stored by
+    let file = &crate::exporter::encryption::stored_path(file);
+    let waylog_dir = project_dir.join(WAYLOG_DIR);
+    let relative = file.strip_prefix(&waylog_dir).map_err(|_| {
+        WaylogError::PathError(format!(
+            "{} is not inside {}",
+            file.display(),
+            waylog_dir.display()
+        ))
+    })?;
+
+    let stamp = Utc::now().format(STAMP_FORMAT).to_string();
+    let target = trash_dir(project_dir).join(stamp).join(relative);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(file, &target)?;
+
+    purge_expired(project_dir, crate::config::get().trash.retention_days)?;
+    Ok(target)
+}
+
+/// Everything in the trash, most recently deleted first
+pub fn list(project_dir: &Path) -> Result<Vec<TrashEntry>> {
+    let trash = trash_dir(project_dir);
+    let waylog_dir = project_dir.join(WAYLOG_DIR);
+    let mut entries = Vec::new();
+    if !trash.exists() {
+        return Ok(entries);
+    }
+
+    for stamp_dir in std::fs::read_dir(&trash)?.filter_map(|e| e.ok()) {
+        let name = stamp_dir.file_name().to_string_lossy().to_string();
+        let Some(deleted_at) = parse_stamp(&name) else {
+            continue;
+        };
+        for file in WalkDir::new(stamp_dir.path())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let relative = file
+                .path()
+                .strip_prefix(stamp_dir.path())
+                .unwrap_or(file.path());
+            entries.push(TrashEntry {
+                path: file.path().to_path_buf(),
+                original: waylog_dir.join(relative),
+                deleted_at,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then(a.path.cmp(&b.path)));
+    Ok(entries)
+}
+
+/// Find the single entry whose id contains `query`
+pub fn find(project_dir: &Path, query: &str) -> Result<TrashEntry> {
+    let mut matches: Vec<_> = list(project_dir)?
+        .into_iter()
+        .filter(|entry| entry.id(project_dir).contains(query))
+        .collect();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(WaylogError::TrashEntryNotFound(query.to_string())),
+        n => Err(WaylogError::PathError(format!(
+            "'{}' matches {} files in the trash; use the full id from `waylog trash list`",
+            query, n
+        ))),
+    }
+}
+
+/// Move an entry back to where it was deleted from
+pub fn restore(project_dir: &Path, entry: &TrashEntry) -> Result<()> {
+    if entry.original.exists() {
+        return Err(WaylogError::PathError(format!(
+            "{} already exists; move it away before restoring",
+            entry.original.display()
+        )));
+    }
+    if let Some(parent) = entry.original.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(&entry.path, &entry.original)?;
+    remove_empty_dirs(&trash_dir(project_dir))?;
+    Ok(())
+}
+
+/// Delete entries older than `retention_days` for good (0 keeps them forever).
+/// Returns the number of files removed.
+pub fn purge_expired(project_dir: &Path, retention_days: u64) -> Result<usize> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+
+    let mut purged = 0;
+    for entry in list(project_dir)? {
+        if entry.deleted_at < cutoff {
+            std::fs::remove_file(&entry.path)?;
+            purged += 1;
+        }
+    }
+    if purged > 0 {
+        remove_empty_dirs(&trash_dir(project_dir))?;
+    }
+    Ok(purged)
+}
+
+fn parse_stamp(name: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(name, STAMP_FORMAT)
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+/// Remove directories under `root` that no longer contain any file
+fn remove_empty_dirs(root: &Path) -> Result<()> {
+    if !root.exists() {
+        return Ok(());
+    }
+    for dir in WalkDir::new(root)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+    {
+        if std::fs::read_dir(dir.path())?.next().is_none() {
+            std::fs::remove_dir(dir.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let history = dir.path().join(WAYLOG_DIR).join(subdirs::HISTORY);
+        std::fs::create_dir_all(&history).unwrap();
+        let file = history.join("2025-01-15_09-30-00Z-claude-hello.md");
+        std::fs::write(&file, "# Hello\n").unwrap();
+        (dir, file)
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let (dir, file) = project();
+
+        let trashed = move_to_trash(dir.path(), &file).unwrap();
+        assert!(!file.exists());
+        assert!(trashed.exists());
+
+        let entries = list(dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original, file);
+        assert!(entries[0]
+            .id(dir.path())
+            .ends_with("Z/history/2025-01-15_09-30-00Z-claude-hello.md"));
+
+        let entry = find(dir.path(), "claude-hello").unwrap();
+        restore(dir.path(), &entry).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "# Hello\n");
+        assert!(list(dir.path()).unwrap().is_empty());
+        // Nothing but the trash directory itself is left behind
+        assert_eq!(std::fs::read_dir(trash_dir(dir.path())).unwrap().count(), 0);
+        assert!(matches!(
+            find(dir.path(), "claude-hello"),
+            Err(WaylogError::TrashEntryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let (dir, file) = project();
+        let old = trash_dir(dir.path())
+            .join("20200101T000000.000Z")
+            .join("history");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::write(old.join("old.md"), "old").unwrap();
+        move_to_trash(dir.path(), &file).unwrap();
+
+        // Moving a file to the trash already purged the expired entry
+        let entries = list(dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0]
+            .original
+            .ends_with("2025-01-15_09-30-00Z-claude-hello.md"));
+        assert!(!old.exists());
+
+        assert_eq!(purge_expired(dir.path(), 0).unwrap(), 0);
+    }
+}
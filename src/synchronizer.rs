@@ -1,142 +1,650 @@
+use crate::config::ConflictPolicy;
 use crate::error::Result;
 use crate::exporter;
-use crate::providers::base::Provider;
+use crate::exporter::anonymize::Anonymizer;
+use crate::exporter::derived::DerivedRenderer;
+use crate::output::Output;
+use crate::providers::base::{ChatMessage, ChatSession, ParseCursor, Provider, TimeRange};
+use crate::providers::telemetry;
+use crate::session::edits;
+use crate::session::exclude::SessionFilter;
+use crate::session::offsets::ParseIndex;
 use crate::session::SessionTracker;
 use crate::utils::path;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use tracing::debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
 
 /// Shared synchronization logic for both watcher and batch sync
 pub struct Synchronizer {
     provider: Arc<dyn Provider>,
     project_dir: PathBuf,
     tracker: Arc<SessionTracker>,
+    /// Extra frontmatter written into newly created markdown files
+    frontmatter: Vec<(&'static str, String)>,
+    /// Where newly synced messages are echoed, if anywhere
+    echo: Option<Echo>,
+    /// Applied to every session before it is written (`--anonymize`)
+    anonymizer: Option<Anonymizer>,
+    /// Where parsing of each session file stopped
+    index: Arc<Mutex<ParseIndex>>,
+    /// Rebuilds the SQLite archive and `[export] command` output
+    renderer: Arc<DerivedRenderer>,
+    /// Report what would be written instead of writing it (`pull --dry-run`)
+    dry_run: bool,
+    /// Only sessions modified within this range are synced (`--since`/`--until`)
+    range: TimeRange,
+    /// Time spent in each phase of the session file being synced
+    timings: Mutex<SessionTimings>,
+    /// Told when each phase of syncing a session starts (`pull`'s progress bar)
+    on_phase: Option<PhaseListener>,
 }
 
+/// Called with each phase of syncing a session as it starts
+pub type PhaseListener = Arc<dyn Fn(Phase) + Send + Sync>;
+
+/// Shared output that synced messages are previewed on (`waylog run --echo-sync`)
+pub type Echo = Arc<Mutex<Output>>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SyncStatus {
-    Synced { new_messages: usize },
+    Synced {
+        new_messages: usize,
+        /// Malformed lines skipped in the part of the file parsed this time
+        warnings: usize,
+    },
     UpToDate,
-    Skipped,
+    /// What a dry run would have written
+    WouldSync {
+        markdown_path: PathBuf,
+        new_messages: usize,
+        /// The file does not exist yet (or is rewritten from scratch)
+        create: bool,
+    },
+    /// Not exported, with the reason
+    Skipped(String),
     Failed(String),
 }
 
+/// How long syncing one session file took, and how much of that went to
+/// parsing it and to writing the history and derived files (`pull --timings`)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionTimings {
+    pub total: Duration,
+    pub parse: Duration,
+    pub export: Duration,
+}
+
+/// The phases of syncing a session that are timed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Parse,
+    Export,
+}
+
+/// Whether `session` is a batch that stopped at `limit`, so more may follow
+fn is_full(limit: Option<usize>, session: &ChatSession) -> bool {
+    limit.is_some_and(|limit| session.messages.len() >= limit)
+}
+
+/// Where the batches of one session are written
+struct BatchWriter<'a> {
+    session_path: &'a Path,
+    markdown_path: &'a Path,
+    frontmatter: &'a [(&'static str, String)],
+}
+
+impl BatchWriter<'_> {
+    /// Write `new_messages` after the `synced_count` already in the markdown,
+    /// then record them and where parsing stopped
+    async fn write(
+        &self,
+        sync: &Synchronizer,
+        session: &ChatSession,
+        synced_count: usize,
+        new_messages: &[ChatMessage],
+        next: Option<ParseCursor>,
+    ) -> Result<()> {
+        let writing = sync.tracker.lock_writes().await;
+        exporter::markdown::parts::write_session(
+            self.markdown_path,
+            session,
+            synced_count,
+            new_messages,
+            self.frontmatter,
+        )
+        .await?;
+
+        if let Some(echo) = &sync.echo {
+            if let Ok(mut output) = echo.lock() {
+                output.echo_messages(new_messages)?;
+            }
+        }
+
+        sync.tracker.record_file(self.markdown_path).await;
+        sync.tracker
+            .record_messages(&session.session_id, synced_count, new_messages)
+            .await;
+        sync.tracker
+            .update_session(
+                session.session_id.clone(),
+                self.session_path.to_path_buf(),
+                self.markdown_path.to_path_buf(),
+                synced_count + new_messages.len(),
+            )
+            .await?;
+        // Held until the session is recorded, so `run` never stops the
+        // watcher between writing the file and recording where it went
+        drop(writing);
+        if let Some(next) = next {
+            sync.remember(self.session_path, next);
+        }
+
+        crate::hooks::messages_synced(&sync.project_dir, self.markdown_path, session, new_messages)
+            .await;
+        Ok(())
+    }
+}
+
 impl Synchronizer {
     pub fn new(
         provider: Arc<dyn Provider>,
         project_dir: PathBuf,
         tracker: Arc<SessionTracker>,
     ) -> Self {
+        let index = Arc::new(Mutex::new(ParseIndex::load(&project_dir)));
+        let renderer = Arc::new(DerivedRenderer::immediate(project_dir.clone()));
         Self {
             provider,
             project_dir,
             tracker,
+            frontmatter: Vec::new(),
+            echo: None,
+            anonymizer: None,
+            index,
+            renderer,
+            dry_run: false,
+            range: TimeRange::default(),
+            timings: Mutex::default(),
+            on_phase: None,
         }
     }
 
-    /// Sync all available sessions from the provider
-    /// Returns stats: (Synced, UpToDate, Skipped, Failed)
-    pub async fn sync_all(&self, force: bool) -> Result<Vec<(PathBuf, SyncStatus)>> {
-        let sessions = self.provider.get_all_sessions(&self.project_dir).await?;
-        let mut results = Vec::new();
+    /// A synchronizer for another provider's sessions in the same project,
+    /// sharing this one's records, echo and renderer but not its extra
+    /// frontmatter, which describes this provider's sessions
+    pub fn for_provider(&self, provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider: provider.clone(),
+            project_dir: self.project_dir.clone(),
+            tracker: Arc::new(self.tracker.for_provider(provider)),
+            frontmatter: Vec::new(),
+            echo: self.echo.clone(),
+            anonymizer: self.anonymizer.clone(),
+            index: self.index.clone(),
+            renderer: self.renderer.clone(),
+            dry_run: self.dry_run,
+            range: self.range,
+            timings: Mutex::default(),
+            on_phase: None,
+        }
+    }
+
+    /// Only sync sessions modified within `range`
+    pub fn with_time_range(mut self, range: TimeRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Write nothing; syncs that would write report `SyncStatus::WouldSync`
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Queue derived renders on `renderer` instead of rendering after every sync
+    pub fn with_renderer(mut self, renderer: Arc<DerivedRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Call `listener` as each phase of syncing a session starts
+    pub fn with_phase_listener(mut self, listener: PhaseListener) -> Self {
+        self.on_phase = Some(listener);
+        self
+    }
 
-        for session_path in sessions {
-            let status = match self.sync_session(&session_path, force).await {
+    /// Preview every newly synced message on `echo`
+    pub fn with_echo(mut self, echo: Echo) -> Self {
+        self.echo = Some(echo);
+        self
+    }
+
+    /// Anonymize every session before it is written
+    pub fn with_anonymizer(mut self, anonymizer: Anonymizer) -> Self {
+        self.anonymizer = Some(anonymizer);
+        self
+    }
+
+    /// Add a frontmatter field to every markdown file this synchronizer creates
+    pub fn with_frontmatter(mut self, key: &'static str, value: String) -> Self {
+        self.frontmatter.push((key, value));
+        self
+    }
+
+    pub fn provider(&self) -> &Arc<dyn Provider> {
+        &self.provider
+    }
+
+    /// The provider's session files for this project, within the time range
+    pub async fn sessions(&self) -> Result<Vec<PathBuf>> {
+        self.provider
+            .get_sessions_in_range(&self.project_dir, &self.range)
+            .await
+    }
+
+    /// Sync `files`, calling `progress` with each file, its index and the
+    /// number of files before syncing it. What was synced is recorded in
+    /// memory; `save_progress` saves it.
+    /// Returns the outcome of each session file and how long it took
+    pub async fn sync_files(
+        &self,
+        files: Vec<PathBuf>,
+        force: bool,
+        mut progress: impl FnMut(&Path, usize, usize),
+    ) -> Vec<(PathBuf, SyncStatus, SessionTimings)> {
+        let mut results = Vec::new();
+        let total = files.len();
+        for (index, session_path) in files.into_iter().enumerate() {
+            progress(&session_path, index, total);
+            if let Ok(mut timings) = self.timings.lock() {
+                *timings = SessionTimings::default();
+            }
+            let started = Instant::now();
+            let status = match self.sync_one(&session_path, force).await {
                 Ok(status) => status,
                 Err(e) => SyncStatus::Failed(e.to_string()),
             };
-            results.push((session_path, status));
+            let mut timings = self.timings.lock().map(|t| *t).unwrap_or_default();
+            timings.total = started.elapsed();
+            results.push((session_path, status, timings));
         }
-
-        Ok(results)
+        results
     }
 
     /// Sync a specific session file
     pub async fn sync_session(&self, session_path: &Path, force: bool) -> Result<SyncStatus> {
-        // 1. Parse session
-        let session = match self.provider.parse_session(session_path).await {
-            Ok(s) => s,
-            Err(e) => return Ok(SyncStatus::Failed(format!("Parse error: {}", e))),
+        let status = self.sync_one(session_path, force).await;
+        self.save_progress().await;
+        status
+    }
+
+    async fn sync_one(&self, session_path: &Path, force: bool) -> Result<SyncStatus> {
+        // 1. Parse session, only what was appended since the last sync if possible
+        let limit = self.batch_limit();
+        let (session, mut earlier, mut next, mut warnings) = match self
+            .timed(Phase::Parse, self.parse(session_path, force, limit))
+            .await
+        {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.forget(session_path);
+                return Ok(SyncStatus::Failed(format!("Parse error: {}", e)));
+            }
         };
+        if !self.dry_run {
+            if let Err(e) = telemetry::flush(&self.project_dir) {
+                debug!("Could not save format telemetry: {}", e);
+            }
+        }
+
+        if earlier == 0 && session.messages.is_empty() {
+            return Ok(SyncStatus::Skipped("empty session".to_string()));
+        }
+        if !self.range.contains(&session) {
+            return Ok(SyncStatus::Skipped(
+                "not modified within --since/--until".to_string(),
+            ));
+        }
 
-        if session.messages.is_empty() {
-            return Ok(SyncStatus::Skipped);
+        if let Some(reason) = SessionFilter::from_config().reason_after(&session, earlier) {
+            return Ok(SyncStatus::Skipped(reason));
         }
+        let mut session = match &self.anonymizer {
+            Some(anonymizer) => anonymizer.anonymize_session(&session),
+            None => session,
+        };
 
         // 2. Check state
         let state = self.tracker.get_state().await;
-        let (markdown_path, mut synced_count) =
+        let (mut markdown_path, mut synced_count) =
             if let Some(s) = state.get_session(&session.session_id) {
                 (s.markdown_path.clone(), s.synced_message_count)
             } else {
                 // New session: generate filename
-                let slug = session
-                    .messages
-                    .iter()
-                    .find(|m| m.role == crate::providers::base::MessageRole::User)
-                    .map(|m| crate::utils::string::slugify(&m.content))
-                    .unwrap_or_else(|| session.session_id.clone());
-
-                let timestamp = session.started_at.format("%Y-%m-%d_%H-%M-%SZ");
-                let filename = format!("{}-{}-{}.md", timestamp, self.provider.name(), slug);
-                let path = path::get_waylog_dir(&self.project_dir).join(filename);
+                let path = exporter::markdown::named_session_path(
+                    &path::get_waylog_dir(&self.project_dir),
+                    &session,
+                    exporter::markdown::given_title(&self.frontmatter).as_deref(),
+                );
 
                 (path, 0)
             };
 
+        let edited = exporter::encryption::exists(&markdown_path)
+            && self.tracker.is_edited(&markdown_path).await;
+
         // 3. Handle force/missing file
-        if force || (!markdown_path.exists() && synced_count > 0) {
+        if force || (!exporter::encryption::exists(&markdown_path) && synced_count > 0) {
             synced_count = 0;
         }
 
         // 4. Calculate new messages
-        let total_messages = session.messages.len();
-        if synced_count >= total_messages {
-            return Ok(SyncStatus::UpToDate);
+        let mut new_messages = self
+            .tracker
+            .diff_messages(&session, synced_count, earlier)
+            .await;
+        // Batches synced before (e.g. by a run that was interrupted) are read past
+        while new_messages.is_empty() && is_full(limit, &session) {
+            let Some(from) = next.take() else {
+                break;
+            };
+            let Some((batch, cursor, skipped)) = self
+                .timed(Phase::Parse, self.next_batch(session_path, &from, limit))
+                .await?
+            else {
+                break;
+            };
+            earlier = from.message_count;
+            session = batch;
+            next = Some(cursor);
+            warnings += skipped;
+            new_messages = self
+                .tracker
+                .diff_messages(&session, synced_count, earlier)
+                .await;
         }
 
-        let new_messages: Vec<_> = session
-            .messages
-            .iter()
-            .skip(synced_count)
-            .cloned()
-            .collect();
-
         if new_messages.is_empty() {
+            if let Some(next) = next {
+                self.remember(session_path, next);
+            }
             return Ok(SyncStatus::UpToDate);
         }
 
-        // 5. Write to file
+        // A file edited by hand is never written to again
+        let mut frontmatter = self.frontmatter_for(&markdown_path).await;
+        if edited {
+            let name = markdown_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            match crate::config::get().sync.on_conflict {
+                ConflictPolicy::Skip => {
+                    return Ok(SyncStatus::Failed(format!(
+                        "{} was edited by hand; {} new messages not synced ([sync] on_conflict = \"skip\")",
+                        name,
+                        new_messages.len()
+                    )));
+                }
+                ConflictPolicy::Revision => {
+                    // Resume cursors are not used for edited files, so the
+                    // whole session was parsed
+                    let (revision_path, revision) = edits::next_revision(&markdown_path);
+                    if !self.dry_run {
+                        warn!(
+                            "{} was edited by hand; writing the session to {}",
+                            name,
+                            revision_path.display()
+                        );
+                    }
+                    frontmatter.retain(|(key, _)| *key != "revision");
+                    frontmatter.push(("revision", revision.to_string()));
+                    markdown_path = revision_path;
+                    synced_count = 0;
+                    new_messages = self.tracker.diff_messages(&session, 0, earlier).await;
+                }
+            }
+        }
+
+        if self.dry_run {
+            return Ok(SyncStatus::WouldSync {
+                markdown_path,
+                new_messages: new_messages.len(),
+                create: synced_count == 0,
+            });
+        }
+
+        // 5. Write to file and update state
         if let Some(parent) = markdown_path.parent() {
             path::ensure_dir_exists(parent)?;
         }
+        let writer = BatchWriter {
+            session_path,
+            markdown_path: &markdown_path,
+            frontmatter: &frontmatter,
+        };
+        self.timed(
+            Phase::Export,
+            writer.write(self, &session, synced_count, &new_messages, next.clone()),
+        )
+        .await?;
+        let mut written = new_messages.len();
+        let mut synced_count = synced_count + written;
 
-        if synced_count == 0 {
-            exporter::create_markdown_file(&markdown_path, &session).await?;
-        } else {
-            exporter::append_messages(&markdown_path, &new_messages).await?;
+        // 6. A huge session is parsed a batch at a time, each one written
+        // before the next is read
+        let mut cursor = next;
+        while is_full(limit, &session) {
+            let Some(from) = cursor.take() else {
+                break;
+            };
+            let Some((batch, next, skipped)) = self
+                .timed(Phase::Parse, self.next_batch(session_path, &from, limit))
+                .await?
+            else {
+                break;
+            };
+            session = batch;
+            warnings += skipped;
+            let new_messages = self
+                .tracker
+                .diff_messages(&session, synced_count, from.message_count)
+                .await;
+            self.timed(
+                Phase::Export,
+                writer.write(
+                    self,
+                    &session,
+                    synced_count,
+                    &new_messages,
+                    Some(next.clone()),
+                ),
+            )
+            .await?;
+            written += new_messages.len();
+            synced_count += new_messages.len();
+            cursor = Some(next);
         }
 
-        // 6. Update state
-        self.tracker
-            .update_session(
-                session.session_id.clone(),
-                session_path.to_path_buf(),
-                markdown_path.clone(),
-                total_messages,
+        // The markdown is already saved, so a failing exporter is reported
+        // without retrying the messages
+        if let Err(e) = self
+            .timed(
+                Phase::Export,
+                self.renderer.schedule(&markdown_path, &session),
             )
-            .await?;
+            .await
+        {
+            return Ok(SyncStatus::Failed(e.to_string()));
+        }
 
         // Log purely for debug, UI is handled by caller
-        debug!(
-            "Synced {} messages to {}",
-            new_messages.len(),
-            markdown_path.display()
-        );
+        debug!("Synced {} messages to {}", written, markdown_path.display());
 
         Ok(SyncStatus::Synced {
-            new_messages: new_messages.len(),
+            new_messages: written,
+            warnings,
         })
     }
+
+    /// Parse a session file, returning the session, how many of its messages
+    /// were skipped because an earlier sync already parsed them, and where the
+    /// next parse can resume (if the provider parses incrementally), and how
+    /// many malformed lines were skipped past the resume point
+    async fn parse(
+        &self,
+        session_path: &Path,
+        force: bool,
+        limit: Option<usize>,
+    ) -> Result<(ChatSession, usize, Option<ParseCursor>, usize)> {
+        let cursor = if force {
+            None
+        } else {
+            self.resume_cursor(session_path).await
+        };
+        match self
+            .provider
+            .parse_session_from(session_path, cursor.as_ref(), limit)
+            .await?
+        {
+            Some((session, next)) => {
+                let (earlier, malformed) =
+                    cursor.map_or((0, 0), |c| (c.message_count, c.malformed_lines));
+                let warnings = next.malformed_lines.saturating_sub(malformed);
+                Ok((session, earlier, Some(next), warnings))
+            }
+            None => Ok((self.provider.parse_session(session_path).await?, 0, None, 0)),
+        }
+    }
+
+    /// How many messages of a session file to parse at a time (`[sync]
+    /// batch_messages`), or `None` to parse all of it at once. Batches are
+    /// not used when something needs the whole session before writing: the
+    /// counts of a dry run, `--since`/`--until` (judged by the last message),
+    /// exclude patterns, and the SQLite archive and external exporter.
+    fn batch_limit(&self) -> Option<usize> {
+        let limit = crate::config::get().sync.batch_messages;
+        let whole = limit == 0
+            || self.dry_run
+            || !self.range.is_unbounded()
+            || SessionFilter::from_config().inspects_messages()
+            || exporter::needs_full_session(&self.project_dir);
+        (!whole).then_some(limit)
+    }
+
+    /// The batch of a session file after `from`, with where the next one
+    /// starts and how many malformed lines were skipped
+    async fn next_batch(
+        &self,
+        session_path: &Path,
+        from: &ParseCursor,
+        limit: Option<usize>,
+    ) -> Result<Option<(ChatSession, ParseCursor, usize)>> {
+        let parsed = self
+            .provider
+            .parse_session_from(session_path, Some(from), limit)
+            .await?;
+        Ok(parsed.map(|(session, next)| {
+            let skipped = next.malformed_lines.saturating_sub(from.malformed_lines);
+            let session = match &self.anonymizer {
+                Some(anonymizer) => anonymizer.anonymize_session(&session),
+                None => session,
+            };
+            (session, next, skipped)
+        }))
+    }
+
+    /// The saved cursor of a session file, if the messages up to there are
+    /// known to be in the markdown
+    async fn resume_cursor(&self, session_path: &Path) -> Option<ParseCursor> {
+        if exporter::needs_full_session(&self.project_dir) {
+            return None;
+        }
+        let cursor = self.index.lock().ok()?.get(session_path).cloned()?;
+        let state = self.tracker.get_state().await;
+        let synced = state.get_session(&cursor.session_id)?;
+        let tracked = self
+            .tracker
+            .tracks_messages(&synced.session_id, synced.synced_message_count)
+            .await;
+        (tracked
+            && synced.synced_message_count > 0
+            && exporter::encryption::exists(&synced.markdown_path)
+            && !self.tracker.is_edited(&synced.markdown_path).await)
+            .then_some(cursor)
+    }
+
+    fn remember(&self, session_path: &Path, cursor: ParseCursor) {
+        if let Ok(mut index) = self.index.lock() {
+            index.set(session_path, cursor);
+        }
+    }
+
+    fn forget(&self, session_path: &Path) {
+        if let Ok(mut index) = self.index.lock() {
+            index.remove(session_path);
+        }
+    }
+
+    /// Run `work`, adding how long it took to `phase` of the session being synced
+    async fn timed<T>(&self, phase: Phase, work: impl std::future::Future<Output = T>) -> T {
+        if let Some(listener) = &self.on_phase {
+            listener(phase);
+        }
+        let started = Instant::now();
+        let result = work.await;
+        if let Ok(mut timings) = self.timings.lock() {
+            match phase {
+                Phase::Parse => timings.parse += started.elapsed(),
+                Phase::Export => timings.export += started.elapsed(),
+            }
+        }
+        result
+    }
+
+    /// Save the record of exported messages and where parsing stopped
+    pub async fn save_progress(&self) {
+        if self.dry_run {
+            return;
+        }
+        if let Err(e) = self.tracker.save_state().await {
+            warn!("Could not save the exported message record: {}", e);
+        }
+        let saved = match self.index.lock() {
+            Ok(index) => index.save(&self.project_dir),
+            Err(_) => return,
+        };
+        if let Err(e) = saved {
+            debug!("Could not save the parse index: {}", e);
+        }
+    }
+
+    /// Frontmatter for a (re)created file, keeping the recorded invocation and
+    /// revision of a file that is being rewritten (e.g. by `pull --force`)
+    async fn frontmatter_for(&self, markdown_path: &Path) -> Vec<(&'static str, String)> {
+        let mut frontmatter = self.frontmatter.clone();
+        if !exporter::encryption::exists(markdown_path) {
+            return frontmatter;
+        }
+        if let Ok(fm) = exporter::parse_frontmatter(markdown_path).await {
+            if !frontmatter.iter().any(|(k, _)| *k == "invocation") {
+                if let Some(invocation) = fm.invocation {
+                    frontmatter.push(("invocation", invocation));
+                }
+            }
+            if let Some(revision) = fm.revision {
+                frontmatter.push(("revision", revision.to_string()));
+            }
+            // A session keeps the name it was synced with
+            if let Some(title) = fm.title {
+                frontmatter.retain(|(key, _)| *key != "title");
+                if let Ok(title) = serde_json::to_string(&title) {
+                    frontmatter.push(("title", title));
+                }
+            }
+        }
+        frontmatter
+    }
 }
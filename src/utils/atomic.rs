@@ -0,0 +1,48 @@
+//! Writes that never leave a half-written file behind: the content goes to a
+//! temporary file next to the target, which then replaces it with one rename.
+//! A crash mid-write leaves the old file (and a stray `.tmp` file) intact.
+
+use crate::error::Result;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Temporary file for `path`, in the same directory so the rename stays on
+/// one filesystem, and not ending in `.md` so nothing mistakes it for history
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+/// Replace the content of `path` atomically
+pub async fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let temp = temp_path(path);
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&temp, path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp).await;
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_replaces_content() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.md");
+        write(&file, "first").await.unwrap();
+        write(&file, "second").await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "second");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,29 @@
+//! Which machine waylog runs on, so that state synced from another machine
+//! (a committed or Dropbox-synced `.waylog`) is not taken for this one's
+
+/// Name of this machine: `[sync] machine`, or else the host name
+pub fn id() -> String {
+    if let Some(machine) = &crate::config::get().sync.machine {
+        return machine.clone();
+    }
+    hostname().unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer outlives the call and its length is passed along
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(windows)]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+}
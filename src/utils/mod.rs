@@ -1,2 +1,6 @@
+pub mod atomic;
+pub mod machine;
 pub mod path;
+pub mod process;
 pub mod string;
+pub mod time;
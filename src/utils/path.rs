@@ -9,11 +9,31 @@ pub fn home_dir() -> Result<PathBuf> {
         .ok_or_else(|| WaylogError::PathError("Could not find home directory".to_string()))
 }
 
+/// Environment variable that points all providers at a fixture tree
+/// (laid out like a home directory) instead of the real one
+pub const PROVIDER_FIXTURES_ENV: &str = "WAYLOG_PROVIDER_FIXTURES";
+
+/// Fixture root if provider sandbox mode is enabled
+pub fn provider_fixtures_dir() -> Option<PathBuf> {
+    std::env::var_os(PROVIDER_FIXTURES_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Directory providers read their session data from: the fixture root in
+/// sandbox mode, the user's home directory otherwise
+pub fn provider_home_dir() -> Result<PathBuf> {
+    match provider_fixtures_dir() {
+        Some(dir) => Ok(dir),
+        None => home_dir(),
+    }
+}
+
 /// Get the data directory for AI tools
 /// On Unix: ~/.{tool}
 /// On Windows: %USERPROFILE%\.{tool} (future extension point)
 pub fn get_ai_data_dir(tool_name: &str) -> Result<PathBuf> {
-    let home = home_dir()?;
+    let home = provider_home_dir()?;
 
     #[cfg(target_os = "windows")]
     {
@@ -89,14 +109,91 @@ pub fn find_project_root() -> Option<PathBuf> {
     None
 }
 
+/// Whether `path` is the user's home directory itself
+pub fn is_home_dir(path: &Path) -> bool {
+    let Ok(home) = home_dir() else {
+        return false;
+    };
+    match (path.canonicalize(), home.canonicalize()) {
+        (Ok(path), Ok(home)) => path == home,
+        _ => path == home,
+    }
+}
+
 /// Ensure a directory exists, creating it if necessary
 pub fn ensure_dir_exists(path: &Path) -> Result<()> {
+    let path = extended_length(path);
     if !path.exists() {
-        std::fs::create_dir_all(path)?;
+        std::fs::create_dir_all(&path)?;
     }
     Ok(())
 }
 
+/// Convert a path to its Windows extended-length form (`\\?\C:\...`) so that deeply
+/// nested project directories are not limited by MAX_PATH (260 chars).
+/// Relative paths are resolved against the current directory. No-op on other platforms.
+#[cfg(windows)]
+pub fn extended_length(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+    to_verbatim(&absolute)
+}
+
+#[cfg(not(windows))]
+pub fn extended_length(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Rewrite an absolute Windows path in verbatim form. Verbatim paths skip Win32
+/// normalization, so `.`/`..` components and forward slashes are resolved here.
+#[cfg(windows)]
+fn to_verbatim(path: &Path) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    let mut components = path.components();
+    let prefix = match components.next() {
+        Some(Component::Prefix(p)) => p,
+        _ => return path.to_path_buf(),
+    };
+
+    let mut result = match prefix.kind() {
+        Prefix::Disk(letter) => format!(r"\\?\{}:", letter as char),
+        Prefix::UNC(server, share) => format!(
+            r"\\?\UNC\{}\{}",
+            server.to_string_lossy(),
+            share.to_string_lossy()
+        ),
+        // Already verbatim or a device path
+        _ => return path.to_path_buf(),
+    };
+
+    let mut parts: Vec<String> = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+
+    for part in parts {
+        result.push('\\');
+        result.push_str(&part);
+    }
+    if result.ends_with(':') {
+        result.push('\\');
+    }
+    PathBuf::from(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +247,30 @@ mod tests {
         assert_eq!(encode_path_claude(path), "C--Users-user-project");
     }
 
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_extended_length_windows() {
+        assert_eq!(
+            extended_length(Path::new(r"C:\Users\me\..\project/sub")),
+            PathBuf::from(r"\\?\C:\Users\project\sub")
+        );
+        assert_eq!(
+            extended_length(Path::new(r"\\server\share\dir")),
+            PathBuf::from(r"\\?\UNC\server\share\dir")
+        );
+        assert_eq!(
+            extended_length(Path::new(r"\\?\C:\already")),
+            PathBuf::from(r"\\?\C:\already")
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_extended_length_noop() {
+        let path = Path::new("/home/user/project/.waylog/history/a.md");
+        assert_eq!(extended_length(path), path.to_path_buf());
+    }
+
     #[test]
     #[cfg(target_os = "windows")]
     fn test_encode_path_claude_windows_relative() {
@@ -304,7 +425,7 @@ mod tests {
         // Restore original working directory
         // If the original directory no longer exists (e.g., in parallel test execution),
         // try to restore to home directory as a fallback
-        if let Err(_) = std::env::set_current_dir(&original_dir) {
+        if std::env::set_current_dir(&original_dir).is_err() {
             // Fallback to home directory if original directory is gone
             if let Ok(home) = home_dir() {
                 let _ = std::env::set_current_dir(&home);
@@ -0,0 +1,32 @@
+//! Starting waylog again in the background, for work that should outlive
+//! the command that started it.
+use std::path::Path;
+
+/// Run this waylog executable with `args` in `dir`, detached from the
+/// terminal, with no stdin and its output discarded
+pub fn spawn_detached(args: &[String], dir: &Path) -> std::io::Result<()> {
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .current_dir(dir)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+
+    // Out of the terminal's process group, so closing the shell does not stop it
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    command.spawn()?;
+    Ok(())
+}
@@ -42,10 +42,190 @@ pub fn slugify(text: &str) -> String {
     }
 }
 
+/// Replace characters that are not allowed in filenames on common platforms
+pub fn sanitize_filename(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_matches('.').trim().to_string()
+}
+
+/// Quote a command-line argument for a POSIX shell, leaving simple words untouched
+pub fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c));
+    if is_plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Split a command line into words, honouring single quotes, double quotes
+/// and backslash escapes (no variable expansion). A backslash only escapes a
+/// quote or whitespace, or `"` and `\` inside double quotes, and is kept as
+/// it is otherwise, so Windows paths such as `C:\tools\claude.exe` survive.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                current.extend(chars.next());
+            }
+            (None, '\\')
+                if chars
+                    .peek()
+                    .is_some_and(|next| matches!(next, '\'' | '"') || next.is_whitespace()) =>
+            {
+                current.extend(chars.next());
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+/// Join a command and its arguments into a single copy-pasteable shell line
+pub fn shell_join<'a>(words: impl IntoIterator<Item = &'a str>) -> String {
+    words
+        .into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Truncate a filename stem so that `{stem}.{extension}` fits within `max_len` bytes,
+/// cutting on a char boundary and dropping any dangling hyphen
+pub fn truncate_filename(stem: &str, extension: &str, max_len: usize) -> String {
+    let budget = max_len.saturating_sub(extension.len() + 1);
+    if stem.len() <= budget {
+        return format!("{}.{}", stem, extension);
+    }
+
+    let mut end = budget;
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}.{}", stem[..end].trim_end_matches('-'), extension)
+}
+
+/// Format a byte count for humans, e.g. `1.5 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Format a duration for humans, e.g. `850ms` or `1.25s`
+pub fn format_duration(duration: std::time::Duration) -> String {
+    if duration.as_millis() < 1000 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("Fix: a/b?"), "Fix- a-b-");
+        assert_eq!(sanitize_filename("  ..hidden. "), "hidden");
+        assert_eq!(sanitize_filename("line\nbreak"), "line break");
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("claude --dangerously-skip-permissions"),
+            ["claude", "--dangerously-skip-permissions"]
+        );
+        assert_eq!(
+            split_command(r#"claude --append-system-prompt "be terse" -p 'it''s' a\ b"#),
+            [
+                "claude",
+                "--append-system-prompt",
+                "be terse",
+                "-p",
+                "its",
+                "a b"
+            ]
+        );
+        assert_eq!(split_command(r#"echo """#), ["echo", ""]);
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn test_split_command_keeps_windows_paths() {
+        assert_eq!(
+            split_command(r"C:\tools\claude.exe --verbose"),
+            [r"C:\tools\claude.exe", "--verbose"]
+        );
+        assert_eq!(
+            split_command(r#""C:\Program Files\Claude\claude.exe" -p "say \"hi\" \\o/""#),
+            [
+                r"C:\Program Files\Claude\claude.exe",
+                "-p",
+                r#"say "hi" \o/"#
+            ]
+        );
+        assert_eq!(
+            split_command(r"\\server\share\agent.exe"),
+            [r"\\server\share\agent.exe"]
+        );
+    }
+
+    #[test]
+    fn test_shell_join() {
+        assert_eq!(
+            shell_join(["claude", "--model", "opus", "-p", "fix the build"]),
+            "claude --model opus -p 'fix the build'"
+        );
+        assert_eq!(shell_join(["echo", "it's", ""]), r"echo 'it'\''s' ''");
+    }
+
     #[test]
     fn test_slugify() {
         assert_eq!(slugify("Who are you?"), "who-are-you");
@@ -53,4 +233,27 @@ mod tests {
         assert_eq!(slugify("!@#$"), "new-chat");
         assert_eq!(slugify("Simple"), "simple");
     }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        use std::time::Duration;
+        assert_eq!(format_duration(Duration::from_micros(850_400)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(1250)), "1.25s");
+    }
+
+    #[test]
+    fn test_truncate_filename() {
+        assert_eq!(truncate_filename("short", "md", 64), "short.md");
+        assert_eq!(truncate_filename("abc-def-ghi", "md", 10), "abc-def.md");
+        // Never splits a multi-byte character
+        assert_eq!(truncate_filename("名字名字", "md", 10), "名字.md");
+    }
 }
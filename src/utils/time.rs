@@ -0,0 +1,94 @@
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+/// Time zone used for timestamps written into exports (`export.timezone`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timezone {
+    Utc,
+    Local,
+    Named(Tz),
+}
+
+impl Timezone {
+    /// Parse `"UTC"`, `"local"` or an IANA name such as `"Europe/Berlin"`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            n if n.eq_ignore_ascii_case("utc") || n.is_empty() => Some(Self::Utc),
+            n if n.eq_ignore_ascii_case("local") => Some(Self::Local),
+            n => n.parse().ok().map(Self::Named),
+        }
+    }
+
+    /// Time zone for the active project config (UTC if the name is not recognised)
+    pub fn from_config() -> Self {
+        Self::parse(&crate::config::get().export.timezone).unwrap_or(Self::Utc)
+    }
+
+    /// Format `dt` in this time zone with a chrono format string
+    pub fn format(&self, dt: &DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            Self::Utc => dt.format(fmt).to_string(),
+            Self::Local => dt.with_timezone(&Local).format(fmt).to_string(),
+            Self::Named(tz) => dt.with_timezone(tz).format(fmt).to_string(),
+        }
+    }
+
+    /// RFC 3339 timestamp carrying this time zone's offset
+    pub fn rfc3339(&self, dt: &DateTime<Utc>) -> String {
+        match self {
+            Self::Utc => dt.to_rfc3339(),
+            Self::Local => dt.with_timezone(&Local).to_rfc3339(),
+            Self::Named(tz) => dt.with_timezone(tz).to_rfc3339(),
+        }
+    }
+
+    /// Human-readable timestamp, e.g. `2024-01-01 12:00:00 UTC` or `2024-01-01 14:00:00 CEST`
+    pub fn format_datetime(&self, dt: &DateTime<Utc>) -> String {
+        match self {
+            Self::Utc => self.format(dt, "%Y-%m-%d %H:%M:%S UTC"),
+            // Local has no abbreviation, so show the numeric offset
+            Self::Local => self.format(dt, "%Y-%m-%d %H:%M:%S %:z"),
+            Self::Named(_) => self.format(dt, "%Y-%m-%d %H:%M:%S %Z"),
+        }
+    }
+
+    /// Filename-safe offset suffix: `Z` for UTC, otherwise e.g. `+0200`
+    pub fn offset_suffix(&self, dt: &DateTime<Utc>) -> String {
+        match self {
+            Self::Utc => "Z".to_string(),
+            _ => self.format(dt, "%z"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(Timezone::parse("UTC"), Some(Timezone::Utc));
+        assert_eq!(Timezone::parse("local"), Some(Timezone::Local));
+        assert_eq!(
+            Timezone::parse("Europe/Berlin"),
+            Some(Timezone::Named(chrono_tz::Europe::Berlin))
+        );
+        assert_eq!(Timezone::parse("Mars/Olympus"), None);
+    }
+
+    #[test]
+    fn test_format_in_named_timezone() {
+        let dt = Utc.with_ymd_and_hms(2024, 7, 1, 22, 30, 0).unwrap();
+        let tz = Timezone::parse("Europe/Berlin").unwrap();
+        assert_eq!(tz.format_datetime(&dt), "2024-07-02 00:30:00 CEST");
+        assert_eq!(tz.rfc3339(&dt), "2024-07-02T00:30:00+02:00");
+        assert_eq!(tz.offset_suffix(&dt), "+0200");
+
+        assert_eq!(
+            Timezone::Utc.format_datetime(&dt),
+            "2024-07-01 22:30:00 UTC"
+        );
+        assert_eq!(Timezone::Utc.offset_suffix(&dt), "Z");
+    }
+}
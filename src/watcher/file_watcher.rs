@@ -1,21 +1,73 @@
-use crate::error::Result;
+//! Live sync while `waylog run` is active. The provider's session directory
+//! (and with `--all-providers`, every installed provider's) is watched with
+//! the operating system's file events (inotify, FSEvents,
+//! ReadDirectoryChangesW), so new messages reach the markdown within a second
+//! of the agent writing them. `[watch] mode = "poll"` syncs on a timer instead,
+//! for file systems that deliver no events, such as network mounts.
+use crate::config::{WatchConfig, WatchMode};
+use crate::error::{Result, WaylogError};
+use crate::exporter::derived::DerivedRenderer;
 use crate::providers::base::Provider;
 use crate::session::SessionTracker;
-use crate::synchronizer::Synchronizer;
+use crate::synchronizer::{Echo, Synchronizer};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time;
-use tracing::{debug, info};
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+use tracing::{debug, info, warn};
 
-/// Sync interval in seconds
-const SYNC_INTERVAL_SECS: u64 = 30;
+/// First wait before restarting a failed watcher, doubled after each failure
+/// in a row up to `RESTART_MAX_DELAY`
+const RESTART_DELAY: Duration = Duration::from_secs(1);
 
-/// Periodic sync watcher (simplified - no file watching)
+/// Longest wait between restarts; a watcher that ran this long before
+/// failing starts over at `RESTART_DELAY`
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How eagerly the watcher syncs (`[watch]`, overridden by `run` flags)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchTuning {
+    /// Quiet time after a file event before syncing, so a burst of writes is
+    /// synced once
+    pub debounce: Duration,
+    /// Least time between two syncs of the same session
+    pub min_sync_interval: Duration,
+    /// How often every watched session is synced anyway (in case an event
+    /// was missed), and the timer when polling
+    pub rescan_interval: Duration,
+}
+
+impl WatchTuning {
+    pub fn from_config(config: &WatchConfig) -> Self {
+        Self {
+            debounce: Duration::from_millis(config.debounce_ms),
+            min_sync_interval: Duration::from_millis(config.min_sync_interval_ms),
+            rescan_interval: Duration::from_secs(config.rescan_interval.max(1)),
+        }
+    }
+
+    /// Longest a sync is held back while events keep coming
+    fn max_delay(&self) -> Duration {
+        self.debounce * 4
+    }
+}
+
+impl Default for WatchTuning {
+    fn default() -> Self {
+        Self::from_config(&WatchConfig::default())
+    }
+}
+
+/// Syncs the latest session whenever the agent writes to it
 pub struct FileWatcher {
-    provider: Arc<dyn Provider>,
     project_dir: PathBuf,
-    synchronizer: Synchronizer,
+    /// The launched agent's provider first
+    targets: Vec<Synchronizer>,
+    renderer: Option<Arc<DerivedRenderer>>,
+    tuning: WatchTuning,
 }
 
 impl FileWatcher {
@@ -28,44 +80,398 @@ impl FileWatcher {
             Synchronizer::new(provider.clone(), project_dir.clone(), tracker.clone());
 
         Self {
-            provider,
             project_dir,
-            synchronizer,
+            targets: vec![synchronizer],
+            renderer: None,
+            tuning: WatchTuning::from_config(&crate::config::get().watch),
         }
     }
 
-    /// Start periodic sync loop
+    /// Sync with `tuning` instead of the `[watch]` settings
+    pub fn with_tuning(mut self, tuning: WatchTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Coalesce the derived renders of watched sessions on `renderer`
+    pub fn with_renderer(mut self, renderer: Arc<DerivedRenderer>) -> Self {
+        self.targets = self
+            .targets
+            .into_iter()
+            .map(|target| target.with_renderer(renderer.clone()))
+            .collect();
+        self.renderer = Some(renderer);
+        self
+    }
+
+    /// Add a frontmatter field to markdown files the launched agent's
+    /// sessions create while watching
+    pub fn with_frontmatter(mut self, key: &'static str, value: String) -> Self {
+        let first = self.targets.remove(0);
+        self.targets.insert(0, first.with_frontmatter(key, value));
+        self
+    }
+
+    /// Preview newly synced messages on `echo`
+    pub fn with_echo(mut self, echo: Echo) -> Self {
+        self.targets = self
+            .targets
+            .into_iter()
+            .map(|target| target.with_echo(echo.clone()))
+            .collect();
+        self
+    }
+
+    /// Also sync the sessions of `provider`, e.g. an agent running in
+    /// another terminal
+    pub fn also_watch(mut self, provider: Arc<dyn Provider>) -> Self {
+        let target = self.targets[0].for_provider(provider);
+        self.targets.push(target);
+        self
+    }
+
+    /// Sync until the task is dropped, on file events or by polling
     pub async fn watch(&self) -> Result<()> {
+        let events = match crate::config::get().watch.mode {
+            WatchMode::Events => {
+                let dirs = self
+                    .targets
+                    .iter()
+                    .map(|target| target.provider().watch_dir(&self.project_dir))
+                    .collect::<Result<Vec<_>>>()?;
+                match EventSource::new(dirs, self.tuning) {
+                    Ok(events) => Some(events),
+                    Err(e) => {
+                        warn!(
+                            "File events unavailable ({}); syncing every {:?} instead",
+                            e, self.tuning.rescan_interval
+                        );
+                        None
+                    }
+                }
+            }
+            WatchMode::Poll => None,
+        };
+
+        match events {
+            Some(events) => self.watch_events(events).await,
+            None => self.poll().await,
+        }
+    }
+
+    /// Sync until the task is dropped like `watch`, restarting with backoff
+    /// whenever it fails. `degraded` is told each error and how long until
+    /// the next attempt.
+    pub async fn watch_supervised(&self, mut degraded: impl FnMut(&WaylogError, Duration)) {
+        let mut failures = 0;
+        loop {
+            let started = Instant::now();
+            let Err(e) = self.watch().await else {
+                return;
+            };
+            if started.elapsed() >= RESTART_MAX_DELAY {
+                failures = 0;
+            }
+            let delay = restart_delay(failures);
+            failures += 1;
+            degraded(&e, delay);
+            time::sleep(delay).await;
+        }
+    }
+
+    /// Sync the providers whose directories changed after each burst of file
+    /// events, and all of them on the rescan interval as a safety net
+    async fn watch_events(&self, mut events: EventSource) -> Result<()> {
+        for dir in &events.dirs {
+            info!("Watching {} for changes", dir.dir.display());
+        }
+        let mut interval = time::interval(self.tuning.rescan_interval);
+        let mut schedule = Schedule::new(self.targets.len(), self.tuning.min_sync_interval);
+
+        loop {
+            let wake = schedule.next_wake();
+            tokio::select! {
+                _ = interval.tick() => schedule.mark_all(),
+                _ = time::sleep_until(wake.unwrap_or_else(Instant::now)), if wake.is_some() => {}
+                received = events.changes.recv() => {
+                    let Some(paths) = received else {
+                        return Err(WaylogError::Internal("file watcher stopped".to_string()));
+                    };
+                    let changed = events.settle(paths).await;
+                    events.attach()?;
+                    for (i, dir) in events.dirs.iter().enumerate() {
+                        if dir.covers(&changed) {
+                            schedule.mark(i);
+                        }
+                    }
+                }
+            }
+            let now = Instant::now();
+            for (i, target) in self.targets.iter().enumerate() {
+                if schedule.due(i, now) {
+                    self.sync_latest(target).await;
+                    schedule.synced(i, Instant::now());
+                }
+            }
+            self.render_due().await;
+        }
+    }
+
+    /// Start periodic sync loop
+    async fn poll(&self) -> Result<()> {
         info!(
-            "Starting periodic sync (every {} seconds)",
-            SYNC_INTERVAL_SECS
+            "Starting periodic sync (every {:?})",
+            self.tuning.rescan_interval
         );
 
-        let mut interval = time::interval(Duration::from_secs(SYNC_INTERVAL_SECS));
+        let mut interval = time::interval(self.tuning.rescan_interval);
 
         loop {
             interval.tick().await;
+            for target in &self.targets {
+                self.sync_latest(target).await;
+            }
+            self.render_due().await;
+        }
+    }
 
-            if let Err(e) = self.sync_latest().await {
-                tracing::error!("Periodic sync error: {}", e);
+    /// Renders held back while messages kept coming
+    async fn render_due(&self) {
+        if let Some(renderer) = &self.renderer {
+            if let Err(e) = renderer.render_due().await {
+                tracing::error!("{}", e);
             }
         }
     }
 
-    /// Sync only the latest session
-    async fn sync_latest(&self) -> Result<()> {
+    /// Sync only the latest session of a provider
+    async fn sync_latest(&self, target: &Synchronizer) {
+        let provider = target.provider();
         // Find the latest session file
-        let session_file = match self.provider.find_latest_session(&self.project_dir).await? {
-            Some(file) => file,
-            None => {
-                debug!("No session file found");
-                return Ok(());
+        let session_file = match provider.find_latest_session(&self.project_dir).await {
+            Ok(Some(file)) => file,
+            Ok(None) => {
+                debug!("No {} session file found", provider.name());
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Periodic sync error: {}", e);
+                return;
             }
         };
 
         // Use shared synchronizer logic
-        self.synchronizer.sync_session(&session_file, false).await?;
+        if let Err(e) = target.sync_session(&session_file, false).await {
+            tracing::error!("Periodic sync error: {}", e);
+        }
+    }
+}
+
+/// Wait before the next restart after `failures` failures in a row
+fn restart_delay(failures: u32) -> Duration {
+    RESTART_DELAY
+        .saturating_mul(2u32.saturating_pow(failures))
+        .min(RESTART_MAX_DELAY)
+}
 
+/// Which targets have changes waiting to be synced, and when each may sync
+/// again after `min_sync_interval`
+struct Schedule {
+    min_sync_interval: Duration,
+    last_sync: Vec<Option<Instant>>,
+    waiting: Vec<bool>,
+}
+
+impl Schedule {
+    fn new(targets: usize, min_sync_interval: Duration) -> Self {
+        Self {
+            min_sync_interval,
+            last_sync: vec![None; targets],
+            waiting: vec![false; targets],
+        }
+    }
+
+    fn mark(&mut self, target: usize) {
+        self.waiting[target] = true;
+    }
+
+    fn mark_all(&mut self) {
+        self.waiting.fill(true);
+    }
+
+    /// When `target` may sync again
+    fn ready_at(&self, target: usize) -> Option<Instant> {
+        self.last_sync[target].map(|last| last + self.min_sync_interval)
+    }
+
+    fn due(&self, target: usize, now: Instant) -> bool {
+        self.waiting[target] && self.ready_at(target).is_none_or(|ready| ready <= now)
+    }
+
+    fn synced(&mut self, target: usize, now: Instant) {
+        self.waiting[target] = false;
+        self.last_sync[target] = Some(now);
+    }
+
+    /// The earliest time a waiting target held back by the interval may sync
+    fn next_wake(&self) -> Option<Instant> {
+        (0..self.waiting.len())
+            .filter(|&target| self.waiting[target])
+            .filter_map(|target| self.ready_at(target))
+            .min()
+    }
+}
+
+/// File events under the session directories. Agents create their
+/// directory when they first write to it, so until one exists its nearest
+/// existing ancestor is watched for it to appear.
+struct EventSource {
+    watcher: RecommendedWatcher,
+    dirs: Vec<WatchedDir>,
+    /// Paths of each change event
+    changes: mpsc::UnboundedReceiver<Vec<PathBuf>>,
+    tuning: WatchTuning,
+}
+
+struct WatchedDir {
+    dir: PathBuf,
+    /// What is watched now: `dir`, or an ancestor while `dir` is missing
+    watched: Option<PathBuf>,
+}
+
+impl WatchedDir {
+    /// Whether a change to any of `paths` may be a change to this directory
+    fn covers(&self, paths: &HashSet<PathBuf>) -> bool {
+        paths.iter().any(|path| {
+            path.starts_with(&self.dir)
+                || self
+                    .watched
+                    .as_ref()
+                    .is_some_and(|watched| path.starts_with(watched))
+        })
+    }
+}
+
+impl EventSource {
+    fn new(dirs: Vec<PathBuf>, tuning: WatchTuning) -> Result<Self> {
+        let (tx, changes) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                // Reads, including waylog's own, are not changes
+                Ok(event) if is_change(&event.kind) => {
+                    let _ = tx.send(event.paths);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("File watcher: {}", e),
+            }
+        })?;
+
+        let mut source = Self {
+            watcher,
+            dirs: dirs
+                .into_iter()
+                .map(|dir| WatchedDir { dir, watched: None })
+                .collect(),
+            changes,
+            tuning,
+        };
+        source.attach()?;
+        Ok(source)
+    }
+
+    /// Watch each session directory that exists now, and the nearest
+    /// existing ancestor of each one that does not
+    fn attach(&mut self) -> Result<()> {
+        for dir in &mut self.dirs {
+            attach(&mut self.watcher, dir)?;
+        }
         Ok(())
     }
+
+    /// Wait for a burst of events that started with one for `first` to end:
+    /// the debounce time without one, or four times that after the first.
+    /// Returns every path that changed.
+    async fn settle(&mut self, first: Vec<PathBuf>) -> HashSet<PathBuf> {
+        let mut changed: HashSet<PathBuf> = first.into_iter().collect();
+        let deadline = Instant::now() + self.tuning.max_delay();
+        loop {
+            tokio::select! {
+                _ = time::sleep(self.tuning.debounce) => return changed,
+                _ = time::sleep_until(deadline) => return changed,
+                received = self.changes.recv() => match received {
+                    Some(paths) => changed.extend(paths),
+                    None => return changed,
+                }
+            }
+        }
+    }
+}
+
+fn attach(watcher: &mut RecommendedWatcher, dir: &mut WatchedDir) -> Result<()> {
+    if dir.watched.as_ref() == Some(&dir.dir) {
+        return Ok(());
+    }
+    let Some(target) = dir.dir.ancestors().find(|path| path.is_dir()) else {
+        return Ok(());
+    };
+    if dir.watched.as_deref() == Some(target) {
+        return Ok(());
+    }
+
+    let mode = if target == dir.dir {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    let target = target.to_path_buf();
+    if let Some(previous) = dir.watched.take() {
+        // The ancestor may be gone too; nothing left to unwatch then
+        let _ = watcher.unwatch(&previous);
+    }
+    watcher.watch(&target, mode)?;
+    debug!("Watching {} ({:?})", target.display(), mode);
+    dir.watched = Some(target);
+    Ok(())
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_holds_changes_back_for_the_min_interval() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(1);
+        let mut schedule = Schedule::new(2, interval);
+
+        schedule.mark(0);
+        assert!(schedule.due(0, start));
+        assert!(!schedule.due(1, start));
+        schedule.synced(0, start);
+
+        // A change right after a sync waits until the interval has passed
+        schedule.mark(0);
+        assert!(!schedule.due(0, start + Duration::from_millis(500)));
+        assert_eq!(schedule.next_wake(), Some(start + interval));
+        assert!(schedule.due(0, start + interval));
+
+        schedule.mark_all();
+        assert!(schedule.due(1, start));
+    }
+
+    #[test]
+    fn test_restart_delay_backs_off_up_to_the_max() {
+        assert_eq!(restart_delay(0), Duration::from_secs(1));
+        assert_eq!(restart_delay(1), Duration::from_secs(2));
+        assert_eq!(restart_delay(5), Duration::from_secs(32));
+        assert_eq!(restart_delay(6), RESTART_MAX_DELAY);
+        assert_eq!(restart_delay(40), RESTART_MAX_DELAY);
+    }
 }
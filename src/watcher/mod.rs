@@ -1,3 +1,3 @@
 pub mod file_watcher;
 
-pub use file_watcher::FileWatcher;
+pub use file_watcher::{FileWatcher, WatchTuning};
@@ -0,0 +1,1966 @@
+//! End-to-end tests that run the `waylog` binary against the recorded provider
+//! sessions in `tests/fixtures/home`, using `WAYLOG_PROVIDER_FIXTURES`.
+//!
+//! Fixture files use `__PROJECT__` wherever the original session recorded its
+//! working directory; it is replaced with the temporary project path when the
+//! tree is copied.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+const PROJECT_PLACEHOLDER: &str = "__PROJECT__";
+
+struct Sandbox {
+    _root: TempDir,
+    project: PathBuf,
+    fixtures: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("project");
+        std::fs::create_dir_all(project.join(".waylog")).unwrap();
+        let project = project.canonicalize().unwrap();
+
+        let fixtures = root.path().join("fixtures");
+        copy_fixture_tree(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/home"),
+            &fixtures,
+            &project,
+        );
+
+        Self {
+            _root: root,
+            project,
+            fixtures,
+        }
+    }
+
+    fn waylog(&self, args: &[&str]) -> Output {
+        self.waylog_in(&self.project, args)
+    }
+
+    fn waylog_in(&self, dir: &Path, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_waylog"))
+            .args(args)
+            .current_dir(dir)
+            .env("WAYLOG_PROVIDER_FIXTURES", &self.fixtures)
+            .env("HOME", self.project.parent().unwrap())
+            .env("NO_COLOR", "1")
+            .env("LC_ALL", "C")
+            .output()
+            .unwrap()
+    }
+
+    fn history(&self) -> Vec<(String, String)> {
+        let mut files: Vec<_> = std::fs::read_dir(self.project.join(".waylog/history"))
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| {
+                (
+                    e.file_name().to_string_lossy().to_string(),
+                    std::fs::read_to_string(e.path()).unwrap(),
+                )
+            })
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// The cells of `provider`'s row in the summary table `pull` prints: provider,
+/// found, synced, up to date, skipped, failed and new messages
+fn summary_row(stdout: &str, provider: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(|line| {
+            line.split(['│', '┆'])
+                .map(|cell| cell.trim().to_string())
+                .filter(|cell| !cell.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .find(|cells| cells.first().map(String::as_str) == Some(provider))
+        .unwrap_or_else(|| panic!("no {} row in\n{}", provider, stdout))
+}
+
+/// Copy `src` to `dest`, substituting the project placeholder in paths and contents
+fn copy_fixture_tree(src: &Path, dest: &Path, project: &Path) {
+    let encoded: String = project
+        .to_string_lossy()
+        .replace('\\', "/")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let project_str = project.to_string_lossy().replace('\\', "\\\\");
+
+    for entry in WalkDir::new(src).into_iter().filter_map(|e| e.ok()) {
+        let relative = entry.path().strip_prefix(src).unwrap();
+        let target = dest.join(
+            relative
+                .to_string_lossy()
+                .replace(PROJECT_PLACEHOLDER, &encoded),
+        );
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target).unwrap();
+        } else {
+            let content = std::fs::read_to_string(entry.path()).unwrap();
+            std::fs::write(&target, content.replace(PROJECT_PLACEHOLDER, &project_str)).unwrap();
+        }
+    }
+}
+
+#[test]
+fn pull_syncs_fixture_sessions() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.waylog(&["pull"]);
+    assert!(
+        output.status.success(),
+        "pull failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // stderr is a pipe here, so no progress bar is drawn on it
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("ETA") && !stderr.contains("scanning"),
+        "{}",
+        stderr
+    );
+
+    let history = sandbox.history();
+    assert_eq!(history.len(), 2, "{:?}", history);
+
+    let (claude_name, claude) = history
+        .iter()
+        .find(|(name, _)| name.contains("-claude-"))
+        .unwrap();
+    assert!(claude_name.starts_with("2025-01-15_09-30-00Z-claude-why-does-cargo-build"));
+    assert!(claude.contains("session_id: 0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"));
+    assert!(claude.contains("message_count: 3"));
+    assert!(claude.contains("- `Bash`"));
+    assert!(claude.contains("files_edited: src/main.rs\n"));
+
+    let (_, codex) = history
+        .iter()
+        .find(|(name, _)| name.contains("-codex-"))
+        .unwrap();
+    assert!(codex.contains("Add a README section about installation"));
+    assert!(!codex.contains("environment_context"));
+}
+
+#[test]
+fn pull_is_idempotent_on_fixtures() {
+    let sandbox = Sandbox::new();
+
+    assert!(sandbox.waylog(&["pull"]).status.success());
+    let first = sandbox.history();
+    assert!(sandbox.waylog(&["pull"]).status.success());
+
+    assert_eq!(first, sandbox.history());
+}
+
+#[test]
+fn verbose_levels_add_per_session_detail() {
+    let sandbox = Sandbox::new();
+    let stdout = |args: &[&str]| {
+        let output = sandbox.waylog(args);
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let first = stdout(&["pull", "--provider", "claude", "-v"]);
+    assert!(first.contains("[claude] Found 1 sessions"), "{}", first);
+    assert!(first.contains("Synced: "), "{}", first);
+    assert!(!first.contains(".claude/projects"), "{}", first);
+    assert_eq!(
+        summary_row(&first, "claude"),
+        ["claude", "1", "1", "0", "0", "0", "3"]
+    );
+
+    let quiet = stdout(&["pull", "--provider", "claude"]);
+    assert!(!quiet.contains("[claude]"), "{}", quiet);
+    assert_eq!(summary_row(&quiet, "Total")[3], "1", "{}", quiet);
+
+    let again = stdout(&["pull", "--provider", "claude", "-v"]);
+    assert!(!again.contains("Up to date: "), "{}", again);
+    assert_eq!(summary_row(&again, "claude")[3], "1", "{}", again);
+
+    let listed = stdout(&["pull", "--provider", "claude", "-vv"]);
+    assert!(listed.contains("Up to date: "), "{}", listed);
+
+    let paths = stdout(&["pull", "--provider", "claude", "-vvv"]);
+    assert!(paths.contains(".claude/projects/"), "{}", paths);
+}
+
+#[test]
+fn init_writes_starter_config_and_pulls() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.waylog(&["init", "--yes"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("claude: installed, 1 sessions"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("gemini: not installed"), "{}", stdout);
+
+    let config = std::fs::read_to_string(sandbox.project.join(".waylog/config.toml")).unwrap();
+    assert!(
+        config.contains("enabled = [\"claude\", \"codex\"]"),
+        "{}",
+        config
+    );
+    assert_eq!(sandbox.history().len(), 2);
+
+    // An existing config is left alone
+    std::fs::write(sandbox.project.join(".waylog/config.toml"), "# mine\n").unwrap();
+    assert!(sandbox.waylog(&["init", "--yes"]).status.success());
+    assert_eq!(
+        std::fs::read_to_string(sandbox.project.join(".waylog/config.toml")).unwrap(),
+        "# mine\n"
+    );
+}
+
+#[test]
+fn init_flags_answer_the_setup_questions() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.waylog(&[
+        "init",
+        "--yes",
+        "--agent",
+        "claude",
+        "--layout",
+        "provider",
+        "--gitignore",
+        "ignore",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let config = std::fs::read_to_string(sandbox.project.join(".waylog/config.toml")).unwrap();
+    assert!(config.contains("default_agent = \"claude\""), "{}", config);
+    assert_eq!(
+        std::fs::read_to_string(sandbox.project.join(".gitignore")).unwrap(),
+        ".waylog/\n"
+    );
+    let history = sandbox.history();
+    assert!(
+        history
+            .iter()
+            .all(|(name, _)| name.starts_with("claude-2025-01-15-")
+                || name.starts_with("codex-2025-")),
+        "{:?}",
+        history
+    );
+}
+
+#[test]
+fn exported_jsonl_imports_back() {
+    let sandbox = Sandbox::new();
+    let dataset = sandbox.project.join("sessions.jsonl");
+
+    let export = sandbox.waylog(&["export", "--out", dataset.to_str().unwrap()]);
+    assert!(export.status.success());
+
+    let import = sandbox.waylog(&["import", dataset.to_str().unwrap()]);
+    assert!(
+        import.status.success(),
+        "import failed: {}",
+        String::from_utf8_lossy(&import.stderr)
+    );
+
+    let history = sandbox.history();
+    assert_eq!(history.len(), 2, "{:?}", history);
+    let (_, claude) = history
+        .iter()
+        .find(|(name, _)| name.contains("-claude-"))
+        .unwrap();
+    assert!(claude.contains("session_id: 0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"));
+    assert!(claude.contains("files_edited: src/main.rs\n"));
+
+    // Pulling afterwards recognises the imported sessions
+    assert!(sandbox.waylog(&["pull"]).status.success());
+    assert_eq!(sandbox.history().len(), 2);
+}
+
+#[test]
+fn export_sqlite_runs_in_the_background() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.waylog(&["export", "--format", "sqlite", "--background"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The detached export records its progress for --status
+    let started = std::time::Instant::now();
+    let status = loop {
+        let output = sandbox.waylog(&["--json", "export", "--status"]);
+        assert!(output.status.success());
+        let status: Option<serde_json::Value> = serde_json::from_slice(&output.stdout).ok();
+        match status {
+            Some(status) if status["finished"] == true => break status,
+            _ if started.elapsed() > std::time::Duration::from_secs(30) => {
+                panic!("backfill did not finish")
+            }
+            _ => std::thread::sleep(std::time::Duration::from_millis(100)),
+        }
+    };
+    assert_eq!(status["written"], status["total"]);
+    assert!(status.get("error").is_none(), "{}", status);
+
+    let output = sandbox.waylog(&["export", "--status"]);
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.starts_with("Wrote "), "{}", text);
+    assert!(text.contains("history.db"), "{}", text);
+
+    let output = sandbox.waylog(&["export", "--background"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn usage_global_counts_sessions_of_other_projects() {
+    let sandbox = Sandbox::new();
+
+    // A Claude session recorded in some other repository
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let other = session
+        .parent()
+        .unwrap()
+        .with_file_name("-elsewhere-other-repo");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::copy(&session, other.join(session.file_name().unwrap())).unwrap();
+
+    let output = sandbox.waylog(&["usage"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("claude: 1 sessions"), "{}", stdout);
+    assert!(
+        stdout.contains("Total for this project: 2 sessions"),
+        "{}",
+        stdout
+    );
+
+    let stdout =
+        String::from_utf8_lossy(&sandbox.waylog(&["usage", "--global"]).stdout).to_string();
+    assert!(stdout.contains("claude: 2 sessions"), "{}", stdout);
+    assert!(stdout.contains("codex: 1 sessions"), "{}", stdout);
+    assert!(stdout.contains("gemini: no sessions"), "{}", stdout);
+    assert!(
+        stdout.contains("Total for all projects on this machine: 3 sessions"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn relink_finds_sessions_recorded_under_the_old_path() {
+    let sandbox = Sandbox::new();
+    let old = sandbox.project.parent().unwrap().join("old-name");
+
+    // The Claude session was recorded before the project was moved here
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let encoded = session
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let old_sessions = session.parent().unwrap().with_file_name(format!(
+        "{}old-name",
+        encoded.strip_suffix("project").unwrap()
+    ));
+    std::fs::create_dir_all(&old_sessions).unwrap();
+    let content = std::fs::read_to_string(&session).unwrap().replace(
+        &sandbox.project.display().to_string(),
+        &old.display().to_string(),
+    );
+    std::fs::write(old_sessions.join(session.file_name().unwrap()), content).unwrap();
+    std::fs::remove_dir_all(session.parent().unwrap()).unwrap();
+    std::fs::create_dir_all(sandbox.project.join(".waylog")).unwrap();
+
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+    assert!(!sandbox.project.join(".waylog/history").exists());
+
+    let output = sandbox.waylog(&["relink", "--old-path", old.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("1 session found"), "{}", stdout);
+
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+    let history = sandbox.history();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].1.contains("cargo build"));
+}
+
+#[test]
+fn pull_fails_fast_while_another_waylog_holds_the_lock() {
+    let sandbox = Sandbox::new();
+    std::fs::create_dir_all(sandbox.project.join(".waylog")).unwrap();
+
+    // Stand in for a `waylog run` in another terminal
+    let lock_path = sandbox.project.join(".waylog/waylog.lock");
+    std::fs::write(
+        &lock_path,
+        r#"{"pid":4242,"host":null,"command":"waylog run claude","started":"2026-10-17T09:30:00+00:00"}"#,
+    )
+    .unwrap();
+    let lock = std::fs::File::open(&lock_path).unwrap();
+    lock.lock().unwrap();
+
+    let output = sandbox.waylog(&["pull"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(75), "{}", stderr);
+    assert!(
+        stderr.contains("`waylog run claude` (pid 4242"),
+        "{}",
+        stderr
+    );
+    assert!(!sandbox.project.join(".waylog/history").exists());
+
+    // Read-only commands are not held up
+    assert!(sandbox.waylog(&["pull", "--dry-run"]).status.success());
+
+    drop(lock);
+    assert!(sandbox.waylog(&["pull"]).status.success());
+    assert!(!sandbox.history().is_empty());
+}
+
+#[test]
+fn pull_rebuild_rewrites_one_session_from_its_source() {
+    let sandbox = Sandbox::new();
+    assert!(sandbox.waylog(&["pull"]).status.success());
+    let before = sandbox.history();
+    let (name, original) = before
+        .iter()
+        .find(|(_, content)| content.contains("0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"))
+        .unwrap()
+        .clone();
+
+    // One export whose messages got mangled, alongside untouched ones
+    let mangled = sandbox.project.join(".waylog/history").join(&name);
+    let end = original[3..].find("\n---").unwrap() + 7;
+    let content = format!("{}mangled\n", &original[..end]);
+    std::fs::write(&mangled, &content).unwrap();
+
+    let output = sandbox.waylog(&["pull", "--session", "0f6c2a4e"]);
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&mangled).unwrap(), content);
+
+    let output = sandbox.waylog(&["pull", "--rebuild", "0f6c2a4e"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(sandbox.history().len(), before.len());
+    let rebuilt = std::fs::read_to_string(&mangled).unwrap();
+    assert!(rebuilt.contains("cargo build"));
+    assert_eq!(
+        rebuilt.lines().filter(|l| l.starts_with("## ")).count(),
+        original.lines().filter(|l| l.starts_with("## ")).count()
+    );
+
+    let output = sandbox.waylog(&["pull", "--rebuild", "no-such-session"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no-such-session"));
+}
+
+#[test]
+fn pull_exit_code_tells_a_partial_sync_from_a_failed_one() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+
+    // The claude session can't be synced: its history file was edited by
+    // hand, the agent added a message and `skip` refuses to write it
+    let (name, original) = sandbox.history().remove(0);
+    std::fs::write(
+        sandbox.project.join(".waylog/history").join(&name),
+        format!("{}\nMy own notes.\n", original),
+    )
+    .unwrap();
+    let mut content = std::fs::read_to_string(&session).unwrap();
+    content.push_str(&format!(
+        r#"{{"parentUuid":"a4","cwd":"{}","sessionId":"0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c","type":"user","message":{{"role":"user","content":"Thanks, that fixed it"}},"uuid":"a5","timestamp":"2025-01-15T09:31:00.000Z"}}"#,
+        sandbox.project.display()
+    ));
+    content.push('\n');
+    std::fs::write(&session, &content).unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[sync]\non_conflict = \"skip\"\n",
+    )
+    .unwrap();
+
+    // The codex session still syncs
+    let output = sandbox.waylog(&["pull"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(80), "{}", stderr);
+    assert_eq!(sandbox.history().len(), 2, "{}", stderr);
+
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(81), "{}", stderr);
+}
+
+#[test]
+fn pull_all_projects_syncs_each_initialized_project() {
+    let sandbox = Sandbox::new();
+    let root = sandbox.project.parent().unwrap();
+
+    // A Claude session recorded in a sibling repository
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let other = root.join("other");
+    std::fs::create_dir_all(&other).unwrap();
+    let encoded = session
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy();
+    let other_sessions = session
+        .parent()
+        .unwrap()
+        .with_file_name(format!("{}other", encoded.strip_suffix("project").unwrap()));
+    std::fs::create_dir_all(&other_sessions).unwrap();
+    let content = std::fs::read_to_string(&session).unwrap().replace(
+        &sandbox.project.display().to_string(),
+        &other.display().to_string(),
+    );
+    std::fs::write(other_sessions.join("other.jsonl"), content).unwrap();
+
+    let output = sandbox.waylog_in(root, &["pull", "--all-projects", "--verbose"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert_eq!(sandbox.history().len(), 2);
+    assert!(stdout.contains("not initialized"), "{}", stdout);
+    assert!(
+        stdout.contains("All projects: 1 projects pulled"),
+        "{}",
+        stdout
+    );
+    assert!(!other.join(".waylog").exists());
+
+    std::fs::create_dir(other.join(".waylog")).unwrap();
+    let output = sandbox.waylog_in(root, &["pull", "--all-projects"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("All projects: 2 projects pulled"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        std::fs::read_dir(other.join(".waylog/history"))
+            .unwrap()
+            .count(),
+        1
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn run_timeout_stops_the_agent_and_syncs() {
+    let sandbox = Sandbox::new();
+    std::fs::create_dir_all(sandbox.project.join(".waylog")).unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[agents.stuck]\nprovider = \"claude\"\ncommand = \"sleep 60\"\n",
+    )
+    .unwrap();
+
+    let started = std::time::Instant::now();
+    let output = sandbox.waylog(&["run", "--timeout", "1s", "stuck"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(124), "{}", stderr);
+    assert!(started.elapsed() < std::time::Duration::from_secs(30));
+    assert!(stderr.contains("--timeout"), "{}", stderr);
+    assert!(sandbox
+        .history()
+        .iter()
+        .any(|(_, content)| content.contains("cargo build")));
+}
+
+#[cfg(unix)]
+#[test]
+fn run_syncs_new_messages_while_the_agent_is_running() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let line = sandbox.project.join("line.jsonl");
+    std::fs::write(
+        &line,
+        "{\"type\":\"user\",\"sessionId\":\"0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c\",\"message\":{\"role\":\"user\",\"content\":\"live marker\"},\"uuid\":\"a9\",\"timestamp\":\"2025-01-15T09:31:00.000Z\"}\n",
+    )
+    .unwrap();
+
+    // A stand-in agent that writes a message, then waits for it to show up
+    // in the history while it is still running
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(
+        &agent,
+        format!(
+            "sleep 1\ncat {line} >> {session}\ni=0\nwhile [ $i -lt 50 ]; do\n  grep -rqs 'live marker' .waylog/history && exit 0\n  sleep 0.1\n  i=$((i + 1))\ndone\nexit 3\n",
+            line = line.display(),
+            session = session.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "live"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn run_detached_final_sync_waits_for_the_project_lock() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let line = sandbox.project.join("line.jsonl");
+    std::fs::write(
+        &line,
+        "{\"type\":\"user\",\"sessionId\":\"0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c\",\"message\":{\"role\":\"user\",\"content\":\"detached marker\"},\"uuid\":\"a9\",\"timestamp\":\"2025-01-15T09:31:00.000Z\"}\n",
+    )
+    .unwrap();
+
+    // The watcher never syncs during the run, so only the detached pull can
+    // write the message; the post_run hook keeps `run` (and its lock) alive
+    // after the pull has started
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh -c 'cat {} >> {}'\"\n\n\
+             [run]\ndetach_final_sync = true\n\n[watch]\nmode = \"poll\"\nrescan_interval = 3600\n\n\
+             [hooks]\npost_run = [\"sleep 1\"]\n",
+            line.display(),
+            session.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "live"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let history = sandbox.project.join(".waylog/history");
+    let synced = || {
+        std::fs::read_dir(&history).is_ok_and(|entries| {
+            entries.filter_map(|e| e.ok()).any(|e| {
+                std::fs::read_to_string(e.path()).is_ok_and(|c| c.contains("detached marker"))
+            })
+        })
+    };
+    let started = std::time::Instant::now();
+    while !synced() && started.elapsed() < std::time::Duration::from_secs(30) {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(synced(), "the detached pull did not sync the session");
+}
+
+#[cfg(unix)]
+#[test]
+fn run_all_providers_syncs_another_agents_session() {
+    let sandbox = Sandbox::new();
+    let codex = WalkDir::new(sandbox.fixtures.join(".codex"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+
+    // While a claude stand-in runs, codex starts a session of its own
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(
+        &agent,
+        format!(
+            "sleep 1\nd={sessions}/$(date -u +%Y/%m/%d)\nmkdir -p $d\nsed 's/Add a README section about installation/codex marker/' {src} > $d/rollout-live.jsonl\ni=0\nwhile [ $i -lt 50 ]; do\n  grep -rqs 'codex marker' .waylog/history && exit 0\n  sleep 0.1\n  i=$((i + 1))\ndone\nexit 3\n",
+            sessions = sandbox.fixtures.join(".codex/sessions").display(),
+            src = codex.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "--all-providers", "live"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // The other agent's session does not claim the launched agent's command
+    let (_, codex_history) = sandbox
+        .history()
+        .into_iter()
+        .find(|(_, content)| content.contains("codex marker"))
+        .unwrap();
+    assert!(!codex_history.contains("invocation:"), "{}", codex_history);
+}
+
+#[cfg(unix)]
+#[test]
+fn batch_labels_the_session_of_each_prompt() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+
+    // A stand-in agent that records one session per prompt (`-p <prompt>`)
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(
+        &agent,
+        format!(
+            "sed \"s/0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c/batch-$2/g\" {src} > {dir}/batch-$2.jsonl\n[ \"$2\" != broken ]\n",
+            src = session.display(),
+            dir = session.parent().unwrap().display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.scripted]\nprovider = \"claude\"\ncommand = \"sh {}\"\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join("prompts.txt"),
+        "# nightly\nfirst\n\nbroken\n",
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["batch", "--agent", "scripted", "--prompts", "prompts.txt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(75), "{}", stdout);
+    assert!(
+        stdout.contains("prompts-1: done, 1 session synced"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("prompts-2: exited with 1, 1 session synced"),
+        "{}",
+        stdout
+    );
+
+    let history = sandbox.history();
+    assert_eq!(history.len(), 2);
+    for (label, id) in [("prompts-1", "batch-first"), ("prompts-2", "batch-broken")] {
+        assert!(history.iter().any(|(_, content)| content.contains(id)
+            && content.contains(&format!("label: \"{}\"", label))));
+    }
+}
+
+#[test]
+fn home_directory_needs_here_flag() {
+    let sandbox = Sandbox::new();
+    let home = sandbox.project.parent().unwrap();
+
+    for args in [&["run", "claude"][..], &["init", "-y"]] {
+        let output = sandbox.waylog_in(home, args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(64), "{}", stderr);
+        assert!(stderr.contains("is your home directory"), "{}", stderr);
+        assert!(stderr.contains("--here"), "{}", stderr);
+        assert!(!home.join(".waylog").exists());
+    }
+
+    let output = sandbox.waylog_in(home, &["init", "-y", "--here"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(home.join(".waylog").is_dir());
+}
+
+#[test]
+fn pull_session_syncs_only_that_file() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+
+    let output = sandbox.waylog(&["pull", "--session", &session.to_string_lossy()]);
+    assert!(output.status.success(), "{:?}", output);
+    let history = sandbox.history();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].0.contains("-claude-"));
+
+    // Files outside every provider's data directory are rejected
+    let stray = sandbox.project.join("notes.jsonl");
+    std::fs::write(&stray, "").unwrap();
+    let output = sandbox.waylog(&["pull", "--session", &stray.to_string_lossy()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--provider"));
+}
+
+#[test]
+fn pull_keeps_hand_edited_history_files() {
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+    let (name, original) = sandbox.history().remove(0);
+    let edited = format!("{}\nMy own notes.\n", original);
+    let file = sandbox.project.join(".waylog/history").join(&name);
+    std::fs::write(&file, &edited).unwrap();
+
+    let mut content = std::fs::read_to_string(&session).unwrap();
+    content.push_str(&format!(
+        r#"{{"parentUuid":"a4","cwd":"{}","sessionId":"0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c","type":"user","message":{{"role":"user","content":"Thanks, that fixed it"}},"uuid":"a5","timestamp":"2025-01-15T09:31:00.000Z"}}"#,
+        sandbox.project.display()
+    ));
+    content.push('\n');
+    std::fs::write(&session, &content).unwrap();
+
+    // `skip` leaves the edited file alone and reports the session
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[sync]\non_conflict = \"skip\"\n",
+    )
+    .unwrap();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("edited by hand"), "{:?}", output);
+    assert_eq!(sandbox.history().len(), 1);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), edited);
+
+    // The default writes the whole session to a new revision instead
+    std::fs::remove_file(sandbox.project.join(".waylog/config.toml")).unwrap();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), edited);
+    let history = sandbox.history();
+    assert_eq!(history.len(), 2, "{:?}", history);
+    let revision = &history.iter().find(|(n, _)| n.contains("-rev2")).unwrap().1;
+    assert!(revision.contains("revision: 2"), "{}", revision);
+    assert!(revision.contains("Thanks, that fixed it"));
+    assert!(revision.contains("message_count: 4"), "{}", revision);
+
+    // Later messages go to the revision
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+    assert_eq!(sandbox.history().len(), 2);
+}
+
+#[test]
+fn pull_dry_run_reports_without_writing() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--provider", "claude", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout.contains("Would create"), "{}", stdout);
+    assert!(stdout.contains("(3 messages from"), "{}", stdout);
+    assert!(
+        stdout.contains("would create 1 files and append to 0"),
+        "{}",
+        stdout
+    );
+    assert!(!sandbox.project.join(".waylog/history").exists());
+    assert!(!sandbox.project.join(".waylog/parse-index.json").exists());
+
+    assert!(sandbox
+        .waylog(&["pull", "--provider", "claude"])
+        .status
+        .success());
+    let before = sandbox.history();
+    let output = sandbox.waylog(&["pull", "--provider", "claude", "--dry-run", "--force"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("Would create {}", before[0].0)),
+        "{}",
+        stdout
+    );
+    assert_eq!(sandbox.history(), before);
+}
+
+#[test]
+fn tail_prints_messages_as_they_are_written() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .args(["tail", "claude", "-n", "1"])
+        .current_dir(&sandbox.project)
+        .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .env("NO_COLOR", "1")
+        .env("LC_ALL", "C")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let (tx, lines) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let _ = tx.send(line);
+        }
+    });
+    let wait_for = |pattern: &str| {
+        let mut seen = Vec::new();
+        while let Ok(line) = lines.recv_timeout(Duration::from_secs(10)) {
+            if line.contains(pattern) {
+                return seen;
+            }
+            seen.push(line);
+        }
+        panic!("'{}' was not printed, only {:?}", pattern, seen);
+    };
+
+    // The end of the existing session first, then what the agent writes next
+    let before = wait_for("assistant");
+    assert!(
+        before.iter().all(|line| !line.contains("user")),
+        "{:?}",
+        before
+    );
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&session)
+        .unwrap();
+    std::io::Write::write_all(
+        &mut file,
+        b"{\"type\":\"user\",\"sessionId\":\"0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c\",\"message\":{\"role\":\"user\",\"content\":\"tail marker\"},\"uuid\":\"a9\",\"timestamp\":\"2025-01-15T09:31:00.000Z\"}\n",
+    )
+    .unwrap();
+    wait_for("tail marker");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn pull_fires_message_hooks_with_the_new_messages() {
+    let sandbox = Sandbox::new();
+    let events = sandbox.project.join("events.jsonl");
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[[hooks.message]]\ncommand = \"sh -c 'cat >> {}; echo >> {}'\"\n",
+            events.display(),
+            events.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let content = std::fs::read_to_string(&events).unwrap();
+    let events: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 1, "{}", content);
+    assert_eq!(events[0]["event"], "message");
+    assert_eq!(events[0]["provider"], "claude");
+    assert_eq!(
+        events[0]["session_id"],
+        "0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"
+    );
+    assert!(!events[0]["messages"].as_array().unwrap().is_empty());
+
+    // Nothing new, no event
+    sandbox.waylog(&["pull", "--provider", "claude"]);
+    let again = std::fs::read_to_string(sandbox.project.join("events.jsonl")).unwrap();
+    assert_eq!(again, content);
+}
+
+#[cfg(unix)]
+#[test]
+fn run_hooks_wrap_the_agent() {
+    let sandbox = Sandbox::new();
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(&agent, "test -f pre.txt || exit 9\ntouch ran.txt\nexit 5\n").unwrap();
+    let config = |pre_run: &str| {
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n\n[hooks]\npre_run = [{:?}]\npost_run = [\"sh -c 'env | grep ^WAYLOG_ > post.txt'\"]\n",
+            agent.display(),
+            pre_run
+        )
+    };
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        config("sh -c 'echo pre > pre.txt'"),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "live"]);
+    assert_eq!(
+        output.status.code(),
+        Some(5),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let post = std::fs::read_to_string(sandbox.project.join("post.txt")).unwrap();
+    assert!(post.contains("WAYLOG_EXIT_CODE=5\n"), "{}", post);
+    assert!(post.contains("WAYLOG_PROVIDER=claude\n"), "{}", post);
+    assert!(
+        post.contains("WAYLOG_SESSION_ID=0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c\n"),
+        "{}",
+        post
+    );
+    let markdown = post
+        .lines()
+        .find_map(|line| line.strip_prefix("WAYLOG_MARKDOWN_PATH="))
+        .unwrap();
+    assert!(Path::new(markdown).is_file(), "{}", post);
+
+    // A failing pre_run keeps the agent from starting
+    std::fs::remove_file(sandbox.project.join("ran.txt")).unwrap();
+    std::fs::write(sandbox.project.join(".waylog/config.toml"), config("false")).unwrap();
+    let output = sandbox.waylog(&["run", "live"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("pre_run `false`"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!sandbox.project.join("ran.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn note_is_added_to_the_running_session() {
+    use std::process::Stdio;
+    use std::time::Duration;
+
+    let sandbox = Sandbox::new();
+    let session = WalkDir::new(sandbox.fixtures.join(".claude"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .unwrap()
+        .into_path();
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(
+        &agent,
+        format!(
+            "touch {}\nfor i in $(seq 300); do test -f done && exit 0; sleep 0.1; done\n",
+            session.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+
+    // Without a run there is nobody to take the note
+    let output = sandbox.waylog(&["note", "too early"]);
+    assert_eq!(output.status.code(), Some(69));
+
+    let mut run = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .args(["run", "live"])
+        .current_dir(&sandbox.project)
+        .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .env("NO_COLOR", "1")
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // Taken once the agent's session has been synced
+    let mut output = sandbox.waylog(&["note", "tried", "the lock-free approach"]);
+    for _ in 0..100 {
+        if output.status.success() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        output = sandbox.waylog(&["note", "tried", "the lock-free approach"]);
+    }
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Noted in "));
+
+    std::fs::write(sandbox.project.join("done"), "").unwrap();
+    assert!(run.wait().unwrap().success());
+
+    let history = sandbox.history();
+    assert_eq!(history.len(), 1);
+    let content = &history[0].1;
+    assert!(
+        content.contains("> **📝 Note** (")
+            && content.contains(")\n>\n> tried the lock-free approach\n"),
+        "{}",
+        content
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn run_session_name_names_the_history_file() {
+    let sandbox = Sandbox::new();
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(&agent, "exit 0\n").unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "--session-name", "Fix race condition", "live"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let history = sandbox.history();
+    assert_eq!(history.len(), 1);
+    let (name, content) = &history[0];
+    assert!(name.ends_with("-claude-fix-race-condition.md"), "{}", name);
+    assert!(
+        content.contains("\ntitle: \"Fix race condition\"\n"),
+        "{}",
+        content
+    );
+    assert!(content.contains("\n# Fix race condition\n"), "{}", content);
+
+    // Found again by the name
+    let output = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .args(["show", "fix-race-condition"])
+        .current_dir(&sandbox.project)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .env("EDITOR", "true")
+        .env_remove("VISUAL")
+        .output()
+        .unwrap();
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains(name.as_str()),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn pull_commits_the_history_to_git() {
+    let sandbox = Sandbox::new();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&sandbox.project)
+            .env("HOME", sandbox.project.parent().unwrap())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.name", "Test"]);
+    git(&["config", "user.email", "test@example.com"]);
+    std::fs::write(sandbox.project.join("README"), "one\n").unwrap();
+    git(&["add", "README"]);
+    git(&["commit", "-qm", "init"]);
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[git]\nauto_commit = true\non_dirty = \"skip\"\n",
+    )
+    .unwrap();
+
+    // Not while other work is uncommitted
+    std::fs::write(sandbox.project.join("README"), "two\n").unwrap();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("other changes"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(git(&["rev-list", "--count", "HEAD"]), "1");
+
+    git(&["checkout", "README"]);
+    let output = sandbox.waylog(&["pull", "--provider", "claude", "--force"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let subject = git(&["log", "-1", "--format=%s"]);
+    assert!(
+        subject.starts_with("waylog: claude session '") && subject.ends_with("', 3 msgs"),
+        "{}",
+        subject
+    );
+    let committed = git(&["show", "--name-only", "--format=", "HEAD"]);
+    assert!(
+        committed
+            .lines()
+            .all(|file| file.starts_with(".waylog/history/")),
+        "{}",
+        committed
+    );
+    assert_eq!(git(&["status", "--porcelain", "--", ".waylog/history"]), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn run_lists_the_commits_made_during_the_session() {
+    let sandbox = Sandbox::new();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&sandbox.project)
+            .env("HOME", sandbox.project.parent().unwrap())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.name", "Test"]);
+    git(&["config", "user.email", "test@example.com"]);
+    std::fs::write(sandbox.project.join("README"), "one\n").unwrap();
+    git(&["add", "README"]);
+    git(&["commit", "-qm", "Before the session"]);
+
+    // The agent commits twice
+    let agent = sandbox.project.join("agent.sh");
+    std::fs::write(
+        &agent,
+        "echo two > README && git commit -qam 'Fix the login redirect'\n\
+         echo three > README && git commit -qam 'Add a test for it'\n",
+    )
+    .unwrap();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[agents.live]\nprovider = \"claude\"\ncommand = \"sh {}\"\n\n[export.public]\nenabled = true\n",
+            agent.display()
+        ),
+    )
+    .unwrap();
+
+    let output = sandbox.waylog(&["run", "live"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let history = sandbox.history();
+    assert_eq!(history.len(), 1);
+    let content = &history[0].1;
+    let section = content
+        .split("## Commits made during this session\n")
+        .nth(1)
+        .unwrap_or_else(|| panic!("{}", content));
+    let listed: Vec<&str> = section.lines().filter(|l| l.starts_with("- `")).collect();
+    assert_eq!(listed.len(), 2, "{}", content);
+    assert!(
+        listed[0].ends_with("` Fix the login redirect"),
+        "{}",
+        content
+    );
+    assert!(listed[1].ends_with("` Add a test for it"), "{}", content);
+    assert!(!content.contains("Before the session"), "{}", content);
+
+    // The shareable copy gets the section too
+    let public = std::fs::read_to_string(
+        sandbox
+            .project
+            .join(".waylog/history-public")
+            .join(&history[0].0),
+    )
+    .unwrap();
+    assert!(public.contains("` Add a test for it"), "{}", public);
+}
+
+#[test]
+fn mcp_serves_the_history_to_agents() {
+    use std::io::Write;
+
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let (_, content) = &sandbox.history()[0];
+    // A word of the session to search for
+    let word = content
+        .split("\n\n")
+        .nth(3)
+        .and_then(|paragraph| paragraph.split_whitespace().find(|w| w.len() > 3))
+        .unwrap()
+        .to_string();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .arg("mcp")
+        .current_dir(&sandbox.project)
+        .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let requests = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"test","version":"1"}}}"#.to_string(),
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#.to_string(),
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#.to_string(),
+        format!(
+            r#"{{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{{"name":"search_sessions","arguments":{{"query":{}}}}}}}"#,
+            serde_json::to_string(&word).unwrap()
+        ),
+        r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"get_session","arguments":{"id":"0f6c2a4e"}}}"#.to_string(),
+        r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"recent_context","arguments":{"messages":1}}}"#.to_string(),
+        r#"{"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"get_session","arguments":{"id":"nope"}}}"#.to_string(),
+    ];
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all((requests.join("\n") + "\n").as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let replies: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // The notification gets no reply
+    let ids: Vec<_> = replies.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+    assert_eq!(ids, [1, 2, 3, 4, 5, 6]);
+    assert_eq!(replies[0]["result"]["serverInfo"]["name"], "waylog");
+    assert_eq!(replies[1]["result"]["tools"].as_array().unwrap().len(), 3);
+
+    let text = |reply: &serde_json::Value| {
+        reply["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let hits: serde_json::Value = serde_json::from_str(&text(&replies[2])).unwrap();
+    assert_eq!(
+        hits[0]["session_id"],
+        "0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"
+    );
+    assert_eq!(&text(&replies[3]), content);
+    assert!(text(&replies[4]).starts_with("# "), "{}", text(&replies[4]));
+    assert_eq!(replies[5]["result"]["isError"], true);
+}
+
+#[test]
+fn serve_answers_json_queries() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .args(["serve", "--port", "0"])
+        .current_dir(&sandbox.project)
+        .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .env("NO_COLOR", "1")
+        .env("LC_ALL", "C")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let addr = banner
+        .split("http://")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or_else(|| panic!("{}", banner))
+        .to_string();
+
+    let send = |request: &str| {
+        let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        let (head, body) = reply.split_once("\r\n\r\n").unwrap();
+        let status: u16 = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (
+            status,
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+        )
+    };
+    let get = |target: &str| {
+        send(&format!(
+            "GET {} HTTP/1.1\r\nhost: {}\r\n\r\n",
+            target, addr
+        ))
+    };
+
+    let (status, body) = get("/sessions");
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 1);
+    let id = body["sessions"][0]["session_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(id, "0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c");
+    assert_eq!(get("/sessions?provider=codex").1["total"], 0);
+    assert_eq!(get("/sessions?until=2000-01-01").1["total"], 0);
+    assert_eq!(get("/sessions?page=2").1["sessions"], serde_json::json!([]));
+
+    let (status, body) = get("/sessions/0f6c2a4e");
+    assert_eq!(status, 200);
+    assert_eq!(body["session_id"], id.as_str());
+    assert!(body["markdown"].as_str().unwrap().starts_with("---\n"));
+    assert_eq!(get("/sessions/nope").0, 404);
+
+    let (status, body) = get(&format!("/search?q={}", &id[..8]));
+    assert_eq!(status, 200);
+    assert_eq!(body["results"][0]["session_id"], id.as_str());
+    assert_eq!(get("/search").0, 400);
+    assert_eq!(get("/elsewhere").0, 404);
+
+    let port = addr.rsplit(':').next().unwrap();
+    let as_host = |host: &str| send(&format!("GET /sessions HTTP/1.1\r\nHost: {}\r\n\r\n", host));
+    assert_eq!(as_host(&format!("localhost:{}", port)).0, 200);
+    assert_eq!(as_host(&format!("rebound.example:{}", port)).0, 403);
+    assert_eq!(send("GET /sessions HTTP/1.0\r\n\r\n").0, 403);
+    let oversized = format!("GET /sessions HTTP/1.1\r\nx-pad: {}", "a".repeat(16 * 1024));
+    assert_eq!(send(&oversized[..16 * 1024]).0, 431);
+
+    child.kill().unwrap();
+    let _ = child.wait();
+}
+
+#[test]
+fn pull_posts_new_sessions_to_the_webhook() {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        while !String::from_utf8_lossy(&request).ends_with('}') {
+            let n = socket.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let sandbox = Sandbox::new();
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[notify]\nwebhook = \"http://{}/hook\"\nlink = \"https://example.com/tree\"\n",
+            addr
+        ),
+    )
+    .unwrap();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let request = server.join().unwrap();
+    let body: serde_json::Value =
+        serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+    let text = body["text"].as_str().unwrap();
+    assert!(
+        text.starts_with("1 session updated in `project`\n• "),
+        "{}",
+        text
+    );
+    assert!(text.contains("(claude, 3 messages"), "{}", text);
+    assert!(
+        text.contains("https://example.com/tree/.waylog/history/"),
+        "{}",
+        text
+    );
+
+    // Nothing new, nothing posted
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn gist_uploads_the_anonymized_session() {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 65536];
+        loop {
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break;
+                }
+            }
+            let n = socket.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        let reply = r#"{"html_url":"https://gist.example.com/abc123"}"#;
+        write!(
+            socket,
+            "HTTP/1.1 201 Created\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            reply.len(),
+            reply
+        )
+        .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(output.status.success());
+    let project = sandbox.project.to_str().unwrap();
+    assert!(sandbox.history()[0].1.contains(project));
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        format!(
+            "[share]\ngithub_api = \"http://{}\"\n\n[audit]\naccess_log = true\n",
+            addr
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_waylog"))
+        .args(["gist", "--latest", "--anonymize"])
+        .current_dir(&sandbox.project)
+        .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+        .env("HOME", sandbox.project.parent().unwrap())
+        .env("GITHUB_TOKEN", "test-token")
+        .env("NO_COLOR", "1")
+        .env("LC_ALL", "C")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "https://gist.example.com/abc123"
+    );
+
+    let request = server.join().unwrap();
+    let (head, body) = request.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("POST /gists"), "{}", head);
+    assert!(
+        head.contains("authorization: Bearer test-token"),
+        "{}",
+        head
+    );
+    let gist: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(gist["public"], false);
+    let (name, file) = gist["files"].as_object().unwrap().iter().next().unwrap();
+    assert!(name.ends_with(".md"), "{}", name);
+    let content = file["content"].as_str().unwrap();
+    assert!(content.contains("0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"));
+    assert!(!content.contains(project), "{}", content);
+
+    let log = std::fs::read_to_string(sandbox.project.join(".waylog/access.log")).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    assert_eq!(entry["command"], "gist");
+    assert_eq!(entry["url"], "https://gist.example.com/abc123");
+    assert!(entry.get("target").is_none(), "{}", log);
+}
+
+#[test]
+fn pull_output_json_prints_one_document() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--output", "json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(document["schema"], "waylog.pull.v1");
+
+    let sessions: Vec<_> = document["providers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|p| p["sessions"].as_array().unwrap().clone())
+        .collect();
+    assert_eq!(sessions.len(), sandbox.history().len());
+    assert_eq!(document["totals"]["synced"], sessions.len());
+    for session in &sessions {
+        assert_eq!(session["status"], "synced");
+        let markdown = session["markdown_path"].as_str().unwrap();
+        assert!(Path::new(markdown).exists(), "{}", markdown);
+    }
+
+    let output = sandbox.waylog(&["pull", "--schema"]);
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["$id"], "urn:waylog.pull.v1");
+}
+
+#[test]
+fn global_json_and_color_flags() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["--json", "pull"]);
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["schema"], "waylog.pull.v1");
+
+    // NO_COLOR is set, and stdout is not a terminal
+    let output = sandbox.waylog(&["pull"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains('\x1b'));
+    let output = sandbox.waylog(&["pull", "--color", "always"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\x1b["));
+}
+
+#[test]
+fn exit_codes_tell_failures_apart() {
+    let sandbox = Sandbox::new();
+    std::fs::create_dir_all(sandbox.project.join(".waylog")).unwrap();
+
+    let output = sandbox.waylog(&["pull", "--provider", "nope"]);
+    assert_eq!(output.status.code(), Some(79));
+
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[agents.ghost]\nprovider = \"claude\"\ncommand = \"waylog-no-such-agent\"\n",
+    )
+    .unwrap();
+    let output = sandbox.waylog(&["run", "ghost"]);
+    assert_eq!(
+        output.status.code(),
+        Some(127),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    std::fs::write(sandbox.project.join(".waylog/config.toml"), "[redaction\n").unwrap();
+    let output = sandbox.waylog(&["pull"]);
+    assert_eq!(
+        output.status.code(),
+        Some(78),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn pull_timings_reports_phases_and_slowest_sessions() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.waylog(&["pull", "--provider", "claude", "--timings"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    let line = stdout
+        .lines()
+        .find(|line| line.starts_with("Timings for claude: scan "))
+        .unwrap_or_else(|| panic!("{}", stdout));
+    assert!(line.contains(", parse "), "{}", line);
+    assert!(line.contains(", state save "), "{}", line);
+    assert!(
+        stdout.contains("0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c.jsonl (parse "),
+        "{}",
+        stdout
+    );
+
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Timings"));
+}
+
+#[test]
+fn messages_follow_the_locale_and_ui_language() {
+    let sandbox = Sandbox::new();
+    let pull = |locale: &str, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_waylog"))
+            .args(args)
+            .current_dir(&sandbox.project)
+            .env("WAYLOG_PROVIDER_FIXTURES", &sandbox.fixtures)
+            .env("HOME", sandbox.project.parent().unwrap())
+            .env("NO_COLOR", "1")
+            .env("LC_ALL", locale)
+            .output()
+            .unwrap()
+    };
+
+    let output = pull("zh_CN.UTF-8", &["pull", "--provider", "claude"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("拉取完成！"), "{}", stdout);
+    assert_eq!(summary_row(&stdout, "合计")[0], "合计");
+
+    // JSON is for scripts, so it stays in English
+    let output = pull("zh_CN.UTF-8", &["pull", "--provider", "claude", "--json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("拉取"), "{}", stdout);
+
+    std::fs::write(
+        sandbox.project.join(".waylog/config.toml"),
+        "[ui]\nlanguage = \"zh-CN\"\n",
+    )
+    .unwrap();
+    let output = sandbox.waylog(&["pull", "--provider", "claude"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("拉取完成！"), "{}", stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn encryption_stores_the_history_as_age_files() {
+    use age::secrecy::ExposeSecret;
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    let identity = age::x25519::Identity::generate();
+    std::fs::write(
+        sandbox.project.join("key.txt"),
+        format!("{}\n", identity.to_string().expose_secret()),
+    )
+    .unwrap();
+    let config = sandbox.project.join(".waylog/config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[encryption]\nrecipients = [\"{}\"]\nidentity = \"key.txt\"\n\
+             [export]\ncommand = \"cat\"\ncommand_extension = \"json\"\n",
+            identity.to_public()
+        ),
+    )
+    .unwrap();
+    let history = sandbox.project.join(".waylog/history");
+    let files = || {
+        let mut names: Vec<_> = std::fs::read_dir(&history)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+    let names = || {
+        let mut names = files();
+        names.retain(|name| name.contains(".md"));
+        names
+    };
+
+    let output = sandbox.waylog(&["pull"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // The export.command output is encrypted like the history file it sits next to
+    let exported: Vec<_> = files()
+        .into_iter()
+        .filter(|name| name.contains(".json"))
+        .collect();
+    assert_eq!(exported.len(), 2, "{:?}", exported);
+    assert!(exported.iter().all(|name| name.ends_with(".json.age")));
+    let encrypted = names();
+    assert_eq!(encrypted.len(), 2, "{:?}", encrypted);
+    assert!(encrypted.iter().all(|name| name.ends_with(".md.age")));
+    for name in &encrypted {
+        let bytes = std::fs::read(history.join(name)).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("session_id"));
+    }
+
+    // Unchanged files are recognized through the encryption
+    let output = sandbox.waylog(&["pull"]);
+    assert!(output.status.success());
+    assert_eq!(names(), encrypted);
+
+    // Without --gui or an editor, the decrypted session is printed
+    let show = |editor: Option<&Path>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_waylog"));
+        command
+            .args(["show", "0f6c2a4e"])
+            .current_dir(&sandbox.project)
+            .env("HOME", sandbox.project.parent().unwrap())
+            .env_remove("VISUAL");
+        match editor {
+            Some(editor) => command.env("EDITOR", editor),
+            None => command.arg("--gui").env_remove("EDITOR"),
+        };
+        command.output().unwrap()
+    };
+    let output = show(None);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("session_id: 0f6c2a4e-3b1d-4c5e-9a7f-1d2e3f4a5b6c"),
+        "{}",
+        stdout
+    );
+
+    // An editor gets a decrypted copy, and its changes are encrypted back
+    let editor = sandbox.project.join("editor.sh");
+    std::fs::write(&editor, "#!/bin/sh\necho 'A hand-written note' >> \"$1\"\n").unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(show(Some(&editor)).status.success());
+    assert_eq!(names(), encrypted);
+    assert!(String::from_utf8_lossy(&show(None).stdout).contains("A hand-written note"));
+
+    // Commands reading the history decrypt it
+    let output = sandbox.waylog(&["site"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let pages = std::fs::read_dir(sandbox.project.join("site/sessions")).unwrap();
+    assert_eq!(pages.count(), 2);
+
+    // Without recipients, a forced pull writes plaintext again
+    std::fs::write(&config, "[encryption]\nidentity = \"key.txt\"\n").unwrap();
+    assert!(sandbox.waylog(&["pull", "--force"]).status.success());
+    let converted = names();
+    assert!(
+        converted.contains(
+            &"2025-01-15_10-00-02Z-codex-add-a-readme-section-about-installation.md".to_string()
+        ),
+        "{:?}",
+        converted
+    );
+    // The hand-edited file is kept as it is; the session goes on in a revision
+    assert!(
+        converted.iter().any(|name| name.ends_with("-rev2.md")),
+        "{:?}",
+        converted
+    );
+}